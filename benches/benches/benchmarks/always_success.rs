@@ -27,6 +27,7 @@ fn bench(c: &mut Criterion) {
                         let mut blocks = vec![shared1
                             .snapshot()
                             .get_block(&shared1.genesis_hash())
+                            .unwrap()
                             .unwrap()];
                         let mut parent = blocks[0].clone();
                         (0..20).for_each(|_| {
@@ -72,6 +73,7 @@ fn bench(c: &mut Criterion) {
                         let mut blocks = vec![shared1
                             .snapshot()
                             .get_block(&shared1.genesis_hash())
+                            .unwrap()
                             .unwrap()];
                         let mut parent = blocks[0].clone();
                         (0..5).for_each(|i| {
@@ -146,6 +148,7 @@ fn bench(c: &mut Criterion) {
                         let mut blocks = vec![shared1
                             .snapshot()
                             .get_block(&shared1.genesis_hash())
+                            .unwrap()
                             .unwrap()];
                         let mut parent = blocks[0].clone();
                         (0..5).for_each(|i| {
@@ -48,6 +48,7 @@ fn block_assembler_config() -> BlockAssemblerConfig {
         notify: vec![],
         notify_scripts: vec![],
         notify_timeout_millis: 800,
+        policy: Default::default(),
     }
 }
 
@@ -184,7 +185,7 @@ fn bench(c: &mut Criterion) {
                         while i > 0 {
                             let snapshot = Arc::clone(&shared.snapshot());
                             let tip_hash = snapshot.tip_hash();
-                            let block = snapshot.get_block(&tip_hash).expect("tip exist");
+                            let block = snapshot.get_block(&tip_hash).unwrap().expect("tip exist");
                             let txs = gen_txs_from_block(&block);
                             let tx_pool = shared.tx_pool_controller();
                             if !txs.is_empty() {
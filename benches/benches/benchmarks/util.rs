@@ -139,6 +139,7 @@ pub fn gen_always_success_block(
     let transactions: Vec<TransactionView> = if blocks.len() > 1 {
         let pp_block = snapshot
             .get_block(&p_block.data().header().raw().parent_hash())
+            .unwrap()
             .expect("gen_block get pp_block");
         pp_block
             .transactions()
@@ -353,6 +354,7 @@ pub fn gen_secp_block(
     let transactions: Vec<TransactionView> = if blocks.len() > 1 {
         let pp_block = snapshot
             .get_block(&p_block.data().header().raw().parent_hash())
+            .unwrap()
             .expect("gen_block get pp_block");
         pp_block
             .transactions()
@@ -2,7 +2,7 @@
 #![allow(missing_docs)]
 
 use ckb_channel::{self as channel, select, Sender};
-use ckb_error::{Error, InternalErrorKind};
+use ckb_error::{prelude::*, Error, ErrorKind, InternalErrorKind};
 use ckb_logger::Level::Trace;
 use ckb_logger::{
     self, debug, error, info, log_enabled, log_enabled_target, trace, trace_target, warn,
@@ -10,8 +10,9 @@ use ckb_logger::{
 use ckb_merkle_mountain_range::leaf_index_to_mmr_size;
 use ckb_proposal_table::ProposalTable;
 use ckb_shared::shared::Shared;
+use ckb_shared::Snapshot;
 use ckb_stop_handler::{new_crossbeam_exit_rx, register_thread};
-use ckb_store::{attach_block_cell, detach_block_cell, ChainStore, StoreTransaction};
+use ckb_store::{attach_block_cell, detach_block_cell, ChainStore, InvalidBlock, StoreTransaction};
 use ckb_systemtime::unix_time_as_millis;
 use ckb_types::{
     core::{
@@ -28,18 +29,54 @@ use ckb_types::{
     U256,
 };
 use ckb_verification::cache::Completed;
-use ckb_verification::{BlockVerifier, InvalidParentError, NonContextualBlockTxsVerifier};
-use ckb_verification_contextual::{ContextualBlockVerifier, VerifyContext};
+use ckb_verification::{
+    BlockError, BlockErrorKind, BlockVerifier, HeaderError, HeaderErrorKind, InvalidParentError,
+    NonContextualBlockTxsVerifier,
+};
+use ckb_verification_contextual::{ContextualBlockVerifier, StageObserver, VerifyContext};
 use ckb_verification_traits::{Switch, Verifier};
 #[cfg(debug_assertions)]
 use is_sorted::IsSorted;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use std::time::Instant;
 use std::{cmp, thread};
 
 type ProcessBlockRequest = Request<(Arc<BlockView>, Switch), Result<bool, Error>>;
 type TruncateRequest = Request<Byte32, Result<(), Error>>;
+type InvalidateBlockRequest = Request<Byte32, Result<Vec<Byte32>, Error>>;
+type ReconsiderBlockRequest = Request<Byte32, Result<Vec<Byte32>, Error>>;
+
+/// The max count of the main chain blocks rolled back by `invalidate_block`.
+pub const MAX_INVALIDATE_DEPTH: BlockNumber = 1000;
+
+/// The errors of `invalidate_block` and `reconsider_block` caused by the requested block.
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum InvalidateBlockError {
+    /// The block is not stored.
+    #[error("block {0} is not found")]
+    NotFound(Byte32),
+    /// The genesis block can't be invalidated.
+    #[error("the genesis block can't be invalidated")]
+    Genesis,
+    /// Invalidating the block rolls back too many main chain blocks.
+    #[error("invalidating block {hash} rolls back {depth} blocks, exceeds {MAX_INVALIDATE_DEPTH}")]
+    TooDeep {
+        /// The requested block hash.
+        hash: Byte32,
+        /// The count of the main chain blocks to roll back.
+        depth: BlockNumber,
+    },
+    /// The block to reconsider is not marked invalid.
+    #[error("block {0} is not marked invalid")]
+    NotMarkedInvalid(Byte32),
+}
+
+impl From<InvalidateBlockError> for Error {
+    fn from(error: InvalidateBlockError) -> Self {
+        ErrorKind::Internal.because(error)
+    }
+}
 
 /// Controller to the chain service.
 ///
@@ -51,6 +88,8 @@ type TruncateRequest = Request<Byte32, Result<(), Error>>;
 pub struct ChainController {
     process_block_sender: Sender<ProcessBlockRequest>,
     truncate_sender: Sender<TruncateRequest>, // Used for testing only
+    invalidate_block_sender: Sender<InvalidateBlockRequest>,
+    reconsider_block_sender: Sender<ReconsiderBlockRequest>,
 }
 
 #[cfg_attr(feature = "mock", faux::methods)]
@@ -58,10 +97,14 @@ impl ChainController {
     pub fn new(
         process_block_sender: Sender<ProcessBlockRequest>,
         truncate_sender: Sender<TruncateRequest>,
+        invalidate_block_sender: Sender<InvalidateBlockRequest>,
+        reconsider_block_sender: Sender<ReconsiderBlockRequest>,
     ) -> Self {
         ChainController {
             process_block_sender,
             truncate_sender,
+            invalidate_block_sender,
+            reconsider_block_sender,
         }
     }
     /// Inserts the block into database.
@@ -100,6 +143,30 @@ impl ChainController {
                 .into())
         })
     }
+
+    /// Marks the block and all its stored descendants invalid manually.
+    ///
+    /// If the block is on the main chain, the chain is rolled back to its parent, at most
+    /// `MAX_INVALIDATE_DEPTH` blocks are rolled back. Returns the hashes of all the blocks marked
+    /// invalid.
+    pub fn invalidate_block(&self, block_hash: Byte32) -> Result<Vec<Byte32>, Error> {
+        Request::call(&self.invalidate_block_sender, block_hash).unwrap_or_else(|| {
+            Err(InternalErrorKind::System
+                .other("Chain service has gone")
+                .into())
+        })
+    }
+
+    /// Removes the invalid mark of the block and its stored descendants.
+    ///
+    /// Returns the hashes of all the blocks no longer marked invalid.
+    pub fn reconsider_block(&self, block_hash: Byte32) -> Result<Vec<Byte32>, Error> {
+        Request::call(&self.reconsider_block_sender, block_hash).unwrap_or_else(|| {
+            Err(InternalErrorKind::System
+                .other("Chain service has gone")
+                .into())
+        })
+    }
 }
 
 /// The struct represent fork
@@ -208,6 +275,7 @@ impl GlobalIndex {
 pub struct ChainService {
     shared: Shared,
     proposal_table: ProposalTable,
+    verify_stage_observer: Option<StageObserver>,
 }
 
 impl ChainService {
@@ -216,14 +284,22 @@ impl ChainService {
         ChainService {
             shared,
             proposal_table,
+            verify_stage_observer: None,
         }
     }
 
+    /// Reports the elapsed time of each contextual verification stage of the processed blocks.
+    pub fn set_verify_stage_observer(&mut self, observer: StageObserver) {
+        self.verify_stage_observer = Some(observer);
+    }
+
     /// start background single-threaded service with specified thread_name.
     pub fn start<S: ToString>(mut self, thread_name: Option<S>) -> ChainController {
         let signal_receiver = new_crossbeam_exit_rx();
         let (process_block_sender, process_block_receiver) = channel::bounded(0);
         let (truncate_sender, truncate_receiver) = channel::bounded(0);
+        let (invalidate_block_sender, invalidate_block_receiver) = channel::bounded(0);
+        let (reconsider_block_sender, reconsider_block_receiver) = channel::bounded(0);
 
         // Mainly for test: give an empty thread_name
         let mut thread_builder = thread::Builder::new();
@@ -265,6 +341,26 @@ impl ChainService {
                             break;
                         },
                     },
+                    recv(invalidate_block_receiver) -> msg => match msg {
+                        Ok(Request { responder, arguments: block_hash }) => {
+                            let _ = tx_control.suspend_chunk_process();
+                            let _ = responder.send(self.invalidate_block(&block_hash));
+                            let _ = tx_control.continue_chunk_process();
+                        },
+                        _ => {
+                            error!("invalidate_block_receiver closed");
+                            break;
+                        },
+                    },
+                    recv(reconsider_block_receiver) -> msg => match msg {
+                        Ok(Request { responder, arguments: block_hash }) => {
+                            let _ = responder.send(self.reconsider_block(&block_hash));
+                        },
+                        _ => {
+                            error!("reconsider_block_receiver closed");
+                            break;
+                        },
+                    },
                     recv(signal_receiver) -> _ => {
                         info!("ChainService received exit signal, exit now");
                         break;
@@ -275,7 +371,12 @@ impl ChainService {
 
         register_thread("ChainService", chain_jh);
 
-        ChainController::new(process_block_sender, truncate_sender)
+        ChainController::new(
+            process_block_sender,
+            truncate_sender,
+            invalidate_block_sender,
+            reconsider_block_sender,
+        )
     }
 
     fn make_fork_for_truncate(&self, target: &HeaderView, current_tip: &HeaderView) -> ForkChanges {
@@ -283,7 +384,10 @@ impl ChainService {
         let store = self.shared.store();
         for bn in (target.number() + 1)..=current_tip.number() {
             let hash = store.get_block_hash(bn).expect("index checked");
-            let old_block = store.get_block(&hash).expect("index checked");
+            let old_block = store
+                .get_block(&hash)
+                .expect("the blocks within the reorg window are not pruned")
+                .expect("index checked");
             fork.detached_blocks.push_back(old_block);
         }
         is_sorted_assert(&fork);
@@ -293,6 +397,20 @@ impl ChainService {
     // Truncate the main chain
     // Use for testing only, can only truncate less than 50000 blocks each time
     pub(crate) fn truncate(&mut self, target_tip_hash: &Byte32) -> Result<(), Error> {
+        // NOTE: Dont update tx-pool when truncate
+        self.truncate_main_chain(target_tip_hash, |_, _| Ok(()))
+            .map(|_| ())
+    }
+
+    // `before_commit` writes the extra data along with the rollback in the same db transaction
+    fn truncate_main_chain<F>(
+        &mut self,
+        target_tip_hash: &Byte32,
+        before_commit: F,
+    ) -> Result<(ForkChanges, Arc<Snapshot>), Error>
+    where
+        F: FnOnce(&ForkChanges, &StoreTransaction) -> Result<(), Error>,
+    {
         let snapshot = Arc::clone(&self.shared.snapshot());
         assert!(snapshot.is_main_chain(target_tip_hash));
 
@@ -327,6 +445,7 @@ impl ChainService {
         // Currently, we only move the target tip header here, we don't delete the block for performance
         // TODO: delete the blocks if we need in the future
 
+        before_commit(&fork, &db_txn)?;
         db_txn.commit()?;
 
         self.update_proposal_table(&fork);
@@ -344,8 +463,180 @@ impl ChainService {
 
         self.shared.store_snapshot(Arc::clone(&new_snapshot));
 
-        // NOTE: Dont update tx-pool when truncate
-        Ok(())
+        Ok((fork, new_snapshot))
+    }
+
+    // Mark the block invalid by the operator, the main chain is rolled back to its parent
+    pub(crate) fn invalidate_block(&mut self, block_hash: &Byte32) -> Result<Vec<Byte32>, Error> {
+        let snapshot = Arc::clone(&self.shared.snapshot());
+        let header = snapshot
+            .get_block_header(block_hash)
+            .ok_or_else(|| InvalidateBlockError::NotFound(block_hash.to_owned()))?;
+        if header.is_genesis() {
+            return Err(InvalidateBlockError::Genesis.into());
+        }
+        let is_main_chain = snapshot.is_main_chain(block_hash);
+        let depth = snapshot.tip_number().saturating_sub(header.number()) + 1;
+        if is_main_chain && depth > MAX_INVALIDATE_DEPTH {
+            return Err(InvalidateBlockError::TooDeep {
+                hash: block_hash.to_owned(),
+                depth,
+            }
+            .into());
+        }
+
+        let timestamp = unix_time_as_millis();
+        let mut invalidated = vec![(
+            header.hash(),
+            InvalidBlock {
+                number: header.number(),
+                timestamp,
+                manual: true,
+                reason: "invalidated manually".to_owned(),
+            },
+        )];
+        // all the stored descendants, including the detached main chain blocks and the side
+        // chain blocks, are found level by level
+        let reason = format!("descends from the invalidated block {block_hash}");
+        let mut parents = HashSet::from([header.hash()]);
+        let mut number = header.number();
+        while !parents.is_empty() {
+            number += 1;
+            let children: HashSet<Byte32> = snapshot
+                .get_block_hashes_by_number(number)
+                .into_iter()
+                .filter(|hash| {
+                    snapshot
+                        .get_block_header(hash)
+                        .map_or(false, |child| parents.contains(&child.parent_hash()))
+                })
+                .collect();
+            invalidated.extend(children.iter().map(|hash| {
+                (
+                    hash.to_owned(),
+                    InvalidBlock {
+                        number,
+                        timestamp,
+                        manual: false,
+                        reason: reason.clone(),
+                    },
+                )
+            }));
+            parents = children;
+        }
+
+        if is_main_chain {
+            // the marks are committed along with the rollback, so the detached blocks can't be
+            // attached again if the node stops in between
+            let (fork, new_snapshot) =
+                self.truncate_main_chain(&header.parent_hash(), |_fork, db_txn| {
+                    for (hash, invalid_block) in &invalidated {
+                        db_txn.insert_invalid_block(hash, invalid_block)?;
+                    }
+                    Ok(())
+                })?;
+
+            let tx_pool_controller = self.shared.tx_pool_controller();
+            if tx_pool_controller.service_started() {
+                if let Err(e) = tx_pool_controller.update_tx_pool_for_reorg(
+                    fork.detached_blocks().clone(),
+                    VecDeque::new(),
+                    fork.detached_proposal_id().clone(),
+                    new_snapshot,
+                ) {
+                    error!("Notify update_tx_pool_for_reorg error {}", e);
+                }
+            }
+            if let Some(metrics) = ckb_metrics::handle() {
+                metrics
+                    .ckb_chain_tip
+                    .set(header.number().saturating_sub(1) as i64);
+            }
+        } else {
+            let db_txn = self.shared.store().begin_transaction();
+            for (hash, invalid_block) in &invalidated {
+                db_txn.insert_invalid_block(hash, invalid_block)?;
+            }
+            db_txn.commit()?;
+        }
+
+        info!(
+            "invalidate block {}-{}, {} blocks marked invalid",
+            header.number(),
+            block_hash,
+            invalidated.len()
+        );
+        Ok(invalidated.into_iter().map(|(hash, _)| hash).collect())
+    }
+
+    // Remove the invalid mark of the block and its stored descendants
+    pub(crate) fn reconsider_block(&self, block_hash: &Byte32) -> Result<Vec<Byte32>, Error> {
+        let store = self.shared.store();
+        let target = store
+            .get_invalid_block(block_hash)?
+            .ok_or_else(|| InvalidateBlockError::NotMarkedInvalid(block_hash.to_owned()))?;
+
+        // index the marked descendants by their parents, then collect them from the target down
+        let mut children: HashMap<Byte32, Vec<Byte32>> = HashMap::new();
+        for (hash, invalid_block) in store.get_invalid_blocks()? {
+            if invalid_block.number <= target.number {
+                continue;
+            }
+            if let Some(header) = store.get_block_header(&hash) {
+                children.entry(header.parent_hash()).or_default().push(hash);
+            }
+        }
+        let mut reconsidered = vec![block_hash.to_owned()];
+        let mut index = 0;
+        while let Some(hash) = reconsidered.get(index) {
+            if let Some(hashes) = children.remove(hash) {
+                reconsidered.extend(hashes);
+            }
+            index += 1;
+        }
+
+        let db_txn = store.begin_transaction();
+        for hash in &reconsidered {
+            db_txn.delete_invalid_block(hash)?;
+            // the blocks failed the contextual verification are verified again, otherwise their
+            // descendants are rejected by the parent check
+            if let Some(mut ext) = store.get_block_ext(hash) {
+                if ext.verified == Some(false) {
+                    ext.verified = None;
+                    db_txn.insert_block_ext(hash, &ext)?;
+                }
+            }
+        }
+        db_txn.commit()?;
+
+        info!(
+            "reconsider block {}-{}, {} blocks no longer marked invalid",
+            target.number,
+            block_hash,
+            reconsidered.len()
+        );
+        Ok(reconsidered)
+    }
+
+    // Record the block rejected by the contextual verification, the descendants of an invalid
+    // block are rejected by the parent check before and not recorded.
+    fn record_invalid_block(&self, block: &BlockView, err: &Error) {
+        if !is_consensus_rejection(err) {
+            return;
+        }
+        let invalid_block = InvalidBlock {
+            number: block.number(),
+            timestamp: unix_time_as_millis(),
+            manual: false,
+            reason: err.to_string(),
+        };
+        let db_txn = self.shared.store().begin_transaction();
+        if let Err(e) = db_txn
+            .insert_invalid_block(&block.hash(), &invalid_block)
+            .and_then(|_| db_txn.commit())
+        {
+            error!("record invalid block {} error {}", block.hash(), e);
+        }
     }
 
     // visible pub just for test
@@ -359,10 +650,22 @@ impl ChainService {
             warn!("Receive 0 number block: 0-{}", block_hash);
         }
 
-        self.insert_block(block, switch).map(|ret| {
-            debug!("Finish processing block");
-            ret
-        })
+        self.insert_block(Arc::clone(&block), switch)
+            .map(|ret| {
+                debug!("Finish processing block");
+                ret
+            })
+            .map_err(|err| {
+                if is_consensus_rejection(&err) {
+                    if let Some(metrics) = ckb_metrics::handle() {
+                        metrics
+                            .ckb_block_rejected
+                            .with_label_values(&[&err.kind().to_string()])
+                            .inc();
+                    }
+                }
+                err
+            })
     }
 
     fn non_contextual_verify(&self, block: &BlockView) -> Result<(), Error> {
@@ -386,33 +689,37 @@ impl ChainService {
 
     fn insert_block(&mut self, block: Arc<BlockView>, switch: Switch) -> Result<bool, Error> {
         let db_txn = Arc::new(self.shared.store().begin_transaction());
-        let txn_snapshot = db_txn.get_snapshot();
-        let _snapshot_tip_hash = db_txn.get_update_for_tip_hash(&txn_snapshot);
+        let _snapshot_tip_hash = db_txn.get_update_for_tip_hash();
 
         // insert_block are assumed be executed in single thread
-        if txn_snapshot.block_exists(&block.header().hash()) {
+        if db_txn.block_exists(&block.header().hash()) {
             return Ok(false);
         }
         // non-contextual verify
         if !switch.disable_non_contextual() {
-            self.non_contextual_verify(&block)?;
+            let instant = Instant::now();
+            let verified = self.non_contextual_verify(&block);
+            observe_stage_duration("non_contextual", instant);
+            verified?;
         }
 
         let mut total_difficulty = U256::zero();
         let mut fork = ForkChanges::default();
 
-        let parent_ext = txn_snapshot
+        let parent_ext = db_txn
             .get_block_ext(&block.data().header().raw().parent_hash())
             .expect("parent already store");
 
-        let parent_header = txn_snapshot
+        let parent_header = db_txn
             .get_block_header(&block.data().header().raw().parent_hash())
             .expect("parent already store");
 
         let cannon_total_difficulty =
             parent_ext.total_difficulty.to_owned() + block.header().difficulty();
 
-        if parent_ext.verified == Some(false) {
+        if parent_ext.verified == Some(false)
+            || db_txn.get_invalid_block(&parent_header.hash())?.is_some()
+        {
             return Err(InvalidParentError {
                 parent_hash: parent_header.hash(),
             }
@@ -424,7 +731,7 @@ impl ChainService {
         let next_block_epoch = self
             .shared
             .consensus()
-            .next_epoch_ext(&parent_header, &txn_snapshot.borrow_as_data_loader())
+            .next_epoch_ext(&parent_header, &db_txn.borrow_as_data_loader())
             .expect("epoch should be stored");
         let new_epoch = next_block_epoch.is_head();
         let epoch = next_block_epoch.epoch();
@@ -468,11 +775,27 @@ impl ChainService {
                 &cannon_total_difficulty - &current_total_difficulty
             );
             self.find_fork(&mut fork, current_tip_header.number(), &block, ext);
+            for attached in fork.attached_blocks() {
+                if db_txn.get_invalid_block(&attached.hash())?.is_some() {
+                    return Err(InvalidParentError {
+                        parent_hash: attached.hash(),
+                    }
+                    .into());
+                }
+            }
             self.rollback(&fork, &db_txn)?;
 
             // update and verify chain root
             // MUST update index before reconcile_main_chain
-            self.reconcile_main_chain(Arc::clone(&db_txn), &mut fork, switch)?;
+            let reconciled = self.reconcile_main_chain(Arc::clone(&db_txn), &mut fork, switch);
+            if let Err(err) = &reconciled {
+                // the body is bound to the header once the non-contextual verification passes,
+                // otherwise a tampered body would get the real block marked invalid
+                if !switch.disable_non_contextual() {
+                    self.record_invalid_block(&block, err);
+                }
+            }
+            reconciled?;
 
             db_txn.insert_tip_header(&block.header())?;
             if new_epoch || fork.has_detached() {
@@ -482,7 +805,9 @@ impl ChainService {
         } else {
             db_txn.insert_block_ext(&block.header().hash(), &ext)?;
         }
+        let instant = Instant::now();
         db_txn.commit()?;
+        observe_stage_duration("store_commit", instant);
 
         if new_best_block {
             let tip_header = block.header();
@@ -509,6 +834,7 @@ impl ChainService {
 
             let tx_pool_controller = self.shared.tx_pool_controller();
             if tx_pool_controller.service_started() {
+                let instant = Instant::now();
                 if let Err(e) = tx_pool_controller.update_tx_pool_for_reorg(
                     fork.detached_blocks().clone(),
                     fork.attached_blocks().clone(),
@@ -517,6 +843,7 @@ impl ChainService {
                 ) {
                     error!("Notify update_tx_pool_for_reorg error {}", e);
                 }
+                observe_stage_duration("tx_pool_update", instant);
             }
 
             let block_ref: &BlockView = &block;
@@ -591,7 +918,12 @@ impl ChainService {
                     .shared
                     .store()
                     .get_block_hash(bn)
-                    .and_then(|hash| self.shared.store().get_block(&hash))
+                    .and_then(|hash| {
+                        self.shared
+                            .store()
+                            .get_block(&hash)
+                            .expect("the blocks within the proposal window are not pruned")
+                    })
                     .expect("block stored");
 
                 self.proposal_table.insert(bn, blk.union_proposal_ids());
@@ -625,6 +957,7 @@ impl ChainService {
                     .shared
                     .store()
                     .get_block(&hash)
+                    .expect("the blocks within the reorg window are not pruned")
                     .expect("block data stored before alignment_fork");
                 fork.detached_blocks.push_back(old_block);
             }
@@ -646,6 +979,7 @@ impl ChainService {
                     .shared
                     .store()
                     .get_block(&index.hash)
+                    .expect("the blocks within the reorg window are not pruned")
                     .expect("block data stored before alignment_fork");
                 index.forward(new_block.data().header().raw().parent_hash());
                 fork.attached_blocks.push_front(new_block);
@@ -670,6 +1004,7 @@ impl ChainService {
                 .shared
                 .store()
                 .get_block(&detached_hash)
+                .expect("the blocks within the reorg window are not pruned")
                 .expect("detached block stored before find_fork_until_latest_common");
             fork.detached_blocks.push_front(detached_blocks);
 
@@ -690,6 +1025,7 @@ impl ChainService {
                 .shared
                 .store()
                 .get_block(&index.hash)
+                .expect("the blocks within the reorg window are not pruned")
                 .expect("attached block stored before find_fork_until_latest_common");
             index.forward(attached_block.data().header().raw().parent_hash());
             fork.attached_blocks.push_front(attached_block);
@@ -776,17 +1112,23 @@ impl ChainService {
         {
             if !switch.disable_all() {
                 if found_error.is_none() {
+                    let instant = Instant::now();
                     let resolved = self.resolve_block_transactions(&txn, b, &verify_context);
+                    observe_stage_duration("resolve", instant);
                     match resolved {
                         Ok(resolved) => {
                             let verified = {
-                                let contextual_block_verifier = ContextualBlockVerifier::new(
+                                let mut contextual_block_verifier = ContextualBlockVerifier::new(
                                     verify_context.clone(),
                                     async_handle,
                                     switch,
                                     Arc::clone(&txs_verify_cache),
                                     &mmr,
                                 );
+                                if let Some(ref observer) = self.verify_stage_observer {
+                                    contextual_block_verifier = contextual_block_verifier
+                                        .with_stage_observer(Arc::clone(observer));
+                                }
                                 contextual_block_verifier.verify(&resolved, b)
                             };
                             match verified {
@@ -977,3 +1319,27 @@ fn is_sorted_assert(fork: &ForkChanges) {
 
 #[cfg(not(debug_assertions))]
 fn is_sorted_assert(_fork: &ForkChanges) {}
+
+// Whether the block breaks the consensus rules, rather than failing on an internal error or
+// inheriting the rejection of its parent.
+pub(crate) fn is_consensus_rejection(err: &Error) -> bool {
+    match err.kind() {
+        ErrorKind::Internal => false,
+        ErrorKind::Header => err
+            .downcast_ref::<HeaderError>()
+            .map_or(true, |err| err.kind() != HeaderErrorKind::InvalidParent),
+        ErrorKind::Block => err
+            .downcast_ref::<BlockError>()
+            .map_or(true, |err| err.kind() != BlockErrorKind::UnknownParent),
+        _ => true,
+    }
+}
+
+fn observe_stage_duration(stage: &str, instant: Instant) {
+    if let Some(metrics) = ckb_metrics::handle() {
+        metrics
+            .ckb_block_process_stage_duration
+            .with_label_values(&[stage])
+            .observe(instant.elapsed().as_secs_f64());
+    }
+}
@@ -38,6 +38,7 @@ fn start_chain(consensus: Option<Consensus>) -> (ChainController, Shared) {
         notify: vec![],
         notify_scripts: vec![],
         notify_timeout_millis: 800,
+        policy: Default::default(),
     };
     let (shared, mut pack) = builder
         .block_assembler_config(Some(config))
@@ -440,6 +441,21 @@ fn test_package_basic() {
         TxEntry::dummy_resolve(tx2_2.clone(), 0, Capacity::shannons(150), 100),
         TxEntry::dummy_resolve(tx2_3.clone(), 0, Capacity::shannons(150), 100),
     ];
+
+    // wait until the tx-pool has caught up with the tip, otherwise a late reorg
+    // update may re-check the plugged entries after they are packaged
+    let shared_tip_number = shared.snapshot().tip_number().into();
+    while {
+        let template_tip_number = tx_pool
+            .get_block_template(None, None, None)
+            .expect("must fetch block template result")
+            .expect("must have block template")
+            .number;
+        template_tip_number <= shared_tip_number
+    } {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+
     tx_pool.plug_entry(entries, PlugTarget::Proposed).unwrap();
 
     // 300 size best scored txs
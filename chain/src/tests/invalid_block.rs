@@ -0,0 +1,283 @@
+use crate::chain::{
+    is_consensus_rejection, ChainService, InvalidateBlockError, MAX_INVALIDATE_DEPTH,
+};
+use ckb_chain_spec::consensus::Consensus;
+use ckb_error::InternalErrorKind;
+use ckb_shared::SharedBuilder;
+use ckb_store::ChainStore;
+use ckb_test_chain_utils::{MockChain, MockStore};
+use ckb_types::{core::TransactionBuilder, packed::Byte32};
+use ckb_verification::{BlockErrorKind, InvalidParentError, UnknownParentError};
+use ckb_verification_traits::Switch;
+use std::sync::Arc;
+
+#[test]
+fn test_invalidate_and_reconsider_block() {
+    let builder = SharedBuilder::with_temp_db();
+
+    let (shared, mut pack) = builder.consensus(Consensus::default()).build().unwrap();
+    let mut chain_service = ChainService::new(shared.clone(), pack.take_proposal_table());
+
+    let genesis = shared
+        .store()
+        .get_block_header(&shared.store().get_block_hash(0).unwrap())
+        .unwrap();
+
+    let mock_store = MockStore::new(&genesis, shared.store());
+    let mut mock = MockChain::new(genesis, shared.consensus());
+
+    for _ in 0..10 {
+        mock.gen_empty_block_with_diff(40u64, &mock_store);
+    }
+
+    for blk in mock.blocks() {
+        chain_service
+            .process_block(Arc::new(blk.clone()), Switch::DISABLE_ALL)
+            .unwrap();
+    }
+
+    let invalidated = mock.blocks()[5].clone();
+    let parent = mock.blocks()[4].header();
+
+    let hashes = chain_service.invalidate_block(&invalidated.hash()).unwrap();
+    assert_eq!(hashes.len(), 5);
+    assert_eq!(hashes[0], invalidated.hash());
+    assert_eq!(shared.snapshot().tip_header(), &parent);
+
+    let invalid_blocks = shared.store().get_invalid_blocks().unwrap();
+    assert_eq!(invalid_blocks.len(), 5);
+    let invalid_block = shared
+        .store()
+        .get_invalid_block(&invalidated.hash())
+        .unwrap()
+        .unwrap();
+    assert!(invalid_block.manual);
+    assert_eq!(invalid_block.number, invalidated.number());
+
+    // the blocks extending the invalidated block are rejected
+    mock.gen_empty_block_with_diff(40u64, &mock_store);
+    let extended = mock.tip().clone();
+    assert!(chain_service
+        .process_block(Arc::new(extended.clone()), Switch::DISABLE_ALL)
+        .is_err());
+    assert_eq!(shared.snapshot().tip_header(), &parent);
+
+    let hashes = chain_service.reconsider_block(&invalidated.hash()).unwrap();
+    assert_eq!(hashes.len(), 5);
+    assert!(shared.store().get_invalid_blocks().unwrap().is_empty());
+    let err = chain_service
+        .reconsider_block(&invalidated.hash())
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<InvalidateBlockError>(),
+        Some(&InvalidateBlockError::NotMarkedInvalid(invalidated.hash()))
+    );
+
+    // the reconsidered blocks become the main chain again once extended
+    chain_service
+        .process_block(Arc::new(extended.clone()), Switch::DISABLE_ALL)
+        .unwrap();
+    assert_eq!(shared.snapshot().tip_header(), &extended.header());
+}
+
+#[test]
+fn test_tampered_block_is_not_recorded_invalid() {
+    let builder = SharedBuilder::with_temp_db();
+
+    let (shared, mut pack) = builder.consensus(Consensus::default()).build().unwrap();
+    let mut chain_service = ChainService::new(shared.clone(), pack.take_proposal_table());
+
+    let genesis = shared
+        .store()
+        .get_block_header(&shared.store().get_block_hash(0).unwrap())
+        .unwrap();
+
+    let mock_store = MockStore::new(&genesis, shared.store());
+    let mut mock = MockChain::new(genesis, shared.consensus());
+    mock.gen_empty_block_with_diff(40u64, &mock_store);
+    let block = mock.tip().clone();
+
+    // the transactions don't match the transactions root of the header
+    let tampered = block
+        .as_advanced_builder()
+        .transaction(TransactionBuilder::default().build())
+        .build_unchecked();
+    assert_eq!(tampered.hash(), block.hash());
+    let switch = Switch::DISABLE_ALL - Switch::DISABLE_NON_CONTEXTUAL;
+    assert!(chain_service
+        .process_block(Arc::new(tampered), switch)
+        .is_err());
+    assert!(shared
+        .store()
+        .get_invalid_block(&block.hash())
+        .unwrap()
+        .is_none());
+
+    chain_service
+        .process_block(Arc::new(block.clone()), switch)
+        .unwrap();
+    assert_eq!(shared.snapshot().tip_header(), &block.header());
+}
+
+#[test]
+fn test_invalidate_block_too_deep() {
+    let builder = SharedBuilder::with_temp_db();
+    // the mock chain can't cross an epoch, keep all the blocks in the genesis epoch
+    let mut consensus = Consensus::default();
+    consensus
+        .genesis_epoch_ext
+        .set_length(MAX_INVALIDATE_DEPTH + 2);
+
+    let (shared, mut pack) = builder.consensus(consensus).build().unwrap();
+    let mut chain_service = ChainService::new(shared.clone(), pack.take_proposal_table());
+
+    let genesis = shared
+        .store()
+        .get_block_header(&shared.store().get_block_hash(0).unwrap())
+        .unwrap();
+
+    let mock_store = MockStore::new(&genesis, shared.store());
+    let mut mock = MockChain::new(genesis, shared.consensus());
+    for _ in 0..=MAX_INVALIDATE_DEPTH {
+        mock.gen_empty_block_with_diff(40u64, &mock_store);
+    }
+    for blk in mock.blocks() {
+        chain_service
+            .process_block(Arc::new(blk.clone()), Switch::DISABLE_ALL)
+            .unwrap();
+    }
+
+    let tip = mock.tip().header();
+    let err = chain_service
+        .invalidate_block(&mock.blocks()[0].hash())
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<InvalidateBlockError>(),
+        Some(&InvalidateBlockError::TooDeep {
+            hash: mock.blocks()[0].hash(),
+            depth: MAX_INVALIDATE_DEPTH + 1,
+        })
+    );
+    assert_eq!(shared.snapshot().tip_header(), &tip);
+    assert!(shared.store().get_invalid_blocks().unwrap().is_empty());
+
+    let hashes = chain_service
+        .invalidate_block(&mock.blocks()[1].hash())
+        .unwrap();
+    assert_eq!(hashes.len() as u64, MAX_INVALIDATE_DEPTH);
+    assert_eq!(
+        shared.store().get_invalid_blocks().unwrap().len() as u64,
+        MAX_INVALIDATE_DEPTH
+    );
+}
+
+#[test]
+fn test_invalidate_and_reconsider_side_chain_block() {
+    let builder = SharedBuilder::with_temp_db();
+
+    let (shared, mut pack) = builder.consensus(Consensus::default()).build().unwrap();
+    let mut chain_service = ChainService::new(shared.clone(), pack.take_proposal_table());
+
+    let genesis = shared
+        .store()
+        .get_block_header(&shared.store().get_block_hash(0).unwrap())
+        .unwrap();
+
+    let mock_store = MockStore::new(&genesis, shared.store());
+    let mut mock = MockChain::new(genesis, shared.consensus());
+    for _ in 0..4 {
+        mock.gen_empty_block_with_diff(100u64, &mock_store);
+    }
+    let mut fork = MockChain::new(mock.blocks()[0].header(), shared.consensus());
+    for _ in 0..3 {
+        fork.gen_empty_block_with_diff(20u64, &mock_store);
+    }
+    for blk in mock.blocks().iter().chain(fork.blocks()) {
+        chain_service
+            .process_block(Arc::new(blk.clone()), Switch::DISABLE_ALL)
+            .unwrap();
+    }
+    let tip = mock.tip().header();
+
+    // the stored side chain descendants are marked invalid, the main chain is untouched
+    let hashes = chain_service
+        .invalidate_block(&fork.blocks()[0].hash())
+        .unwrap();
+    assert_eq!(hashes.len(), 3);
+    assert_eq!(hashes[0], fork.blocks()[0].hash());
+    assert_eq!(shared.snapshot().tip_header(), &tip);
+    for blk in fork.blocks() {
+        assert!(shared
+            .store()
+            .get_invalid_block(&blk.hash())
+            .unwrap()
+            .is_some());
+    }
+
+    // the block failed the contextual verification before
+    let failed = fork.blocks()[1].hash();
+    let mut ext = shared.store().get_block_ext(&failed).unwrap();
+    ext.verified = Some(false);
+    let db_txn = shared.store().begin_transaction();
+    db_txn.insert_block_ext(&failed, &ext).unwrap();
+    db_txn.commit().unwrap();
+
+    let hashes = chain_service
+        .reconsider_block(&fork.blocks()[0].hash())
+        .unwrap();
+    assert_eq!(hashes.len(), 3);
+    assert!(shared.store().get_invalid_blocks().unwrap().is_empty());
+    assert_eq!(
+        shared.store().get_block_ext(&failed).unwrap().verified,
+        None
+    );
+
+    // the reconsidered side chain can be extended again
+    fork.gen_empty_block_with_diff(20u64, &mock_store);
+    chain_service
+        .process_block(Arc::new(fork.tip().clone()), Switch::DISABLE_ALL)
+        .unwrap();
+}
+
+#[test]
+fn test_invalidate_block_errors() {
+    let builder = SharedBuilder::with_temp_db();
+
+    let (shared, mut pack) = builder.consensus(Consensus::default()).build().unwrap();
+    let mut chain_service = ChainService::new(shared.clone(), pack.take_proposal_table());
+    let genesis_hash = shared.store().get_block_hash(0).unwrap();
+
+    let err = chain_service.invalidate_block(&genesis_hash).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<InvalidateBlockError>(),
+        Some(&InvalidateBlockError::Genesis)
+    );
+    let unknown = Byte32::zero();
+    let err = chain_service.invalidate_block(&unknown).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<InvalidateBlockError>(),
+        Some(&InvalidateBlockError::NotFound(unknown))
+    );
+}
+
+#[test]
+fn test_consensus_rejection() {
+    assert!(is_consensus_rejection(
+        &BlockErrorKind::ExceededMaximumCycles.into()
+    ));
+    assert!(!is_consensus_rejection(
+        &InternalErrorKind::Database.other("db").into()
+    ));
+    assert!(!is_consensus_rejection(
+        &InvalidParentError {
+            parent_hash: Byte32::zero()
+        }
+        .into()
+    ));
+    assert!(!is_consensus_rejection(
+        &UnknownParentError {
+            parent_hash: Byte32::zero()
+        }
+        .into()
+    ));
+}
@@ -0,0 +1,64 @@
+use crate::tests::dep_cell::build_tx;
+use crate::tests::util::start_chain;
+use ckb_tx_pool::PersistedEntry;
+use ckb_types::core::Capacity;
+
+#[test]
+fn test_load_tx_pool_entries() {
+    let (_chain_controller, shared, _parent) = start_chain(None);
+    let tx_pool = shared.tx_pool_controller();
+    let genesis = shared.consensus().genesis_block().clone();
+    let genesis_txs = genesis.transactions();
+    let fee = Capacity::shannons(100_000);
+
+    let tx = build_tx((&genesis_txs[1], &[0]), (&genesis_txs[1], &[]), 1, fee);
+    let parent = build_tx((&genesis_txs[2], &[0]), (&genesis_txs[2], &[]), 1, fee);
+    let orphan = build_tx((&parent, &[0]), (&parent, &[]), 1, fee);
+    let entries = vec![
+        // the timestamp in the future is clamped
+        PersistedEntry {
+            transaction: tx.clone(),
+            timestamp: u64::MAX,
+            fee_delta: 500,
+            local: true,
+        },
+        // fails to load without the parent
+        PersistedEntry {
+            transaction: orphan.clone(),
+            timestamp: 0,
+            fee_delta: 700,
+            local: true,
+        },
+    ];
+    let now = ckb_systemtime::unix_time_as_millis();
+    assert_eq!(tx_pool.load_pool(entries).unwrap(), 1);
+
+    let dumped = tx_pool.dump_pool().unwrap();
+    assert_eq!(dumped.len(), 1);
+    assert_eq!(dumped[0].transaction.hash(), tx.hash());
+    assert!(dumped[0].timestamp >= now && dumped[0].timestamp <= now + 60_000);
+    assert_eq!(dumped[0].fee_delta, 500);
+
+    // the fee delta of the tx failed to load is dropped
+    let entries = vec![
+        PersistedEntry {
+            transaction: parent,
+            timestamp: now,
+            fee_delta: 0,
+            local: true,
+        },
+        PersistedEntry {
+            transaction: orphan.clone(),
+            timestamp: now,
+            fee_delta: 0,
+            local: true,
+        },
+    ];
+    assert_eq!(tx_pool.load_pool(entries).unwrap(), 2);
+    let dumped = tx_pool.dump_pool().unwrap();
+    let entry = dumped
+        .iter()
+        .find(|entry| entry.transaction.hash() == orphan.hash())
+        .unwrap();
+    assert_eq!(entry.fee_delta, 0);
+}
@@ -0,0 +1,53 @@
+use crate::tests::dep_cell::build_tx;
+use crate::tests::util::start_chain_with_tx_pool_config;
+use ckb_app_config::TxPoolConfig;
+use ckb_types::{core::Capacity, packed::Byte32};
+use std::{thread::sleep, time::Duration};
+
+#[test]
+fn test_low_fee_parent_waits_again_after_child_removed() {
+    let tx_pool_config = TxPoolConfig {
+        max_low_fee_parents: 10,
+        low_fee_parent_window_secs: 1,
+        ..Default::default()
+    };
+    let (_chain_controller, shared, _parent) =
+        start_chain_with_tx_pool_config(None, tx_pool_config);
+    let tx_pool = shared.tx_pool_controller();
+    let genesis = shared.consensus().genesis_block().clone();
+    let genesis_txs = genesis.transactions();
+    let fee = Capacity::shannons(100_000);
+    let is_pending = |hash: &Byte32| tx_pool.get_all_ids().unwrap().pending.contains(hash);
+    // every submission checks the low fee parents which are waiting for a child
+    let mut triggers = genesis_txs[2..]
+        .iter()
+        .map(|issue_tx| build_tx((issue_tx, &[0]), (issue_tx, &[]), 1, fee));
+    let mut trigger = || {
+        let tx = triggers.next().unwrap();
+        tx_pool.submit_local_tx(tx).unwrap().unwrap();
+    };
+
+    let parent = build_tx(
+        (&genesis_txs[1], &[0]),
+        (&genesis_txs[1], &[]),
+        1,
+        Capacity::zero(),
+    );
+    tx_pool.submit_local_tx(parent.clone()).unwrap().unwrap();
+    let child = build_tx((&parent, &[0]), (&parent, &[]), 1, fee);
+    tx_pool.submit_local_tx(child.clone()).unwrap().unwrap();
+
+    // the child pays for the parent
+    sleep(Duration::from_millis(1500));
+    trigger();
+    assert!(is_pending(&parent.hash()));
+    assert!(is_pending(&child.hash()));
+
+    // the parent waits for another child once the paying one is removed
+    assert!(tx_pool.remove_local_tx(child.hash()).unwrap());
+    trigger();
+    assert!(is_pending(&parent.hash()));
+    sleep(Duration::from_millis(1500));
+    trigger();
+    assert!(!is_pending(&parent.hash()));
+}
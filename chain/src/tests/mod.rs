@@ -4,11 +4,15 @@ mod cell;
 mod delay_verify;
 mod dep_cell;
 mod find_fork;
+mod invalid_block;
 mod load_code_with_snapshot;
 mod load_input_cell_data;
 mod load_input_data_hash_cell;
+mod load_tx_pool;
+mod low_fee_parent;
 mod non_contextual_block_txs_verify;
 mod reward;
 mod truncate;
 mod uncle;
 mod util;
+mod verify_stage;
@@ -74,6 +74,7 @@ pub(crate) fn start_chain_with_tx_pool_config(
         notify: vec![],
         notify_scripts: vec![],
         notify_timeout_millis: 800,
+        policy: Default::default(),
     };
 
     let (shared, mut pack) = builder
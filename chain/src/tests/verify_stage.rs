@@ -0,0 +1,91 @@
+use crate::chain::ChainService;
+use ckb_chain_spec::consensus::Consensus;
+use ckb_shared::SharedBuilder;
+use ckb_store::ChainStore;
+use ckb_test_chain_utils::{MockChain, MockStore};
+
+use ckb_verification_contextual::VerifyStage;
+use ckb_verification_traits::Switch;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_verify_stage_observer() {
+    let (shared, mut pack) = SharedBuilder::with_temp_db()
+        .consensus(Consensus::default())
+        .build()
+        .unwrap();
+    let mut chain_service = ChainService::new(shared.clone(), pack.take_proposal_table());
+    let stages = Arc::new(Mutex::new(Vec::new()));
+    let observed = Arc::clone(&stages);
+    chain_service.set_verify_stage_observer(Arc::new(move |stage, _| {
+        observed.lock().unwrap().push(stage);
+    }));
+
+    let genesis = shared
+        .store()
+        .get_block_header(&shared.store().get_block_hash(0).unwrap())
+        .unwrap();
+    let mock_store = MockStore::new(&genesis, shared.store());
+    let mut mock = MockChain::new(genesis, shared.consensus());
+    mock.gen_empty_block(&mock_store);
+    chain_service
+        .process_block(
+            Arc::new(mock.tip().clone()),
+            Switch::DISABLE_NON_CONTEXTUAL | Switch::DISABLE_EXTENSION,
+        )
+        .unwrap();
+    // the stages are observed once each, in the order of the verification
+    assert_eq!(
+        *stages.lock().unwrap(),
+        vec![
+            VerifyStage::Epoch,
+            VerifyStage::Uncles,
+            VerifyStage::TwoPhaseCommit,
+            VerifyStage::DaoHeader,
+            VerifyStage::Reward,
+            VerifyStage::Transactions,
+            VerifyStage::Scripts,
+        ]
+    );
+
+    // the disabled stages are not observed
+    stages.lock().unwrap().clear();
+    mock.gen_empty_block(&mock_store);
+    chain_service
+        .process_block(
+            Arc::new(mock.tip().clone()),
+            Switch::DISABLE_NON_CONTEXTUAL
+                | Switch::DISABLE_EXTENSION
+                | Switch::DISABLE_EPOCH
+                | Switch::DISABLE_UNCLES
+                | Switch::DISABLE_REWARD,
+        )
+        .unwrap();
+    assert_eq!(
+        *stages.lock().unwrap(),
+        vec![
+            VerifyStage::TwoPhaseCommit,
+            VerifyStage::DaoHeader,
+            VerifyStage::Transactions,
+            VerifyStage::Scripts,
+        ]
+    );
+
+    // the failed stage is the last one observed, the mock block has no extension
+    stages.lock().unwrap().clear();
+    mock.gen_empty_block(&mock_store);
+    assert!(chain_service
+        .process_block(Arc::new(mock.tip().clone()), Switch::DISABLE_NON_CONTEXTUAL)
+        .is_err());
+    assert_eq!(
+        *stages.lock().unwrap(),
+        vec![
+            VerifyStage::Epoch,
+            VerifyStage::Uncles,
+            VerifyStage::TwoPhaseCommit,
+            VerifyStage::DaoHeader,
+            VerifyStage::Reward,
+            VerifyStage::Extension,
+        ]
+    );
+}
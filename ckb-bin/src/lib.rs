@@ -136,6 +136,11 @@ fn run_app_inner(
         cli::CMD_STATS => subcommand::stats(setup.stats(matches)?, handle.clone()),
         cli::CMD_RESET_DATA => subcommand::reset_data(setup.reset_data(matches)?),
         cli::CMD_MIGRATE => subcommand::migrate(setup.migrate(matches)?),
+        cli::CMD_FREEZER => subcommand::freezer(setup.freezer(matches)?),
+        cli::CMD_BACKUP => subcommand::backup(setup.backup(matches)?, handle.clone()),
+        cli::CMD_REBUILD_TX_INDEX => {
+            subcommand::rebuild_tx_index(setup.rebuild_tx_index()?, handle.clone())
+        }
         #[cfg(not(target_os = "windows"))]
         cli::CMD_DAEMON => subcommand::daemon(setup.daemon(matches)?),
         _ => unreachable!(),
@@ -173,5 +178,8 @@ fn is_silent_logging(cmd: &str) -> Silent {
             | cli::CMD_MIGRATE
             | cli::CMD_RESET_DATA
             | cli::CMD_DAEMON
+            | cli::CMD_FREEZER
+            | cli::CMD_BACKUP
+            | cli::CMD_REBUILD_TX_INDEX
     )
 }
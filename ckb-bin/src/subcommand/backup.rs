@@ -0,0 +1,224 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::helper::prompt;
+use ckb_app_config::{BackupArgs, ExitCode};
+use ckb_async_runtime::Handle;
+use ckb_db::{ReadOnlyDB, RocksDB};
+use ckb_db_schema::{CHAIN_SPEC_HASH_KEY, COLUMNS, COLUMN_INDEX};
+use ckb_shared::shared::{BACKUP_ANCIENT_DIR, BACKUP_DB_DIR};
+use ckb_shared::SharedBuilder;
+use ckb_store::Freezer;
+use ckb_types::{packed, prelude::*};
+
+pub fn backup(args: BackupArgs, async_handle: Handle) -> Result<(), ExitCode> {
+    if let Some(ref target) = args.target {
+        create(&args, target, async_handle)
+    } else if let Some(ref source) = args.restore {
+        restore(&args, source)
+    } else {
+        unreachable!("the group of arguments is required")
+    }
+}
+
+fn create(args: &BackupArgs, target: &Path, async_handle: Handle) -> Result<(), ExitCode> {
+    let builder = SharedBuilder::new(
+        &args.config.bin_name,
+        args.config.root_dir.as_path(),
+        &args.config.db,
+        Some(args.config.ancient.clone()),
+        async_handle,
+        args.consensus.clone(),
+    )?
+    .store_config(args.config.store);
+    let (shared, _) = builder.build()?;
+    shared.create_backup(target).map_err(|err| {
+        eprintln!("Backup error: {err}");
+        ExitCode::Failure
+    })?;
+    println!("Backup created in {}", target.display());
+    Ok(())
+}
+
+fn restore(args: &BackupArgs, source: &Path) -> Result<(), ExitCode> {
+    let db_path = source.join(BACKUP_DB_DIR);
+    let ancient_path = source.join(BACKUP_ANCIENT_DIR);
+    validate(args, &db_path, &ancient_path)?;
+
+    let (db_target, ancient_target) = (&args.config.db.path, &args.config.ancient);
+    let existing = [db_target, ancient_target]
+        .iter()
+        .filter(|to| to.exists())
+        .map(|to| to.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(", ");
+    if !args.force && !existing.is_empty() {
+        let input = prompt(format!("overwrite {existing}? ").as_str());
+        if !["y", "Y"].contains(&input.trim()) {
+            return Ok(());
+        }
+    }
+
+    // hold the lock of the database, so the node can't run while restoring
+    let lock = RocksDB::prepare_for_bulk_load_open(db_target, COLUMNS).map_err(|err| {
+        eprintln!(
+            "Failed to lock the database {}, stop the node before restoring: {err}",
+            db_target.display()
+        );
+        ExitCode::Failure
+    })?;
+
+    // the backup is copied next to the targets, then renamed into place, so an interrupted
+    // restore never leaves a partial database behind
+    let db_restoring = sibling(db_target, "restoring");
+    println!(
+        "Restoring {} from {}",
+        db_target.display(),
+        db_path.display()
+    );
+    copy_dir(&db_path, &db_restoring)?;
+    let ancient_restoring = sibling(ancient_target, "restoring");
+    if ancient_path.exists() {
+        println!(
+            "Restoring {} from {}",
+            ancient_target.display(),
+            ancient_path.display()
+        );
+        copy_dir(&ancient_path, &ancient_restoring)?;
+    }
+
+    let db_old = replace_dir(Some(&db_restoring), db_target)?;
+    // the frozen blocks in place don't match the restored database, they are cleared if the
+    // backup has none
+    let ancient_old = if ancient_path.exists() {
+        replace_dir(Some(&ancient_restoring), ancient_target)?
+    } else {
+        replace_dir(None, ancient_target)?
+    };
+    drop(lock);
+    for old in db_old.iter().chain(ancient_old.iter()) {
+        fs::remove_dir_all(old)?;
+    }
+    Ok(())
+}
+
+// the path of the directory next to the provided one, e.g. `data/db.restoring` for `data/db`
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+// renames the directory `to` aside and `from` into its place, returns the path of the previous
+// directory to be removed
+fn replace_dir(from: Option<&Path>, to: &Path) -> Result<Option<PathBuf>, ExitCode> {
+    let old = if to.exists() {
+        let old = sibling(to, "old");
+        if old.exists() {
+            fs::remove_dir_all(&old)?;
+        }
+        fs::rename(to, &old)?;
+        Some(old)
+    } else {
+        None
+    };
+    if let Some(from) = from {
+        fs::rename(from, to)?;
+    }
+    Ok(old)
+}
+
+// checks the backup is created with the configured chain spec and the frozen blocks are intact
+fn validate(args: &BackupArgs, db_path: &Path, ancient_path: &Path) -> Result<(), ExitCode> {
+    let failure = |msg: String| {
+        eprintln!("Backup validation error: {msg}");
+        ExitCode::Failure
+    };
+
+    let db = ReadOnlyDB::open_cf(db_path, vec![COLUMN_INDEX])
+        .map_err(|err| failure(err.to_string()))?
+        .ok_or_else(|| failure(format!("database {} not found", db_path.display())))?;
+    let get_hash = |raw: Option<_>| {
+        raw.map(|raw: ckb_db::DBPinnableSlice| {
+            packed::Byte32Reader::from_slice_should_be_ok(raw.as_ref()).to_entity()
+        })
+    };
+
+    let spec_hash = db
+        .get_pinned_default(CHAIN_SPEC_HASH_KEY)
+        .map(get_hash)
+        .map_err(|err| failure(err.to_string()))?;
+    if let Some(spec_hash) = spec_hash {
+        if spec_hash != args.chain_spec_hash {
+            return Err(failure(format!(
+                "chain spec hash mismatch: Config({}), backup({})",
+                args.chain_spec_hash, spec_hash
+            )));
+        }
+    }
+
+    let block_hash = |number: u64| {
+        let number: packed::Uint64 = number.pack();
+        db.get_pinned(COLUMN_INDEX, number.as_slice())
+            .map(get_hash)
+            .map_err(|err| failure(err.to_string()))
+    };
+    let genesis_hash = args.consensus.genesis_hash();
+    if block_hash(0)? != Some(genesis_hash.clone()) {
+        return Err(failure(format!(
+            "genesis hash mismatch, expect {genesis_hash}"
+        )));
+    }
+
+    if ancient_path.exists() {
+        let freezer = Freezer::open(ancient_path.to_path_buf())
+            .map_err(|err| failure(format!("open freezer {err}")))?;
+        let corrupted = freezer
+            .verify()
+            .map_err(|err| failure(format!("verify freezer {err}")))?;
+        if !corrupted.is_empty() {
+            return Err(failure(format!("corrupted frozen blocks {corrupted:?}")));
+        }
+        let number = freezer.number();
+        if number > 1 {
+            let raw_block = freezer
+                .retrieve(number - 1)
+                .map_err(|err| failure(err.to_string()))?
+                .ok_or_else(|| failure(format!("frozen block {} missing", number - 1)))?;
+            let hash = packed::BlockReader::from_compatible_slice(&raw_block)
+                .map_err(|err| failure(err.to_string()))?
+                .to_entity()
+                .calc_header_hash();
+            if block_hash(number - 1)? != Some(hash) {
+                return Err(failure(format!(
+                    "frozen block {} is not on the chain of the database",
+                    number - 1
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &PathBuf) -> Result<(), ExitCode> {
+    // the leftover of an interrupted restore
+    if to.exists() {
+        fs::remove_dir_all(to)?;
+    }
+    copy_dir_all(from, to)
+}
+
+fn copy_dir_all(from: &Path, to: &PathBuf) -> Result<(), ExitCode> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &path)?;
+        } else {
+            fs::copy(entry.path(), path)?;
+        }
+    }
+    Ok(())
+}
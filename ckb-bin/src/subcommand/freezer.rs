@@ -0,0 +1,45 @@
+use ckb_app_config::{ExitCode, FreezerArgs};
+use ckb_store::Freezer;
+
+pub fn freezer(args: FreezerArgs) -> Result<(), ExitCode> {
+    let path = args.config.ancient;
+    if !path.exists() {
+        eprintln!("Freezer path {} does not exist", path.display());
+        return Err(ExitCode::Config);
+    }
+
+    if args.repair {
+        let number = Freezer::repair(path.clone(), args.force).map_err(|e| {
+            eprintln!("Freezer repair error {e}");
+            ExitCode::Failure
+        })?;
+        println!("Freezer index rebuilt, {} blocks frozen", number);
+    }
+    if !args.verify {
+        return Ok(());
+    }
+
+    let freezer = Freezer::open(path).map_err(|e| {
+        eprintln!("Freezer open error {e}");
+        ExitCode::Failure
+    })?;
+    let corrupted = freezer.verify().map_err(|e| {
+        eprintln!("Freezer verify error {e}");
+        ExitCode::Failure
+    })?;
+    if corrupted.is_empty() {
+        println!(
+            "Freezer verified blocks {}-{}, no corruption found",
+            freezer.tail(),
+            freezer.number().saturating_sub(1)
+        );
+        Ok(())
+    } else {
+        eprintln!(
+            "Freezer found {} corrupted blocks: {:?}",
+            corrupted.len(),
+            corrupted
+        );
+        Err(ExitCode::Failure)
+    }
+}
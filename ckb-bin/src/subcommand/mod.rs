@@ -1,26 +1,32 @@
+mod backup;
 #[cfg(not(target_os = "windows"))]
 mod daemon;
 mod export;
+mod freezer;
 mod import;
 mod init;
 mod list_hashes;
 mod migrate;
 mod miner;
 mod peer_id;
+mod rebuild_tx_index;
 mod replay;
 mod reset_data;
 mod run;
 mod stats;
 
+pub use self::backup::backup;
 #[cfg(not(target_os = "windows"))]
 pub use self::daemon::{check_process, daemon};
 pub use self::export::export;
+pub use self::freezer::freezer;
 pub use self::import::import;
 pub use self::init::init;
 pub use self::list_hashes::list_hashes;
 pub use self::migrate::migrate;
 pub use self::miner::miner;
 pub use self::peer_id::peer_id;
+pub use self::rebuild_tx_index::rebuild_tx_index;
 pub use self::replay::replay;
 pub use self::reset_data::reset_data;
 pub use self::run::run;
@@ -0,0 +1,70 @@
+use ckb_app_config::{ExitCode, RebuildTxIndexArgs};
+use ckb_async_runtime::Handle;
+use ckb_instrument::{ProgressBar, ProgressStyle};
+use ckb_shared::{shared::TxIndexStatus, SharedBuilder};
+use ckb_store::ChainStore;
+
+const BATCH_SIZE: u64 = 1_000;
+
+pub fn rebuild_tx_index(args: RebuildTxIndexArgs, async_handle: Handle) -> Result<(), ExitCode> {
+    if args.config.store.tx_index_prune_depth.is_some() {
+        eprintln!(
+            "The option `store.tx_index_prune_depth` is set, \
+             remove it before rebuilding the transaction index"
+        );
+        return Err(ExitCode::Config);
+    }
+
+    let builder = SharedBuilder::new(
+        &args.config.bin_name,
+        args.config.root_dir.as_path(),
+        &args.config.db,
+        Some(args.config.ancient.clone()),
+        async_handle,
+        args.consensus,
+    )?
+    .store_config(args.config.store);
+    let (shared, _) = builder.build()?;
+
+    let pruned = match shared.store().get_tx_index_pruned_number() {
+        Some(pruned) => pruned,
+        None => {
+            println!("The transaction index is complete");
+            return Ok(());
+        }
+    };
+
+    let pb = ProgressBar::new(pruned + 1);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template(
+                "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})",
+            )
+            .progress_chars("#>-"),
+    );
+    shared.start_rebuild_tx_index().map_err(|err| {
+        eprintln!("Rebuild error: {err}");
+        ExitCode::Failure
+    })?;
+    loop {
+        let status = shared.rebuild_tx_index(BATCH_SIZE).map_err(|err| {
+            eprintln!("Rebuild error: {err}");
+            ExitCode::Failure
+        })?;
+        match status {
+            TxIndexStatus::Rebuilding(number) => pb.set_position(pruned - number),
+            TxIndexStatus::Incomplete(number) => {
+                pb.finish_with_message("incomplete");
+                println!(
+                    "The transaction index is rebuilt except the blocks up to {number}, \
+                     the block bodies are pruned"
+                );
+                return Ok(());
+            }
+            TxIndexStatus::Complete | TxIndexStatus::Pruned(_) => break,
+        }
+    }
+    pb.finish_with_message("done!");
+    println!("The transaction index is rebuilt");
+    Ok(())
+}
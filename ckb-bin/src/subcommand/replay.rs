@@ -2,11 +2,29 @@ use ckb_app_config::{ExitCode, ReplayArgs};
 use ckb_async_runtime::Handle;
 use ckb_chain::chain::ChainService;
 use ckb_chain_iter::ChainIterator;
+use ckb_error::{Error, InternalErrorKind};
 use ckb_instrument::{ProgressBar, ProgressStyle};
 use ckb_shared::{Shared, SharedBuilder};
-use ckb_store::ChainStore;
-use ckb_verification_traits::Switch;
+use ckb_store::{data_loader_wrapper::AsDataLoader, ChainStore};
+use ckb_types::{
+    core::{
+        cell::{resolve_transaction, BlockCellProvider, OverlayCellProvider},
+        BlockView, Cycle,
+    },
+    packed::Byte32,
+    prelude::*,
+    H256,
+};
+use ckb_util::Mutex;
+use ckb_verification::{
+    BlockVerifier, HeaderVerifier, NonContextualBlockTxsVerifier, ScriptVerifier, TxVerifyEnv,
+};
+use ckb_verification_contextual::VerifyStage;
+use ckb_verification_traits::{Switch, Verifier};
+use serde::Serialize;
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 const MIN_PROFILING_TIME: u64 = 5;
 
@@ -46,13 +64,18 @@ pub fn replay(args: ReplayArgs, async_handle: Handle) -> Result<(), ExitCode> {
             async_handle,
             args.consensus,
         )?;
-        let (tmp_shared, mut pack) = shared_builder.tx_pool_config(args.config.tx_pool).build()?;
-        let chain = ChainService::new(tmp_shared, pack.take_proposal_table());
+        // verify the scripts from scratch instead of hitting the persisted cache of the node
+        let mut tmp_tx_pool_config = args.config.tx_pool;
+        tmp_tx_pool_config.persisted_verify_cache_size = 0;
+        let (tmp_shared, mut pack) = shared_builder.tx_pool_config(tmp_tx_pool_config).build()?;
+        let chain = ChainService::new(tmp_shared.clone(), pack.take_proposal_table());
 
         if let Some((from, to)) = args.profile {
             profile(shared, chain, from, to);
         } else if args.sanity_check {
             sanity_check(shared, chain, args.full_verification);
+        } else if let Some(block_hash) = args.block {
+            replay_block(shared, tmp_shared, chain, block_hash)?;
         }
     }
     tmp_db_dir.close().map_err(|err| {
@@ -105,8 +128,9 @@ fn process_range_block(
     for index in range {
         let block = snapshot
             .get_block_hash(index)
-            .and_then(|hash| snapshot.get_block(&hash))
-            .expect("read block from store");
+            .and_then(|hash| snapshot.get_block(&hash).transpose())
+            .expect("read block from store")
+            .expect("the pruned blocks can not be replayed");
         tx_count += block.transactions().len().saturating_sub(1);
         chain.process_block(Arc::new(block), Switch::NONE).unwrap();
     }
@@ -131,6 +155,13 @@ fn sanity_check(shared: Shared, mut chain: ChainService, full_verification: bool
     };
     let mut cursor = shared.consensus().genesis_block().header();
     for block in chain_iter {
+        let block = match block {
+            Ok(block) => block,
+            Err(err) => {
+                eprintln!("Replay sanity-check error: {err}");
+                break;
+            }
+        };
         let header = block.header();
         if let Err(e) = chain.process_block(Arc::new(block), switch) {
             eprintln!(
@@ -166,3 +197,415 @@ fn sanity_check(shared: Shared, mut chain: ChainService, full_verification: bool
 
     println!("Finishing replay; please wait...");
 }
+
+#[derive(Serialize)]
+struct BlockReport {
+    hash: H256,
+    number: u64,
+    // the blocks replayed to reconstruct the parent state
+    replayed_blocks: usize,
+    stages: Vec<StageReport>,
+    transactions: Vec<TransactionReport>,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct StageReport {
+    name: &'static str,
+    duration_us: u128,
+}
+
+#[derive(Serialize)]
+struct TransactionReport {
+    index: usize,
+    hash: H256,
+    cycles: Option<Cycle>,
+    duration_us: u128,
+    error: Option<String>,
+}
+
+fn replay_block(
+    shared: Shared,
+    tmp_shared: Shared,
+    chain: ChainService,
+    hash: H256,
+) -> Result<(), ExitCode> {
+    let report = block_report(shared, tmp_shared, chain, hash)?;
+    let json = serde_json::to_string_pretty(&report).map_err(|err| {
+        eprintln!("Replay error: {err}");
+        ExitCode::Failure
+    })?;
+    println!("{json}");
+    Ok(())
+}
+
+fn block_report(
+    shared: Shared,
+    tmp_shared: Shared,
+    mut chain: ChainService,
+    hash: H256,
+) -> Result<BlockReport, ExitCode> {
+    let snapshot = shared.snapshot();
+    let block = snapshot
+        .get_block(&hash.pack())
+        .map_err(|err| {
+            eprintln!("Replay error: {err}");
+            ExitCode::Failure
+        })?
+        .ok_or_else(|| {
+            eprintln!("Replay error: block {hash:#x} is not found");
+            ExitCode::Failure
+        })?;
+    if block.is_genesis() {
+        eprintln!("Replay error: the genesis block can not be replayed");
+        return Err(ExitCode::Failure);
+    }
+
+    // the ancestors out of the main chain, from the parent to the fork point
+    let mut fork_blocks = Vec::new();
+    let mut parent_hash = block.parent_hash();
+    while !snapshot.is_main_chain(&parent_hash) {
+        let parent = snapshot
+            .get_block(&parent_hash)
+            .map_err(|err| {
+                eprintln!("Replay error: {err}");
+                ExitCode::Failure
+            })?
+            .ok_or_else(|| {
+                eprintln!("Replay error: ancestor {parent_hash} is not found");
+                ExitCode::Failure
+            })?;
+        parent_hash = parent.parent_hash();
+        fork_blocks.push(parent);
+    }
+    let fork_point = snapshot.get_block_number(&parent_hash).ok_or_else(|| {
+        eprintln!("Replay error: ancestor {parent_hash} is not found");
+        ExitCode::Failure
+    })?;
+    let main_blocks = (1..=fork_point).map(|number| {
+        snapshot
+            .get_block_hash(number)
+            .and_then(|hash| snapshot.get_block(&hash).transpose())
+            .expect("read block from store")
+            .map_err(|err| {
+                eprintln!("Replay error: {err}");
+                ExitCode::Failure
+            })
+    });
+    let mut replayed_blocks = 0;
+    for ancestor in main_blocks.chain(fork_blocks.into_iter().rev().map(Ok)) {
+        let ancestor = ancestor?;
+        chain
+            .process_block(Arc::new(ancestor), Switch::DISABLE_ALL)
+            .map_err(|err| {
+                eprintln!("Replay error: {err}");
+                ExitCode::Failure
+            })?;
+        replayed_blocks += 1;
+    }
+
+    let mut report = BlockReport {
+        hash,
+        number: block.number(),
+        replayed_blocks,
+        stages: Vec::new(),
+        transactions: Vec::new(),
+        error: None,
+    };
+    if let Err(err) = verify_block(&tmp_shared, &mut chain, block, &mut report) {
+        report.error = Some(err.to_string());
+    }
+    Ok(report)
+}
+
+fn timed<T>(stages: &mut Vec<StageReport>, name: &'static str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let ret = f();
+    stages.push(StageReport {
+        name,
+        duration_us: start.elapsed().as_micros(),
+    });
+    ret
+}
+
+fn verify_block(
+    shared: &Shared,
+    chain: &mut ChainService,
+    block: BlockView,
+    report: &mut BlockReport,
+) -> Result<(), Error> {
+    let snapshot = shared.snapshot();
+    let consensus = shared.cloned_consensus();
+    if snapshot.tip_hash() != block.parent_hash() {
+        return Err(InternalErrorKind::System
+            .other("failed to reconstruct the parent state")
+            .into());
+    }
+
+    let header = block.header();
+    timed(&mut report.stages, "header", || {
+        HeaderVerifier::new(snapshot.as_ref(), &consensus).verify(&header)
+    })?;
+    timed(&mut report.stages, "non_contextual", || {
+        BlockVerifier::new(&consensus).verify(&block)?;
+        NonContextualBlockTxsVerifier::new(&consensus)
+            .verify(&block)
+            .map(|_| ())
+    })?;
+
+    // run the scripts of each transaction serially, the block verification below runs them in
+    // parallel and reports the total time only
+    let block_cell_provider = BlockCellProvider::new(&block)?;
+    let cell_provider = OverlayCellProvider::new(&block_cell_provider, snapshot.as_ref());
+    let tx_env = Arc::new(TxVerifyEnv::new_commit(&header));
+    let mut seen_inputs = HashSet::new();
+    for (index, tx) in block.transactions().into_iter().enumerate() {
+        let tx_hash: Byte32 = tx.hash();
+        let resolved =
+            resolve_transaction(tx, &mut seen_inputs, &cell_provider, snapshot.as_ref())?;
+        if index == 0 {
+            continue;
+        }
+        let verifier = ScriptVerifier::new(
+            Arc::new(resolved),
+            snapshot.as_data_loader(),
+            Arc::clone(&consensus),
+            Arc::clone(&tx_env),
+        );
+        let start = Instant::now();
+        let ret = verifier.verify(consensus.max_block_cycles());
+        report.transactions.push(TransactionReport {
+            index,
+            hash: tx_hash.unpack(),
+            cycles: ret.as_ref().ok().copied(),
+            duration_us: start.elapsed().as_micros(),
+            error: ret.err().map(|err| err.to_string()),
+        });
+    }
+
+    let stages: Arc<Mutex<Vec<(VerifyStage, Duration)>>> = Default::default();
+    let observed = Arc::clone(&stages);
+    chain.set_verify_stage_observer(Arc::new(move |stage, duration| {
+        observed.lock().push((stage, duration));
+    }));
+    let ret = chain.process_block(Arc::new(block), Switch::DISABLE_NON_CONTEXTUAL);
+    report
+        .stages
+        .extend(stages.lock().iter().map(|(stage, duration)| StageReport {
+            name: stage.name(),
+            duration_us: duration.as_micros(),
+        }));
+    ret.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_dao::DaoCalculator;
+    use ckb_test_chain_utils::{always_success_cell, always_success_consensus};
+    use ckb_types::{
+        bytes::Bytes,
+        core::{BlockBuilder, Capacity, HeaderView, TransactionBuilder, TransactionView},
+        packed::{CellDep, CellInput, CellOutput, OutPoint, ProposalShortId},
+    };
+
+    fn next_block(
+        shared: &Shared,
+        parent: &HeaderView,
+        proposals: Vec<ProposalShortId>,
+        txs: Vec<TransactionView>,
+    ) -> BlockView {
+        let snapshot = shared.snapshot();
+        let consensus = shared.consensus();
+        let number = parent.number() + 1;
+        let epoch = consensus
+            .next_epoch_ext(parent, &snapshot.borrow_as_data_loader())
+            .unwrap()
+            .epoch();
+        // the cellbases within the finalization delay have no outputs
+        let cellbase =
+            ckb_test_chain_utils::always_success_cellbase(number, Capacity::zero(), consensus);
+        let mut seen_inputs = HashSet::new();
+        let resolved_txs: Vec<_> = std::iter::once(cellbase.clone())
+            .chain(txs.iter().cloned())
+            .map(|tx| {
+                resolve_transaction(tx, &mut seen_inputs, snapshot.as_ref(), snapshot.as_ref())
+                    .unwrap()
+            })
+            .collect();
+        let data_loader = snapshot.borrow_as_data_loader();
+        let dao = DaoCalculator::new(consensus, &data_loader)
+            .dao_field(resolved_txs.iter(), parent)
+            .unwrap();
+        let chain_root = snapshot.chain_root_mmr(parent.number()).get_root().unwrap();
+        BlockBuilder::default()
+            .transaction(cellbase)
+            .transactions(txs)
+            .proposals(proposals)
+            .parent_hash(parent.hash())
+            .number(number.pack())
+            .epoch(epoch.number_with_fraction(number).pack())
+            .timestamp((parent.timestamp() + 1).pack())
+            .compact_target(epoch.compact_target().pack())
+            .dao(dao)
+            .extension(Some(chain_root.calc_mmr_hash().as_bytes().pack()))
+            .build()
+    }
+
+    // Stores 3 blocks: block 1 commits `tx1` which splits the genesis cell into the always
+    // success code cell and a spendable cell, and proposes `tx2`, which spends the latter and is
+    // committed in block 3.
+    fn setup_chain() -> (Shared, Vec<BlockView>, TransactionView) {
+        let (shared, mut pack) = SharedBuilder::with_temp_db()
+            .consensus(always_success_consensus())
+            .build()
+            .unwrap();
+        let mut chain = ChainService::new(shared.clone(), pack.take_proposal_table());
+
+        let (always_success_cell, always_success_data, always_success_script) =
+            always_success_cell();
+        let genesis = shared.consensus().genesis_block().clone();
+        let genesis_tx = &genesis.transactions()[0];
+        let capacity: Capacity = genesis_tx.outputs().get(0).unwrap().capacity().unpack();
+        let spendable = Capacity::shannons(capacity.as_u64() / 2);
+        let tx1 = TransactionBuilder::default()
+            .input(CellInput::new(OutPoint::new(genesis_tx.hash(), 0), 0))
+            .output(
+                always_success_cell
+                    .clone()
+                    .as_builder()
+                    .capacity(capacity.safe_sub(spendable).unwrap().pack())
+                    .build(),
+            )
+            .output_data(always_success_data.pack())
+            .output(
+                CellOutput::new_builder()
+                    .capacity(spendable.pack())
+                    .lock(always_success_script.clone())
+                    .build(),
+            )
+            .output_data(Bytes::new().pack())
+            .build();
+        let tx2 = TransactionBuilder::default()
+            .cell_dep(
+                CellDep::new_builder()
+                    .out_point(OutPoint::new(tx1.hash(), 0))
+                    .build(),
+            )
+            .input(CellInput::new(OutPoint::new(tx1.hash(), 1), 0))
+            .output(
+                CellOutput::new_builder()
+                    .capacity(Capacity::bytes(100).unwrap().pack())
+                    .lock(always_success_script.clone())
+                    .build(),
+            )
+            .output_data(Bytes::new().pack())
+            .build();
+
+        let mut blocks = vec![genesis];
+        for number in 1..=3 {
+            let parent = blocks.last().unwrap().header();
+            let block = match number {
+                1 => next_block(
+                    &shared,
+                    &parent,
+                    vec![tx2.proposal_short_id()],
+                    vec![tx1.clone()],
+                ),
+                3 => next_block(&shared, &parent, vec![], vec![tx2.clone()]),
+                _ => next_block(&shared, &parent, vec![], vec![]),
+            };
+            chain
+                .process_block(Arc::new(block.clone()), Switch::DISABLE_ALL)
+                .expect("process block");
+            blocks.push(block);
+        }
+        (shared, blocks, tx2)
+    }
+
+    fn tmp_chain() -> (Shared, ChainService) {
+        let (tmp_shared, mut pack) = SharedBuilder::with_temp_db()
+            .consensus(always_success_consensus())
+            .build()
+            .unwrap();
+        let chain = ChainService::new(tmp_shared.clone(), pack.take_proposal_table());
+        (tmp_shared, chain)
+    }
+
+    fn stage_names(report: &serde_json::Value) -> Vec<&str> {
+        report["stages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|stage| stage["name"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_block_report() {
+        let (shared, blocks, tx2) = setup_chain();
+        let (tmp_shared, chain) = tmp_chain();
+        let hash: H256 = blocks[3].hash().unpack();
+        let report = block_report(shared, tmp_shared, chain, hash.clone()).unwrap();
+        let report = serde_json::to_value(report).unwrap();
+
+        assert_eq!(report["hash"], serde_json::to_value(&hash).unwrap());
+        assert_eq!(report["number"], 3);
+        assert_eq!(report["replayed_blocks"], 2);
+        assert_eq!(report["error"], serde_json::Value::Null);
+        assert_eq!(
+            stage_names(&report),
+            vec![
+                "header",
+                "non_contextual",
+                "epoch",
+                "uncles",
+                "two_phase_commit",
+                "dao_header",
+                "reward",
+                "extension",
+                "transactions",
+                "scripts",
+            ]
+        );
+
+        let transactions = report["transactions"].as_array().unwrap();
+        assert_eq!(transactions.len(), 1);
+        let tx_hash: H256 = tx2.hash().unpack();
+        assert_eq!(transactions[0]["index"], 1);
+        assert_eq!(
+            transactions[0]["hash"],
+            serde_json::to_value(tx_hash).unwrap()
+        );
+        assert!(transactions[0]["cycles"].as_u64().is_some());
+        assert_eq!(transactions[0]["error"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_block_report_rejected() {
+        let (shared, blocks, _) = setup_chain();
+        let (tmp_shared, chain) = tmp_chain();
+        // a sibling of block 3 older than the median time of its ancestors
+        let block = blocks[3]
+            .as_advanced_builder()
+            .timestamp(blocks[0].timestamp().pack())
+            .build();
+        {
+            let txn = shared.store().begin_transaction();
+            txn.insert_block(&block).unwrap();
+            txn.commit().unwrap();
+        }
+        shared.refresh_snapshot();
+        let report = block_report(shared, tmp_shared, chain, block.hash().unpack()).unwrap();
+        let report = serde_json::to_value(report).unwrap();
+
+        assert_eq!(report["number"], 3);
+        assert_eq!(report["replayed_blocks"], 2);
+        assert_eq!(stage_names(&report), vec!["header"]);
+        assert!(report["transactions"].as_array().unwrap().is_empty());
+        assert!(report["error"]
+            .as_str()
+            .unwrap()
+            .contains("BlockTimeTooOld"));
+    }
+}
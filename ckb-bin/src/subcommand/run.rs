@@ -28,6 +28,7 @@ pub fn run(args: RunArgs, version: Version, async_handle: Handle) -> Result<(),
 
     // spawn freezer background process
     let _freezer = shared.spawn_freeze();
+    shared.spawn_tx_index();
 
     setup_system_cell_cache(
         shared.consensus().genesis_block(),
@@ -42,7 +43,7 @@ pub fn run(args: RunArgs, version: Version, async_handle: Handle) -> Result<(),
 
     ckb_memory_tracker::track_current_process(
         launcher.args.config.memory_tracker.interval,
-        Some(shared.store().db().inner()),
+        shared.store().db().map(|db| db.inner()),
     );
 
     let chain_controller = launcher.start_chain_service(&shared, pack.take_proposal_table());
@@ -56,7 +57,8 @@ pub fn run(args: RunArgs, version: Version, async_handle: Handle) -> Result<(),
         pack.take_relay_tx_receiver(),
     );
 
-    let tx_pool_builder = pack.take_tx_pool_builder();
+    let mut tx_pool_builder = pack.take_tx_pool_builder();
+    launcher.set_block_template_policy(&mut tx_pool_builder);
     tx_pool_builder.start(network_controller);
 
     info!("CKB service started ...");
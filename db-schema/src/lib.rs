@@ -3,7 +3,7 @@
 /// Column families alias type
 pub type Col = &'static str;
 /// Total column number
-pub const COLUMNS: u32 = 19;
+pub const COLUMNS: u32 = 21;
 /// Column store chain index
 pub const COLUMN_INDEX: Col = "0";
 /// Column store block's header
@@ -44,6 +44,10 @@ pub const COLUMN_CHAIN_ROOT_MMR: Col = "16";
 pub const COLUMN_BLOCK_FILTER: Col = "17";
 /// Column store filter data hash for client-side filtering
 pub const COLUMN_BLOCK_FILTER_HASH: Col = "18";
+/// Column store the number of spent outputs of the transactions whose index may be pruned
+pub const COLUMN_TRANSACTION_SPENT: Col = "19";
+/// Column store the blocks rejected by the chain and the reasons
+pub const COLUMN_INVALID_BLOCK: Col = "20";
 
 /// META_TIP_HEADER_KEY tracks the latest known best block header
 pub const META_TIP_HEADER_KEY: &[u8] = b"TIP_HEADER";
@@ -51,6 +55,9 @@ pub const META_TIP_HEADER_KEY: &[u8] = b"TIP_HEADER";
 pub const META_CURRENT_EPOCH_KEY: &[u8] = b"CURRENT_EPOCH";
 /// META_FILTER_DATA_KEY tracks the latest built filter data block hash
 pub const META_LATEST_BUILT_FILTER_DATA_KEY: &[u8] = b"LATEST_BUILT_FILTER_DATA";
+/// META_TX_INDEX_PRUNED_KEY tracks the latest block whose spent transactions are pruned from the
+/// transaction index, the index is complete if it does not exist
+pub const META_TX_INDEX_PRUNED_KEY: &[u8] = b"TX_INDEX_PRUNED";
 
 /// CHAIN_SPEC_HASH_KEY tracks the hash of chain spec which created current database
 pub const CHAIN_SPEC_HASH_KEY: &[u8] = b"chain-spec-hash";
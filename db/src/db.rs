@@ -7,8 +7,8 @@ use ckb_app_config::DBConfig;
 use ckb_db_schema::Col;
 use ckb_logger::info;
 use rocksdb::ops::{
-    CompactRangeCF, CreateCF, DropCF, GetColumnFamilys, GetPinned, GetPinnedCF, IterateCF, OpenCF,
-    Put, SetOptions, WriteOps,
+    CompactRangeCF, CreateCF, CreateCheckpointObject, DropCF, GetColumnFamilys, GetPinned,
+    GetPinnedCF, IterateCF, OpenCF, Put, SetOptions, WriteOps,
 };
 use rocksdb::{
     ffi, BlockBasedIndexType, BlockBasedOptions, Cache, ColumnFamily, ColumnFamilyDescriptor,
@@ -310,6 +310,18 @@ impl RocksDB {
         }
     }
 
+    /// Creates an openable snapshot of the database at the specified path while it's running.
+    ///
+    /// The path must not exist, the sst files are hard-linked if the path is on the
+    /// same filesystem as the database, otherwise they are copied.
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let checkpoint = self
+            .inner
+            .create_checkpoint_object()
+            .map_err(internal_error)?;
+        checkpoint.create_checkpoint(path).map_err(internal_error)
+    }
+
     /// Return rocksdb `OptimisticTransactionDB`.
     pub fn inner(&self) -> Arc<OptimisticTransactionDB> {
         Arc::clone(&self.inner)
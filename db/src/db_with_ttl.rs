@@ -1,9 +1,10 @@
 //! DB with ttl support wrapper
 
 use crate::{internal_error, Result};
-use rocksdb::ops::{DropCF, GetColumnFamilys, GetPinnedCF, GetPropertyCF, OpenCF, PutCF};
+use rocksdb::ops::{DropCF, GetColumnFamilys, GetPinnedCF, GetPropertyCF, OpenCF, PutCF, WriteOps};
 use rocksdb::{
     ColumnFamilyDescriptor, DBPinnableSlice, DBWithTTL as RawDBWithTTL, Options, TTLOpenDescriptor,
+    WriteBatch,
 };
 use std::path::Path;
 
@@ -77,6 +78,24 @@ impl DBWithTTL {
         self.inner.put_cf(cf, key, value).map_err(internal_error)
     }
 
+    /// Insert the values into the database atomically, each under the given column and key.
+    pub fn put_batch<'a, I, K, V>(&self, items: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (&'a str, K, V)>,
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let mut batch = WriteBatch::default();
+        for (col, key, value) in items {
+            let cf = self
+                .inner
+                .cf_handle(col)
+                .ok_or_else(|| internal_error(format!("column {col} not found")))?;
+            batch.put_cf(cf, key, value).map_err(internal_error)?;
+        }
+        self.inner.write(&batch).map_err(internal_error)
+    }
+
     /// Create a new column family for the database.
     pub fn create_cf_with_ttl(&mut self, col: &str, ttl: i32) -> Result<()> {
         let opts = Options::default();
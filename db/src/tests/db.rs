@@ -137,3 +137,28 @@ fn write_and_partial_read() {
 
     assert!(vec![4u8, 3, 2].as_slice() == &ret.as_ref()[1..4]);
 }
+
+#[test]
+fn checkpoint() {
+    let tmp_dir = tempfile::Builder::new()
+        .prefix("checkpoint")
+        .tempdir()
+        .unwrap();
+    let db = RocksDB::open_in(tmp_dir.path().join("db"), 2);
+    let txn = db.transaction();
+    txn.put("0", &[0, 0], &[5, 4, 3, 2]).unwrap();
+    txn.commit().unwrap();
+
+    let path = tmp_dir.path().join("backup");
+    db.create_checkpoint(&path).unwrap();
+    assert!(db.create_checkpoint(&path).is_err());
+
+    // writes after the checkpoint are not included
+    db.put_default([1, 1], [1, 2, 3]).unwrap();
+    let backup = RocksDB::open_in(&path, 2);
+    assert_eq!(
+        backup.get_pinned("0", &[0, 0]).unwrap().unwrap().as_ref(),
+        &[5, 4, 3, 2]
+    );
+    assert!(backup.get_pinned_default(&[1, 1]).unwrap().is_none());
+}
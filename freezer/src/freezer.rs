@@ -1,4 +1,4 @@
-use crate::freezer_files::FreezerFiles;
+use crate::freezer_files::{link_files, Compression, FreezerFiles, FreezerFilesBuilder};
 use crate::internal_error;
 use ckb_error::Error;
 use ckb_types::{
@@ -15,6 +15,8 @@ use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 
 const LOCKNAME: &str = "FLOCK";
+// number of the latest frozen blocks used to train the zstd dictionary
+const DICT_TRAIN_SAMPLES: u64 = 1000;
 
 /// freeze result represent blkhash -> (blknum, txsnum) btree-map
 /// sorted blkhash for making ranges for compaction
@@ -30,6 +32,8 @@ struct Inner {
 pub struct Freezer {
     inner: Arc<Mutex<Inner>>,
     number: Arc<AtomicU64>,
+    tail: Arc<AtomicU64>,
+    prune_depth: Option<BlockNumber>,
     /// stop flag
     pub stopped: Arc<AtomicBool>,
     /// file lock to prevent double opens
@@ -39,13 +43,7 @@ pub struct Freezer {
 impl Freezer {
     /// Creates a freezer at specified path
     pub fn open(path: PathBuf) -> Result<Freezer, Error> {
-        let lock_path = path.join(LOCKNAME);
-        let lock = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(lock_path)
-            .map_err(internal_error)?;
-        lock.try_lock_exclusive().map_err(internal_error)?;
+        let lock = Self::lock(&path)?;
         let mut files = FreezerFiles::open(path).map_err(internal_error)?;
         let freezer_number = files.number();
 
@@ -67,6 +65,8 @@ impl Freezer {
         let inner = Inner { files, tip };
         Ok(Freezer {
             number: Arc::clone(&inner.files.number),
+            tail: Arc::clone(&inner.files.tail),
+            prune_depth: None,
             inner: Arc::new(Mutex::new(inner)),
             stopped: Arc::new(AtomicBool::new(false)),
             _lock: Arc::new(lock),
@@ -78,6 +78,51 @@ impl Freezer {
         Self::open(path.as_ref().to_path_buf())
     }
 
+    fn lock(path: &Path) -> Result<File, Error> {
+        let lock = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .open(path.join(LOCKNAME))
+            .map_err(internal_error)?;
+        lock.try_lock_exclusive().map_err(internal_error)?;
+        Ok(lock)
+    }
+
+    /// Rebuilds the index file of the freezer at specified path from its data files.
+    ///
+    /// The data files are left untouched if any item can't be recovered, unless `force` is set,
+    /// then the blocks from the first unrecoverable one are dropped.
+    /// The freezer must not be opened by others. Returns the total item number after repair.
+    pub fn repair(path: PathBuf, force: bool) -> Result<BlockNumber, Error> {
+        let _lock = Self::lock(&path)?;
+        let builder = FreezerFilesBuilder::new(path);
+        let recovered = builder.rebuild_index(force).map_err(internal_error)?;
+        let files = builder.build().map_err(internal_error)?;
+        ckb_logger::info!(
+            "Freezer repaired, recovered {} framed items, total {}",
+            recovered,
+            files.number()
+        );
+        Ok(files.number())
+    }
+
+    /// Sets the compression algorithm used for the newly frozen blocks.
+    pub fn with_compression(self, compression: Compression) -> Self {
+        self.inner.lock().files.compression = compression;
+        self
+    }
+
+    /// Enables pruned mode, keeping the bodies of the latest `depth` blocks only.
+    pub fn with_prune_depth(mut self, depth: Option<BlockNumber>) -> Self {
+        self.prune_depth = depth;
+        self
+    }
+
+    /// Return the prune depth, `None` means pruned mode is disabled
+    pub fn prune_depth(&self) -> Option<BlockNumber> {
+        self.prune_depth
+    }
+
     /// Freeze background process that periodically checks the chain data for any
     /// import progress and moves ancient data from the kv-db into the freezer.
     pub fn freeze<F>(
@@ -131,6 +176,13 @@ impl Freezer {
             }
         }
         guard.files.sync_all().map_err(internal_error)?;
+
+        if guard.files.need_dictionary() {
+            // failed training is not fatal, blocks are compressed without dictionary
+            if let Err(e) = guard.files.train_dictionary(DICT_TRAIN_SAMPLES) {
+                ckb_logger::warn!("Freezer train dictionary error {}", e);
+            }
+        }
         Ok(ret)
     }
 
@@ -148,6 +200,86 @@ impl Freezer {
         self.number.load(Ordering::SeqCst)
     }
 
+    /// Return the number of the earliest block still stored in the freezer
+    pub fn tail(&self) -> BlockNumber {
+        self.tail.load(Ordering::SeqCst)
+    }
+
+    /// Return whether the frozen block with the given number has been pruned
+    pub fn is_pruned(&self, number: BlockNumber) -> bool {
+        number > 0 && number < self.tail()
+    }
+
+    /// Prune deletes the frozen blocks below the provided threshold number.
+    ///
+    /// Blocks are deleted by whole data files, so some blocks below the threshold may be kept.
+    /// Returns the number of the earliest block still stored.
+    pub fn prune(&self, threshold: BlockNumber) -> Result<BlockNumber, Error> {
+        let tail = self
+            .inner
+            .lock()
+            .files
+            .prune(threshold)
+            .map_err(internal_error)?;
+        ckb_logger::trace!("Freezer pruned below {}", tail);
+        Ok(tail)
+    }
+
+    /// Checks the checksum and the encoding of every stored block, as well as the
+    /// parent hash links between the adjacent blocks.
+    ///
+    /// Returns the numbers of the corrupted blocks.
+    pub fn verify(&self) -> Result<Vec<BlockNumber>, Error> {
+        let mut parent: Option<(BlockNumber, packed::Byte32)> = None;
+        let mut guard = self.inner.lock();
+        guard
+            .files
+            .verify(|number, raw_block| {
+                let header = match packed::BlockReader::from_compatible_slice(raw_block) {
+                    Ok(block) => block.to_entity().header().into_view(),
+                    Err(e) => {
+                        ckb_logger::error!("Freezer verify block {} {}", number, e);
+                        return false;
+                    }
+                };
+                let linked = match parent {
+                    Some((parent_number, ref parent_hash)) if parent_number + 1 == number => {
+                        &header.parent_hash() == parent_hash
+                    }
+                    _ => true,
+                };
+                if !linked {
+                    ckb_logger::error!("Freezer verify block {} parent hash mismatch", number);
+                }
+                parent = Some((number, header.hash()));
+                linked && header.number() == number
+            })
+            .map_err(internal_error)
+    }
+
+    /// Copies the frozen blocks into the provided directory, which can be opened as a freezer.
+    ///
+    /// The data files before the head are hard linked without pausing freezing, then freezing is
+    /// paused while the head file and the index are copied, and until `f` returns, so a kv-db
+    /// checkpoint created in `f` is consistent with the copy.
+    pub fn snapshot<F, T>(&self, path: &Path, f: F) -> Result<T, Error>
+    where
+        F: FnOnce() -> Result<T, Error>,
+    {
+        let (file_path, linked) = {
+            let guard = self.inner.lock();
+            (
+                guard.files.file_path.clone(),
+                guard.files.immutable_file_ids(),
+            )
+        };
+        link_files(&file_path, path, linked.clone()).map_err(internal_error)?;
+
+        let guard = self.inner.lock();
+        guard.files.snapshot(path, linked).map_err(internal_error)?;
+        f()
+    }
+
     /// Truncate discards any recent data above the provided threshold number.
     pub fn truncate(&self, item: u64) -> Result<(), Error> {
         if item > 0 && ((item + 1) < self.number()) {
@@ -5,6 +5,7 @@ use std::fs::{self, File};
 use std::io::{Error as IoError, ErrorKind as IoErrorKind};
 use std::io::{Read, Write};
 use std::io::{Seek, SeekFrom};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
@@ -12,7 +13,17 @@ use std::sync::Arc;
 const MAX_FILE_SIZE: u64 = 2 * 1_000 * 1_000 * 1_000; // 2G
 const OPEN_FILES_LIMIT: usize = 256;
 const INDEX_FILE_NAME: &str = "INDEX";
+const META_FILE_NAME: &str = "META";
+const DICT_FILE_NAME: &str = "DICT";
+const DICT_MAX_SIZE: usize = 112 * 1024;
+const ZSTD_LEVEL: i32 = 3;
 pub(crate) const INDEX_ENTRY_SIZE: u64 = 12;
+pub(crate) const ITEM_HEADER_SIZE: u64 = 17;
+
+const CODEC_RAW: u8 = 0;
+const CODEC_SNAPPY: u8 = 1;
+const CODEC_ZSTD: u8 = 2;
+const CODEC_ZSTD_DICT: u8 = 3;
 
 /// File id alias
 pub type FileId = u32;
@@ -36,6 +47,17 @@ impl Head {
     }
 }
 
+/// Compression algorithm used for the items appended into freezer files
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// Store items as is
+    None,
+    /// Snappy compression per item
+    Snappy,
+    /// Zstd compression per item, with a shared dictionary once it's trained
+    Zstd,
+}
+
 /// FreezerFiles represents a single chained block data,
 /// it consists of a data file and an index file
 pub struct FreezerFiles {
@@ -49,14 +71,22 @@ pub struct FreezerFiles {
     max_size: u64,
     // number of the earliest file
     pub(crate) tail_id: FileId,
+    // number of the earliest item still stored in the data files
+    pub(crate) tail: Arc<AtomicU64>,
     // number of the currently active head file
     pub(crate) head_id: FileId,
     // data file path
-    file_path: PathBuf,
+    pub(crate) file_path: PathBuf,
     // index for freezer files
     pub(crate) index: File,
-    // enable compression
+    // enable compression for the legacy items without header
     pub(crate) enable_compression: bool,
+    // compression for the appended items
+    pub(crate) compression: Compression,
+    // items from this number are framed with a checksummed header
+    pub(crate) framed_from: u64,
+    // shared zstd dictionary
+    pub(crate) dict: Option<Vec<u8>>,
 }
 
 /// An instance of IndexEntry represents an entry inside of a index files
@@ -95,6 +125,49 @@ impl IndexEntry {
     }
 }
 
+/// An instance of ItemHeader represents the header in front of each framed item inside of data files
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct ItemHeader {
+    pub number: u64,
+    pub size: u32,
+    pub codec: u8,
+    pub checksum: u32,
+}
+
+impl ItemHeader {
+    /// Encodes this header into the provided byte buffer
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(ITEM_HEADER_SIZE as usize);
+        bytes.extend_from_slice(&self.number.to_le_bytes());
+        bytes.extend_from_slice(&self.size.to_le_bytes());
+        bytes.push(self.codec);
+        bytes.extend_from_slice(&self.checksum.to_le_bytes());
+        bytes
+    }
+
+    /// Decode header from the provided bytes
+    pub fn decode(raw: &[u8]) -> Result<Self, IoError> {
+        if raw.len() < ITEM_HEADER_SIZE as usize {
+            return Err(IoError::new(
+                IoErrorKind::InvalidData,
+                format!("item header too short {}", raw.len()),
+            ));
+        }
+        let mut number = [0u8; 8];
+        number.copy_from_slice(&raw[0..8]);
+        let mut size = [0u8; 4];
+        size.copy_from_slice(&raw[8..12]);
+        let mut checksum = [0u8; 4];
+        checksum.copy_from_slice(&raw[13..17]);
+        Ok(ItemHeader {
+            number: u64::from_le_bytes(number),
+            size: u32::from_le_bytes(size),
+            codec: raw[12],
+            checksum: u32::from_le_bytes(checksum),
+        })
+    }
+}
+
 impl FreezerFiles {
     /// Opens freezer files at path.
     pub fn open(file_path: PathBuf) -> Result<FreezerFiles, IoError> {
@@ -120,16 +193,15 @@ impl FreezerFiles {
             ));
         }
 
-        // https://github.com/rust-lang/rust/issues/49171
-        #[allow(unused_mut)]
-        let mut compressed_data;
-        let mut data = input;
-        if self.enable_compression {
-            compressed_data = SnappyEncoder::new()
-                .compress_vec(data)
-                .map_err(|e| IoError::new(IoErrorKind::Other, format!("compress error {e}")))?;
-            data = &compressed_data;
+        let (codec, payload) = self.compress(input)?;
+        let header = ItemHeader {
+            number,
+            size: payload.len() as u32,
+            codec,
+            checksum: crc32fast::hash(&payload),
         };
+        let mut data = header.encode();
+        data.extend_from_slice(&payload);
 
         let data_size = data.len();
         // open a new file
@@ -146,7 +218,7 @@ impl FreezerFiles {
             self.head = Head::new(new_head_file, 0);
         }
 
-        self.head.write(data)?;
+        self.head.write(&data)?;
         self.write_index(self.head_id, self.head.bytes)?;
         self.number.fetch_add(1, Ordering::SeqCst);
 
@@ -176,6 +248,11 @@ impl FreezerFiles {
 
         let bounds = self.get_bounds(item)?;
         if let Some((start_offset, end_offset, file_id)) = bounds {
+            // the data file has been pruned
+            if file_id < self.tail_id {
+                return Ok(None);
+            }
+
             let open_read_only;
 
             let mut file = if let Some(file) = self.files.get(&file_id) {
@@ -190,7 +267,16 @@ impl FreezerFiles {
             file.seek(SeekFrom::Start(start_offset))?;
             file.read_exact(&mut data)?;
 
-            if self.enable_compression {
+            if item >= self.framed_from {
+                data = self.unframe(item, &data).map_err(|e| {
+                    IoError::new(
+                        e.kind(),
+                        format!(
+                            "item-{item} file-id-{file_id} offset-{start_offset} size-{size}: {e}"
+                        ),
+                    )
+                })?;
+            } else if self.enable_compression {
                 data = SnappyDecoder::new().decompress_vec(&data).map_err(|e| {
                     IoError::new(
                         IoErrorKind::Other,
@@ -212,6 +298,168 @@ impl FreezerFiles {
         }
     }
 
+    fn compress(&self, input: &[u8]) -> Result<(u8, Vec<u8>), IoError> {
+        let compress_error = |e| IoError::new(IoErrorKind::Other, format!("compress error {e}"));
+        match self.compression {
+            Compression::None => Ok((CODEC_RAW, input.to_vec())),
+            Compression::Snappy => SnappyEncoder::new()
+                .compress_vec(input)
+                .map(|data| (CODEC_SNAPPY, data))
+                .map_err(|e| compress_error(e.to_string())),
+            Compression::Zstd => {
+                if let Some(ref dict) = self.dict {
+                    zstd::bulk::Compressor::with_dictionary(ZSTD_LEVEL, dict)
+                        .and_then(|mut compressor| compressor.compress(input))
+                        .map(|data| (CODEC_ZSTD_DICT, data))
+                        .map_err(|e| compress_error(e.to_string()))
+                } else {
+                    zstd::bulk::compress(input, ZSTD_LEVEL)
+                        .map(|data| (CODEC_ZSTD, data))
+                        .map_err(|e| compress_error(e.to_string()))
+                }
+            }
+        }
+    }
+
+    // verifies the header and checksum of a framed item, returns the decompressed payload
+    fn unframe(&self, item: u64, data: &[u8]) -> Result<Vec<u8>, IoError> {
+        let invalid = |msg: String| IoError::new(IoErrorKind::InvalidData, msg);
+        let header = ItemHeader::decode(data)?;
+        let payload = &data[ITEM_HEADER_SIZE as usize..];
+        if header.number != item {
+            return Err(invalid(format!("unexpected item number {}", header.number)));
+        }
+        if header.size as usize != payload.len() {
+            return Err(invalid(format!(
+                "unexpected payload size {} have {}",
+                header.size,
+                payload.len()
+            )));
+        }
+        let checksum = crc32fast::hash(payload);
+        if header.checksum != checksum {
+            if let Some(metrics) = ckb_metrics::handle() {
+                metrics.ckb_freezer_checksum_failed.inc();
+            }
+            return Err(invalid(format!(
+                "checksum mismatch expected {:#x} have {:#x}",
+                header.checksum, checksum
+            )));
+        }
+        let decompress_error = |e: String| invalid(format!("decompress error {e}"));
+        match header.codec {
+            CODEC_RAW => Ok(payload.to_vec()),
+            CODEC_SNAPPY => SnappyDecoder::new()
+                .decompress_vec(payload)
+                .map_err(|e| decompress_error(e.to_string())),
+            CODEC_ZSTD => {
+                zstd::stream::decode_all(payload).map_err(|e| decompress_error(e.to_string()))
+            }
+            CODEC_ZSTD_DICT => {
+                let dict = self
+                    .dict
+                    .as_ref()
+                    .ok_or_else(|| invalid("missing zstd dictionary".to_string()))?;
+                let mut decoder = zstd::stream::Decoder::with_dictionary(payload, dict)
+                    .map_err(|e| decompress_error(e.to_string()))?;
+                let mut data = Vec::new();
+                decoder
+                    .read_to_end(&mut data)
+                    .map_err(|e| decompress_error(e.to_string()))?;
+                Ok(data)
+            }
+            codec => Err(invalid(format!("unknown codec {codec}"))),
+        }
+    }
+
+    /// Return whether a zstd dictionary should be trained for the appended items
+    pub fn need_dictionary(&self) -> bool {
+        self.compression == Compression::Zstd && self.dict.is_none()
+    }
+
+    /// Trains the shared zstd dictionary with the latest `samples` items and persists it.
+    ///
+    /// The dictionary is written only once, items compressed before it's available are
+    /// still readable. Returns false if there are not enough items to train.
+    pub fn train_dictionary(&mut self, samples: u64) -> Result<bool, IoError> {
+        if self.dict.is_some() {
+            return Ok(false);
+        }
+        let start = self.tail().max(self.number().saturating_sub(samples));
+        if self.number().saturating_sub(start) < samples {
+            return Ok(false);
+        }
+        let mut items = Vec::with_capacity(samples as usize);
+        for item in start..self.number() {
+            if let Some(data) = self.retrieve(item)? {
+                items.push(data);
+            }
+        }
+        let dict = zstd::dict::from_samples(&items, DICT_MAX_SIZE)?;
+        helper::write_file_sync(&self.file_path.join(DICT_FILE_NAME), &dict)?;
+        ckb_logger::info!("Freezer zstd dictionary trained, size {}", dict.len());
+        self.dict = Some(dict);
+        Ok(true)
+    }
+
+    /// Checks every stored item, returns the numbers of the corrupted items
+    pub fn verify<F>(&mut self, mut check: F) -> Result<Vec<u64>, IoError>
+    where
+        F: FnMut(u64, &[u8]) -> bool,
+    {
+        let mut corrupted = Vec::new();
+        for item in self.tail()..self.number() {
+            match self.retrieve(item) {
+                Ok(Some(data)) if check(item, &data) => {}
+                Ok(Some(_)) | Ok(None) => corrupted.push(item),
+                Err(e) if e.kind() == IoErrorKind::InvalidData => {
+                    ckb_logger::error!("Freezer verify {}", e);
+                    corrupted.push(item);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(corrupted)
+    }
+
+    /// Returns the ids of the data files before the head, which are never written again.
+    pub fn immutable_file_ids(&self) -> Range<FileId> {
+        self.tail_id..self.head_id
+    }
+
+    /// Completes the snapshot in the provided directory, which can be opened as freezer files.
+    ///
+    /// The data files of `linked` have been linked by `link_files` without holding the freezer,
+    /// the ones pruned or truncated since then are removed, and the head file, the index and the
+    /// meta files are copied.
+    pub fn snapshot(&self, path: &Path, linked: Range<FileId>) -> Result<(), IoError> {
+        self.sync_all()?;
+        fs::create_dir_all(path)?;
+        for id in linked.clone() {
+            if id < self.tail_id || id >= self.head_id {
+                let file = path.join(helper::file_name(id));
+                if file.exists() {
+                    fs::remove_file(file)?;
+                }
+            }
+        }
+        // the files become immutable while linking
+        link_files(
+            &self.file_path,
+            path,
+            linked.end.max(self.tail_id)..self.head_id,
+        )?;
+        let name = helper::file_name(self.head_id);
+        fs::copy(self.file_path.join(&name), path.join(&name))?;
+        for name in [INDEX_FILE_NAME, META_FILE_NAME, DICT_FILE_NAME] {
+            let file = self.file_path.join(name);
+            if file.exists() {
+                fs::copy(file, path.join(name))?;
+            }
+        }
+        Ok(())
+    }
+
     fn get_bounds(&self, item: u64) -> Result<Option<(u64, u64, FileId)>, IoError> {
         let mut buffer = [0; INDEX_ENTRY_SIZE as usize];
         let mut index = &self.index;
@@ -259,6 +507,12 @@ impl FreezerFiles {
         if item < 1 || ((item + 1) >= self.number()) {
             return Ok(());
         }
+        if item < self.tail() {
+            return Err(IoError::new(
+                IoErrorKind::Other,
+                format!("truncating pruned item {item}, tail {}", self.tail()),
+            ));
+        }
         ckb_logger::trace!("Freezer truncate items {}", item);
 
         let mut buffer = [0; INDEX_ENTRY_SIZE as usize];
@@ -271,6 +525,14 @@ impl FreezerFiles {
         // truncate files
         if new_index.file_id != self.head_id {
             self.release(new_index.file_id);
+            // the file is truncated in place, unless it's hard linked by a snapshot, which
+            // must keep the items, then the new head is written into a copy of it
+            let name = helper::file_name(new_index.file_id);
+            if helper::is_hard_linked(&self.file_path.join(&name))? {
+                let tmp = self.file_path.join(format!("{name}.tmp"));
+                fs::copy(self.file_path.join(&name), &tmp)?;
+                fs::rename(&tmp, self.file_path.join(&name))?;
+            }
             let (new_head_file, offset) = self.open_append(new_index.file_id)?;
 
             self.delete_after(new_index.file_id)?;
@@ -281,9 +543,77 @@ impl FreezerFiles {
         helper::truncate_file(&mut self.head.file, new_index.offset)?;
         self.head.bytes = new_index.offset;
         self.number.store(item + 1, Ordering::SeqCst);
+
+        // the following items will be appended with header
+        if self.framed_from > item + 1 {
+            self.framed_from = item + 1;
+            helper::write_file_sync(
+                &self.file_path.join(META_FILE_NAME),
+                &self.framed_from.to_le_bytes(),
+            )?;
+        }
         Ok(())
     }
 
+    /// Return the number of the earliest item still stored in the data files
+    #[inline]
+    pub fn tail(&self) -> u64 {
+        self.tail.load(Ordering::SeqCst)
+    }
+
+    /// Deletes the data files which only contain items below the provided number.
+    ///
+    /// Data files are removed as a whole, the head file is never removed, so the items
+    /// of the data file where `item` lives are kept. The index file is left untouched.
+    /// Returns the number of the earliest item still stored.
+    pub fn prune(&mut self, item: u64) -> Result<u64, IoError> {
+        // the latest frozen item is always kept, e.g. when the freezer lags behind the threshold
+        let item = item.min(self.number().saturating_sub(1));
+        // out of bound, this has no effect.
+        if item <= self.tail() {
+            return Ok(self.tail());
+        }
+
+        let file_id = self.read_index_entry(item)?.file_id;
+        if file_id <= self.tail_id {
+            return Ok(self.tail());
+        }
+        ckb_logger::trace!("Freezer prune files {}-{}", self.tail_id, file_id);
+
+        for id in self.tail_id..file_id {
+            self.release(id);
+        }
+        self.delete_files_by_id(self.tail_id..file_id)?;
+        self.tail_id = file_id;
+
+        let tail = self.first_item_in_file(file_id)?;
+        self.tail.store(tail, Ordering::SeqCst);
+        Ok(tail)
+    }
+
+    fn read_index_entry(&self, item: u64) -> Result<IndexEntry, IoError> {
+        let mut buffer = [0; INDEX_ENTRY_SIZE as usize];
+        let mut index = &self.index;
+        index.seek(SeekFrom::Start(item * INDEX_ENTRY_SIZE))?;
+        index.read_exact(&mut buffer)?;
+        IndexEntry::decode(&buffer)
+    }
+
+    // binary search the first item whose data lives in the given file,
+    // index entries are sorted by file id
+    fn first_item_in_file(&self, file_id: FileId) -> Result<u64, IoError> {
+        let (mut low, mut high) = (1, self.number());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.read_index_entry(mid)?.file_id < file_id {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        Ok(low)
+    }
+
     /// Attempts to open files, initialize fd map
     pub fn preopen(&mut self) -> Result<(), IoError> {
         self.release_all();
@@ -363,11 +693,36 @@ impl FreezerFiles {
     }
 }
 
+/// Hard links the data files of the ids from the freezer directory into the snapshot directory,
+/// or copies them if they can't be linked, e.g. across file systems.
+///
+/// The data files before the head are never written again, so it doesn't hold the freezer. The
+/// files pruned meanwhile are skipped.
+pub fn link_files(from: &Path, to: &Path, ids: Range<FileId>) -> Result<(), IoError> {
+    fs::create_dir_all(to)?;
+    for id in ids {
+        let name = helper::file_name(id);
+        let (src, dst) = (from.join(&name), to.join(&name));
+        if dst.exists() {
+            continue;
+        }
+        if let Err(err) = fs::hard_link(&src, &dst) {
+            if !src.exists() {
+                continue;
+            }
+            ckb_logger::debug!("Freezer snapshot hard link {} failed {}", name, err);
+            fs::copy(&src, &dst)?;
+        }
+    }
+    Ok(())
+}
+
 /// Freezer factory, which can be used in order to configure the properties of a new freezer.
 pub struct FreezerFilesBuilder {
     file_path: PathBuf,
     max_file_size: u64,
     enable_compression: bool,
+    compression: Compression,
     open_files_limit: usize,
 }
 
@@ -378,6 +733,7 @@ impl FreezerFilesBuilder {
             file_path,
             max_file_size: MAX_FILE_SIZE,
             enable_compression: true,
+            compression: Compression::Snappy,
             open_files_limit: OPEN_FILES_LIMIT,
         }
     }
@@ -405,6 +761,19 @@ impl FreezerFilesBuilder {
     #[allow(dead_code)]
     pub fn enable_compression(mut self, enable_compression: bool) -> Self {
         self.enable_compression = enable_compression;
+        self.compression = if enable_compression {
+            Compression::Snappy
+        } else {
+            Compression::None
+        };
+        self
+    }
+
+    /// Sets the compression algorithm of the appended items for the new freezer.
+    ///
+    /// The legacy items without header are still decompressed according to `enable_compression`.
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
         self
     }
 
@@ -417,7 +786,7 @@ impl FreezerFilesBuilder {
         index.rewind()?;
         index.read_exact(&mut buffer)?;
         let tail_index = IndexEntry::decode(&buffer)?;
-        let tail_id = tail_index.file_id;
+        let mut tail_id = tail_index.file_id;
 
         index.seek(SeekFrom::Start(index_size - INDEX_ENTRY_SIZE))?;
         index.read_exact(&mut buffer)?;
@@ -474,18 +843,253 @@ impl FreezerFilesBuilder {
         index.sync_all()?;
 
         let number = index_size / INDEX_ENTRY_SIZE;
+        let framed_from = self.open_meta(number)?;
+        let dict = self.open_dict()?;
+
+        // skip the data files which have been pruned
+        while tail_id < head_index.file_id
+            && !self.file_path.join(helper::file_name(tail_id)).exists()
+        {
+            tail_id += 1;
+        }
 
-        Ok(FreezerFiles {
+        let files = FreezerFiles {
             files: LruCache::new(self.open_files_limit),
             head: Head::new(head, head_size),
             tail_id,
+            tail: Arc::new(AtomicU64::new(1)),
             number: Arc::new(AtomicU64::new(number)),
             max_size: self.max_file_size,
             head_id: head_index.file_id,
             file_path: self.file_path,
             index,
             enable_compression: self.enable_compression,
-        })
+            compression: self.compression,
+            framed_from,
+            dict,
+        };
+        if tail_id > tail_index.file_id {
+            let tail = files.first_item_in_file(tail_id)?;
+            files.tail.store(tail, Ordering::SeqCst);
+        }
+        Ok(files)
+    }
+
+    /// Rebuilds the index file by scanning the framed items inside of data files.
+    ///
+    /// The index entries of the legacy items without header can't be rebuilt, they are kept as
+    /// is and must be intact. The data files are never modified, the rebuilt index is written
+    /// into a temporary file which then replaces the index file.
+    ///
+    /// The scan fails on the first item which can't be recovered, e.g. a checksum mismatch,
+    /// unless `force` is set. Then the index stops before that item, and the data behind it is
+    /// dropped the next time the freezer files are opened. A partially written item at the end
+    /// of the last data file is the trace of a crash, it's always skipped.
+    /// Returns the number of recovered items.
+    pub fn rebuild_index(&self, force: bool) -> Result<u64, IoError> {
+        let invalid = |msg: String| IoError::new(IoErrorKind::InvalidData, msg);
+        let index_path = self.file_path.join(INDEX_FILE_NAME);
+        let raw_index = if index_path.exists() {
+            fs::read(&index_path)?
+        } else {
+            Vec::new()
+        };
+        let indexed = raw_index.len() as u64 / INDEX_ENTRY_SIZE;
+        let framed_from = self
+            .read_meta()?
+            .ok_or_else(|| {
+                invalid(
+                    "missing meta file, the items written by the old version can't be rebuilt"
+                        .to_string(),
+                )
+            })?
+            .max(1);
+        let mut kept = raw_index[..(indexed.min(framed_from) * INDEX_ENTRY_SIZE) as usize].to_vec();
+        // the first entry is always the default one
+        if kept.is_empty() {
+            kept = IndexEntry::default().encode();
+        }
+        if (kept.len() as u64) < framed_from * INDEX_ENTRY_SIZE {
+            return Err(invalid(format!(
+                "only {indexed} index entries left, the legacy items below {framed_from} can't be rebuilt"
+            )));
+        }
+        let IndexEntry {
+            mut file_id,
+            mut offset,
+        } = IndexEntry::decode(&kept[kept.len() - INDEX_ENTRY_SIZE as usize..])?;
+        let max_file_id = helper::max_file_id(&self.file_path)?;
+        let mut number = framed_from;
+        let mut entries = Vec::new();
+
+        // the data files of the earliest items have been pruned, the index entries of the
+        // pruned items point to the file before the earliest data file
+        if !self.file_path.join(helper::file_name(file_id)).exists() {
+            while !self.file_path.join(helper::file_name(file_id)).exists() {
+                if file_id >= max_file_id {
+                    return Err(invalid("no data file found".to_string()));
+                }
+                file_id += 1;
+            }
+            offset = 0;
+            let mut file = File::open(self.file_path.join(helper::file_name(file_id)))?;
+            let mut raw = [0; ITEM_HEADER_SIZE as usize];
+            file.read_exact(&mut raw)?;
+            let first = ItemHeader::decode(&raw)?.number;
+            if first < framed_from {
+                return Err(invalid(format!(
+                    "unexpected first item {first} of the data file {file_id}"
+                )));
+            }
+            for _ in framed_from..first {
+                entries.push(IndexEntry {
+                    file_id: file_id - 1,
+                    offset: 0,
+                });
+            }
+            number = first;
+        }
+        let pruned = entries.len() as u64;
+
+        let mut data = Vec::new();
+        'files: loop {
+            let name = helper::file_name(file_id);
+            let path = self.file_path.join(&name);
+            if !path.exists() {
+                if file_id <= max_file_id {
+                    let error = invalid(format!("missing data file {name}"));
+                    if !force {
+                        return Err(error);
+                    }
+                    ckb_logger::warn!("Freezer rebuild index stops at item {}: {}", number, error);
+                }
+                break;
+            }
+            let mut file = File::open(&path)?;
+            let len = file.metadata()?.len();
+            file.seek(SeekFrom::Start(offset))?;
+            while offset < len {
+                let end = match Self::read_item(&mut file, offset, len, number, &mut data) {
+                    Ok(end) => end,
+                    Err(e) if e.kind() == IoErrorKind::UnexpectedEof && file_id == max_file_id => {
+                        ckb_logger::warn!(
+                            "Freezer rebuild index skips the partially written item {}",
+                            number
+                        );
+                        break 'files;
+                    }
+                    Err(e) => {
+                        let error = invalid(format!(
+                            "item {number} in {name} at offset {offset} can't be recovered: {e}"
+                        ));
+                        if !force {
+                            return Err(error);
+                        }
+                        ckb_logger::warn!("Freezer rebuild index stops: {}", error);
+                        break 'files;
+                    }
+                };
+                entries.push(IndexEntry {
+                    file_id,
+                    offset: end,
+                });
+                offset = end;
+                number += 1;
+            }
+            if file_id >= max_file_id {
+                break;
+            }
+            file_id += 1;
+            offset = 0;
+        }
+
+        ckb_logger::info!(
+            "Freezer rebuild index from {} to {}, {} items pruned",
+            framed_from,
+            number,
+            pruned
+        );
+        let tmp_path = self.file_path.join(format!("{INDEX_FILE_NAME}.tmp"));
+        let mut tmp = File::create(&tmp_path)?;
+        tmp.write_all(&kept)?;
+        for entry in &entries {
+            tmp.write_all(&entry.encode())?;
+        }
+        tmp.sync_all()?;
+        fs::rename(&tmp_path, &index_path)?;
+        Ok(entries.len() as u64 - pruned)
+    }
+
+    // Reads the framed item starting at the offset, returns the end offset of the item.
+    //
+    // Returns an `UnexpectedEof` error if the item is cut off by the end of the file.
+    fn read_item(
+        file: &mut File,
+        offset: u64,
+        len: u64,
+        number: u64,
+        data: &mut Vec<u8>,
+    ) -> Result<u64, IoError> {
+        let eof = || IoError::new(IoErrorKind::UnexpectedEof, "item cut off");
+        if offset + ITEM_HEADER_SIZE > len {
+            return Err(eof());
+        }
+        let mut raw = [0; ITEM_HEADER_SIZE as usize];
+        file.read_exact(&mut raw)?;
+        let header = ItemHeader::decode(&raw)?;
+        if header.number != number {
+            return Err(IoError::new(
+                IoErrorKind::InvalidData,
+                format!("unexpected item number {}", header.number),
+            ));
+        }
+        let end = offset + ITEM_HEADER_SIZE + u64::from(header.size);
+        if end > len {
+            return Err(eof());
+        }
+        data.resize(header.size as usize, 0);
+        file.read_exact(data)?;
+        if crc32fast::hash(data) != header.checksum {
+            return Err(IoError::new(
+                IoErrorKind::InvalidData,
+                "checksum mismatch".to_string(),
+            ));
+        }
+        Ok(end)
+    }
+
+    // Returns the number from which items are framed with header.
+    //
+    // A freezer written by an old version has no meta file, all of its items are legacy.
+    fn open_meta(&self, number: u64) -> Result<u64, IoError> {
+        if let Some(framed_from) = self.read_meta()? {
+            Ok(framed_from)
+        } else {
+            helper::write_file_sync(&self.file_path.join(META_FILE_NAME), &number.to_le_bytes())?;
+            Ok(number)
+        }
+    }
+
+    fn read_meta(&self) -> Result<Option<u64>, IoError> {
+        let path = self.file_path.join(META_FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let raw = fs::read(path)?;
+        let raw: [u8; 8] = raw
+            .as_slice()
+            .try_into()
+            .map_err(|e| IoError::new(IoErrorKind::InvalidData, format!("decode meta {e}")))?;
+        Ok(Some(u64::from_le_bytes(raw)))
+    }
+
+    fn open_dict(&self) -> Result<Option<Vec<u8>>, IoError> {
+        let path = self.file_path.join(DICT_FILE_NAME);
+        if path.exists() {
+            fs::read(path).map(Some)
+        } else {
+            Ok(None)
+        }
     }
 
     // Open the file without append mode
@@ -534,4 +1138,39 @@ pub(crate) mod helper {
     pub(crate) fn file_name(file_id: FileId) -> String {
         format!("blk{file_id:06}")
     }
+
+    // whether the file is hard linked from another path, e.g. by a snapshot
+    #[cfg(unix)]
+    pub(crate) fn is_hard_linked(path: &Path) -> Result<bool, IoError> {
+        use std::os::unix::fs::MetadataExt;
+        Ok(fs::metadata(path)?.nlink() > 1)
+    }
+
+    // the count of the links is unknown, assume the file is linked
+    #[cfg(not(unix))]
+    pub(crate) fn is_hard_linked(_path: &Path) -> Result<bool, IoError> {
+        Ok(true)
+    }
+
+    pub(crate) fn write_file_sync(path: &Path, data: &[u8]) -> Result<(), IoError> {
+        let mut file = File::create(path)?;
+        file.write_all(data)?;
+        file.sync_all()
+    }
+
+    // the largest id of the data files inside of the directory
+    pub(crate) fn max_file_id(path: &Path) -> Result<FileId, IoError> {
+        let mut max = 0;
+        for entry in fs::read_dir(path)? {
+            let name = entry?.file_name();
+            if let Some(id) = name
+                .to_str()
+                .and_then(|name| name.strip_prefix("blk"))
+                .and_then(|id| id.parse::<FileId>().ok())
+            {
+                max = max.max(id);
+            }
+        }
+        Ok(max)
+    }
 }
@@ -1,5 +1,9 @@
-use crate::freezer_files::helper::truncate_file;
-use crate::freezer_files::{FreezerFilesBuilder, INDEX_ENTRY_SIZE};
+use crate::freezer_files::helper::{self, truncate_file};
+use crate::freezer_files::{
+    link_files, Compression, FreezerFilesBuilder, IndexEntry, INDEX_ENTRY_SIZE, ITEM_HEADER_SIZE,
+};
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind as IoErrorKind, Seek, SeekFrom, Write};
 
 fn make_bytes(size: usize, byte: u8) -> Vec<u8> {
     let mut ret = Vec::with_capacity(size);
@@ -158,7 +162,7 @@ fn try_repair_dangling_index() {
     {
         let mut freezer = FreezerFilesBuilder::new(tempdir.path().to_path_buf())
             .enable_compression(false)
-            .max_file_size(50)
+            .max_file_size(100)
             .build()
             .unwrap();
 
@@ -174,18 +178,18 @@ fn try_repair_dangling_index() {
             assert_eq!(Some(expect), actual);
         }
 
-        truncate_file(&mut freezer.head.file, 20).unwrap();
+        truncate_file(&mut freezer.head.file, 40).unwrap();
     }
 
     let mut freezer = FreezerFilesBuilder::new(tempdir.path().to_path_buf())
         .enable_compression(false)
-        .max_file_size(50)
+        .max_file_size(100)
         .build()
         .unwrap();
     freezer.preopen().unwrap();
 
     assert_eq!(freezer.number(), 8);
-    assert_eq!(freezer.head.file.metadata().unwrap().len(), 15);
+    assert_eq!(freezer.head.file.metadata().unwrap().len(), 32);
     for i in 1..8 {
         let expect = make_bytes(15, i);
         let actual = freezer.retrieve(i.into()).unwrap();
@@ -199,7 +203,7 @@ fn truncate() {
     {
         let mut freezer = FreezerFilesBuilder::new(tempdir.path().to_path_buf())
             .enable_compression(false)
-            .max_file_size(50)
+            .max_file_size(100)
             .build()
             .unwrap();
 
@@ -222,11 +226,23 @@ fn truncate() {
     {
         let mut freezer = FreezerFilesBuilder::new(tempdir.path().to_path_buf())
             .enable_compression(false)
-            .max_file_size(50)
+            .max_file_size(100)
             .build()
             .unwrap();
         freezer.preopen().unwrap();
+        #[cfg(unix)]
+        let inode = |id| {
+            use std::os::unix::fs::MetadataExt;
+            std::fs::metadata(tempdir.path().join(helper::file_name(id)))
+                .unwrap()
+                .ino()
+        };
+        #[cfg(unix)]
+        let head_inode = inode(3);
         freezer.truncate(10).unwrap();
+        // the new head is truncated in place
+        #[cfg(unix)]
+        assert_eq!(inode(3), head_inode);
 
         for i in 1..11 {
             let expect = make_bytes(15, i);
@@ -237,12 +253,12 @@ fn truncate() {
         let retrieve_out_of_bound = freezer.retrieve(11).unwrap();
         assert_eq!(None, retrieve_out_of_bound);
         assert_eq!(freezer.number(), 11);
-        assert_eq!(freezer.head.bytes, 15);
+        assert_eq!(freezer.head.bytes, 32);
     }
 
     let mut freezer = FreezerFilesBuilder::new(tempdir.path().to_path_buf())
         .enable_compression(false)
-        .max_file_size(50)
+        .max_file_size(100)
         .build()
         .unwrap();
     freezer.preopen().unwrap();
@@ -285,3 +301,356 @@ fn open_files_limit_retrieve() {
         assert_eq!(Some(expect), actual);
     }
 }
+
+#[test]
+fn prune() {
+    let tempdir = tempfile::Builder::new().tempdir().unwrap();
+
+    {
+        let mut freezer = FreezerFilesBuilder::new(tempdir.path().to_path_buf())
+            .enable_compression(false)
+            .max_file_size(100)
+            .build()
+            .unwrap();
+        freezer.preopen().unwrap();
+        for i in 1..30 {
+            let data = make_bytes(15, i);
+            freezer.append(i.into(), &data).unwrap();
+        }
+
+        assert_eq!(freezer.tail(), 1);
+        // every file holds 3 items, item 8 lives in the file with items 7..=9
+        assert_eq!(freezer.prune(8).unwrap(), 7);
+        assert_eq!(freezer.tail_id, 2);
+        assert!(!tempdir.path().join(helper::file_name(1)).exists());
+
+        for i in 1..7 {
+            assert_eq!(None, freezer.retrieve(i).unwrap());
+        }
+        for i in 7..30 {
+            let expect = make_bytes(15, i);
+            let actual = freezer.retrieve(i.into()).unwrap();
+            assert_eq!(Some(expect), actual);
+        }
+
+        // prune below the tail has no effect
+        assert_eq!(freezer.prune(3).unwrap(), 7);
+        assert!(freezer.truncate(3).is_err());
+    }
+
+    let mut freezer = FreezerFilesBuilder::new(tempdir.path().to_path_buf())
+        .enable_compression(false)
+        .max_file_size(100)
+        .build()
+        .unwrap();
+    freezer.preopen().unwrap();
+    assert_eq!(freezer.tail(), 7);
+    assert_eq!(freezer.number(), 30);
+    assert_eq!(None, freezer.retrieve(6).unwrap());
+    assert_eq!(Some(make_bytes(15, 7)), freezer.retrieve(7).unwrap());
+
+    // the threshold beyond the frozen items prunes up to the file of the latest item
+    assert_eq!(freezer.prune(100).unwrap(), 28);
+    assert_eq!(None, freezer.retrieve(27).unwrap());
+    assert_eq!(Some(make_bytes(15, 29)), freezer.retrieve(29).unwrap());
+}
+
+#[test]
+fn checksum_mismatch() {
+    let tempdir = tempfile::Builder::new().tempdir().unwrap();
+    let mut freezer = FreezerFilesBuilder::new(tempdir.path().to_path_buf())
+        .enable_compression(false)
+        .max_file_size(50)
+        .build()
+        .unwrap();
+    freezer.preopen().unwrap();
+    for i in 1..10 {
+        let data = make_bytes(15, i);
+        freezer.append(i.into(), &data).unwrap();
+    }
+
+    // every file holds 1 item, flip a byte of the payload of item 5
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(tempdir.path().join(helper::file_name(4)))
+        .unwrap();
+    file.seek(SeekFrom::Start(ITEM_HEADER_SIZE + 1)).unwrap();
+    file.write_all(&[0xff]).unwrap();
+    file.sync_all().unwrap();
+
+    let err = freezer.retrieve(5).unwrap_err();
+    assert_eq!(err.kind(), IoErrorKind::InvalidData);
+    assert_eq!(Some(make_bytes(15, 6)), freezer.retrieve(6).unwrap());
+    assert_eq!(freezer.verify(|_, _| true).unwrap(), vec![5]);
+    assert_eq!(freezer.verify(|item, _| item != 7).unwrap(), vec![5, 7]);
+}
+
+#[test]
+fn zstd_dictionary() {
+    let tempdir = tempfile::Builder::new().tempdir().unwrap();
+    let make_item = |i: u64| -> Vec<u8> {
+        (0..200u64)
+            .flat_map(|j| (i * j % 97).to_le_bytes())
+            .collect()
+    };
+
+    {
+        let mut freezer = FreezerFilesBuilder::new(tempdir.path().to_path_buf())
+            .compression(Compression::Zstd)
+            .max_file_size(5000)
+            .build()
+            .unwrap();
+        freezer.preopen().unwrap();
+        for i in 1..300 {
+            freezer.append(i, &make_item(i)).unwrap();
+        }
+
+        assert!(freezer.need_dictionary());
+        assert!(!freezer.train_dictionary(500).unwrap());
+        assert!(freezer.train_dictionary(200).unwrap());
+        assert!(!freezer.need_dictionary());
+
+        for i in 300..400 {
+            freezer.append(i, &make_item(i)).unwrap();
+        }
+        for i in 1..400 {
+            assert_eq!(Some(make_item(i)), freezer.retrieve(i).unwrap());
+        }
+    }
+
+    // compression can be changed, the dictionary is loaded on reopen
+    let mut freezer = FreezerFilesBuilder::new(tempdir.path().to_path_buf())
+        .compression(Compression::Snappy)
+        .max_file_size(5000)
+        .build()
+        .unwrap();
+    freezer.preopen().unwrap();
+    assert!(freezer.dict.is_some());
+    for i in 400..450 {
+        freezer.append(i, &make_item(i)).unwrap();
+    }
+    for i in 1..450 {
+        assert_eq!(Some(make_item(i)), freezer.retrieve(i).unwrap());
+    }
+}
+
+#[test]
+fn rebuild_index() {
+    let tempdir = tempfile::Builder::new().tempdir().unwrap();
+    {
+        let mut freezer = FreezerFilesBuilder::new(tempdir.path().to_path_buf())
+            .max_file_size(100)
+            .build()
+            .unwrap();
+        freezer.preopen().unwrap();
+        for i in 1..30 {
+            let data = make_bytes(15, i);
+            freezer.append(i.into(), &data).unwrap();
+        }
+        // a partially written item
+        freezer.head.write(&make_bytes(10, 0xff)).unwrap();
+    }
+
+    fs::remove_file(tempdir.path().join("INDEX")).unwrap();
+    let builder = FreezerFilesBuilder::new(tempdir.path().to_path_buf()).max_file_size(100);
+    assert_eq!(builder.rebuild_index(false).unwrap(), 29);
+
+    let mut freezer = builder.build().unwrap();
+    freezer.preopen().unwrap();
+    assert_eq!(freezer.number(), 30);
+    for i in 1..30 {
+        let expect = make_bytes(15, i);
+        let actual = freezer.retrieve(i.into()).unwrap();
+        assert_eq!(Some(expect), actual);
+    }
+    freezer.append(30, &make_bytes(15, 30)).unwrap();
+    assert_eq!(Some(make_bytes(15, 30)), freezer.retrieve(30).unwrap());
+}
+
+#[test]
+fn rebuild_index_keeps_data_on_corruption() {
+    let tempdir = tempfile::Builder::new().tempdir().unwrap();
+    {
+        let mut freezer = FreezerFilesBuilder::new(tempdir.path().to_path_buf())
+            .enable_compression(false)
+            .max_file_size(100)
+            .build()
+            .unwrap();
+        freezer.preopen().unwrap();
+        for i in 1..30 {
+            let data = make_bytes(15, i);
+            freezer.append(i.into(), &data).unwrap();
+        }
+    }
+
+    // every file holds 3 items, flip a byte of the payload of item 5
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(tempdir.path().join(helper::file_name(1)))
+        .unwrap();
+    file.seek(SeekFrom::Start(32 + ITEM_HEADER_SIZE + 1))
+        .unwrap();
+    file.write_all(&[0xff]).unwrap();
+    file.sync_all().unwrap();
+    let read_files = || {
+        (0..10)
+            .map(|id| fs::read(tempdir.path().join(helper::file_name(id))).unwrap())
+            .collect::<Vec<_>>()
+    };
+    let files = read_files();
+    let index = fs::read(tempdir.path().join("INDEX")).unwrap();
+
+    let builder = FreezerFilesBuilder::new(tempdir.path().to_path_buf())
+        .enable_compression(false)
+        .max_file_size(100);
+    let err = builder.rebuild_index(false).unwrap_err();
+    assert_eq!(err.kind(), IoErrorKind::InvalidData);
+    assert_eq!(files, read_files());
+    assert_eq!(index, fs::read(tempdir.path().join("INDEX")).unwrap());
+
+    // the forced rebuild drops the items from the corrupted one from the index only
+    assert_eq!(builder.rebuild_index(true).unwrap(), 4);
+    assert_eq!(files, read_files());
+    let mut freezer = builder.build().unwrap();
+    freezer.preopen().unwrap();
+    assert_eq!(freezer.number(), 5);
+    for i in 1..5 {
+        assert_eq!(Some(make_bytes(15, i)), freezer.retrieve(i.into()).unwrap());
+    }
+}
+
+#[test]
+fn rebuild_index_of_pruned_and_legacy_items() {
+    let tempdir = tempfile::Builder::new().tempdir().unwrap();
+    {
+        let mut freezer = FreezerFilesBuilder::new(tempdir.path().to_path_buf())
+            .max_file_size(100)
+            .build()
+            .unwrap();
+        freezer.preopen().unwrap();
+        for i in 1..30 {
+            let data = make_bytes(15, i);
+            freezer.append(i.into(), &data).unwrap();
+        }
+        assert_eq!(freezer.prune(8).unwrap(), 7);
+    }
+
+    let builder = || FreezerFilesBuilder::new(tempdir.path().to_path_buf()).max_file_size(100);
+    fs::remove_file(tempdir.path().join("INDEX")).unwrap();
+    assert_eq!(builder().rebuild_index(false).unwrap(), 23);
+    let mut freezer = builder().build().unwrap();
+    freezer.preopen().unwrap();
+    assert_eq!(freezer.number(), 30);
+    assert_eq!(freezer.tail(), 7);
+    assert_eq!(None, freezer.retrieve(6).unwrap());
+    for i in 7..30 {
+        assert_eq!(Some(make_bytes(15, i)), freezer.retrieve(i.into()).unwrap());
+    }
+    drop(freezer);
+
+    // the index entries of the legacy items can't be rebuilt
+    fs::write(tempdir.path().join("META"), 10u64.to_le_bytes()).unwrap();
+    fs::remove_file(tempdir.path().join("INDEX")).unwrap();
+    let err = builder().rebuild_index(true).unwrap_err();
+    assert_eq!(err.kind(), IoErrorKind::InvalidData);
+    assert!(!tempdir.path().join("INDEX").exists());
+}
+
+#[test]
+fn legacy_items() {
+    let tempdir = tempfile::Builder::new().tempdir().unwrap();
+
+    // the items written by the old version have no header
+    {
+        let mut index = File::create(tempdir.path().join("INDEX")).unwrap();
+        index.write_all(&IndexEntry::default().encode()).unwrap();
+        let mut data = File::create(tempdir.path().join(helper::file_name(0))).unwrap();
+        for i in 1..10 {
+            data.write_all(&make_bytes(15, i)).unwrap();
+            let entry = IndexEntry {
+                file_id: 0,
+                offset: i as u64 * 15,
+            };
+            index.write_all(&entry.encode()).unwrap();
+        }
+    }
+
+    {
+        let mut freezer = FreezerFilesBuilder::new(tempdir.path().to_path_buf())
+            .enable_compression(false)
+            .max_file_size(200)
+            .build()
+            .unwrap();
+        freezer.preopen().unwrap();
+        assert_eq!(freezer.framed_from, 10);
+        for i in 10..20 {
+            let data = make_bytes(15, i);
+            freezer.append(i.into(), &data).unwrap();
+        }
+        for i in 1..20 {
+            let expect = make_bytes(15, i);
+            let actual = freezer.retrieve(i.into()).unwrap();
+            assert_eq!(Some(expect), actual);
+        }
+        freezer.truncate(5).unwrap();
+        assert_eq!(freezer.framed_from, 6);
+        for i in 6..20 {
+            let data = make_bytes(15, i);
+            freezer.append(i.into(), &data).unwrap();
+        }
+    }
+
+    let mut freezer = FreezerFilesBuilder::new(tempdir.path().to_path_buf())
+        .enable_compression(false)
+        .max_file_size(200)
+        .build()
+        .unwrap();
+    freezer.preopen().unwrap();
+    assert_eq!(freezer.framed_from, 6);
+    for i in 1..20 {
+        let expect = make_bytes(15, i);
+        let actual = freezer.retrieve(i.into()).unwrap();
+        assert_eq!(Some(expect), actual);
+    }
+}
+
+#[test]
+fn snapshot() {
+    let tempdir = tempfile::Builder::new().tempdir().unwrap();
+    let path = tempdir.path().join("freezer");
+    let snapshot_path = tempdir.path().join("snapshot");
+
+    let mut freezer = FreezerFilesBuilder::new(path)
+        .max_file_size(100)
+        .build()
+        .unwrap();
+    freezer.preopen().unwrap();
+    for i in 1..30 {
+        let data = make_bytes(15, i);
+        freezer.append(i.into(), &data).unwrap();
+    }
+    let linked = freezer.immutable_file_ids();
+    link_files(&freezer.file_path, &snapshot_path, linked.clone()).unwrap();
+    // the files pruned after linking are removed from the snapshot
+    freezer.prune(8).unwrap();
+    freezer.snapshot(&snapshot_path, linked).unwrap();
+    assert!(!snapshot_path.join(helper::file_name(1)).exists());
+
+    // items appended after the snapshot are not included
+    freezer.append(30, &make_bytes(15, 30)).unwrap();
+    // truncating the freezer doesn't change the linked files of the snapshot
+    freezer.truncate(10).unwrap();
+
+    let mut snapshot = FreezerFilesBuilder::new(snapshot_path)
+        .max_file_size(100)
+        .build()
+        .unwrap();
+    snapshot.preopen().unwrap();
+    assert_eq!(snapshot.number(), 30);
+    assert_eq!(snapshot.tail(), freezer.tail());
+    for i in snapshot.tail()..30 {
+        let expect = make_bytes(15, i as u8);
+        let actual = snapshot.retrieve(i).unwrap();
+        assert_eq!(Some(expect), actual);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::Work;
+use ckb_app_config::ExtraHashFunction;
+use ckb_hash::blake2b_256;
+use ckb_pow::pow_message;
+use ckb_types::{packed::Byte32, prelude::*, U256};
+use eaglesong::eaglesong;
+use std::cmp;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// The max count of the jobs kept for the late shares.
+const MAX_JOBS: usize = 16;
+/// The max count of the workers with share accounting, the least recently active one is dropped
+/// once reached.
+pub(crate) const MAX_WORKER_STATS: usize = 1024;
+
+pub(crate) struct Job {
+    pub(crate) id: String,
+    pub(crate) pow_hash: Byte32,
+    pub(crate) work: Work,
+    pub(crate) target: U256,
+    // nonces of the accepted shares
+    submitted: HashSet<u128>,
+}
+
+impl Job {
+    pub(crate) fn number(&self) -> u64 {
+        self.work.block.header().raw().number().unpack()
+    }
+
+    pub(crate) fn parent_hash(&self) -> Byte32 {
+        self.work.block.header().raw().parent_hash()
+    }
+}
+
+/// The reasons to reject a share, with the error codes used by Stratum v1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ShareError {
+    /// The job is unknown or stale
+    JobNotFound,
+    /// The share has been submitted before
+    Duplicate,
+    /// The PoW output is larger than the share target
+    LowDifficulty,
+}
+
+impl ShareError {
+    pub(crate) fn code(self) -> i64 {
+        match self {
+            ShareError::JobNotFound => 21,
+            ShareError::Duplicate => 22,
+            ShareError::LowDifficulty => 23,
+        }
+    }
+
+    pub(crate) fn message(self) -> &'static str {
+        match self {
+            ShareError::JobNotFound => "Job not found",
+            ShareError::Duplicate => "Duplicate share",
+            ShareError::LowDifficulty => "Low difficulty share",
+        }
+    }
+}
+
+/// The share accounting of a downstream worker.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ShareStats {
+    pub(crate) accepted: u64,
+    pub(crate) rejected: u64,
+    pub(crate) blocks: u64,
+    // the sequence number of the last share
+    last_share: u64,
+}
+
+/// The jobs distributed to the downstream miners, each one maps to a `work_id` of the node.
+pub(crate) struct Jobs {
+    jobs: VecDeque<Job>,
+    next_id: u64,
+    share_difficulty: u64,
+    extra_hash_function: Option<ExtraHashFunction>,
+    stats: HashMap<String, ShareStats>,
+    share_seq: u64,
+    // the node stopped the work, the shares meeting the block target are not sent back
+    stale: bool,
+}
+
+impl Jobs {
+    pub(crate) fn new(
+        share_difficulty: u64,
+        extra_hash_function: Option<ExtraHashFunction>,
+    ) -> Self {
+        Jobs {
+            jobs: VecDeque::new(),
+            next_id: 0,
+            share_difficulty,
+            extra_hash_function,
+            stats: HashMap::new(),
+            share_seq: 0,
+            stale: false,
+        }
+    }
+
+    /// Adds the new work as the latest job. The previous jobs are dropped if the work is on
+    /// another parent or the jobs are stale, which is returned as the `clean_jobs` flag.
+    pub(crate) fn push(&mut self, pow_hash: Byte32, work: Work, target: U256) -> bool {
+        let parent_hash = work.block.header().raw().parent_hash();
+        let clean = self.stale
+            || self
+                .latest()
+                .map_or(true, |job| job.parent_hash() != parent_hash);
+        self.stale = false;
+        if clean {
+            self.jobs.clear();
+        } else if self.jobs.len() >= MAX_JOBS {
+            self.jobs.pop_front();
+        }
+
+        let id = format!("{:x}", self.next_id);
+        self.next_id += 1;
+        self.jobs.push_back(Job {
+            id,
+            pow_hash,
+            work,
+            target,
+            submitted: HashSet::new(),
+        });
+        clean
+    }
+
+    pub(crate) fn latest(&self) -> Option<&Job> {
+        self.jobs.back()
+    }
+
+    /// Marks the jobs stale until the new work arrives or the work resumes. The shares of the
+    /// stale jobs are still accepted, but never sent back as blocks.
+    pub(crate) fn set_stale(&mut self, stale: bool) {
+        self.stale = stale;
+    }
+
+    /// The target of the shares, never harder than the block target.
+    pub(crate) fn share_target(&self, job: &Job) -> U256 {
+        let share_target = U256::max_value() / U256::from(cmp::max(self.share_difficulty, 1));
+        cmp::max(share_target, job.target.clone())
+    }
+
+    /// Validates the share and accounts it to the worker.
+    ///
+    /// Returns the pow hash and the work when the share meets the block target.
+    pub(crate) fn submit(
+        &mut self,
+        worker: &str,
+        job_id: &str,
+        nonce: u128,
+    ) -> Result<Option<(Byte32, Work)>, ShareError> {
+        let result = self.validate(job_id, nonce);
+        let stats = self.worker_stats(worker);
+        match result {
+            Ok(Some(_)) => {
+                stats.accepted += 1;
+                stats.blocks += 1;
+            }
+            Ok(None) => stats.accepted += 1,
+            Err(_) => stats.rejected += 1,
+        }
+        result
+    }
+
+    fn worker_stats(&mut self, worker: &str) -> &mut ShareStats {
+        if !self.stats.contains_key(worker) && self.stats.len() >= MAX_WORKER_STATS {
+            let idle = self
+                .stats
+                .iter()
+                .min_by_key(|(_, stats)| stats.last_share)
+                .map(|(worker, _)| worker.to_owned());
+            if let Some(idle) = idle {
+                self.stats.remove(&idle);
+            }
+        }
+        self.share_seq += 1;
+        let stats = self.stats.entry(worker.to_owned()).or_default();
+        stats.last_share = self.share_seq;
+        stats
+    }
+
+    fn validate(
+        &mut self,
+        job_id: &str,
+        nonce: u128,
+    ) -> Result<Option<(Byte32, Work)>, ShareError> {
+        let index = self
+            .jobs
+            .iter()
+            .position(|job| job.id == job_id)
+            .ok_or(ShareError::JobNotFound)?;
+        let share_target = self.share_target(&self.jobs[index]);
+        let output = self.pow_output(&self.jobs[index].pow_hash, nonce);
+
+        let job = &mut self.jobs[index];
+        if job.submitted.contains(&nonce) {
+            return Err(ShareError::Duplicate);
+        }
+        if output > share_target {
+            return Err(ShareError::LowDifficulty);
+        }
+        job.submitted.insert(nonce);
+
+        if output <= job.target && !self.stale {
+            Ok(Some((job.pow_hash.clone(), job.work.clone())))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn pow_output(&self, pow_hash: &Byte32, nonce: u128) -> U256 {
+        let input = pow_message(pow_hash, nonce);
+        let mut output = [0u8; 32];
+        eaglesong(&input, &mut output);
+        if let Some(ExtraHashFunction::Blake2b) = self.extra_hash_function {
+            output = blake2b_256(output);
+        }
+        U256::from_big_endian(&output[..]).expect("bound checked")
+    }
+
+    pub(crate) fn stats(&self) -> &HashMap<String, ShareStats> {
+        &self.stats
+    }
+}
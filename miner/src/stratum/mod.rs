@@ -0,0 +1,490 @@
+//! Stratum server which distributes the work to the downstream miners.
+//!
+//! The server speaks Stratum v1, the newline-delimited JSON-RPC protocol used by the CKB mining
+//! pools. Stratum v2 is out of scope: besides its own encrypted binary framing, its job and
+//! template negotiation is built around the Bitcoin header and coinbase, which can't carry the
+//! CKB pow hash and cellbase without a CKB specific extension that no downstream miner speaks.
+//! The templates are customized on the node side instead, by the params of `get_block_template`.
+//!
+//! * `mining.subscribe` returns `[null, extranonce1, extranonce2_size]`. The nonce of a share is
+//!   the 16 bytes `extranonce1 || extranonce2` in big endian.
+//! * `mining.authorize` with `[worker_name, password]` registers the worker, and then the
+//!   current job is pushed to the session.
+//! * `mining.set_target` with `[share_target]` and `mining.notify` with
+//!   `[job_id, pow_hash, number, parent_hash, clean_jobs]` are pushed on each new job.
+//! * `mining.submit` with `[worker_name, job_id, extranonce2]` submits a share. The shares which
+//!   meet the block target are sent back to the miner to submit the block with the `work_id` of
+//!   the job.
+//!
+//! When the node stops providing work, e.g. once a block is found, the sessions are kept and the
+//! jobs are marked stale: the shares of them are still accepted, but not sent back as blocks. The
+//! next job is pushed with `clean_jobs` set, so the downstream miners switch to it right away.
+//!
+//! The authorized sessions have no read timeout, since a miner may submit no share for a long
+//! time at a high share difficulty. A dead session is closed once writing the jobs to it fails.
+mod job;
+
+#[cfg(test)]
+mod tests;
+
+use crate::worker::WorkerMessage;
+use crate::Work;
+use ckb_app_config::MinerStratumConfig;
+use ckb_channel::{bounded, Receiver, Sender};
+use ckb_logger::{debug, error, info};
+use ckb_types::{packed::Byte32, prelude::*, U256};
+use ckb_util::Mutex;
+use faster_hex::hex_string;
+use indicatif::ProgressBar;
+use job::{Jobs, ShareError};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The size of extranonce2 in bytes, extranonce1 takes the other 4 bytes of the nonce.
+const EXTRANONCE2_SIZE: usize = 12;
+/// The max length of a request in bytes.
+const MAX_LINE_LENGTH: usize = 4096;
+/// The max count of the workers authorized by a session.
+const MAX_WORKERS_PER_SESSION: usize = 16;
+/// The max length of a worker name in bytes.
+const MAX_WORKER_NAME_LENGTH: usize = 128;
+/// The max count of the messages waiting to be written to a session, the session is closed if
+/// it doesn't keep up.
+const MAX_PENDING_MESSAGES: usize = 64;
+/// The time for a new session to authorize a worker.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+/// The session is closed if writing a message takes longer.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+const ERROR_OTHER: i64 = 20;
+const ERROR_UNAUTHORIZED: i64 = 24;
+const ERROR_NOT_SUBSCRIBED: i64 = 25;
+
+#[derive(Deserialize)]
+struct StratumRequest {
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Vec<Value>,
+}
+
+/// The messages to a session are queued and written by the writer thread of the session, so a
+/// slow miner never blocks the others.
+#[derive(Clone)]
+struct SessionHandle {
+    queue: Sender<Vec<u8>>,
+    stream: Arc<TcpStream>,
+}
+
+impl SessionHandle {
+    /// Queues the message, returns false if the session is closed or doesn't keep up.
+    fn send(&self, message: &Value) -> bool {
+        let mut line = serde_json::to_vec(message).expect("serialize json");
+        line.push(b'\n');
+        self.queue.try_send(line).is_ok()
+    }
+
+    /// Closes the connection, the reader and the writer of the session exit then.
+    fn close(&self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+struct Session {
+    extranonce1: u32,
+    subscribed: bool,
+    workers: HashSet<String>,
+    handle: SessionHandle,
+}
+
+struct Shared {
+    jobs: Mutex<Jobs>,
+    // the sessions with authorized workers, which receive the new jobs
+    sessions: Mutex<HashMap<u32, SessionHandle>>,
+    connections: AtomicUsize,
+    max_connections: usize,
+    nonce_tx: Sender<(Byte32, Work, u128)>,
+    next_session_id: AtomicU32,
+    pb: ProgressBar,
+}
+
+pub struct StratumServer {
+    listener: TcpListener,
+    shared: Arc<Shared>,
+    worker_rx: Receiver<WorkerMessage>,
+}
+
+impl StratumServer {
+    pub fn new(
+        config: &MinerStratumConfig,
+        nonce_tx: Sender<(Byte32, Work, u128)>,
+        worker_rx: Receiver<WorkerMessage>,
+        pb: ProgressBar,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(config.listen)?;
+        let shared = Shared {
+            jobs: Mutex::new(Jobs::new(
+                config.share_difficulty,
+                config.extra_hash_function,
+            )),
+            sessions: Mutex::new(HashMap::new()),
+            connections: AtomicUsize::new(0),
+            max_connections: config.max_connections,
+            nonce_tx,
+            next_session_id: AtomicU32::new(0),
+            pb,
+        };
+        Ok(StratumServer {
+            listener,
+            shared: Arc::new(shared),
+            worker_rx,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accepts the downstream miners in the background, and distributes the new work until the
+    /// miner is closed.
+    pub fn run(self) {
+        if let Ok(addr) = self.local_addr() {
+            info!("Stratum server listens on {}", addr);
+        }
+        let StratumServer {
+            listener,
+            shared,
+            worker_rx,
+        } = self;
+
+        let accept_shared = Arc::clone(&shared);
+        thread::Builder::new()
+            .name("StratumListener".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => accept_shared.accept(stream),
+                        Err(err) => error!("Stratum server accept error {:?}", err),
+                    }
+                }
+            })
+            .expect("Start StratumListener thread failed");
+
+        while let Ok(message) = worker_rx.recv() {
+            match message {
+                WorkerMessage::NewWork {
+                    pow_hash,
+                    work,
+                    target,
+                } => shared.notify_new_job(pow_hash, work, target),
+                WorkerMessage::Stop => shared.jobs.lock().set_stale(true),
+                WorkerMessage::Start => shared.jobs.lock().set_stale(false),
+            }
+        }
+    }
+}
+
+impl Shared {
+    fn accept(self: &Arc<Self>, stream: TcpStream) {
+        let peer = stream.peer_addr().ok();
+        if self.connections.fetch_add(1, Ordering::SeqCst) >= self.max_connections {
+            self.connections.fetch_sub(1, Ordering::SeqCst);
+            debug!("Stratum server rejects {:?}, too many connections", peer);
+            return;
+        }
+        let shared = Arc::clone(self);
+        let spawned = thread::Builder::new()
+            .name("StratumSession".to_string())
+            .spawn(move || {
+                shared.serve(stream);
+                shared.connections.fetch_sub(1, Ordering::SeqCst);
+            });
+        if let Err(err) = spawned {
+            self.connections.fetch_sub(1, Ordering::SeqCst);
+            error!("Stratum session {:?} spawn thread error {:?}", peer, err);
+        }
+    }
+
+    fn serve(&self, stream: TcpStream) {
+        let peer = stream.peer_addr().ok();
+        let writer = match stream
+            .set_read_timeout(Some(HANDSHAKE_TIMEOUT))
+            .and_then(|_| stream.set_write_timeout(Some(WRITE_TIMEOUT)))
+            .and_then(|_| stream.try_clone())
+        {
+            Ok(writer) => writer,
+            Err(err) => {
+                error!("Stratum session {:?} setup stream error {:?}", peer, err);
+                return;
+            }
+        };
+        let session_id = self.next_session_id.fetch_add(1, Ordering::SeqCst);
+        let (queue, queue_rx) = bounded(MAX_PENDING_MESSAGES);
+        let writer_thread = match thread::Builder::new()
+            .name("StratumWriter".to_string())
+            .spawn(move || write_messages(writer, queue_rx))
+        {
+            Ok(writer_thread) => writer_thread,
+            Err(err) => {
+                error!("Stratum session {:?} spawn thread error {:?}", peer, err);
+                return;
+            }
+        };
+        let stream = Arc::new(stream);
+        let mut session = Session {
+            extranonce1: session_id,
+            subscribed: false,
+            workers: HashSet::new(),
+            handle: SessionHandle {
+                queue,
+                stream: Arc::clone(&stream),
+            },
+        };
+        debug!("Stratum session {} connected from {:?}", session_id, peer);
+
+        let connected_at = Instant::now();
+        let mut reader = BufReader::new(stream.as_ref());
+        let mut line = Vec::new();
+        loop {
+            if session.workers.is_empty() && connected_at.elapsed() > HANDSHAKE_TIMEOUT {
+                debug!("Stratum session {} handshake timeout", session_id);
+                break;
+            }
+            match read_line(&mut reader, &mut line) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(err) => {
+                    debug!("Stratum session {} read error {:?}", session_id, err);
+                    break;
+                }
+            }
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            let request: StratumRequest = match serde_json::from_slice(&line) {
+                Ok(request) => request,
+                Err(err) => {
+                    debug!("Stratum session {} invalid request {:?}", session_id, err);
+                    break;
+                }
+            };
+
+            let newly_authorized =
+                request.method == "mining.authorize" && session.workers.is_empty();
+            let result = self.handle_request(&mut session, &request.method, &request.params);
+            let response = match result {
+                Ok(result) => json!({ "id": request.id, "result": result, "error": null }),
+                Err((code, message)) => {
+                    json!({ "id": request.id, "result": null, "error": [code, message, null] })
+                }
+            };
+            if !session.handle.send(&response) {
+                break;
+            }
+
+            if newly_authorized && !session.workers.is_empty() {
+                if stream.set_read_timeout(None).is_err() {
+                    break;
+                }
+                // hold the jobs lock to queue the current job exactly once, before the new ones
+                let jobs = self.jobs.lock();
+                self.sessions
+                    .lock()
+                    .insert(session_id, session.handle.clone());
+                let queued = jobs.latest().map_or(true, |job| {
+                    job_notifications(job, jobs.share_target(job), true)
+                        .iter()
+                        .all(|notification| session.handle.send(notification))
+                });
+                if !queued {
+                    break;
+                }
+            }
+        }
+
+        self.sessions.lock().remove(&session_id);
+        session.handle.close();
+        drop(session);
+        let _ = writer_thread.join();
+        debug!("Stratum session {} disconnected", session_id);
+    }
+
+    fn handle_request(
+        &self,
+        session: &mut Session,
+        method: &str,
+        params: &[Value],
+    ) -> Result<Value, (i64, &'static str)> {
+        match method {
+            "mining.subscribe" => {
+                session.subscribed = true;
+                Ok(json!([
+                    null,
+                    format!("{:08x}", session.extranonce1),
+                    EXTRANONCE2_SIZE
+                ]))
+            }
+            "mining.authorize" => {
+                if !session.subscribed {
+                    return Err((ERROR_NOT_SUBSCRIBED, "Not subscribed"));
+                }
+                let worker = params
+                    .first()
+                    .and_then(Value::as_str)
+                    .filter(|worker| !worker.is_empty() && worker.len() <= MAX_WORKER_NAME_LENGTH)
+                    .ok_or((ERROR_OTHER, "Invalid worker name"))?;
+                if !session.workers.contains(worker)
+                    && session.workers.len() >= MAX_WORKERS_PER_SESSION
+                {
+                    return Err((ERROR_OTHER, "Too many workers"));
+                }
+                session.workers.insert(worker.to_owned());
+                Ok(json!(true))
+            }
+            "mining.submit" => {
+                let param = |index: usize| params.get(index).and_then(Value::as_str);
+                let worker = param(0).unwrap_or_default();
+                if !session.workers.contains(worker) {
+                    return Err((ERROR_UNAUTHORIZED, "Unauthorized worker"));
+                }
+                let job_id = param(1).ok_or((ERROR_OTHER, "Invalid job id"))?;
+                let extranonce2 = param(2)
+                    .and_then(parse_extranonce2)
+                    .ok_or((ERROR_OTHER, "Invalid extranonce2"))?;
+                let nonce =
+                    (u128::from(session.extranonce1) << (EXTRANONCE2_SIZE * 8)) | extranonce2;
+                self.submit(worker, job_id, nonce)
+                    .map(|_| json!(true))
+                    .map_err(|err| (err.code(), err.message()))
+            }
+            _ => Err((ERROR_OTHER, "Method not found")),
+        }
+    }
+
+    fn submit(&self, worker: &str, job_id: &str, nonce: u128) -> Result<(), ShareError> {
+        let mut jobs = self.jobs.lock();
+        let result = jobs.submit(worker, job_id, nonce);
+
+        let (accepted, rejected, blocks) =
+            jobs.stats()
+                .values()
+                .fold((0, 0, 0), |(accepted, rejected, blocks), stats| {
+                    (
+                        accepted + stats.accepted,
+                        rejected + stats.rejected,
+                        blocks + stats.blocks,
+                    )
+                });
+        self.pb.set_message(format!(
+            "shares accepted: {accepted}, rejected: {rejected}, blocks: {blocks}"
+        ));
+
+        match result {
+            Ok(Some((pow_hash, work))) => {
+                debug!(
+                    "Stratum worker {} found block, job {} work_id {} nonce {:x}",
+                    worker, job_id, work.work_id, nonce
+                );
+                if let Err(err) = self.nonce_tx.send((pow_hash, work, nonce)) {
+                    error!("nonce_tx send error {:?}", err);
+                }
+                Ok(())
+            }
+            Ok(None) => Ok(()),
+            Err(err) => {
+                debug!(
+                    "Stratum worker {} share rejected, job {} nonce {:x}: {:?}",
+                    worker, job_id, nonce, err
+                );
+                Err(err)
+            }
+        }
+    }
+
+    fn notify_new_job(&self, pow_hash: Byte32, work: Work, target: U256) {
+        let mut jobs = self.jobs.lock();
+        let work_id = work.work_id;
+        let clean = jobs.push(pow_hash, work, target);
+        let job = jobs.latest().expect("just pushed");
+        debug!(
+            "Stratum new job {} for work_id {}, clean_jobs {}",
+            job.id, work_id, clean
+        );
+        let notifications = job_notifications(job, jobs.share_target(job), clean);
+
+        self.sessions.lock().retain(|session_id, handle| {
+            let queued = notifications
+                .iter()
+                .all(|notification| handle.send(notification));
+            if !queued {
+                debug!("Stratum session {} doesn't keep up, close it", session_id);
+                handle.close();
+            }
+            queued
+        });
+    }
+}
+
+fn job_notifications(job: &job::Job, share_target: U256, clean: bool) -> [Value; 2] {
+    [
+        json!({
+            "id": null,
+            "method": "mining.set_target",
+            "params": [hex_string(&share_target.to_be_bytes())],
+        }),
+        json!({
+            "id": null,
+            "method": "mining.notify",
+            "params": [
+                job.id,
+                hex_string(job.pow_hash.as_slice()),
+                job.number(),
+                hex_string(job.parent_hash().as_slice()),
+                clean,
+            ],
+        }),
+    ]
+}
+
+fn parse_extranonce2(hex: &str) -> Option<u128> {
+    if hex.len() != EXTRANONCE2_SIZE * 2 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u128::from_str_radix(hex, 16).ok()
+}
+
+// Reads a line without the newline into the buffer, returns false at the end of the stream.
+fn read_line<R: BufRead>(reader: &mut R, line: &mut Vec<u8>) -> io::Result<bool> {
+    line.clear();
+    let read = reader
+        .take(MAX_LINE_LENGTH as u64 + 1)
+        .read_until(b'\n', line)?;
+    if read == 0 {
+        return Ok(false);
+    }
+    if line.last() == Some(&b'\n') {
+        line.pop();
+    } else if line.len() > MAX_LINE_LENGTH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the request is too long",
+        ));
+    }
+    Ok(true)
+}
+
+fn write_messages(mut stream: TcpStream, queue: Receiver<Vec<u8>>) {
+    for message in queue {
+        if stream.write_all(&message).is_err() {
+            let _ = stream.shutdown(Shutdown::Both);
+            break;
+        }
+    }
+}
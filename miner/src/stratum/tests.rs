@@ -0,0 +1,353 @@
+use super::job::{Jobs, ShareError, MAX_WORKER_STATS};
+use super::{StratumServer, MAX_LINE_LENGTH, MAX_WORKERS_PER_SESSION};
+use crate::worker::WorkerMessage;
+use crate::Work;
+use ckb_app_config::MinerStratumConfig;
+use ckb_channel::{unbounded, Receiver, Sender};
+use ckb_types::{
+    packed::{Block, Byte32, Header, RawHeader},
+    prelude::*,
+    U256,
+};
+use indicatif::ProgressBar;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+fn new_work(work_id: u64, parent_hash: Byte32) -> (Byte32, Work) {
+    let raw = RawHeader::new_builder()
+        .number(1u64.pack())
+        .parent_hash(parent_hash)
+        .timestamp(work_id.pack())
+        .build();
+    let block = Block::new_builder()
+        .header(Header::new_builder().raw(raw).build())
+        .build();
+    let pow_hash = block.header().calc_pow_hash();
+    (pow_hash, Work { work_id, block })
+}
+
+fn start_server(
+    share_difficulty: u64,
+    max_connections: usize,
+) -> (
+    SocketAddr,
+    Sender<WorkerMessage>,
+    Receiver<(Byte32, Work, u128)>,
+) {
+    let config = MinerStratumConfig {
+        listen: "127.0.0.1:0".parse().unwrap(),
+        share_difficulty,
+        extra_hash_function: None,
+        max_connections,
+    };
+    let (nonce_tx, nonce_rx) = unbounded();
+    let (worker_tx, worker_rx) = unbounded();
+    let server = StratumServer::new(&config, nonce_tx, worker_rx, ProgressBar::hidden()).unwrap();
+    let addr = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+    (addr, worker_tx, nonce_rx)
+}
+
+fn send_work(worker_tx: &Sender<WorkerMessage>, work_id: u64, parent_hash: Byte32, target: U256) {
+    let (pow_hash, work) = new_work(work_id, parent_hash);
+    worker_tx
+        .send(WorkerMessage::NewWork {
+            pow_hash,
+            work,
+            target,
+        })
+        .unwrap();
+}
+
+struct TestClient {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl TestClient {
+    fn connect(addr: SocketAddr) -> Self {
+        let stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        TestClient {
+            reader: BufReader::new(stream.try_clone().unwrap()),
+            writer: stream,
+        }
+    }
+
+    fn is_closed(&mut self) -> bool {
+        let mut line = String::new();
+        matches!(self.reader.read_line(&mut line), Ok(0) | Err(_))
+    }
+
+    fn recv(&mut self) -> Value {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    fn call(&mut self, id: u64, method: &str, params: Value) -> Value {
+        let request = json!({ "id": id, "method": method, "params": params });
+        writeln!(self.writer, "{request}").unwrap();
+        let response = self.recv();
+        assert_eq!(response["id"], json!(id));
+        response
+    }
+
+    // returns the share target and the params of `mining.notify`
+    fn recv_job(&mut self) -> (Value, Value) {
+        let set_target = self.recv();
+        assert_eq!(set_target["method"], json!("mining.set_target"));
+        let notify = self.recv();
+        assert_eq!(notify["method"], json!("mining.notify"));
+        (set_target["params"][0].clone(), notify["params"].clone())
+    }
+}
+
+#[test]
+fn test_stratum_session() {
+    let (addr, worker_tx, nonce_rx) = start_server(1, 16);
+    let parent_hash = Byte32::zero();
+    send_work(&worker_tx, 1, parent_hash.clone(), U256::zero());
+
+    let mut client = TestClient::connect(addr);
+    let response = client.call(1, "mining.authorize", json!(["worker", "x"]));
+    assert_eq!(response["error"][0], json!(25));
+
+    let response = client.call(2, "mining.subscribe", json!(["test-miner"]));
+    assert_eq!(response["result"], json!([null, "00000000", 12]));
+    let response = client.call(
+        3,
+        "mining.submit",
+        json!(["worker", "0", "000000000000000000000001"]),
+    );
+    assert_eq!(response["error"][0], json!(24));
+
+    let response = client.call(4, "mining.authorize", json!(["worker", "x"]));
+    assert_eq!(response["result"], json!(true));
+    let (share_target, notify) = client.recv_job();
+    assert_eq!(share_target, json!("f".repeat(64)));
+    assert_eq!(notify[0], json!("0"));
+    assert_eq!(notify[2], json!(1));
+    assert_eq!(notify[3], json!("0".repeat(64)));
+    assert_eq!(notify[4], json!(true));
+
+    // every share is accepted, but none meets the block target
+    let response = client.call(
+        5,
+        "mining.submit",
+        json!(["worker", "0", "000000000000000000000001"]),
+    );
+    assert_eq!(response["result"], json!(true));
+    assert!(nonce_rx.try_recv().is_err());
+    let response = client.call(
+        6,
+        "mining.submit",
+        json!(["worker", "0", "000000000000000000000001"]),
+    );
+    assert_eq!(response["error"][0], json!(22));
+    let response = client.call(7, "mining.submit", json!(["worker", "0", "01"]));
+    assert_eq!(response["error"][0], json!(20));
+    let response = client.call(
+        8,
+        "mining.submit",
+        json!(["worker", "ff", "000000000000000000000001"]),
+    );
+    assert_eq!(response["error"][0], json!(21));
+
+    // the share meeting the block target is sent back with the work of the job
+    send_work(&worker_tx, 2, parent_hash, U256::max_value());
+    let (_share_target, notify) = client.recv_job();
+    assert_eq!(notify[0], json!("1"));
+    assert_eq!(notify[4], json!(false));
+    let response = client.call(
+        9,
+        "mining.submit",
+        json!(["worker", "1", "000000000000000000000002"]),
+    );
+    assert_eq!(response["result"], json!(true));
+    let (pow_hash, work, nonce) = nonce_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(work.work_id, 2);
+    assert_eq!(pow_hash, work.block.header().calc_pow_hash());
+    assert_eq!(nonce, 2);
+
+    // the jobs on the previous parent are stale
+    send_work(&worker_tx, 3, Byte32::new([1u8; 32]), U256::zero());
+    let (_share_target, notify) = client.recv_job();
+    assert_eq!(notify[0], json!("2"));
+    assert_eq!(notify[4], json!(true));
+    let response = client.call(
+        10,
+        "mining.submit",
+        json!(["worker", "1", "000000000000000000000003"]),
+    );
+    assert_eq!(response["error"][0], json!(21));
+
+    // the sessions take disjoint nonce spaces
+    let mut other = TestClient::connect(addr);
+    let response = other.call(1, "mining.subscribe", json!([]));
+    assert_eq!(response["result"], json!([null, "00000001", 12]));
+}
+
+#[test]
+fn test_stratum_stop() {
+    let (addr, worker_tx, nonce_rx) = start_server(1, 16);
+    let parent_hash = Byte32::zero();
+    send_work(&worker_tx, 1, parent_hash.clone(), U256::max_value());
+
+    let mut client = TestClient::connect(addr);
+    client.call(1, "mining.subscribe", json!([]));
+    client.call(2, "mining.authorize", json!(["worker", "x"]));
+    client.recv_job();
+
+    // the block is found, and the miner stops the work to submit it
+    let response = client.call(
+        3,
+        "mining.submit",
+        json!(["worker", "0", "000000000000000000000001"]),
+    );
+    assert_eq!(response["result"], json!(true));
+    let (_pow_hash, work, _nonce) = nonce_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(work.work_id, 1);
+    worker_tx.send(WorkerMessage::Stop).unwrap();
+    // wait for the server to take the message
+    thread::sleep(Duration::from_millis(200));
+
+    // the session is kept, the shares of the stale job are accepted but not sent back
+    let response = client.call(
+        4,
+        "mining.submit",
+        json!(["worker", "0", "000000000000000000000002"]),
+    );
+    assert_eq!(response["result"], json!(true));
+    assert!(nonce_rx.try_recv().is_err());
+
+    // the next job replaces the stale ones, even on the same parent
+    send_work(&worker_tx, 2, parent_hash.clone(), U256::max_value());
+    worker_tx.send(WorkerMessage::Start).unwrap();
+    let (_share_target, notify) = client.recv_job();
+    assert_eq!(notify[0], json!("1"));
+    assert_eq!(notify[4], json!(true));
+    let response = client.call(
+        5,
+        "mining.submit",
+        json!(["worker", "0", "000000000000000000000003"]),
+    );
+    assert_eq!(response["error"][0], json!(21));
+    let response = client.call(
+        6,
+        "mining.submit",
+        json!(["worker", "1", "000000000000000000000003"]),
+    );
+    assert_eq!(response["result"], json!(true));
+    let (_pow_hash, work, _nonce) = nonce_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(work.work_id, 2);
+
+    // the work resumes on the same job when the found block is an uncle
+    worker_tx.send(WorkerMessage::Stop).unwrap();
+    worker_tx.send(WorkerMessage::Start).unwrap();
+    let response = client.call(
+        7,
+        "mining.submit",
+        json!(["worker", "1", "000000000000000000000004"]),
+    );
+    assert_eq!(response["result"], json!(true));
+    let (_pow_hash, work, _nonce) = nonce_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(work.work_id, 2);
+}
+
+#[test]
+fn test_stratum_limits() {
+    let (addr, _worker_tx, _nonce_rx) = start_server(1, 1);
+
+    let mut client = TestClient::connect(addr);
+    client.call(1, "mining.subscribe", json!([]));
+    for i in 0..MAX_WORKERS_PER_SESSION {
+        let response = client.call(2, "mining.authorize", json!([format!("worker{i}"), "x"]));
+        assert_eq!(response["result"], json!(true));
+    }
+    let response = client.call(3, "mining.authorize", json!(["other", "x"]));
+    assert_eq!(response["error"][1], json!("Too many workers"));
+    let response = client.call(4, "mining.authorize", json!(["", "x"]));
+    assert_eq!(response["error"][1], json!("Invalid worker name"));
+
+    // the connections exceeding the limit are closed
+    let mut other = TestClient::connect(addr);
+    assert!(other.is_closed());
+
+    // the session sending a too long request is closed
+    client
+        .writer
+        .write_all(&vec![b' '; MAX_LINE_LENGTH + 1])
+        .unwrap();
+    assert!(client.is_closed());
+
+    // the connection is accepted once the previous one is closed
+    let accepted = (0..100).any(|_| {
+        thread::sleep(Duration::from_millis(50));
+        let mut other = TestClient::connect(addr);
+        let request = json!({ "id": 1, "method": "mining.subscribe", "params": [] });
+        writeln!(other.writer, "{request}").is_ok() && !other.is_closed()
+    });
+    assert!(accepted);
+}
+
+#[test]
+fn test_stratum_share_accounting() {
+    let (pow_hash, work) = new_work(1, Byte32::zero());
+    let mut jobs = Jobs::new(u64::MAX, None);
+    assert!(jobs.push(pow_hash.clone(), work.clone(), U256::zero()));
+
+    let job = jobs.latest().unwrap();
+    assert_eq!(
+        jobs.share_target(job),
+        U256::max_value() / U256::from(u64::MAX)
+    );
+    // the odds to reach the share target are 2^-64
+    assert!(matches!(
+        jobs.submit("a", "0", 1),
+        Err(ShareError::LowDifficulty)
+    ));
+    assert!(matches!(
+        jobs.submit("a", "1", 1),
+        Err(ShareError::JobNotFound)
+    ));
+
+    // the share target is never harder than the block target
+    let mut jobs = Jobs::new(u64::MAX, None);
+    jobs.push(pow_hash.clone(), work.clone(), U256::max_value());
+    assert!(jobs.submit("a", "0", 1).unwrap().is_some());
+    assert!(jobs.submit("b", "0", 2).unwrap().is_some());
+    assert!(matches!(
+        jobs.submit("b", "0", 2),
+        Err(ShareError::Duplicate)
+    ));
+
+    let stats = jobs.stats();
+    assert_eq!(
+        (stats["a"].accepted, stats["a"].rejected, stats["a"].blocks),
+        (1, 0, 1)
+    );
+    assert_eq!(
+        (stats["b"].accepted, stats["b"].rejected, stats["b"].blocks),
+        (1, 1, 1)
+    );
+
+    // the jobs on the same parent are kept
+    assert!(!jobs.push(pow_hash, work, U256::max_value()));
+    assert_eq!(jobs.latest().unwrap().id, "1");
+    assert!(jobs.submit("a", "0", 3).unwrap().is_some());
+
+    // the least recently active workers are dropped from the accounting
+    for i in 0..MAX_WORKER_STATS - 1 {
+        assert!(jobs.submit(&format!("w{i}"), "ff", 0).is_err());
+    }
+    let stats = jobs.stats();
+    assert_eq!(stats.len(), MAX_WORKER_STATS);
+    assert!(!stats.contains_key("b"));
+    assert_eq!(stats["a"].blocks, 2);
+}
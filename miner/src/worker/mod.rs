@@ -1,6 +1,7 @@
 mod dummy;
 mod eaglesong_simple;
 
+use crate::stratum::StratumServer;
 use crate::Work;
 use ckb_app_config::MinerWorkerConfig;
 use ckb_channel::{unbounded, Sender};
@@ -130,6 +131,33 @@ pub fn start_worker(
                 panic!("incompatible pow engine and worker type");
             }
         }
+        MinerWorkerConfig::Stratum(config) => {
+            if pow.as_any().downcast_ref::<EaglesongPowEngine>().is_some()
+                || pow
+                    .as_any()
+                    .downcast_ref::<EaglesongBlake2bPowEngine>()
+                    .is_some()
+            {
+                let worker_name = "Stratum-Server";
+                let pb = mp.add(ProgressBar::new(100));
+                pb.set_style(ProgressStyle::default_bar().template(PROGRESS_BAR_TEMPLATE));
+                pb.set_prefix(worker_name);
+
+                let (worker_tx, worker_rx) = unbounded();
+                let server = StratumServer::new(config, nonce_tx, worker_rx, pb)
+                    .expect("Start Stratum server failed");
+
+                thread::Builder::new()
+                    .name(worker_name.to_string())
+                    .spawn(move || {
+                        server.run();
+                    })
+                    .expect("Start `Stratum` server thread failed");
+                WorkerController::new(vec![worker_tx])
+            } else {
+                panic!("incompatible pow engine and worker type");
+            }
+        }
     }
 }
 
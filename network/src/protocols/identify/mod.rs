@@ -639,5 +639,7 @@ bitflags::bitflags! {
         const LIGHT_CLIENT = 0b10000;
         /// Client-side block filter protocol can provide BlockFilter download service
         const BLOCK_FILTER = 0b100000;
+        /// Pruned node, which only keeps the recent block bodies and cannot serve the ancient blocks
+        const PRUNED = 0b1000000;
     }
 }
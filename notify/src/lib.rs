@@ -5,7 +5,10 @@ use ckb_logger::{debug, error, info, trace};
 use ckb_stop_handler::{new_tokio_exit_rx, CancellationToken};
 use ckb_types::packed::Byte32;
 use ckb_types::{
-    core::{tx_pool::Reject, BlockView},
+    core::{
+        tx_pool::{Reject, TxConflict},
+        BlockView,
+    },
     packed::Alert,
 };
 use std::{collections::HashMap, time::Duration};
@@ -97,6 +100,8 @@ pub struct NotifyController {
     proposed_transaction_notifier: Sender<PoolTransactionEntry>,
     reject_transaction_register: NotifyRegister<(PoolTransactionEntry, Reject)>,
     reject_transaction_notifier: Sender<(PoolTransactionEntry, Reject)>,
+    conflict_transaction_register: NotifyRegister<(PoolTransactionEntry, TxConflict)>,
+    conflict_transaction_notifier: Sender<(PoolTransactionEntry, TxConflict)>,
     network_alert_register: NotifyRegister<Alert>,
     network_alert_notifier: Sender<Alert>,
     handle: Handle,
@@ -110,6 +115,7 @@ pub struct NotifyService {
     new_transaction_subscribers: HashMap<String, Sender<PoolTransactionEntry>>,
     proposed_transaction_subscribers: HashMap<String, Sender<PoolTransactionEntry>>,
     reject_transaction_subscribers: HashMap<String, Sender<(PoolTransactionEntry, Reject)>>,
+    conflict_transaction_subscribers: HashMap<String, Sender<(PoolTransactionEntry, TxConflict)>>,
     network_alert_subscribers: HashMap<String, Sender<Alert>>,
     timeout: NotifyTimeout,
     handle: Handle,
@@ -127,6 +133,7 @@ impl NotifyService {
             new_transaction_subscribers: HashMap::default(),
             proposed_transaction_subscribers: HashMap::default(),
             reject_transaction_subscribers: HashMap::default(),
+            conflict_transaction_subscribers: HashMap::default(),
             network_alert_subscribers: HashMap::default(),
             timeout,
             handle,
@@ -159,6 +166,11 @@ impl NotifyService {
         let (reject_transaction_sender, mut reject_transaction_receiver) =
             mpsc::channel(NOTIFY_CHANNEL_SIZE);
 
+        let (conflict_transaction_register, mut conflict_transaction_register_receiver) =
+            mpsc::channel(REGISTER_CHANNEL_SIZE);
+        let (conflict_transaction_sender, mut conflict_transaction_receiver) =
+            mpsc::channel(NOTIFY_CHANNEL_SIZE);
+
         let (network_alert_register, mut network_alert_register_receiver) =
             mpsc::channel(REGISTER_CHANNEL_SIZE);
         let (network_alert_sender, mut network_alert_receiver) = mpsc::channel(NOTIFY_CHANNEL_SIZE);
@@ -175,6 +187,8 @@ impl NotifyService {
                     Some(msg) = proposed_transaction_receiver.recv() => { self.handle_notify_proposed_transaction(msg) },
                     Some(msg) = reject_transaction_register_receiver.recv() => { self.handle_register_reject_transaction(msg) },
                     Some(msg) = reject_transaction_receiver.recv() => { self.handle_notify_reject_transaction(msg) },
+                    Some(msg) = conflict_transaction_register_receiver.recv() => { self.handle_register_conflict_transaction(msg) },
+                    Some(msg) = conflict_transaction_receiver.recv() => { self.handle_notify_conflict_transaction(msg) },
                     Some(msg) = network_alert_register_receiver.recv() => { self.handle_register_network_alert(msg) },
                     Some(msg) = network_alert_receiver.recv() => { self.handle_notify_network_alert(msg) },
                     _ = signal_receiver.cancelled() => {
@@ -196,6 +210,8 @@ impl NotifyService {
             proposed_transaction_notifier: proposed_transaction_sender,
             reject_transaction_register,
             reject_transaction_notifier: reject_transaction_sender,
+            conflict_transaction_register,
+            conflict_transaction_notifier: conflict_transaction_sender,
             network_alert_register,
             network_alert_notifier: network_alert_sender,
             handle,
@@ -354,6 +370,35 @@ impl NotifyService {
         }
     }
 
+    fn handle_register_conflict_transaction(
+        &mut self,
+        msg: Request<String, Receiver<(PoolTransactionEntry, TxConflict)>>,
+    ) {
+        let Request {
+            responder,
+            arguments: name,
+        } = msg;
+        debug!("Register conflict_transaction {:?}", name);
+        let (sender, receiver) = mpsc::channel(NOTIFY_CHANNEL_SIZE);
+        self.conflict_transaction_subscribers.insert(name, sender);
+        let _ = responder.send(receiver);
+    }
+
+    fn handle_notify_conflict_transaction(&self, tx_entry: (PoolTransactionEntry, TxConflict)) {
+        trace!("Tx conflict event {:?}", tx_entry);
+        let tx_timeout = self.timeout.tx;
+        // notify all subscribers
+        for subscriber in self.conflict_transaction_subscribers.values() {
+            let tx_entry = tx_entry.clone();
+            let subscriber = subscriber.clone();
+            self.handle.spawn(async move {
+                if let Err(e) = subscriber.send_timeout(tx_entry, tx_timeout).await {
+                    error!("Failed to notify transaction conflict, error: {}", e);
+                }
+            });
+        }
+    }
+
     fn handle_register_network_alert(&mut self, msg: Request<String, Receiver<Alert>>) {
         let Request {
             responder,
@@ -493,6 +538,33 @@ impl NotifyController {
         });
     }
 
+    /// Subscribes the pool transactions which are replaced or double spent by other transactions.
+    pub async fn subscribe_conflict_transaction<S: ToString>(
+        &self,
+        name: S,
+    ) -> Receiver<(PoolTransactionEntry, TxConflict)> {
+        Request::call(&self.conflict_transaction_register, name.to_string())
+            .await
+            .expect("Subscribe conflicted transaction should be OK")
+    }
+
+    /// Notifies that the pool transaction is replaced or double spent by another transaction.
+    pub fn notify_conflict_transaction(
+        &self,
+        tx_entry: PoolTransactionEntry,
+        conflict: TxConflict,
+    ) {
+        let conflict_transaction_notifier = self.conflict_transaction_notifier.clone();
+        self.handle.spawn(async move {
+            if let Err(e) = conflict_transaction_notifier
+                .send((tx_entry, conflict))
+                .await
+            {
+                error!("notify_conflict_transaction channel is closed: {}", e);
+            }
+        });
+    }
+
     /// TODO(doc): @quake
     pub async fn subscribe_network_alert<S: ToString>(&self, name: S) -> Receiver<Alert> {
         Request::call(&self.network_alert_register, name.to_string())
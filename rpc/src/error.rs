@@ -1,4 +1,5 @@
 use ckb_error::{AnyError, Error as CKBError, ErrorKind, InternalError, InternalErrorKind};
+use ckb_store::BlockPruned;
 use ckb_tx_pool::error::Reject;
 use jsonrpc_core::{Error, ErrorCode, Value};
 use schemars::JsonSchema;
@@ -77,6 +78,11 @@ pub enum RPCError {
     /// This is a fatal error usually caused by the underlying database used by CKB. Please back up
     /// the data directory and re-sync the chain from scratch.
     DatabaseIsCorrupt = -202,
+    /// (-203): The block body has been pruned.
+    ///
+    /// The node runs in pruned mode, see the config option `store.freezer_prune_depth`. The
+    /// headers are still available but the old block bodies and transactions are deleted.
+    BlockIsPruned = -203,
     /// (-301): Failed to resolve the referenced cells and headers used in the transaction, as inputs or
     /// dependencies.
     TransactionFailedToResolve = -301,
@@ -168,11 +174,14 @@ impl RPCError {
     /// Creates an RPC error from the reason that a transaction is rejected to be submitted.
     pub fn from_submit_transaction_reject(reject: &Reject) -> Error {
         let code = match reject {
-            Reject::LowFeeRate(_, _, _) => RPCError::PoolRejectedTransactionByMinFeeRate,
+            Reject::LowFeeRate(_, _, _)
+            | Reject::ExceededLowFeeParentsLimit(_)
+            | Reject::LowPackageFeeRate(_, _, _)
+            | Reject::LowCellDepChurnFee(_, _, _) => RPCError::PoolRejectedTransactionByMinFeeRate,
             Reject::ExceededMaximumAncestorsCount => {
                 RPCError::PoolRejectedTransactionByMaxAncestorsCountLimit
             }
-            Reject::Full(_) => RPCError::PoolIsFull,
+            Reject::Full(_) | Reject::ExceededCellDepReferencesLimit(_) => RPCError::PoolIsFull,
             Reject::Duplicated(_) => RPCError::PoolRejectedDuplicatedTransaction,
             Reject::Malformed(_, _) => RPCError::PoolRejectedMalformedTransaction,
             Reject::DeclaredWrongCycles(..) => RPCError::PoolRejectedMalformedTransaction,
@@ -234,6 +243,11 @@ impl RPCError {
         Self::custom_with_error(RPCError::CKBInternalError, err)
     }
 
+    /// RPC error which indicates that the block body has been deleted in the pruned mode.
+    pub fn block_is_pruned(err: BlockPruned) -> Error {
+        Self::custom(RPCError::BlockIsPruned, err)
+    }
+
     /// RPC error which indicates that the method is disabled.
     ///
     /// RPC methods belong to modules and they are only enabled when the belonging module is
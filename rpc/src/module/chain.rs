@@ -3,20 +3,28 @@ use crate::util::FeeRateCollector;
 use async_trait::async_trait;
 use ckb_jsonrpc_types::{
     BlockEconomicState, BlockFilter, BlockNumber, BlockResponse, BlockView, CellWithStatus,
-    Consensus, EpochNumber, EpochView, EstimateCycles, FeeRateStatistics, HeaderView, OutPoint,
-    ResponseFormat, ResponseFormatInnerType, Timestamp, Transaction, TransactionAndWitnessProof,
-    TransactionProof, TransactionWithStatusResponse, Uint32, Uint64,
+    ChildVmKind, ChildVmTrace, Consensus, EpochNumber, EpochView, EstimateCycles,
+    FeeRateStatistics, HeaderView, JsonBytes, OutPoint, ResponseFormat, ResponseFormatInnerType,
+    ScriptGroupProfile, ScriptGroupTrace, ScriptGroupType, StateOverrides, Timestamp, Transaction,
+    TransactionAndWitnessProof, TransactionProof, TransactionTrace, TransactionWithStatusResponse,
+    Uint32, Uint64,
 };
 use ckb_logger::error;
 use ckb_reward_calculator::RewardCalculator;
 use ckb_shared::{shared::Shared, Snapshot};
-use ckb_store::{data_loader_wrapper::AsDataLoader, ChainStore};
+use ckb_store::{
+    data_loader_wrapper::{self, AsDataLoader, DataLoaderWrapper, OverrideDataLoader},
+    ChainStore,
+};
 use ckb_traits::HeaderFieldsProvider;
 use ckb_types::core::tx_pool::TransactionWithStatus;
 use ckb_types::{
     core::{
         self,
-        cell::{resolve_transaction, CellProvider, CellStatus, HeaderChecker},
+        cell::{
+            resolve_transaction, CellMetaBuilder, CellProvider, CellStatus, HeaderChecker,
+            ResolvedTransaction,
+        },
         error::OutPointError,
     },
     packed,
@@ -24,11 +32,15 @@ use ckb_types::{
     utilities::{merkle_root, MerkleProof, CBMT},
     H256,
 };
-use ckb_verification::ScriptVerifier;
 use ckb_verification::TxVerifyEnv;
-use jsonrpc_core::Result;
+use ckb_verification::{
+    ContextualTransactionVerifier, ScriptVerifier, TimeRelativeTransactionVerifier,
+};
+use jsonrpc_core::{Error, Result};
 use jsonrpc_utils::rpc;
-use std::collections::HashSet;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
 use std::sync::Arc;
 
 /// RPC Module Chain for methods related to the canonical chain.
@@ -81,6 +93,10 @@ pub trait ChainRpc {
     /// When `verbosity` is 0, it returns a 0x-prefixed hex string as the `result`. The string
     /// encodes the block serialized by molecule using schema `table Block`.
     ///
+    /// ## Errors
+    ///
+    /// * [`BlockIsPruned (-203)`](../enum.RPCError.html#variant.BlockIsPruned) - The block body has been deleted because the node runs in pruned mode.
+    ///
     /// ## Examples
     ///
     /// Request
@@ -214,6 +230,7 @@ pub trait ChainRpc {
     ///
     /// * [`ChainIndexIsInconsistent (-201)`](../enum.RPCError.html#variant.ChainIndexIsInconsistent) - The index is inconsistent. It says a block hash is in the main chain, but cannot read it from the database.
     /// * [`DatabaseIsCorrupt (-202)`](../enum.RPCError.html#variant.DatabaseIsCorrupt) - The data read from database is dirty. Please report it as a bug.
+    /// * [`BlockIsPruned (-203)`](../enum.RPCError.html#variant.BlockIsPruned) - The block body has been deleted because the node runs in pruned mode.
     ///
     /// ## Examples
     ///
@@ -539,6 +556,10 @@ pub trait ChainRpc {
     ///
     /// If the transaction is in the chain, the block hash is also returned.
     ///
+    /// When the node is configured with `store.tx_index_prune_depth`, the committed
+    /// transaction is only returned if it is in the recent blocks within the depth, or it still
+    /// has live cells. Otherwise it's reported as `unknown`.
+    ///
     /// ## Params
     ///
     /// * `tx_hash` - Hash of a transaction
@@ -555,6 +576,10 @@ pub trait ChainRpc {
     /// When verbosity is 2: if tx_status.status is pending, proposed, or committed,
     /// the RPC returns the transaction content as field transaction, otherwise the field is null.
     ///
+    /// ## Errors
+    ///
+    /// * [`BlockIsPruned (-203)`](../enum.RPCError.html#variant.BlockIsPruned) - The committed transaction has been deleted with its block body because the node runs in pruned mode.
+    ///
     /// ## Examples
     ///
     /// Request
@@ -1938,6 +1963,7 @@ impl ChainRpc for ChainRpcImpl {
 
         snapshot
             .get_block(&tx_proof.block_hash.pack())
+            .map_err(RPCError::block_is_pruned)?
             .ok_or_else(|| {
                 RPCError::invalid_params(format!("Cannot find block {:#x}", tx_proof.block_hash))
             })
@@ -2007,6 +2033,7 @@ impl ChainRpc for ChainRpcImpl {
         let snapshot = self.shared.snapshot();
         snapshot
             .get_block(&tx_proof.block_hash.pack())
+            .map_err(RPCError::block_is_pruned)?
             .ok_or_else(|| {
                 RPCError::invalid_params(format!("Cannot find block {:#x}", tx_proof.block_hash))
             })
@@ -2090,6 +2117,7 @@ impl ChainRpc for ChainRpcImpl {
         if verbosity == 2 {
             Ok(snapshot
                 .get_block(&block_hash)
+                .map_err(RPCError::block_is_pruned)?
                 .map(|block| ResponseFormat::json(block.into())))
         } else if verbosity == 0 {
             Ok(snapshot
@@ -2190,6 +2218,17 @@ impl ChainRpcImpl {
         only_committed: bool,
     ) -> Result<TransactionWithStatus> {
         let snapshot = self.shared.snapshot();
+        if let Some(tx_info) = snapshot.get_transaction_info(&tx_hash) {
+            if snapshot.is_block_body_pruned(tx_info.block_number) {
+                return Err(RPCError::custom(
+                    RPCError::BlockIsPruned,
+                    format!(
+                        "transaction {tx_hash:#x} is committed in the pruned block #{}",
+                        tx_info.block_number
+                    ),
+                ));
+            }
+        }
         if let Some((tx, tx_info)) = snapshot.get_transaction_with_info(&tx_hash) {
             let cycles = if tx_info.is_cellbase() {
                 None
@@ -2250,10 +2289,20 @@ impl ChainRpcImpl {
         // default false
         let with_cycles = with_cycles.unwrap_or(false);
 
+        if let Some(header) = snapshot.get_block_header(block_hash) {
+            if snapshot.is_block_body_pruned(header.number()) {
+                return Err(RPCError::custom(
+                    RPCError::BlockIsPruned,
+                    format!("block #{} {block_hash:#x} is pruned", header.number()),
+                ));
+            }
+        }
+
         // TODO: verbosity level == 1, output block only contains tx_hash in JSON format
         let block_view = if verbosity == 2 {
             snapshot
                 .get_block(block_hash)
+                .map_err(RPCError::block_is_pruned)?
                 .map(|block| ResponseFormat::json(block.into()))
         } else if verbosity == 0 {
             snapshot
@@ -2327,6 +2376,7 @@ impl ChainRpcImpl {
 
         snapshot
             .get_block(&retrieved_block_hash)
+            .map_err(RPCError::block_is_pruned)?
             .ok_or_else(|| {
                 let message = format!(
                     "Chain TransactionInfo says block {retrieved_block_hash:#x} existing, but that block is not in the database"
@@ -2369,40 +2419,460 @@ impl<'a> HeaderChecker for CyclesEstimator<'a> {
     }
 }
 
+// HistoricalCellProvider resolves the cells as of the given main chain block: the cells created
+// in the block or before, and not consumed before the block.
+//
+// The store only indexes the live cells, so it finds out whether a dead cell was consumed before
+// the block by scanning the inputs of the blocks, either from its creation to the block, or from
+// the block to the tip, whichever is shorter. The inputs of the scanned blocks are cached, and at
+// most `MAX_HISTORICAL_SCANNED_BLOCKS` blocks are scanned for a transaction.
+//
+// The history is unavailable when a scanned block body is pruned, or when the creating
+// transaction is not found while the transaction index is pruned.
+const MAX_HISTORICAL_SCANNED_BLOCKS: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum HistoryUnavailable {
+    // More than `MAX_HISTORICAL_SCANNED_BLOCKS` blocks have to be scanned.
+    TooManyBlocks,
+    // The body of the block is pruned or missing.
+    BlockPruned(core::BlockNumber),
+    // The transactions committed at and before the block are pruned from the index.
+    TxIndexPruned(core::BlockNumber),
+}
+
+impl HistoryUnavailable {
+    fn into_rpc_error(self, block_hash: &packed::Byte32) -> Error {
+        match self {
+            HistoryUnavailable::TooManyBlocks => RPCError::invalid_params(format!(
+                "Resolving the transaction as of block {block_hash} scans more than \
+                 {MAX_HISTORICAL_SCANNED_BLOCKS} blocks"
+            )),
+            HistoryUnavailable::BlockPruned(number) => RPCError::custom(
+                RPCError::BlockIsPruned,
+                format!(
+                    "The history as of block {block_hash} is unavailable, \
+                     the body of block #{number} is pruned"
+                ),
+            ),
+            HistoryUnavailable::TxIndexPruned(number) => RPCError::custom(
+                RPCError::BlockIsPruned,
+                format!(
+                    "The history as of block {block_hash} is unavailable, \
+                     the transaction index is pruned up to block #{number}"
+                ),
+            ),
+        }
+    }
+}
+
+pub(crate) struct HistoricalCellProvider<'a> {
+    snapshot: &'a Snapshot,
+    header: core::HeaderView,
+    scanned: RefCell<HashMap<core::BlockNumber, HashSet<packed::OutPoint>>>,
+    unavailable: Cell<Option<HistoryUnavailable>>,
+}
+
+impl<'a> HistoricalCellProvider<'a> {
+    pub(crate) fn new(snapshot: &'a Snapshot, header: core::HeaderView) -> Self {
+        HistoricalCellProvider {
+            snapshot,
+            header,
+            scanned: Default::default(),
+            unavailable: Cell::new(None),
+        }
+    }
+
+    // Returns why a cell was resolved as unknown because of the unavailable history.
+    pub(crate) fn unavailable(&self) -> Option<HistoryUnavailable> {
+        self.unavailable.get()
+    }
+
+    fn is_consumed_in(
+        &self,
+        out_point: &packed::OutPoint,
+        numbers: RangeInclusive<u64>,
+    ) -> std::result::Result<bool, HistoryUnavailable> {
+        let mut scanned = self.scanned.borrow_mut();
+        for number in numbers {
+            if !scanned.contains_key(&number) {
+                if scanned.len() >= MAX_HISTORICAL_SCANNED_BLOCKS {
+                    return Err(HistoryUnavailable::TooManyBlocks);
+                }
+                let block = match self
+                    .snapshot
+                    .get_block_hash(number)
+                    .map(|hash| self.snapshot.get_block(&hash))
+                {
+                    Some(Ok(Some(block))) => block,
+                    _ => return Err(HistoryUnavailable::BlockPruned(number)),
+                };
+                let inputs = block
+                    .transactions()
+                    .iter()
+                    .skip(1)
+                    .flat_map(|tx| tx.input_pts_iter())
+                    .collect();
+                scanned.insert(number, inputs);
+            }
+            if scanned[&number].contains(out_point) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn is_consumed_before_block(
+        &self,
+        out_point: &packed::OutPoint,
+        created: u64,
+    ) -> std::result::Result<bool, HistoryUnavailable> {
+        if self.snapshot.get_cell(out_point).is_some() {
+            return Ok(false);
+        }
+        let number = self.header.number();
+        let tip_number = self.snapshot.tip_number();
+        // a dead cell not consumed since the block must have been consumed before it
+        let (numbers, since_block) = if tip_number - number <= number - created {
+            (number..=tip_number, true)
+        } else if number > created {
+            (created..=number - 1, false)
+        } else {
+            return Ok(false);
+        };
+        if numbers.end() - numbers.start() >= MAX_HISTORICAL_SCANNED_BLOCKS as u64 {
+            return Err(HistoryUnavailable::TooManyBlocks);
+        }
+        let consumed = self.is_consumed_in(out_point, numbers)?;
+        Ok(consumed != since_block)
+    }
+}
+
+impl<'a> CellProvider for HistoricalCellProvider<'a> {
+    fn cell(&self, out_point: &packed::OutPoint, _eager_load: bool) -> CellStatus {
+        let tx_hash = out_point.tx_hash();
+        let (tx, tx_info) = match self.snapshot.get_transaction_with_info(&tx_hash) {
+            Some(found) if found.1.block_number <= self.header.number() => found,
+            Some(_) => return CellStatus::Unknown,
+            None => {
+                let unavailable = match self.snapshot.get_transaction_info(&tx_hash) {
+                    // committed in a pruned block
+                    Some(tx_info) if tx_info.block_number <= self.header.number() => {
+                        Some(HistoryUnavailable::BlockPruned(tx_info.block_number))
+                    }
+                    Some(_) => None,
+                    None => self
+                        .snapshot
+                        .get_tx_index_pruned_number()
+                        .map(HistoryUnavailable::TxIndexPruned),
+                };
+                if unavailable.is_some() {
+                    self.unavailable.set(unavailable);
+                }
+                return CellStatus::Unknown;
+            }
+        };
+        let index: usize = out_point.index().unpack();
+        let (output, data) = match tx.output_with_data(index) {
+            Some(output_with_data) => output_with_data,
+            None => return CellStatus::Unknown,
+        };
+        match self.is_consumed_before_block(out_point, tx_info.block_number) {
+            Ok(false) => {}
+            Ok(true) => return CellStatus::Dead,
+            Err(unavailable) => {
+                self.unavailable.set(Some(unavailable));
+                return CellStatus::Unknown;
+            }
+        }
+        CellStatus::live_cell(
+            CellMetaBuilder::from_cell_output(output, data)
+                .out_point(out_point.to_owned())
+                .transaction_info(tx_info)
+                .build(),
+        )
+    }
+}
+
+impl<'a> HeaderChecker for HistoricalCellProvider<'a> {
+    fn check_valid(&self, block_hash: &packed::Byte32) -> std::result::Result<(), OutPointError> {
+        match self.snapshot.get_block_number(block_hash) {
+            Some(number)
+                if number <= self.header.number() && self.snapshot.is_main_chain(block_hash) =>
+            {
+                Ok(())
+            }
+            _ => Err(OutPointError::InvalidHeader(block_hash.to_owned())),
+        }
+    }
+}
+
 impl<'a> CyclesEstimator<'a> {
     pub(crate) fn new(shared: &'a Shared) -> Self {
         Self { shared }
     }
 
     pub(crate) fn run(&self, tx: packed::Transaction) -> Result<EstimateCycles> {
+        let (verifier, max_cycles) = self.script_verifier(tx, None)?;
+        match verifier.verify(max_cycles) {
+            Ok(cycles) => Ok(EstimateCycles {
+                cycles: cycles.into(),
+            }),
+            Err(err) => Err(RPCError::custom_with_error(
+                RPCError::TransactionFailedToVerify,
+                err,
+            )),
+        }
+    }
+
+    pub(crate) fn run_with_overrides(
+        &self,
+        tx: packed::Transaction,
+        overrides: StateOverrides,
+    ) -> Result<EstimateCycles> {
         let snapshot = self.shared.cloned_snapshot();
         let consensus = snapshot.cloned_consensus();
-        match resolve_transaction(tx.into_view(), &mut HashSet::new(), self, self) {
-            Ok(resolved) => {
-                let max_cycles = consensus.max_block_cycles;
-                let tip_header = snapshot.tip_header();
-                let tx_env = TxVerifyEnv::new_submit(tip_header);
-                match ScriptVerifier::new(
-                    Arc::new(resolved),
-                    snapshot.as_data_loader(),
-                    consensus,
-                    Arc::new(tx_env),
-                )
-                .verify(max_cycles)
-                {
-                    Ok(cycles) => Ok(EstimateCycles {
-                        cycles: cycles.into(),
-                    }),
-                    Err(err) => Err(RPCError::custom_with_error(
-                        RPCError::TransactionFailedToVerify,
-                        err,
-                    )),
-                }
+
+        let mut state = data_loader_wrapper::StateOverrides::new();
+        for header in overrides.headers {
+            state.insert_header(packed::Header::from(header).into_view());
+        }
+        for cell in overrides.cells {
+            let mut builder =
+                CellMetaBuilder::from_cell_output(cell.output.into(), cell.data.into_bytes())
+                    .out_point(cell.out_point.into());
+            if let Some(block_hash) = cell.block_hash {
+                let block_hash = block_hash.pack();
+                let header = state
+                    .header(&block_hash)
+                    .cloned()
+                    .or_else(|| {
+                        snapshot
+                            .get_block_header(&block_hash)
+                            .filter(|_| snapshot.is_main_chain(&block_hash))
+                    })
+                    .ok_or_else(|| {
+                        RPCError::invalid_params(format!(
+                            "Cannot find block {block_hash} of the overridden cell"
+                        ))
+                    })?;
+                // the injected cells are never cellbase outputs
+                builder = builder.transaction_info(core::TransactionInfo::new(
+                    header.number(),
+                    header.epoch(),
+                    block_hash,
+                    1,
+                ));
             }
+            state.insert_cell(builder.build());
+        }
+
+        // the tip is replaced by a pseudo one with the overridden epoch, and the since checks
+        // use the overridden median time of it
+        let tip_header = snapshot.tip_header();
+        let median_time = match overrides.timestamp {
+            Some(timestamp) => timestamp.into(),
+            None => {
+                snapshot.block_median_time(&tip_header.hash(), consensus.median_time_block_count())
+            }
+        };
+        let tip_header = match overrides.epoch {
+            Some(epoch) => tip_header
+                .as_advanced_builder()
+                .epoch(core::EpochNumberWithFraction::from_full_value(epoch.into()).pack())
+                .build(),
+            None => tip_header.to_owned(),
+        };
+        state.set_block_median_time(tip_header.hash(), median_time);
+
+        let data_loader = OverrideDataLoader::new(snapshot.as_data_loader(), state);
+        let resolved = resolve_transaction(
+            tx.into_view(),
+            &mut HashSet::new(),
+            &data_loader,
+            &data_loader,
+        )
+        .map_err(|err| RPCError::custom_with_error(RPCError::TransactionFailedToResolve, err))?;
+        let resolved = Arc::new(resolved);
+        let tx_env = Arc::new(TxVerifyEnv::new_submit(&tip_header));
+        TimeRelativeTransactionVerifier::new(
+            Arc::clone(&resolved),
+            Arc::clone(&consensus),
+            data_loader.clone(),
+            Arc::clone(&tx_env),
+        )
+        .verify()
+        .map_err(|err| RPCError::custom_with_error(RPCError::TransactionFailedToVerify, err))?;
+
+        let max_cycles = consensus.max_block_cycles;
+        let verifier = ScriptVerifier::new(resolved, data_loader, consensus, tx_env);
+        match verifier.verify(max_cycles) {
+            Ok(cycles) => Ok(EstimateCycles {
+                cycles: cycles.into(),
+            }),
             Err(err) => Err(RPCError::custom_with_error(
-                RPCError::TransactionFailedToResolve,
+                RPCError::TransactionFailedToVerify,
                 err,
             )),
         }
     }
+
+    /// Verifies the transaction against the chain state as of the given main chain block, as if
+    /// it is committed in the block. Unlike `run`, it runs the full contextual verification,
+    /// including the since, maturity and capacity checks besides the scripts.
+    pub(crate) fn verify_at_block(
+        &self,
+        tx: packed::Transaction,
+        block_hash: packed::Byte32,
+    ) -> Result<EstimateCycles> {
+        let (snapshot, resolved, tx_env) = self.resolve(tx, Some(block_hash))?;
+        let consensus = snapshot.cloned_consensus();
+        let max_cycles = consensus.max_block_cycles;
+        ContextualTransactionVerifier::new(
+            Arc::new(resolved),
+            consensus,
+            snapshot.as_data_loader(),
+            Arc::new(tx_env),
+        )
+        .verify(max_cycles, false)
+        .map(|completed| EstimateCycles {
+            cycles: completed.cycles.into(),
+        })
+        .map_err(|err| RPCError::custom_with_error(RPCError::TransactionFailedToVerify, err))
+    }
+
+    // Resolves the transaction against the tip, or against the chain state as of the given block
+    // as if the transaction is committed in it.
+    fn resolve(
+        &self,
+        tx: packed::Transaction,
+        block_hash: Option<packed::Byte32>,
+    ) -> Result<(Arc<Snapshot>, ResolvedTransaction, TxVerifyEnv)> {
+        let snapshot = self.shared.cloned_snapshot();
+        let resolved = match block_hash {
+            Some(block_hash) => {
+                let header = snapshot
+                    .get_block_header(&block_hash)
+                    .filter(|_| snapshot.is_main_chain(&block_hash))
+                    .ok_or_else(|| {
+                        RPCError::invalid_params(format!(
+                            "Cannot find block {block_hash} in the main chain"
+                        ))
+                    })?;
+                let provider = HistoricalCellProvider::new(&snapshot, header);
+                let resolved =
+                    resolve_transaction(tx.into_view(), &mut HashSet::new(), &provider, &provider);
+                if resolved.is_err() {
+                    if let Some(unavailable) = provider.unavailable() {
+                        return Err(unavailable.into_rpc_error(&block_hash));
+                    }
+                }
+                resolved.map(|resolved| (resolved, TxVerifyEnv::new_commit(&provider.header)))
+            }
+            None => resolve_transaction(tx.into_view(), &mut HashSet::new(), self, self)
+                .map(|resolved| (resolved, TxVerifyEnv::new_submit(snapshot.tip_header()))),
+        };
+        let (resolved, tx_env) = resolved.map_err(|err| {
+            RPCError::custom_with_error(RPCError::TransactionFailedToResolve, err)
+        })?;
+        Ok((snapshot, resolved, tx_env))
+    }
+
+    // Creates the script verifier of the transaction against the tip, or against the chain state
+    // as of the given block as if the transaction is committed in it.
+    fn script_verifier(
+        &self,
+        tx: packed::Transaction,
+        block_hash: Option<packed::Byte32>,
+    ) -> Result<(ScriptVerifier<DataLoaderWrapper<Snapshot>>, core::Cycle)> {
+        let (snapshot, resolved, tx_env) = self.resolve(tx, block_hash)?;
+        let consensus = snapshot.cloned_consensus();
+        let max_cycles = consensus.max_block_cycles;
+        let verifier = ScriptVerifier::new(
+            Arc::new(resolved),
+            snapshot.as_data_loader(),
+            consensus,
+            Arc::new(tx_env),
+        );
+        Ok((verifier, max_cycles))
+    }
+
+    pub(crate) fn trace(
+        &self,
+        tx: packed::Transaction,
+        block_hash: Option<packed::Byte32>,
+    ) -> Result<TransactionTrace> {
+        let (mut verifier, max_cycles) = self.script_verifier(tx, block_hash)?;
+        let script_groups: Vec<ScriptGroupTrace> = verifier
+            .trace(max_cycles)
+            .into_iter()
+            .map(|trace| ScriptGroupTrace {
+                group_type: json_script_group_type(trace.group_type),
+                script_hash: trace.script_hash.unpack(),
+                cycles: trace.cycles.into(),
+                exit_code: trace.exit_code,
+                debug_messages: trace.debug_messages,
+                child_vms: trace
+                    .child_vms
+                    .into_iter()
+                    .map(|vm| ChildVmTrace {
+                        kind: match vm.kind {
+                            ckb_verification::ChildVmKind::Exec => ChildVmKind::Exec,
+                            ckb_verification::ChildVmKind::Spawn => ChildVmKind::Spawn,
+                        },
+                        program_hash: vm.program_hash.unpack(),
+                        argv: vm.argv.into_iter().map(JsonBytes::from_bytes).collect(),
+                        exit_code: vm.exit_code,
+                        cycles: vm.cycles.map(Into::into),
+                    })
+                    .collect(),
+                error: trace.error.map(|err| err.to_string()),
+            })
+            .collect();
+        let cycles = script_groups
+            .iter()
+            .fold(0u64, |acc, trace| acc.saturating_add(trace.cycles.value()));
+        Ok(TransactionTrace {
+            cycles: cycles.into(),
+            script_groups,
+        })
+    }
+
+    pub(crate) fn profile(
+        &self,
+        tx: packed::Transaction,
+        block_hash: Option<packed::Byte32>,
+    ) -> Result<Vec<ScriptGroupProfile>> {
+        let (mut verifier, max_cycles) = self.script_verifier(tx, block_hash)?;
+        let groups: Vec<_> = verifier
+            .groups_with_type()
+            .map(|(group_type, hash, _)| (group_type, hash.to_owned()))
+            .collect();
+        let mut cycles: core::Cycle = 0;
+        Ok(groups
+            .into_iter()
+            .map(|(group_type, hash)| {
+                let result = verifier.profile(group_type, &hash, max_cycles.saturating_sub(cycles));
+                let profile = result.as_ref().ok();
+                let group_cycles = profile.map(|profile| profile.cycles).unwrap_or_default();
+                cycles = cycles.saturating_add(group_cycles);
+                ScriptGroupProfile {
+                    group_type: json_script_group_type(group_type),
+                    script_hash: hash.unpack(),
+                    cycles: group_cycles.into(),
+                    folded_stacks: profile
+                        .map(|profile| profile.folded_stacks())
+                        .unwrap_or_default(),
+                    error: result.err().map(|err| err.to_string()),
+                }
+            })
+            .collect())
+    }
+}
+
+fn json_script_group_type(group_type: ckb_verification::ScriptGroupType) -> ScriptGroupType {
+    match group_type {
+        ckb_verification::ScriptGroupType::Lock => ScriptGroupType::Lock,
+        ckb_verification::ScriptGroupType::Type => ScriptGroupType::Type,
+    }
 }
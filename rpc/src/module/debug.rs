@@ -1,8 +1,21 @@
+use crate::error::RPCError;
+use crate::module::chain::CyclesEstimator;
 use async_trait::async_trait;
-use ckb_jsonrpc_types::{ExtraLoggerConfig, MainLoggerConfig};
+use ckb_chain::chain::{ChainController, InvalidateBlockError};
+use ckb_error::Error as CKBError;
+use ckb_jsonrpc_types::{
+    ExtraLoggerConfig, InvalidBlock, MainLoggerConfig, ScriptGroupProfile, Transaction,
+    TxIndexState, TxIndexStatus,
+};
 use ckb_logger_service::Logger;
+use ckb_shared::shared::{Shared, TxIndexStatus as CoreTxIndexStatus};
+use ckb_store::ChainStore;
+use ckb_sync::{BlockStatus, SyncShared};
+use ckb_types::{packed, prelude::*, H256};
 use jsonrpc_core::{Error, ErrorCode::InternalError, Result};
 use jsonrpc_utils::rpc;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time;
 /// RPC Module Debug for internal RPC methods.
 ///
@@ -33,10 +46,183 @@ pub trait DebugRpc {
     /// Removes the logger when this is null.
     #[rpc(name = "set_extra_logger")]
     fn set_extra_logger(&self, name: String, config_opt: Option<ExtraLoggerConfig>) -> Result<()>;
+    /// Creates a consistent backup of the chain data while CKB is running.
+    ///
+    /// The backup is stored in the server running the CKB node. It contains a RocksDB checkpoint
+    /// of the database in the sub-directory `db` and a copy of the frozen blocks in the
+    /// sub-directory `ancient`. Use `ckb backup --restore` to restore it.
+    ///
+    /// ## Params
+    ///
+    /// * `target` - The backup directory, which must not exist.
+    #[rpc(name = "create_backup")]
+    async fn create_backup(&self, target: String) -> Result<()>;
+    /// Returns the blocks marked invalid by the chain.
+    ///
+    /// A block is marked invalid when it fails the contextual verification, or when it's
+    /// invalidated by the RPC `invalidate_block`. The marks are persisted, the node never accepts an invalid
+    /// block or its descendants again until the mark is removed by `reconsider_block`.
+    #[rpc(name = "get_invalid_blocks")]
+    fn get_invalid_blocks(&self) -> Result<Vec<InvalidBlock>>;
+    /// Returns the state of the transaction index maintained in the background, when the node is
+    /// configured with `store.tx_index_prune_depth` or the index pruned before is being rebuilt.
+    ///
+    /// The failing block is reported with the error, and it's tried again every minute.
+    ///
+    /// ## Returns
+    ///
+    /// The state of the transaction index, `null` if it's not maintained in the background.
+    #[rpc(name = "get_tx_index_state")]
+    fn get_tx_index_state(&self) -> Result<Option<TxIndexState>>;
+    /// Marks the block and all its stored descendants invalid manually.
+    ///
+    /// When the block is on the main chain, the chain is rolled back to its parent. At most 1000
+    /// blocks can be rolled back.
+    ///
+    /// ## Params
+    ///
+    /// * `block_hash` - The hash of a stored block, it must not be the genesis block.
+    ///
+    /// ## Returns
+    ///
+    /// The hashes of the blocks marked invalid.
+    ///
+    /// ## Errors
+    ///
+    /// * [`InvalidParams (-32602)`](../enum.RPCError.html#variant.InvalidParams) - The block is not found, is the genesis block, or rolls back too many blocks.
+    #[rpc(name = "invalidate_block")]
+    fn invalidate_block(&self, block_hash: H256) -> Result<Vec<H256>>;
+    /// Removes the invalid mark of the block and its stored descendants.
+    ///
+    /// The blocks can be accepted again, the chain switches to them once they have the most
+    /// accumulated work, e.g., when a new block extending them arrives.
+    ///
+    /// ## Params
+    ///
+    /// * `block_hash` - The hash of the block marked invalid.
+    ///
+    /// ## Returns
+    ///
+    /// The hashes of the blocks no longer marked invalid.
+    ///
+    /// ## Errors
+    ///
+    /// * [`InvalidParams (-32602)`](../enum.RPCError.html#variant.InvalidParams) - The block is not marked invalid.
+    #[rpc(name = "reconsider_block")]
+    fn reconsider_block(&self, block_hash: H256) -> Result<Vec<H256>>;
+
+    /// Runs the scripts of a transaction and profiles where the cycles are spent in each script
+    /// group.
+    ///
+    /// The scripts run with the interpreter step by step, which is much slower than
+    /// [`trace_transaction`](trait.ExperimentRpc.html#method.trace_transaction), so it's only
+    /// available in this module. The cycles are aggregated by the call stacks, which are named by
+    /// the function symbols in the script binaries if they are not stripped. The cycles of the
+    /// spawned child VMs are counted in the frame calling `spawn`.
+    ///
+    /// ## Params
+    ///
+    /// * `tx` - The transaction.
+    /// * `block_hash` - Optional block hash. The scripts run against the chain state as of this
+    /// block like
+    /// [`verify_transaction_at_block`](trait.ExperimentRpc.html#method.verify_transaction_at_block),
+    /// otherwise they run as if the transaction is submitted at the tip.
+    ///
+    /// ## Returns
+    ///
+    /// The profiles of the lock script groups followed by the type script groups. The
+    /// `folded_stacks` can be saved into a file and rendered by flame graph tools, such as
+    /// `inferno-flamegraph profile.folded > profile.svg`.
+    ///
+    /// ## Errors
+    ///
+    /// * [`TransactionFailedToResolve (-301)`](../enum.RPCError.html#variant.TransactionFailedToResolve) - Failed to resolve the referenced cells and headers used in the transaction, as inputs or dependencies.
+    /// * [`InvalidParams (-32602)`](../enum.RPCError.html#variant.InvalidParams) - The block of `block_hash` is not found in the canonical chain.
+    ///
+    /// ## Examples
+    ///
+    /// Request
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "method": "profile_transaction",
+    ///   "params": [
+    ///     {
+    ///       "cell_deps": [
+    ///         {
+    ///           "dep_type": "code",
+    ///           "out_point": {
+    ///             "index": "0x0",
+    ///             "tx_hash": "0xa4037a893eb48e18ed4ef61034ce26eba9c585f15c9cee102ae58505565eccc3"
+    ///           }
+    ///         }
+    ///       ],
+    ///       "header_deps": [
+    ///         "0x7978ec7ce5b507cfb52e149e36b1a23f6062ed150503c85bbf825da3599095ed"
+    ///       ],
+    ///       "inputs": [
+    ///         {
+    ///           "previous_output": {
+    ///             "index": "0x0",
+    ///             "tx_hash": "0x365698b50ca0da75dca2c87f9e7b563811d3b5813736b8cc62cc3b106faceb17"
+    ///           },
+    ///           "since": "0x0"
+    ///         }
+    ///       ],
+    ///       "outputs": [
+    ///         {
+    ///           "capacity": "0x2540be400",
+    ///           "lock": {
+    ///             "code_hash": "0x28e83a1277d48add8e72fadaa9248559e1b632bab2bd60b27955ebc4c03800a5",
+    ///             "hash_type": "data",
+    ///             "args": "0x"
+    ///           },
+    ///           "type": null
+    ///         }
+    ///       ],
+    ///       "outputs_data": [
+    ///         "0x"
+    ///       ],
+    ///       "version": "0x0",
+    ///       "witnesses": []
+    ///     }
+    ///   ]
+    /// }
+    /// ```
+    ///
+    /// Response
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "result": [
+    ///     {
+    ///       "cycles": "0x219",
+    ///       "error": null,
+    ///       "folded_stacks": "0x10078 537\n",
+    ///       "group_type": "lock",
+    ///       "script_hash": "0x4ceaa32f692948413e213ce6f3a83337145bde6e11fd8cb94377ce2637dcc412"
+    ///     }
+    ///   ]
+    /// }
+    /// ```
+    #[rpc(name = "profile_transaction")]
+    fn profile_transaction(
+        &self,
+        tx: Transaction,
+        block_hash: Option<H256>,
+    ) -> Result<Vec<ScriptGroupProfile>>;
 }
 
 #[derive(Clone)]
-pub(crate) struct DebugRpcImpl {}
+pub(crate) struct DebugRpcImpl {
+    pub shared: Shared,
+    pub chain: ChainController,
+    pub sync_shared: Arc<SyncShared>,
+}
 
 #[async_trait]
 impl DebugRpc for DebugRpcImpl {
@@ -92,4 +278,102 @@ impl DebugRpc for DebugRpcImpl {
             data: None,
         })
     }
+
+    async fn create_backup(&self, target: String) -> Result<()> {
+        // the checkpoint and the copy of the frozen blocks take a while, don't block the worker
+        let shared = self.shared.clone();
+        self.shared
+            .async_handle()
+            .spawn_blocking(move || shared.create_backup(&PathBuf::from(target)))
+            .await
+            .map_err(|err| err.to_string())
+            .and_then(|ret| ret.map_err(|err| err.to_string()))
+            .map_err(|message| Error {
+                code: InternalError,
+                message,
+                data: None,
+            })
+    }
+
+    fn get_invalid_blocks(&self) -> Result<Vec<InvalidBlock>> {
+        Ok(self
+            .shared
+            .store()
+            .get_invalid_blocks()
+            .map_err(RPCError::from_ckb_error)?
+            .into_iter()
+            .map(|(hash, invalid_block)| InvalidBlock {
+                hash: hash.unpack(),
+                number: invalid_block.number.into(),
+                timestamp: invalid_block.timestamp.into(),
+                manual: invalid_block.manual,
+                reason: invalid_block.reason,
+            })
+            .collect())
+    }
+
+    fn get_tx_index_state(&self) -> Result<Option<TxIndexState>> {
+        Ok(self.shared.tx_index_state().map(|state| {
+            let (status, number) = match state.status {
+                CoreTxIndexStatus::Complete => (TxIndexStatus::Complete, None),
+                CoreTxIndexStatus::Pruned(number) => (TxIndexStatus::Pruned, Some(number)),
+                CoreTxIndexStatus::Rebuilding(number) => (TxIndexStatus::Rebuilding, Some(number)),
+                CoreTxIndexStatus::Incomplete(number) => (TxIndexStatus::Incomplete, Some(number)),
+            };
+            let (error_number, error) = state.error.unzip();
+            TxIndexState {
+                status,
+                number: number.map(Into::into),
+                error_number: error_number.map(Into::into),
+                error,
+            }
+        }))
+    }
+
+    fn invalidate_block(&self, block_hash: H256) -> Result<Vec<H256>> {
+        let hashes = self
+            .chain
+            .invalidate_block(block_hash.pack())
+            .map_err(invalidate_block_error)?;
+        let state = self.sync_shared.state();
+        Ok(hashes
+            .into_iter()
+            .map(|hash| {
+                state.insert_block_status(hash.clone(), BlockStatus::BLOCK_INVALID);
+                hash.unpack()
+            })
+            .collect())
+    }
+
+    fn reconsider_block(&self, block_hash: H256) -> Result<Vec<H256>> {
+        let hashes = self
+            .chain
+            .reconsider_block(block_hash.pack())
+            .map_err(invalidate_block_error)?;
+        let state = self.sync_shared.state();
+        Ok(hashes
+            .into_iter()
+            .map(|hash| {
+                state.remove_block_status(&hash);
+                hash.unpack()
+            })
+            .collect())
+    }
+
+    fn profile_transaction(
+        &self,
+        tx: Transaction,
+        block_hash: Option<H256>,
+    ) -> Result<Vec<ScriptGroupProfile>> {
+        let tx: packed::Transaction = tx.into();
+        CyclesEstimator::new(&self.shared).profile(tx, block_hash.map(|hash| hash.pack()))
+    }
+}
+
+// The errors caused by the requested block are invalid params
+fn invalidate_block_error(err: CKBError) -> Error {
+    match err.downcast_ref::<InvalidateBlockError>() {
+        Some(err) => RPCError::invalid_params(err.to_string()),
+        None => RPCError::custom_with_error(RPCError::CKBInternalError, err),
+    }
 }
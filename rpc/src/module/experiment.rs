@@ -3,11 +3,12 @@ use crate::module::chain::CyclesEstimator;
 use async_trait::async_trait;
 use ckb_dao::DaoCalculator;
 use ckb_jsonrpc_types::{
-    Capacity, DaoWithdrawingCalculationKind, EstimateCycles, OutPoint, Transaction,
+    Capacity, DaoWithdrawingCalculationKind, EstimateCycles, OutPoint, StateOverrides, Transaction,
+    TransactionTrace,
 };
 use ckb_shared::{shared::Shared, Snapshot};
 use ckb_store::ChainStore;
-use ckb_types::{core, packed, prelude::*};
+use ckb_types::{core, packed, prelude::*, H256};
 use jsonrpc_core::Result;
 use jsonrpc_utils::rpc;
 
@@ -102,6 +103,321 @@ pub trait ExperimentRpc {
     #[rpc(name = "dry_run_transaction")]
     fn dry_run_transaction(&self, tx: Transaction) -> Result<EstimateCycles>;
 
+    /// Verifies a transaction against the chain state as of a block, and returns the execution
+    /// cycles.
+    ///
+    /// Unlike [`estimate_cycles`](trait.ChainRpc.html#method.estimate_cycles) which only runs the
+    /// scripts against the tip, the transaction is verified as if it is committed in the given
+    /// block, including the since, cellbase maturity and capacity checks besides the scripts.
+    /// The inputs and cell deps are resolved to the cells created in the block or before and not
+    /// consumed before the block, and the header deps must be in the main chain up to the block.
+    ///
+    /// It is used to investigate historical script failures, and to check whether existing
+    /// transactions are still valid after a script upgrade.
+    ///
+    /// The node doesn't index the consumed cells, so resolving a dead cell scans the blocks
+    /// between its creation and the given block, or between the given block and the tip,
+    /// whichever is shorter. At most 1000 blocks are scanned for a transaction.
+    ///
+    /// ## Params
+    ///
+    /// * `tx` - The transaction, which can be a committed one or a hypothetical one.
+    /// * `block_hash` - The hash of a block in the [canonical chain](trait.ChainRpc.html#canonical-chain).
+    ///
+    /// ## Errors
+    ///
+    /// * [`TransactionFailedToResolve (-301)`](../enum.RPCError.html#variant.TransactionFailedToResolve) - Failed to resolve the referenced cells and headers used in the transaction as of the block.
+    /// * [`TransactionFailedToVerify (-302)`](../enum.RPCError.html#variant.TransactionFailedToVerify) - The transaction fails the contextual verification as of the block.
+    /// * [`InvalidParams (-32602)`](../enum.RPCError.html#variant.InvalidParams) - The block of `block_hash` is not found in the canonical chain, or resolving the transaction scans more than 1000 blocks.
+    /// * [`BlockIsPruned (-203)`](../enum.RPCError.html#variant.BlockIsPruned) - The history as of the block is unavailable, because a scanned block body or the transaction index is pruned.
+    ///
+    /// ## Examples
+    ///
+    /// Request
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "method": "verify_transaction_at_block",
+    ///   "params": [
+    ///     {
+    ///       "cell_deps": [
+    ///         {
+    ///           "dep_type": "code",
+    ///           "out_point": {
+    ///             "index": "0x0",
+    ///             "tx_hash": "0xa4037a893eb48e18ed4ef61034ce26eba9c585f15c9cee102ae58505565eccc3"
+    ///           }
+    ///         }
+    ///       ],
+    ///       "header_deps": [
+    ///         "0x7978ec7ce5b507cfb52e149e36b1a23f6062ed150503c85bbf825da3599095ed"
+    ///       ],
+    ///       "inputs": [
+    ///         {
+    ///           "previous_output": {
+    ///             "index": "0x0",
+    ///             "tx_hash": "0x365698b50ca0da75dca2c87f9e7b563811d3b5813736b8cc62cc3b106faceb17"
+    ///           },
+    ///           "since": "0x0"
+    ///         }
+    ///       ],
+    ///       "outputs": [
+    ///         {
+    ///           "capacity": "0x2540be400",
+    ///           "lock": {
+    ///             "code_hash": "0x28e83a1277d48add8e72fadaa9248559e1b632bab2bd60b27955ebc4c03800a5",
+    ///             "hash_type": "data",
+    ///             "args": "0x"
+    ///           },
+    ///           "type": null
+    ///         }
+    ///       ],
+    ///       "outputs_data": [
+    ///         "0x"
+    ///       ],
+    ///       "version": "0x0",
+    ///       "witnesses": []
+    ///     },
+    ///     "0xa5f5c85987a15de25661e5a214f2c1449cd803f071acc7999820f25246471f40"
+    ///   ]
+    /// }
+    /// ```
+    ///
+    /// Response
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "cycles": "0x219"
+    ///   }
+    /// }
+    /// ```
+    #[rpc(name = "verify_transaction_at_block")]
+    fn verify_transaction_at_block(
+        &self,
+        tx: Transaction,
+        block_hash: H256,
+    ) -> Result<EstimateCycles>;
+
+    /// Dry runs a transaction against the tip with the chain state overrides, and returns the
+    /// execution cycles.
+    ///
+    /// The overrides inject cells and headers which are not in the chain, so the transaction
+    /// can depend on the outputs of the transactions not committed yet. The epoch of the tip and
+    /// the median time of the past blocks can also be overridden to test the `since` locks.
+    ///
+    /// Besides the scripts, this method checks the `since` fields of the inputs and the
+    /// maturity of the cellbase outputs. The injected cells are resolved before the live cells
+    /// in the chain, and they are never cellbase outputs.
+    ///
+    /// ## Params
+    ///
+    /// * `tx` - The transaction.
+    /// * `overrides` - The chain state overrides.
+    ///
+    /// ## Errors
+    ///
+    /// * [`TransactionFailedToResolve (-301)`](../enum.RPCError.html#variant.TransactionFailedToResolve) - Failed to resolve the referenced cells and headers used in the transaction, as inputs or dependencies.
+    /// * [`TransactionFailedToVerify (-302)`](../enum.RPCError.html#variant.TransactionFailedToVerify) - There is a script returns with an error, or an input is immature.
+    /// * [`InvalidParams (-32602)`](../enum.RPCError.html#variant.InvalidParams) - The block of an injected cell is not found.
+    ///
+    /// ## Examples
+    ///
+    /// Request
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "method": "dry_run_transaction_with_overrides",
+    ///   "params": [
+    ///     {
+    ///       "cell_deps": [
+    ///         {
+    ///           "dep_type": "code",
+    ///           "out_point": {
+    ///             "index": "0x0",
+    ///             "tx_hash": "0xa4037a893eb48e18ed4ef61034ce26eba9c585f15c9cee102ae58505565eccc3"
+    ///           }
+    ///         }
+    ///       ],
+    ///       "header_deps": [],
+    ///       "inputs": [
+    ///         {
+    ///           "previous_output": {
+    ///             "index": "0x0",
+    ///             "tx_hash": "0x0000000000000000000000000000000000000000000000000000000000000001"
+    ///           },
+    ///           "since": "0x0"
+    ///         }
+    ///       ],
+    ///       "outputs": [
+    ///         {
+    ///           "capacity": "0x2540be400",
+    ///           "lock": {
+    ///             "code_hash": "0x28e83a1277d48add8e72fadaa9248559e1b632bab2bd60b27955ebc4c03800a5",
+    ///             "hash_type": "data",
+    ///             "args": "0x"
+    ///           },
+    ///           "type": null
+    ///         }
+    ///       ],
+    ///       "outputs_data": [
+    ///         "0x"
+    ///       ],
+    ///       "version": "0x0",
+    ///       "witnesses": []
+    ///     },
+    ///     {
+    ///       "cells": [
+    ///         {
+    ///           "out_point": {
+    ///             "index": "0x0",
+    ///             "tx_hash": "0x0000000000000000000000000000000000000000000000000000000000000001"
+    ///           },
+    ///           "output": {
+    ///             "capacity": "0x2540be400",
+    ///             "lock": {
+    ///               "code_hash": "0x28e83a1277d48add8e72fadaa9248559e1b632bab2bd60b27955ebc4c03800a5",
+    ///               "hash_type": "data",
+    ///               "args": "0x"
+    ///             },
+    ///             "type": null
+    ///           },
+    ///           "data": "0x"
+    ///         }
+    ///       ],
+    ///       "headers": []
+    ///     }
+    ///   ]
+    /// }
+    /// ```
+    ///
+    /// Response
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "cycles": "0x219"
+    ///   }
+    /// }
+    /// ```
+    #[rpc(name = "dry_run_transaction_with_overrides")]
+    fn dry_run_transaction_with_overrides(
+        &self,
+        tx: Transaction,
+        overrides: StateOverrides,
+    ) -> Result<EstimateCycles>;
+
+    /// Runs the scripts of a transaction and returns the execution details of each script group.
+    ///
+    /// Like [`estimate_cycles`](trait.ChainRpc.html#method.estimate_cycles), this method does
+    /// not check the transaction validity, but only runs the lock scripts and type scripts. A
+    /// failing script group does not stop the remaining groups, so all the failures are reported.
+    ///
+    /// It is used to debug transaction scripts on a node.
+    ///
+    /// ## Params
+    ///
+    /// * `tx` - The transaction.
+    /// * `block_hash` - Optional block hash. The scripts run against the chain state as of this
+    /// block like [`verify_transaction_at_block`](#method.verify_transaction_at_block), otherwise
+    /// they run as if the transaction is submitted at the tip.
+    ///
+    /// ## Errors
+    ///
+    /// * [`TransactionFailedToResolve (-301)`](../enum.RPCError.html#variant.TransactionFailedToResolve) - Failed to resolve the referenced cells and headers used in the transaction, as inputs or dependencies.
+    /// * [`InvalidParams (-32602)`](../enum.RPCError.html#variant.InvalidParams) - The block of `block_hash` is not found in the canonical chain.
+    /// * [`BlockIsPruned (-203)`](../enum.RPCError.html#variant.BlockIsPruned) - The history as of `block_hash` is unavailable, because a scanned block body or the transaction index is pruned.
+    ///
+    /// ## Examples
+    ///
+    /// Request
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "method": "trace_transaction",
+    ///   "params": [
+    ///     {
+    ///       "cell_deps": [
+    ///         {
+    ///           "dep_type": "code",
+    ///           "out_point": {
+    ///             "index": "0x0",
+    ///             "tx_hash": "0xa4037a893eb48e18ed4ef61034ce26eba9c585f15c9cee102ae58505565eccc3"
+    ///           }
+    ///         }
+    ///       ],
+    ///       "header_deps": [
+    ///         "0x7978ec7ce5b507cfb52e149e36b1a23f6062ed150503c85bbf825da3599095ed"
+    ///       ],
+    ///       "inputs": [
+    ///         {
+    ///           "previous_output": {
+    ///             "index": "0x0",
+    ///             "tx_hash": "0x365698b50ca0da75dca2c87f9e7b563811d3b5813736b8cc62cc3b106faceb17"
+    ///           },
+    ///           "since": "0x0"
+    ///         }
+    ///       ],
+    ///       "outputs": [
+    ///         {
+    ///           "capacity": "0x2540be400",
+    ///           "lock": {
+    ///             "code_hash": "0x28e83a1277d48add8e72fadaa9248559e1b632bab2bd60b27955ebc4c03800a5",
+    ///             "hash_type": "data",
+    ///             "args": "0x"
+    ///           },
+    ///           "type": null
+    ///         }
+    ///       ],
+    ///       "outputs_data": [
+    ///         "0x"
+    ///       ],
+    ///       "version": "0x0",
+    ///       "witnesses": []
+    ///     }
+    ///   ]
+    /// }
+    /// ```
+    ///
+    /// Response
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "cycles": "0x219",
+    ///     "script_groups": [
+    ///       {
+    ///         "child_vms": [],
+    ///         "cycles": "0x219",
+    ///         "debug_messages": [],
+    ///         "error": null,
+    ///         "exit_code": 0,
+    ///         "group_type": "lock",
+    ///         "script_hash": "0x4ceaa32f692948413e213ce6f3a83337145bde6e11fd8cb94377ce2637dcc412"
+    ///       }
+    ///     ]
+    ///   }
+    /// }
+    /// ```
+    #[rpc(name = "trace_transaction")]
+    fn trace_transaction(
+        &self,
+        tx: Transaction,
+        block_hash: Option<H256>,
+    ) -> Result<TransactionTrace>;
+
     /// Calculates the maximum withdrawal one can get, given a referenced DAO cell, and
     /// a withdrawing block hash.
     ///
@@ -176,6 +492,33 @@ impl ExperimentRpc for ExperimentRpcImpl {
         CyclesEstimator::new(&self.shared).run(tx)
     }
 
+    fn verify_transaction_at_block(
+        &self,
+        tx: Transaction,
+        block_hash: H256,
+    ) -> Result<EstimateCycles> {
+        let tx: packed::Transaction = tx.into();
+        CyclesEstimator::new(&self.shared).verify_at_block(tx, block_hash.pack())
+    }
+
+    fn dry_run_transaction_with_overrides(
+        &self,
+        tx: Transaction,
+        overrides: StateOverrides,
+    ) -> Result<EstimateCycles> {
+        let tx: packed::Transaction = tx.into();
+        CyclesEstimator::new(&self.shared).run_with_overrides(tx, overrides)
+    }
+
+    fn trace_transaction(
+        &self,
+        tx: Transaction,
+        block_hash: Option<H256>,
+    ) -> Result<TransactionTrace> {
+        let tx: packed::Transaction = tx.into();
+        CyclesEstimator::new(&self.shared).trace(tx, block_hash.map(|hash| hash.pack()))
+    }
+
     fn calculate_dao_maximum_withdraw(
         &self,
         out_point: OutPoint,
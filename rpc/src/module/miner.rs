@@ -1,7 +1,7 @@
 use crate::error::RPCError;
 use async_trait::async_trait;
 use ckb_chain::chain::ChainController;
-use ckb_jsonrpc_types::{Block, BlockTemplate, Uint64, Version};
+use ckb_jsonrpc_types::{Block, BlockTemplate, BlockTemplateParams, Uint64, Version};
 use ckb_logger::{debug, error, info, warn};
 use ckb_network::{NetworkController, PeerIndex, SupportProtocols, TargetSession};
 use ckb_shared::{shared::Shared, Snapshot};
@@ -35,6 +35,13 @@ pub trait MinerRpc {
     ///     (**Optional:** the default is the consensus limit.)
     /// * `max_version` - the max block version.
     ///     (**Optional:** the default is one configured in the current client version.)
+    /// * `params` - the extra parameters to customize the template for this request, such as the
+    ///     cellbase lock and message, the cycles limit and the transactions to include or exclude.
+    ///     (**Optional:** the default is the template shared by all the miners.)
+    ///
+    /// When `params` is present, the node builds a one-off template with a new `work_id` from the
+    /// current one, and `bytes_limit` and `proposals_limit` are applied to it as well. This lets a
+    /// mining pool pay the rewards to different addresses without changing `[block_assembler]`.
     ///
     /// ## Examples
     ///
@@ -138,6 +145,7 @@ pub trait MinerRpc {
         bytes_limit: Option<Uint64>,
         proposals_limit: Option<Uint64>,
         max_version: Option<Version>,
+        params: Option<BlockTemplateParams>,
     ) -> Result<BlockTemplate>;
 
     /// Submit new block to the network.
@@ -239,21 +247,32 @@ impl MinerRpc for MinerRpcImpl {
         bytes_limit: Option<Uint64>,
         proposals_limit: Option<Uint64>,
         max_version: Option<Version>,
+        params: Option<BlockTemplateParams>,
     ) -> Result<BlockTemplate> {
         let bytes_limit = bytes_limit.map(|b| b.into());
 
         let proposals_limit = proposals_limit.map(|b| b.into());
 
-        self.shared
-            .get_block_template(bytes_limit, proposals_limit, max_version.map(Into::into))
-            .map_err(|err| {
-                error!("Send get_block_template request error {}", err);
-                RPCError::ckb_internal_error(err)
-            })?
-            .map_err(|err| {
-                error!("Get_block_template result error {}", err);
-                RPCError::from_any_error(err)
-            })
+        let max_version = max_version.map(Into::into);
+        match params {
+            Some(params) => self.shared.get_block_template_with_params(
+                bytes_limit,
+                proposals_limit,
+                max_version,
+                params,
+            ),
+            None => self
+                .shared
+                .get_block_template(bytes_limit, proposals_limit, max_version),
+        }
+        .map_err(|err| {
+            error!("Send get_block_template request error {}", err);
+            RPCError::ckb_internal_error(err)
+        })?
+        .map_err(|err| {
+            error!("Get_block_template result error {}", err);
+            RPCError::from_any_error(err)
+        })
     }
 
     fn submit_block(&self, work_id: String, block: Block) -> Result<H256> {
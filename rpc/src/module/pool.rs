@@ -3,10 +3,12 @@ use async_trait::async_trait;
 use ckb_chain_spec::consensus::Consensus;
 use ckb_constant::hardfork::{mainnet, testnet};
 use ckb_jsonrpc_types::{
-    EntryCompleted, OutputsValidator, PoolTxDetailInfo, RawTxPool, Script, Transaction, TxPoolInfo,
+    EntryCompleted, Int64, LocalTransaction, OutputsValidator, PoolTxDetailInfo, RawTxPool, Script,
+    Transaction, TransactionHistoryEvent, TxPoolDump, TxPoolDumpEntry, TxPoolInfo, Uint64,
 };
 use ckb_logger::error;
 use ckb_shared::shared::Shared;
+use ckb_tx_pool::PersistedEntry;
 use ckb_types::core::TransactionView;
 use ckb_types::{core, packed, prelude::*, H256};
 use ckb_verification::{Since, SinceMetric};
@@ -14,6 +16,9 @@ use jsonrpc_core::Result;
 use jsonrpc_utils::rpc;
 use std::sync::Arc;
 
+// The version of the tx-pool dump format returned by `dump_tx_pool`
+const TX_POOL_DUMP_VERSION: u32 = 1;
+
 /// RPC Module Pool for transaction memory pool.
 #[rpc(openrpc)]
 #[async_trait]
@@ -254,6 +259,54 @@ pub trait PoolRpc {
     #[rpc(name = "remove_transaction")]
     fn remove_transaction(&self, tx_hash: H256) -> Result<bool>;
 
+    /// Adds a virtual fee delta to the transaction to change its priority in the pool.
+    ///
+    /// The delta is applied to the fee when sorting the transactions for block assembly and
+    /// eviction, including the fees aggregated from the ancestors and descendants. The actual fee
+    /// paid by the transaction and the fee rate checks are not affected.
+    ///
+    /// The deltas are accumulated, persisted with the pool, and dropped when the transaction leaves
+    /// the pool for any reason. The transaction doesn't need to be in the pool yet, such deltas
+    /// expire as the pool entries, and at most 10000 of them are kept.
+    ///
+    /// ## Params
+    ///
+    /// * `tx_hash` - Hash of the transaction.
+    /// * `fee_delta` - The fee delta in shannons, a positive value bumps the priority and a
+    /// negative value penalizes it.
+    ///
+    /// ## Returns
+    ///
+    /// The accumulated fee delta of the transaction.
+    ///
+    /// ## Examples
+    ///
+    /// Request
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "method": "prioritise_transaction",
+    ///   "params": [
+    ///     "0xa0ef4eb5f4ceeb08a4c8524d84c5da95dce2f608e0ca2ec8091191b0f330c6e3",
+    ///     "0x2710"
+    ///   ]
+    /// }
+    /// ```
+    ///
+    /// Response
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "result": "0x2710"
+    /// }
+    /// ```
+    #[rpc(name = "prioritise_transaction")]
+    fn prioritise_transaction(&self, tx_hash: H256, fee_delta: Int64) -> Result<Int64>;
+
     /// Returns the transaction pool information.
     ///
     /// ## Examples
@@ -412,6 +465,278 @@ pub trait PoolRpc {
     #[rpc(name = "get_pool_tx_detail_info")]
     fn get_pool_tx_detail_info(&self, tx_hash: H256) -> Result<PoolTxDetailInfo>;
 
+    /// Returns the recorded lifecycle events of a transaction in order, only for trouble shooting.
+    ///
+    /// The events are kept after the transaction leaves the pool, until they expire after
+    /// `tx_pool.keep_tx_history_days` or are dropped by the count limit
+    /// `tx_pool.keep_tx_history_count`. At most 64 latest events are kept for a transaction.
+    /// The events are written in the background, so the latest ones may show up after a short
+    /// delay, and they may be dropped when the node is too busy to write them, then a `dropped`
+    /// event marks the history as incomplete.
+    ///
+    /// ## Params
+    ///
+    /// * `tx_hash` - Hash of a transaction
+    ///
+    /// ## Returns
+    ///
+    /// The events in order, it's empty if the transaction is unknown.
+    ///
+    /// ## Examples
+    ///
+    /// Request
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "method": "get_transaction_history",
+    ///   "params": [
+    ///     "0xa0ef4eb5f4ceeb08a4c8524d84c5da95dce2f608e0ca2ec8091191b0f330c6e3"
+    ///   ]
+    /// }
+    /// ```
+    ///
+    /// Response
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "result": [
+    ///     {
+    ///       "event": { "type": "received", "peer": null },
+    ///       "timestamp": "0x18aa1baa54c"
+    ///     },
+    ///     {
+    ///       "event": { "type": "verified", "cycles": "0x219", "fee": "0x16923f7dcf" },
+    ///       "timestamp": "0x18aa1baa552"
+    ///     },
+    ///     {
+    ///       "event": { "type": "pending" },
+    ///       "timestamp": "0x18aa1baa552"
+    ///     }
+    ///   ]
+    /// }
+    /// ```
+    #[rpc(name = "get_transaction_history")]
+    fn get_transaction_history(&self, tx_hash: H256) -> Result<Vec<TransactionHistoryEvent>>;
+
+    /// Returns all the transactions in the pool with the metadata, which can be loaded into another
+    /// node by `load_tx_pool`, e.g. to migrate the pool between nodes during maintenance.
+    ///
+    /// ## Returns
+    ///
+    /// The dump of the pool. The entries are sorted by the dependencies, a transaction is always
+    /// after its ancestors.
+    ///
+    /// ## Examples
+    ///
+    /// Request
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "method": "dump_tx_pool",
+    ///   "params": []
+    /// }
+    /// ```
+    ///
+    /// Response
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "result": {
+    ///     "version": "0x1",
+    ///     "entries": [
+    ///       {
+    ///         "transaction": {
+    ///           "cell_deps": [
+    ///             {
+    ///               "dep_type": "code",
+    ///               "out_point": {
+    ///                 "index": "0x0",
+    ///                 "tx_hash": "0xa4037a893eb48e18ed4ef61034ce26eba9c585f15c9cee102ae58505565eccc3"
+    ///               }
+    ///             }
+    ///           ],
+    ///           "header_deps": [
+    ///             "0x7978ec7ce5b507cfb52e149e36b1a23f6062ed150503c85bbf825da3599095ed"
+    ///           ],
+    ///           "inputs": [
+    ///             {
+    ///               "previous_output": {
+    ///                 "index": "0x0",
+    ///                 "tx_hash": "0x365698b50ca0da75dca2c87f9e7b563811d3b5813736b8cc62cc3b106faceb17"
+    ///               },
+    ///               "since": "0x0"
+    ///             }
+    ///           ],
+    ///           "outputs": [
+    ///             {
+    ///               "capacity": "0x2540be400",
+    ///               "lock": {
+    ///                 "code_hash": "0x28e83a1277d48add8e72fadaa9248559e1b632bab2bd60b27955ebc4c03800a5",
+    ///                 "hash_type": "data",
+    ///                 "args": "0x"
+    ///               },
+    ///               "type": null
+    ///             }
+    ///           ],
+    ///           "outputs_data": [
+    ///             "0x"
+    ///           ],
+    ///           "version": "0x0",
+    ///           "witnesses": []
+    ///         },
+    ///         "timestamp": "0x18aa1baa54c",
+    ///         "fee_delta": "0x0",
+    ///         "local": true
+    ///       }
+    ///     ]
+    ///   }
+    /// }
+    /// ```
+    #[rpc(name = "dump_tx_pool")]
+    fn dump_tx_pool(&self) -> Result<TxPoolDump>;
+
+    /// Loads the transactions dumped by `dump_tx_pool` into the pool, and restores the time
+    /// entering the pool, the fee deltas and the local flags.
+    ///
+    /// The transactions are verified again, the invalid ones, the ones already in the pool and
+    /// the expired ones are ignored.
+    ///
+    /// ## Params
+    ///
+    /// * `dump` - The dump of the pool returned by `dump_tx_pool`
+    ///
+    /// ## Returns
+    ///
+    /// The count of the transactions loaded into the pool.
+    ///
+    /// ## Examples
+    ///
+    /// Request
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "method": "load_tx_pool",
+    ///   "params": [
+    ///     {
+    ///       "version": "0x1",
+    ///       "entries": [
+    ///         {
+    ///           "transaction": {
+    ///             "cell_deps": [
+    ///               {
+    ///                 "dep_type": "code",
+    ///                 "out_point": {
+    ///                   "index": "0x0",
+    ///                   "tx_hash": "0xa4037a893eb48e18ed4ef61034ce26eba9c585f15c9cee102ae58505565eccc3"
+    ///                 }
+    ///               }
+    ///             ],
+    ///             "header_deps": [
+    ///               "0x7978ec7ce5b507cfb52e149e36b1a23f6062ed150503c85bbf825da3599095ed"
+    ///             ],
+    ///             "inputs": [
+    ///               {
+    ///                 "previous_output": {
+    ///                   "index": "0x0",
+    ///                   "tx_hash": "0x365698b50ca0da75dca2c87f9e7b563811d3b5813736b8cc62cc3b106faceb17"
+    ///                 },
+    ///                 "since": "0x0"
+    ///               }
+    ///             ],
+    ///             "outputs": [
+    ///               {
+    ///                 "capacity": "0x2540be400",
+    ///                 "lock": {
+    ///                   "code_hash": "0x28e83a1277d48add8e72fadaa9248559e1b632bab2bd60b27955ebc4c03800a5",
+    ///                   "hash_type": "data",
+    ///                   "args": "0x"
+    ///                 },
+    ///                 "type": null
+    ///               }
+    ///             ],
+    ///             "outputs_data": [
+    ///               "0x"
+    ///             ],
+    ///             "version": "0x0",
+    ///             "witnesses": []
+    ///           },
+    ///           "timestamp": "0x18aa1baa54c",
+    ///           "fee_delta": "0x0",
+    ///           "local": true
+    ///         }
+    ///       ]
+    ///     }
+    ///   ]
+    /// }
+    /// ```
+    ///
+    /// Response
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "result": "0x1"
+    /// }
+    /// ```
+    #[rpc(name = "load_tx_pool")]
+    fn load_tx_pool(&self, dump: TxPoolDump) -> Result<Uint64>;
+
+    /// Returns the transactions submitted locally in the pool.
+    ///
+    /// When the config option `tx_pool.local_rebroadcast_interval_secs` is set, the local
+    /// transactions are rebroadcast until they are committed, the interval starts from the option
+    /// and is doubled after each rebroadcast up to 1 day. When the pool is full, the other
+    /// transactions with the same fee rate are evicted before them.
+    ///
+    /// ## Returns
+    ///
+    /// The local transactions sorted by the time added into the pool.
+    ///
+    /// ## Examples
+    ///
+    /// Request
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "method": "get_local_transactions",
+    ///   "params": []
+    /// }
+    /// ```
+    ///
+    /// Response
+    ///
+    /// ```json
+    /// {
+    ///   "id": 42,
+    ///   "jsonrpc": "2.0",
+    ///   "result": [
+    ///     {
+    ///       "tx_hash": "0xa0ef4eb5f4ceeb08a4c8524d84c5da95dce2f608e0ca2ec8091191b0f330c6e3",
+    ///       "entry_status": "pending",
+    ///       "timestamp": "0x18aa1baa54c",
+    ///       "fee": "0x16923f7dcf",
+    ///       "rebroadcast_count": "0x0",
+    ///       "next_rebroadcast_at": "0x18aa1c3cd0c"
+    ///     }
+    ///   ]
+    /// }
+    /// ```
+    #[rpc(name = "get_local_transactions")]
+    fn get_local_transactions(&self) -> Result<Vec<LocalTransaction>>;
+
     /// Returns whether tx-pool service is started, ready for request.
     ///
     /// ## Examples
@@ -638,6 +963,18 @@ impl PoolRpc for PoolRpcImpl {
         })
     }
 
+    fn prioritise_transaction(&self, tx_hash: H256, fee_delta: Int64) -> Result<Int64> {
+        let tx_pool = self.shared.tx_pool_controller();
+
+        tx_pool
+            .prioritise_tx(tx_hash.pack(), fee_delta.value())
+            .map(Into::into)
+            .map_err(|e| {
+                error!("Send prioritise_tx request error {}", e);
+                RPCError::ckb_internal_error(e)
+            })
+    }
+
     fn tx_pool_info(&self) -> Result<TxPoolInfo> {
         let tx_pool = self.shared.tx_pool_controller();
         let get_tx_pool_info = tx_pool.get_tx_pool_info();
@@ -685,6 +1022,66 @@ impl PoolRpc for PoolRpcImpl {
             .map_err(|err| RPCError::custom(RPCError::CKBInternalError, err.to_string()))?;
         Ok(tx_detail.into())
     }
+
+    fn get_transaction_history(&self, tx_hash: H256) -> Result<Vec<TransactionHistoryEvent>> {
+        let tx_pool = self.shared.tx_pool_controller();
+        tx_pool
+            .get_tx_history(tx_hash.pack())
+            .map_err(|err| RPCError::custom(RPCError::CKBInternalError, err.to_string()))?
+            .map_err(|err| RPCError::custom(RPCError::CKBInternalError, err.to_string()))
+    }
+
+    fn dump_tx_pool(&self) -> Result<TxPoolDump> {
+        let tx_pool = self.shared.tx_pool_controller();
+        let entries = tx_pool
+            .dump_pool()
+            .map_err(|err| RPCError::custom(RPCError::CKBInternalError, err.to_string()))?;
+        Ok(TxPoolDump {
+            version: TX_POOL_DUMP_VERSION.into(),
+            entries: entries
+                .into_iter()
+                .map(|entry| TxPoolDumpEntry {
+                    transaction: entry.transaction.data().into(),
+                    timestamp: entry.timestamp.into(),
+                    fee_delta: entry.fee_delta.into(),
+                    local: entry.local,
+                })
+                .collect(),
+        })
+    }
+
+    fn load_tx_pool(&self, dump: TxPoolDump) -> Result<Uint64> {
+        if dump.version.value() != TX_POOL_DUMP_VERSION {
+            return Err(RPCError::invalid_params(format!(
+                "unsupported tx-pool dump version {}, expect {}",
+                dump.version.value(),
+                TX_POOL_DUMP_VERSION
+            )));
+        }
+        let entries = dump
+            .entries
+            .into_iter()
+            .map(|entry| PersistedEntry {
+                transaction: packed::Transaction::from(entry.transaction).into_view(),
+                timestamp: entry.timestamp.into(),
+                fee_delta: entry.fee_delta.value(),
+                local: entry.local,
+            })
+            .collect();
+        let tx_pool = self.shared.tx_pool_controller();
+        let loaded = tx_pool
+            .load_pool(entries)
+            .map_err(|err| RPCError::custom(RPCError::CKBInternalError, err.to_string()))?;
+        Ok((loaded as u64).into())
+    }
+
+    fn get_local_transactions(&self) -> Result<Vec<LocalTransaction>> {
+        let tx_pool = self.shared.tx_pool_controller();
+        let txs = tx_pool
+            .get_local_txs()
+            .map_err(|err| RPCError::custom(RPCError::CKBInternalError, err.to_string()))?;
+        Ok(txs.into_iter().map(Into::into).collect())
+    }
 }
 
 pub(crate) struct WellKnownScriptsOnlyValidator<'a> {
@@ -60,7 +60,7 @@ pub trait SubscriptionRpc {
     ///
     /// ###### Params
     ///
-    /// * `topic` - Subscription topic (enum: new_tip_header | new_tip_block | new_transaction | proposed_transaction | rejected_transaction)
+    /// * `topic` - Subscription topic (enum: new_tip_header | new_tip_block | new_transaction | proposed_transaction | rejected_transaction | conflicted_transaction)
     ///
     /// ###### Returns
     ///
@@ -120,6 +120,17 @@ pub trait SubscriptionRpc {
     /// -   the first item type is [`PoolTransactionEntry`](../../ckb_jsonrpc_types/struct.PoolTransactionEntry.html), and
     /// -   the second item type is [`PoolTransactionReject`](../../ckb_jsonrpc_types/struct.PoolTransactionReject.html).
     ///
+    /// ###### `conflicted_transaction`
+    ///
+    /// Subscribers will get notified when an in-pool transaction is replaced by another transaction
+    /// via RBF, or double spent by a transaction committed in the chain. The descendants removed
+    /// along with the transaction are notified as well.
+    ///
+    /// The type of the `params.result` in the push message is a two-elements array, where
+    ///
+    /// -   the first item type is [`PoolTransactionEntry`](../../ckb_jsonrpc_types/struct.PoolTransactionEntry.html), and
+    /// -   the second item type is [`PoolTransactionConflict`](../../ckb_jsonrpc_types/struct.PoolTransactionConflict.html).
+    ///
     /// ###### Examples
     ///
     /// Subscribe Request
@@ -191,6 +202,7 @@ pub struct SubscriptionRpcImpl {
     pub new_transaction_sender: broadcast::Sender<PublishMsg<String>>,
     pub proposed_transaction_sender: broadcast::Sender<PublishMsg<String>>,
     pub new_reject_transaction_sender: broadcast::Sender<PublishMsg<String>>,
+    pub new_conflict_transaction_sender: broadcast::Sender<PublishMsg<String>>,
 }
 
 macro_rules! publiser_send {
@@ -211,6 +223,7 @@ impl SubscriptionRpc for SubscriptionRpcImpl {
             Topic::NewTransaction => self.new_transaction_sender.clone(),
             Topic::ProposedTransaction => self.proposed_transaction_sender.clone(),
             Topic::RejectedTransaction => self.new_reject_transaction_sender.clone(),
+            Topic::ConflictedTransaction => self.new_conflict_transaction_sender.clone(),
         };
         let mut rx = tx.subscribe();
         Ok(Box::pin(async_stream::stream! {
@@ -244,12 +257,16 @@ impl SubscriptionRpcImpl {
         );
         let mut reject_transaction_receiver = handle
             .block_on(notify_controller.subscribe_reject_transaction(SUBSCRIBER_NAME.to_string()));
+        let mut conflict_transaction_receiver = handle.block_on(
+            notify_controller.subscribe_conflict_transaction(SUBSCRIBER_NAME.to_string()),
+        );
 
         let (new_tip_header_sender, _) = broadcast::channel(NOTIFY_CHANNEL_SIZE);
         let (new_tip_block_sender, _) = broadcast::channel(NOTIFY_CHANNEL_SIZE);
         let (proposed_transaction_sender, _) = broadcast::channel(NOTIFY_CHANNEL_SIZE);
         let (new_transaction_sender, _) = broadcast::channel(NOTIFY_CHANNEL_SIZE);
         let (new_reject_transaction_sender, _) = broadcast::channel(NOTIFY_CHANNEL_SIZE);
+        let (new_conflict_transaction_sender, _) = broadcast::channel(NOTIFY_CHANNEL_SIZE);
 
         let stop_rx = new_tokio_exit_rx();
         handle.spawn({
@@ -258,6 +275,7 @@ impl SubscriptionRpcImpl {
             let new_transaction_sender = new_transaction_sender.clone();
             let proposed_transaction_sender = proposed_transaction_sender.clone();
             let new_reject_transaction_sender = new_reject_transaction_sender.clone();
+            let new_conflict_transaction_sender = new_conflict_transaction_sender.clone();
             async move {
                 loop {
                     tokio::select! {
@@ -276,6 +294,11 @@ impl SubscriptionRpcImpl {
                                             (tx_entry.into(), reject.into()),
                                             new_reject_transaction_sender);
                         }
+                        Some((tx_entry, conflict)) = conflict_transaction_receiver.recv() => {
+                            publiser_send!((ckb_jsonrpc_types::PoolTransactionEntry, ckb_jsonrpc_types::PoolTransactionConflict),
+                                            (tx_entry.into(), conflict.into()),
+                                            new_conflict_transaction_sender);
+                        }
                         _ = stop_rx.cancelled() => {
                             break;
                         },
@@ -294,6 +317,7 @@ impl SubscriptionRpcImpl {
             new_transaction_sender,
             proposed_transaction_sender,
             new_reject_transaction_sender,
+            new_conflict_transaction_sender,
         }
     }
 }
@@ -177,8 +177,17 @@ impl<'a> ServiceBuilder<'a> {
     }
 
     /// Mounts methods from module Debug if it is enabled in the config.
-    pub fn enable_debug(mut self) -> Self {
-        let methods = DebugRpcImpl {};
+    pub fn enable_debug(
+        mut self,
+        shared: Shared,
+        chain: ChainController,
+        sync_shared: Arc<SyncShared>,
+    ) -> Self {
+        let methods = DebugRpcImpl {
+            shared,
+            chain,
+            sync_shared,
+        };
         set_rpc_module_methods!(self, "Debug", debug_enable, add_debug_rpc_methods, methods)
     }
 
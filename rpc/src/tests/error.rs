@@ -26,6 +26,12 @@ fn test_submit_transaction_error() {
             RPCError::from_submit_transaction_reject(&reject).message
         );
 
+    let reject = Reject::ExceededLowFeeParentsLimit(100);
+    assert_eq!(
+            "PoolRejectedTransactionByMinFeeRate: Exceeded the limit 100 of transactions waiting for a child to pay the fee; try later",
+            RPCError::from_submit_transaction_reject(&reject).message
+        );
+
     let reject = Reject::ExceededMaximumAncestorsCount;
     assert_eq!(
             "PoolRejectedTransactionByMaxAncestorsCountLimit: Transaction exceeded maximum ancestors count limit; try later",
@@ -350,7 +350,9 @@ where
 // * Use replace_rpc_response to skip the response matching assertions.
 // * Fix timestamp related fields.
 fn mock_rpc_response(example: &RpcTestExample, response: &mut RpcTestResponse) {
-    use ckb_jsonrpc_types::{BannedAddr, Capacity, LocalNode, RemoteNode, Uint64};
+    use ckb_jsonrpc_types::{
+        BannedAddr, Capacity, LocalNode, RemoteNode, TransactionHistoryEvent, Uint64,
+    };
 
     let example_tx_hash = format!("{EXAMPLE_TX_HASH:#x}");
 
@@ -389,6 +391,19 @@ fn mock_rpc_response(example: &RpcTestExample, response: &mut RpcTestResponse) {
         "get_pool_tx_detail_info" => {
             response.result["timestamp"] = example.response.result["timestamp"].clone()
         }
+        "get_transaction_history" => {
+            replace_rpc_response::<Vec<TransactionHistoryEvent>>(example, response)
+        }
+        "dump_tx_pool" => {
+            response.result["entries"][0]["timestamp"] =
+                example.response.result["entries"][0]["timestamp"].clone()
+        }
+        "load_tx_pool" => replace_rpc_response::<Uint64>(example, response),
+        "get_local_transactions" => {
+            for field in ["timestamp", "next_rebroadcast_at"] {
+                response.result[0][field] = example.response.result[0][field].clone()
+            }
+        }
         _ => {}
     }
 }
@@ -0,0 +1,120 @@
+use ckb_chain::chain::ChainService;
+use ckb_shared::{Shared, SharedBuilder};
+use ckb_store::prune_block_tx_index;
+use ckb_types::{
+    core::{
+        cell::{CellProvider, CellStatus},
+        BlockView, Capacity, TransactionBuilder, TransactionView,
+    },
+    packed::{CellInput, CellOutput, OutPoint},
+    prelude::*,
+};
+use ckb_verification_traits::Switch;
+use std::sync::Arc;
+
+use crate::{
+    module::chain::{HistoricalCellProvider, HistoryUnavailable},
+    tests::{always_success_transaction, next_block, setup::always_success_consensus},
+};
+
+fn spend(out_point: OutPoint) -> TransactionView {
+    TransactionBuilder::default()
+        .input(CellInput::new(out_point, 0))
+        .output(
+            CellOutput::new_builder()
+                .capacity(Capacity::bytes(100).unwrap().pack())
+                .build(),
+        )
+        .output_data(Default::default())
+        .build()
+}
+
+// Builds the chain of 5 blocks: block 1 commits `tx1` which spends the genesis cell, block 3
+// commits `tx2` which spends the output of `tx1`.
+fn setup_history() -> (Shared, Vec<BlockView>, TransactionView) {
+    let (shared, mut pack) = SharedBuilder::with_temp_db()
+        .consensus(always_success_consensus())
+        .build()
+        .unwrap();
+    let chain_controller =
+        ChainService::new(shared.clone(), pack.take_proposal_table()).start::<&str>(None);
+
+    let tx1 = spend(OutPoint::new(always_success_transaction().hash(), 0));
+    let tx2 = spend(OutPoint::new(tx1.hash(), 0));
+    let mut blocks = vec![shared.consensus().genesis_block().clone()];
+    for number in 1..=5 {
+        let block = next_block(&shared, &blocks.last().unwrap().header());
+        let block = match number {
+            1 => block.as_advanced_builder().transaction(tx1.clone()).build(),
+            3 => block.as_advanced_builder().transaction(tx2.clone()).build(),
+            _ => block,
+        };
+        chain_controller
+            .internal_process_block(Arc::new(block.clone()), Switch::DISABLE_ALL)
+            .expect("process block");
+        blocks.push(block);
+    }
+    (shared, blocks, tx1)
+}
+
+#[test]
+fn test_historical_cell_provider() {
+    let (shared, blocks, tx1) = setup_history();
+    let snapshot = shared.snapshot();
+    let out_point = OutPoint::new(tx1.hash(), 0);
+    let cell_at = |number: usize| {
+        let provider = HistoricalCellProvider::new(&snapshot, blocks[number].header());
+        let status = provider.cell(&out_point, false);
+        assert_eq!(provider.unavailable(), None);
+        status
+    };
+
+    // not created yet
+    assert_eq!(cell_at(0), CellStatus::Unknown);
+    // live until it is spent in block 3
+    for number in 1..=3 {
+        assert!(cell_at(number).is_live(), "live at block {number}");
+    }
+    // spent in block 3
+    for number in 4..=5 {
+        assert_eq!(cell_at(number), CellStatus::Dead, "dead at block {number}");
+    }
+
+    // the live cell at tip
+    let out_point = OutPoint::new(blocks[3].transactions()[1].hash(), 0);
+    let provider = HistoricalCellProvider::new(&snapshot, blocks[5].header());
+    assert!(provider.cell(&out_point, false).is_live());
+}
+
+#[test]
+fn test_historical_cell_provider_pruned() {
+    let (shared, blocks, tx1) = setup_history();
+    let out_point = OutPoint::new(tx1.hash(), 0);
+
+    // a block scanned to find the consuming transaction is missing
+    let txn = shared.store().begin_transaction();
+    txn.detach_block(&blocks[4]).unwrap();
+    txn.commit().unwrap();
+    shared.refresh_snapshot();
+    let snapshot = shared.snapshot();
+    let provider = HistoricalCellProvider::new(&snapshot, blocks[4].header());
+    assert_eq!(provider.cell(&out_point, false), CellStatus::Unknown);
+    assert_eq!(
+        provider.unavailable(),
+        Some(HistoryUnavailable::BlockPruned(4))
+    );
+
+    // the consumed transaction is pruned from the index
+    let txn = shared.store().begin_transaction();
+    prune_block_tx_index(&txn, &blocks[3]).unwrap();
+    txn.insert_tx_index_pruned_number(3).unwrap();
+    txn.commit().unwrap();
+    shared.refresh_snapshot();
+    let snapshot = shared.snapshot();
+    let provider = HistoricalCellProvider::new(&snapshot, blocks[2].header());
+    assert_eq!(provider.cell(&out_point, false), CellStatus::Unknown);
+    assert_eq!(
+        provider.unavailable(),
+        Some(HistoryUnavailable::TxIndexPruned(3))
+    );
+}
@@ -1,9 +1,10 @@
 use crate::tests::{always_success_transaction, setup, RpcTestRequest};
+use ckb_jsonrpc_types::JsonBytes;
 use ckb_store::ChainStore;
 use ckb_test_chain_utils::{always_success_cell, always_success_consensus};
 use ckb_types::{
     core::{capacity_bytes, Capacity, TransactionBuilder},
-    packed::{CellDep, CellInput, CellOutputBuilder, OutPoint},
+    packed::{CellDep, CellInput, CellOutputBuilder, CellbaseWitness, OutPoint},
     prelude::*,
 };
 use serde_json::json;
@@ -24,7 +25,7 @@ fn test_get_block_template_cache() {
 
         let store = suite.shared.store();
         let tip = store.get_tip_header().unwrap();
-        let parent = store.get_block(&tip.parent_hash()).unwrap();
+        let parent = store.get_block(&tip.parent_hash()).unwrap().unwrap();
         let fork_block = parent
             .as_advanced_builder()
             .header(
@@ -62,7 +63,7 @@ fn test_get_block_template_cache() {
 
         let store = suite.shared.store();
         let tip = store.get_tip_header().unwrap();
-        let tip_block = store.get_block(&tip.hash()).unwrap();
+        let tip_block = store.get_block(&tip.hash()).unwrap().unwrap();
         let previous_output = OutPoint::new(tip_block.transactions().first().unwrap().hash(), 0);
 
         let input = CellInput::new(previous_output, 0);
@@ -99,3 +100,46 @@ fn test_get_block_template_cache() {
         assert_ne!(response_new.result["proposals"].to_string(), "[]");
     }
 }
+
+#[test]
+fn test_get_block_template_with_params() {
+    let suite = setup(always_success_consensus());
+    let get_block_template = |params: Vec<serde_json::Value>| {
+        suite
+            .rpc(&RpcTestRequest {
+                id: 42,
+                jsonrpc: "2.0".to_string(),
+                method: "get_block_template".to_string(),
+                params,
+            })
+            .result
+    };
+
+    let shared = get_block_template(vec![]);
+    let custom = get_block_template(vec![
+        json!(null),
+        json!(null),
+        json!(null),
+        json!({
+            "cellbase_lock": {
+                "code_hash": "0x0000000000000000000000000000000000000000000000000000000000000001",
+                "hash_type": "type",
+                "args": "0xab"
+            },
+            "cellbase_message": "0x706f6f6c",
+            "cycles_limit": "0x3e8"
+        }),
+    ]);
+    assert_ne!(shared["work_id"], custom["work_id"]);
+    assert_eq!(custom["cycles_limit"], json!("0x3e8"));
+    assert_eq!(shared["number"], custom["number"]);
+
+    let witness: JsonBytes =
+        serde_json::from_value(custom["cellbase"]["data"]["witnesses"][0].clone()).unwrap();
+    let witness = CellbaseWitness::from_slice(witness.as_bytes()).unwrap();
+    assert_eq!(&witness.lock().args().raw_data()[..], &[0xab]);
+    assert!(witness.message().raw_data().ends_with(b"pool"));
+
+    // the shared template is left untouched
+    assert_eq!(get_block_template(vec![])["cellbase"], shared["cellbase"]);
+}
@@ -85,6 +85,7 @@ pub(crate) fn setup_rpc_test_suite(height: u64, consensus: Option<Consensus>) ->
             notify: vec![],
             notify_scripts: vec![],
             notify_timeout_millis: 800,
+            policy: Default::default(),
         }))
         .build()
         .unwrap();
@@ -207,7 +208,7 @@ pub(crate) fn setup_rpc_test_suite(height: u64, consensus: Option<Consensus>) ->
             chain_controller.clone(),
             true,
         )
-        .enable_net(network_controller.clone(), sync_shared)
+        .enable_net(network_controller.clone(), Arc::clone(&sync_shared))
         .enable_stats(shared.clone(), Arc::clone(&alert_notifier))
         .enable_experiment(shared.clone())
         .enable_integration_test(
@@ -215,7 +216,7 @@ pub(crate) fn setup_rpc_test_suite(height: u64, consensus: Option<Consensus>) ->
             network_controller.clone(),
             chain_controller.clone(),
         )
-        .enable_debug()
+        .enable_debug(shared.clone(), chain_controller.clone(), sync_shared)
         .enable_alert(alert_verifier, alert_notifier, network_controller);
 
     let io_handler = builder.build();
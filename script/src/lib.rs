@@ -1,6 +1,7 @@
 //! CKB component to run the type/lock scripts.
 pub mod cost_model;
 mod error;
+mod profile;
 mod syscalls;
 mod type_id;
 mod types;
@@ -8,10 +9,12 @@ mod verify;
 mod verify_env;
 
 pub use crate::error::{ScriptError, TransactionScriptError};
+pub use crate::profile::ScriptProfile;
 pub use crate::syscalls::spawn::update_caller_machine;
 pub use crate::types::{
-    CoreMachine, MachineContext, ResumableMachine, ScriptGroup, ScriptGroupType, ScriptVersion,
-    TransactionSnapshot, TransactionState, VerifyResult, VmIsa, VmVersion,
+    ChildVm, ChildVmKind, CoreMachine, CyclesBudget, MachineContext, ResumableMachine, ScriptGroup,
+    ScriptGroupTrace, ScriptGroupType, ScriptVersion, TransactionSnapshot, TransactionState,
+    VerifyResult, VmIsa, VmVersion,
 };
 pub use crate::verify::{TransactionScriptsSyscallsGenerator, TransactionScriptsVerifier};
 pub use crate::verify_env::TxVerifyEnv;
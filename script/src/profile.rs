@@ -0,0 +1,244 @@
+//! Cycle profiling of the scripts.
+//!
+//! The profiler runs the script step by step with the interpreter, and attributes the cycles
+//! of each instruction to the current call stack. The call stack is tracked by the return
+//! address register: an instruction which jumps and sets `ra` to the next instruction is a
+//! call, and jumping to a return address on the stack is a return.
+use crate::{cost_model::transferred_byte_cycles, types::Machine, ScriptError};
+use ckb_types::{bytes::Bytes, core::Cycle};
+use ckb_vm::{
+    decoder::build_decoder, machine::VERSION0, registers::RA, CoreMachine,
+    Error as VMInternalError, Register, SupportMachine, ISA_MOP,
+};
+use goblin::elf::{sym::STT_FUNC, Elf};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+
+// The longest instruction is a macro-op fused `auipc` + `jalr`.
+const MAX_CALL_INSTRUCTION_LENGTH: u64 = 8;
+
+/// The cycles consumed by a script group, aggregated by the call stacks.
+///
+/// The frames are named by the ELF function symbols of the script binary, or by the addresses
+/// when the binary is stripped. The cycles of spawned VMs are attributed to the frame which
+/// invokes the spawn syscall.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScriptProfile {
+    /// The total consumed cycles, including the cycles to load the program.
+    pub cycles: Cycle,
+    // The interned frame names, the stacks refer to the frames by the indexes.
+    frames: Vec<String>,
+    frame_ids: HashMap<String, u32>,
+    stacks: HashMap<Vec<u32>, Cycle>,
+}
+
+impl ScriptProfile {
+    /// Returns the cycles keyed by the call stacks, the frames are separated by `;` from the
+    /// outermost.
+    pub fn stacks(&self) -> BTreeMap<String, Cycle> {
+        self.stacks
+            .iter()
+            .map(|(stack, cycles)| {
+                let names: Vec<&str> = stack
+                    .iter()
+                    .map(|id| self.frames[*id as usize].as_str())
+                    .collect();
+                (names.join(";"), *cycles)
+            })
+            .collect()
+    }
+
+    /// Returns the stacks in the folded format, one `frame;frame cycles` line per stack, which
+    /// is accepted by flame graph tools such as `inferno-flamegraph`.
+    pub fn folded_stacks(&self) -> String {
+        self.stacks()
+            .iter()
+            .fold(String::new(), |mut folded, (stack, cycles)| {
+                let _ = writeln!(folded, "{stack} {cycles}");
+                folded
+            })
+    }
+
+    pub(crate) fn single_frame(frame: &str, cycles: Cycle) -> Self {
+        let mut profile = ScriptProfile {
+            cycles,
+            ..Default::default()
+        };
+        let id = profile.intern(frame);
+        profile.stacks.insert(vec![id], cycles);
+        profile
+    }
+
+    fn intern(&mut self, name: &str) -> u32 {
+        if let Some(id) = self.frame_ids.get(name) {
+            return *id;
+        }
+        let id = self.frames.len() as u32;
+        self.frames.push(name.to_owned());
+        self.frame_ids.insert(name.to_owned(), id);
+        id
+    }
+
+    // `stack` is a reused buffer of the frame ids from the outermost.
+    fn add(&mut self, stack: &[u32], cycles: Cycle) {
+        if cycles == 0 {
+            return;
+        }
+        match self.stacks.get_mut(stack) {
+            Some(total) => *total += cycles,
+            None => {
+                self.stacks.insert(stack.to_vec(), cycles);
+            }
+        }
+    }
+}
+
+/// The function symbols of a script binary, sorted by the start address.
+#[derive(Default)]
+pub(crate) struct Symbols(Vec<(u64, u64, String)>);
+
+impl Symbols {
+    pub(crate) fn parse(program: &[u8]) -> Self {
+        let mut symbols = Vec::new();
+        if let Ok(elf) = Elf::parse(program) {
+            for sym in elf.syms.iter() {
+                if sym.st_type() != STT_FUNC || sym.st_size == 0 {
+                    continue;
+                }
+                if let Some(Ok(name)) = elf.strtab.get(sym.st_name) {
+                    symbols.push((sym.st_value, sym.st_value + sym.st_size, name.to_owned()));
+                }
+            }
+        }
+        symbols.sort();
+        Symbols(symbols)
+    }
+
+    // Returns the index of the symbol which contains `pc`.
+    fn lookup(&self, pc: u64) -> Option<usize> {
+        let index = self.0.partition_point(|(start, _, _)| *start <= pc);
+        index.checked_sub(1).filter(|index| pc < self.0[*index].1)
+    }
+
+    // Interns the symbol names into the profile, returns the frame ids by the symbol indexes.
+    fn intern(&self, profile: &mut ScriptProfile) -> Vec<u32> {
+        self.0
+            .iter()
+            .map(|(_, _, name)| profile.intern(name))
+            .collect()
+    }
+}
+
+struct Frame {
+    id: u32,
+    return_address: u64,
+}
+
+fn frame(
+    profile: &mut ScriptProfile,
+    symbols: &Symbols,
+    symbol_ids: &[u32],
+    pc: u64,
+    return_address: u64,
+) -> Frame {
+    let id = match symbols.lookup(pc) {
+        Some(index) => symbol_ids[index],
+        None => profile.intern(&format!("{pc:#x}")),
+    };
+    Frame { id, return_address }
+}
+
+/// Runs the program like `Machine::run` but step by step, and profiles the cycles.
+///
+/// The exec syscall replaces the program, `exec_symbols` returns the symbols of the new
+/// program once it's loaded.
+pub(crate) fn profile_run<F: FnMut() -> Option<Symbols>>(
+    machine: &mut Machine,
+    program: &Bytes,
+    max_cycles: Cycle,
+    mut exec_symbols: F,
+) -> Result<(i8, ScriptProfile), ScriptError> {
+    let map_vm_internal_error = |error: VMInternalError| match error {
+        VMInternalError::CyclesExceeded => ScriptError::ExceededMaximumCycles(max_cycles),
+        _ => ScriptError::VMInternalError(error),
+    };
+    let machine = &mut machine.machine;
+    if machine.isa() & ISA_MOP != 0 && machine.version() == VERSION0 {
+        return Err(ScriptError::VMInternalError(
+            VMInternalError::InvalidVersion,
+        ));
+    }
+
+    let mut profile = ScriptProfile::default();
+    let mut symbols = Symbols::parse(program);
+    let mut symbol_ids = symbols.intern(&mut profile);
+    let bytes = machine
+        .load_program(program, &[])
+        .map_err(map_vm_internal_error)?;
+    machine
+        .add_cycles_no_checking(transferred_byte_cycles(bytes))
+        .map_err(map_vm_internal_error)?;
+    let mut frames = vec![frame(
+        &mut profile,
+        &symbols,
+        &symbol_ids,
+        machine.pc().to_u64(),
+        0,
+    )];
+    let mut stack: Vec<u32> = frames.iter().map(|frame| frame.id).collect();
+    profile.add(&stack, machine.cycles());
+
+    let mut decoder = build_decoder::<u64>(machine.isa(), machine.version());
+    machine.set_running(true);
+    while machine.running() {
+        if machine.reset_signal() {
+            decoder.reset_instructions_cache();
+            symbols = exec_symbols().unwrap_or_default();
+            symbol_ids = symbols.intern(&mut profile);
+            frames = vec![frame(
+                &mut profile,
+                &symbols,
+                &symbol_ids,
+                machine.pc().to_u64(),
+                0,
+            )];
+        }
+        let pc = machine.pc().to_u64();
+        let return_address = machine.registers()[RA].to_u64();
+        let cycles = machine.cycles();
+
+        let result = machine.step(&mut decoder);
+        // the cycles go to the function of the instruction, in place of the innermost frame
+        stack.clear();
+        stack.extend(frames.iter().map(|frame| frame.id));
+        if let (Some(index), Some(last)) = (symbols.lookup(pc), stack.last_mut()) {
+            *last = symbol_ids[index];
+        }
+        profile.add(&stack, machine.cycles() - cycles);
+        result.map_err(map_vm_internal_error)?;
+
+        let next_pc = machine.pc().to_u64();
+        let next_return_address = machine.registers()[RA].to_u64();
+        if next_return_address != return_address
+            && next_return_address > pc
+            && next_return_address <= pc + MAX_CALL_INSTRUCTION_LENGTH
+            && next_pc != next_return_address
+        {
+            frames.push(frame(
+                &mut profile,
+                &symbols,
+                &symbol_ids,
+                next_pc,
+                next_return_address,
+            ));
+        } else if let Some(index) = frames
+            .iter()
+            .skip(1)
+            .rposition(|frame| frame.return_address == next_pc)
+        {
+            frames.truncate(index + 1);
+        }
+    }
+    profile.cycles = machine.cycles();
+    Ok((machine.exit_code(), profile))
+}
@@ -3,10 +3,10 @@ use crate::syscalls::utils::load_c_string;
 use crate::syscalls::{
     Place, Source, SourceEntry, EXEC, INDEX_OUT_OF_BOUND, SLICE_OUT_OF_BOUND, WRONG_FORMAT,
 };
-use crate::types::Indices;
+use crate::types::{ChildVm, ChildVmKind, ChildVmTracer, Indices};
 use ckb_traits::CellDataProvider;
 use ckb_types::core::cell::{CellMeta, ResolvedTransaction};
-use ckb_types::packed::{Bytes as PackedBytes, BytesVec};
+use ckb_types::packed::{Bytes as PackedBytes, BytesVec, CellOutput};
 use ckb_vm::Memory;
 use ckb_vm::{
     registers::{A0, A1, A2, A3, A4, A5, A7},
@@ -15,13 +15,13 @@ use ckb_vm::{
 use ckb_vm::{DEFAULT_STACK_SIZE, RISCV_MAX_MEMORY};
 use std::sync::Arc;
 
-#[derive(Debug)]
 pub struct Exec<DL> {
     data_loader: DL,
     rtx: Arc<ResolvedTransaction>,
     outputs: Arc<Vec<CellMeta>>,
     group_inputs: Indices,
     group_outputs: Indices,
+    tracer: Option<ChildVmTracer>,
 }
 
 impl<DL: CellDataProvider> Exec<DL> {
@@ -31,6 +31,7 @@ impl<DL: CellDataProvider> Exec<DL> {
         outputs: Arc<Vec<CellMeta>>,
         group_inputs: Indices,
         group_outputs: Indices,
+        tracer: Option<ChildVmTracer>,
     ) -> Exec<DL> {
         Exec {
             data_loader,
@@ -38,6 +39,7 @@ impl<DL: CellDataProvider> Exec<DL> {
             outputs,
             group_inputs,
             group_outputs,
+            tracer,
         }
     }
 
@@ -199,6 +201,15 @@ impl<Mac: SupportMachine, DL: CellDataProvider + Send + Sync> Syscalls<Mac> for
                 return Ok(true);
             }
         }
+        if let Some(tracer) = &self.tracer {
+            tracer(ChildVm {
+                kind: ChildVmKind::Exec,
+                program_hash: CellOutput::calc_data_hash(&data),
+                argv,
+                exit_code: None,
+                cycles: None,
+            });
+        }
         Ok(true)
     }
 }
@@ -7,12 +7,13 @@ use crate::syscalls::{
     SPAWN_MAX_PEAK_MEMORY, SPAWN_MEMORY_PAGE_SIZE, SPAWN_WRONG_MEMORY_LIMIT, WRONG_FORMAT,
 };
 use crate::types::{
-    set_vm_max_cycles, CoreMachineType, Machine, MachineContext, ResumableMachine, SpawnData,
+    set_vm_max_cycles, ChildVm, ChildVmKind, CoreMachineType, Machine, MachineContext,
+    ResumableMachine, SpawnData,
 };
 use crate::TransactionScriptsSyscallsGenerator;
 use crate::{ScriptGroup, ScriptVersion};
 use ckb_traits::{CellDataProvider, ExtensionProvider, HeaderProvider};
-use ckb_types::core::cell::CellMeta;
+use ckb_types::{core::cell::CellMeta, packed::CellOutput};
 use ckb_vm::{
     cost_model::estimate_cycles,
     registers::{A0, A1, A2, A3, A4, A5, A7},
@@ -229,7 +230,17 @@ where
             }
         }
         // Run the child machine and check result.
-        match machine_child.run() {
+        let result = machine_child.run();
+        if let Some(tracer) = &self.syscalls_generator.child_vm_tracer {
+            tracer(ChildVm {
+                kind: ChildVmKind::Spawn,
+                program_hash: CellOutput::calc_data_hash(&program),
+                argv: argv_vec,
+                exit_code: result.as_ref().ok().copied(),
+                cycles: Some(machine_child.machine.cycles()),
+            });
+        }
+        match result {
             Ok(data) => {
                 update_caller_machine(machine, data, machine_child.machine.cycles(), &spawn_data)?;
                 Ok(true)
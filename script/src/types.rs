@@ -1,6 +1,7 @@
-use crate::ScriptError;
+use crate::{ScriptError, TransactionScriptError};
 use ckb_error::Error;
 use ckb_types::{
+    bytes::Bytes,
     core::{Cycle, ScriptHashType},
     packed::{Byte32, Script},
 };
@@ -11,6 +12,7 @@ use ckb_vm::{
 };
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 
 #[cfg(has_asm)]
@@ -44,6 +46,100 @@ pub(crate) type Indices = Arc<Vec<usize>>;
 
 pub(crate) type DebugPrinter = Arc<dyn Fn(&Byte32, &str) + Send + Sync>;
 
+pub(crate) type ChildVmTracer = Arc<dyn Fn(ChildVm) + Send + Sync>;
+
+/// The syscall which starts a child VM.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ChildVmKind {
+    /// The `exec` syscall, which replaces the program of the current VM.
+    Exec,
+    /// The `spawn` syscall, which runs the program in a new VM and waits for its exit.
+    Spawn,
+}
+
+/// A child VM started by a script, see [`ScriptGroupTrace`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChildVm {
+    /// The syscall which starts the VM.
+    pub kind: ChildVmKind,
+    /// The data hash of the loaded program.
+    pub program_hash: Byte32,
+    /// The arguments passed to the program.
+    pub argv: Vec<Bytes>,
+    /// The exit code of a spawned VM, `None` for exec or when the VM fails.
+    pub exit_code: Option<i8>,
+    /// The cycles consumed by a spawned VM, `None` for exec.
+    pub cycles: Option<Cycle>,
+}
+
+/// The execution details of a script group, returned by
+/// [`TransactionScriptsVerifier::trace`](crate::TransactionScriptsVerifier::trace).
+#[derive(Debug)]
+pub struct ScriptGroupTrace {
+    /// The type of the script group.
+    pub group_type: ScriptGroupType,
+    /// The script hash of the script group.
+    pub script_hash: Byte32,
+    /// The cycles consumed by the script group, including the failed runs.
+    pub cycles: Cycle,
+    /// The exit code of the root VM, `None` if the VM fails before exit.
+    pub exit_code: Option<i8>,
+    /// The messages printed by the debug syscall, in order.
+    pub debug_messages: Vec<String>,
+    /// The child VMs, exec'd VMs are recorded when the program is loaded and spawned VMs are
+    /// recorded when they exit.
+    pub child_vms: Vec<ChildVm>,
+    /// The error of the script group with the index and type of its first cell, `None` if the
+    /// script group succeeds.
+    pub error: Option<TransactionScriptError>,
+}
+
+/// The cycles budget shared by the script groups verified in parallel, e.g., the groups of all
+/// the transactions in a block.
+///
+/// A group never runs with more cycles than the remaining budget, so the VM work of a parallel
+/// verification is bounded by the budget times the count of the worker threads.
+#[derive(Debug)]
+pub struct CyclesBudget {
+    remaining: AtomicU64,
+    cancelled: AtomicBool,
+}
+
+impl CyclesBudget {
+    /// Creates a budget of the given cycles.
+    pub fn new(cycles: Cycle) -> Self {
+        CyclesBudget {
+            remaining: AtomicU64::new(cycles),
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the cycles not consumed yet.
+    pub fn remaining(&self) -> Cycle {
+        self.remaining.load(Ordering::Acquire)
+    }
+
+    /// Consumes the cycles, the budget is exhausted if the cycles exceed the remaining.
+    pub fn consume(&self, cycles: Cycle) {
+        let _ = self
+            .remaining
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |remaining| {
+                Some(remaining.saturating_sub(cycles))
+            });
+    }
+
+    /// Cancels the verification, the pending groups are skipped. The remaining cycles are kept,
+    /// the skipped groups are not run rather than out of cycles.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Returns whether the verification is cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
+
 /// The version of CKB Script Verifier.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ScriptVersion {
@@ -3,6 +3,7 @@ use crate::syscalls::Pause;
 use crate::{
     cost_model::transferred_byte_cycles,
     error::{ScriptError, TransactionScriptError},
+    profile::{profile_run, ScriptProfile, Symbols},
     syscalls::{
         spawn::{build_child_machine, update_caller_machine},
         CurrentCycles, CurrentMemory, Debugger, Exec, GetMemoryLimit, LoadBlockExtension, LoadCell,
@@ -11,9 +12,10 @@ use crate::{
     },
     type_id::TypeIdSystemScript,
     types::{
-        CoreMachine, DebugPrinter, Indices, Machine, MachineContext, ResumableMachine, ResumePoint,
-        ScriptGroup, ScriptGroupType, ScriptVersion, SpawnData, TransactionSnapshot,
-        TransactionState, VerifyResult,
+        ChildVm, ChildVmKind, ChildVmTracer, CoreMachine, CyclesBudget, DebugPrinter, Indices,
+        Machine, MachineContext, ResumableMachine, ResumePoint, ScriptGroup, ScriptGroupTrace,
+        ScriptGroupType, ScriptVersion, SpawnData, TransactionSnapshot, TransactionState,
+        VerifyResult,
     },
     verify_env::TxVerifyEnv,
 };
@@ -36,11 +38,11 @@ use ckb_vm::{
     snapshot::{resume, Snapshot},
     DefaultMachineBuilder, Error as VMInternalError, SupportMachine, Syscalls,
 };
-use std::cell::RefCell;
+use rayon::prelude::*;
+use std::cmp;
 use std::collections::{BTreeMap, HashMap};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 
-#[cfg(test)]
 use core::sync::atomic::{AtomicBool, Ordering};
 
 #[cfg(test)]
@@ -68,25 +70,25 @@ enum DataGuard {
 }
 
 /// LazyData wrapper make sure not-loaded data will be loaded only after one access
-#[derive(Debug, PartialEq, Eq, Clone)]
-struct LazyData(RefCell<DataGuard>);
+#[derive(Debug, Clone)]
+struct LazyData(Arc<RwLock<DataGuard>>);
 
 impl LazyData {
     fn from_cell_meta(cell_meta: &CellMeta) -> LazyData {
         match &cell_meta.mem_cell_data {
-            Some(data) => LazyData(RefCell::new(DataGuard::Loaded(data.to_owned()))),
-            None => LazyData(RefCell::new(DataGuard::NotLoaded(
+            Some(data) => LazyData(Arc::new(RwLock::new(DataGuard::Loaded(data.to_owned())))),
+            None => LazyData(Arc::new(RwLock::new(DataGuard::NotLoaded(
                 cell_meta.out_point.clone(),
-            ))),
+            )))),
         }
     }
 
     fn access<DL: CellDataProvider>(&self, data_loader: &DL) -> Bytes {
-        let guard = self.0.borrow().to_owned();
+        let guard = self.0.read().expect("poisoned lock").to_owned();
         match guard {
             DataGuard::NotLoaded(out_point) => {
                 let data = data_loader.get_cell_data(&out_point).expect("cell data");
-                self.0.replace(DataGuard::Loaded(data.to_owned()));
+                *self.0.write().expect("poisoned lock") = DataGuard::Loaded(data.to_owned());
                 data
             }
             DataGuard::Loaded(bytes) => bytes,
@@ -94,7 +96,7 @@ impl LazyData {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, Clone)]
 enum Binaries {
     Unique(Byte32, LazyData),
     Duplicate(Byte32, LazyData),
@@ -127,6 +129,7 @@ impl Binaries {
 pub struct TransactionScriptsSyscallsGenerator<DL> {
     pub(crate) data_loader: DL,
     debug_printer: DebugPrinter,
+    pub(crate) child_vm_tracer: Option<ChildVmTracer>,
     pub(crate) outputs: Arc<Vec<CellMeta>>,
     pub(crate) rtx: Arc<ResolvedTransaction>,
     #[cfg(test)]
@@ -154,6 +157,7 @@ impl<DL: CellDataProvider + HeaderProvider + ExtensionProvider + Send + Sync + C
             Arc::clone(&self.outputs),
             group_inputs,
             group_outputs,
+            self.child_vm_tracer.clone(),
         )
     }
 
@@ -465,6 +469,7 @@ impl<DL: CellDataProvider + HeaderProvider + ExtensionProvider + Send + Sync + C
         let generator = TransactionScriptsSyscallsGenerator {
             data_loader: data_loader.clone(),
             debug_printer: Arc::clone(&debug_printer),
+            child_vm_tracer: None,
             outputs: Arc::clone(&outputs),
             rtx: Arc::clone(&rtx),
             #[cfg(test)]
@@ -623,6 +628,137 @@ impl<DL: CellDataProvider + HeaderProvider + ExtensionProvider + Send + Sync + C
         Ok(cycles)
     }
 
+    /// Verifies the transaction by running the script groups in parallel.
+    ///
+    /// The script groups are independent, so they are scheduled on the global rayon thread pool
+    /// and the idle threads steal the pending groups, including the ones of other transactions
+    /// verified in the same pool. The groups share the `max_cycles` budget, each one runs with
+    /// the cycles not consumed by the finished groups, and the pending groups are skipped once a
+    /// group fails. The skipped or failed groups are verified again in order with the cycles
+    /// left, so the VM work is bounded by `max_cycles` times the count of the worker threads.
+    ///
+    /// The result is deterministic regardless of the scheduling: it returns the error of the
+    /// first failing group in order, and the cycles limit error is reported by the group which
+    /// makes the sum exceed the limit, like [`verify`](Self::verify).
+    pub fn parallel_verify(&self, max_cycles: Cycle) -> Result<Cycle, Error> {
+        let groups: Vec<_> = self.groups().collect();
+        let budget = CyclesBudget::new(max_cycles);
+        let failed = AtomicBool::new(false);
+        let results: Vec<_> = groups
+            .par_iter()
+            .map(|(_hash, group)| {
+                let limit = budget.remaining();
+                if limit == 0 || failed.load(Ordering::Acquire) {
+                    return None;
+                }
+                let result = self.verify_script_group(group, limit);
+                match result {
+                    Ok(used_cycles) => budget.consume(used_cycles),
+                    Err(_) => failed.store(true, Ordering::Release),
+                }
+                Some(result)
+            })
+            .collect();
+
+        let mut cycles: Cycle = 0;
+        for ((_hash, group), result) in groups.into_iter().zip(results) {
+            let remain_cycles = max_cycles - cycles;
+            let result = match result {
+                Some(Ok(used_cycles)) => Ok(used_cycles),
+                // the group is skipped, or it may fail because of a lower limit than the cycles
+                // left in order, the groups verified here consume at most `max_cycles` in total
+                _ => {
+                    if remain_cycles == 0 {
+                        Err(ScriptError::ExceededMaximumCycles(remain_cycles))
+                    } else {
+                        self.verify_script_group(group, remain_cycles)
+                    }
+                }
+            };
+            let used_cycles = result
+                .and_then(|used_cycles| {
+                    if used_cycles > remain_cycles {
+                        Err(ScriptError::ExceededMaximumCycles(remain_cycles))
+                    } else {
+                        Ok(used_cycles)
+                    }
+                })
+                .map_err(|e| {
+                    let e = match e {
+                        ScriptError::ExceededMaximumCycles(_) => {
+                            ScriptError::ExceededMaximumCycles(remain_cycles)
+                        }
+                        e => e,
+                    };
+                    #[cfg(feature = "logging")]
+                    logging::on_script_error(_hash, &self.hash(), &e);
+                    e.source(group)
+                })?;
+
+            cycles = wrapping_cycles_add(cycles, used_cycles, group)?;
+        }
+        Ok(cycles)
+    }
+
+    /// Verifies the transaction like [`parallel_verify`](Self::parallel_verify), but the groups
+    /// take the cycles from a budget shared with other transactions, e.g., the other
+    /// transactions in a block.
+    ///
+    /// A group never runs with more cycles than the remaining budget, and the budget is
+    /// cancelled once a group fails, so the pending groups of all the transactions sharing it
+    /// are skipped. It returns `Ok(None)` if the transaction isn't verified to the end, i.e. a
+    /// group is skipped or exceeds the cycles limited by the budget, so the caller has to verify
+    /// it again in order to tell whether it passes. The returned error is the one of the first
+    /// failing group which ran to the end.
+    pub fn parallel_verify_with_budget(
+        &self,
+        max_cycles: Cycle,
+        budget: &CyclesBudget,
+    ) -> Result<Option<Cycle>, Error> {
+        let groups: Vec<_> = self.groups().collect();
+        let results: Vec<_> = groups
+            .par_iter()
+            .map(|(_hash, group)| {
+                let limit = cmp::min(max_cycles, budget.remaining());
+                if limit == 0 || budget.is_cancelled() {
+                    return None;
+                }
+                match self.verify_script_group(group, limit) {
+                    Ok(used_cycles) => {
+                        budget.consume(used_cycles);
+                        Some(Ok(used_cycles))
+                    }
+                    // the group may pass with the cycles not limited by the budget
+                    Err(ScriptError::ExceededMaximumCycles(_)) if limit < max_cycles => None,
+                    Err(e) => {
+                        budget.cancel();
+                        Some(Err(e))
+                    }
+                }
+            })
+            .collect();
+
+        let mut cycles: Cycle = 0;
+        for ((_hash, group), result) in groups.into_iter().zip(results) {
+            match result {
+                Some(Ok(used_cycles)) => {
+                    cycles = wrapping_cycles_add(cycles, used_cycles, group)?;
+                    // the groups ran in parallel may consume more than the limit in total
+                    if cycles > max_cycles {
+                        return Ok(None);
+                    }
+                }
+                Some(Err(e)) => {
+                    #[cfg(feature = "logging")]
+                    logging::on_script_error(_hash, &self.hash(), &e);
+                    return Err(e.source(group).into());
+                }
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(cycles))
+    }
+
     /// Performing a resumable verification on the transaction scripts.
     ///
     /// ## Params
@@ -909,6 +1045,171 @@ impl<DL: CellDataProvider + HeaderProvider + ExtensionProvider + Send + Sync + C
         }
     }
 
+    /// Runs all the script groups and collects the execution details of each group, which helps
+    /// to debug the scripts.
+    ///
+    /// Unlike [`verify`](Self::verify), the remaining groups still run after a group fails. The
+    /// groups share the `max_cycles` limit in order.
+    pub fn trace(&mut self, max_cycles: Cycle) -> Vec<ScriptGroupTrace> {
+        let debug_messages: Arc<Mutex<Vec<String>>> = Default::default();
+        let child_vms: Arc<Mutex<Vec<ChildVm>>> = Default::default();
+
+        let debug_printer = Arc::clone(&self.generator.debug_printer);
+        self.generator.debug_printer = {
+            let debug_messages = Arc::clone(&debug_messages);
+            let debug_printer = Arc::clone(&debug_printer);
+            Arc::new(move |hash: &Byte32, message: &str| {
+                debug_printer(hash, message);
+                debug_messages
+                    .lock()
+                    .expect("lock debug messages")
+                    .push(message.to_owned());
+            })
+        };
+        self.generator.child_vm_tracer = {
+            let child_vms = Arc::clone(&child_vms);
+            Some(Arc::new(move |vm: ChildVm| {
+                child_vms.lock().expect("lock child vms").push(vm);
+            }))
+        };
+
+        let mut cycles: Cycle = 0;
+        let traces = self
+            .groups_with_type()
+            .map(|(group_type, hash, group)| {
+                let (used_cycles, exit_code, error) =
+                    self.trace_script_group(group, max_cycles.saturating_sub(cycles));
+                cycles = cycles.saturating_add(used_cycles);
+                ScriptGroupTrace {
+                    group_type,
+                    script_hash: hash.to_owned(),
+                    cycles: used_cycles,
+                    exit_code,
+                    debug_messages: debug_messages
+                        .lock()
+                        .expect("lock debug messages")
+                        .drain(..)
+                        .collect(),
+                    child_vms: child_vms
+                        .lock()
+                        .expect("lock child vms")
+                        .drain(..)
+                        .collect(),
+                    error,
+                }
+            })
+            .collect();
+
+        self.generator.debug_printer = debug_printer;
+        self.generator.child_vm_tracer = None;
+        traces
+    }
+
+    /// Runs a single script group with the interpreter and profiles where the cycles are spent,
+    /// see [`ScriptProfile`].
+    ///
+    /// It's much slower than [`verify_single`](Self::verify_single), and only for debugging.
+    pub fn profile(
+        &mut self,
+        script_group_type: ScriptGroupType,
+        script_hash: &Byte32,
+        max_cycles: Cycle,
+    ) -> Result<ScriptProfile, ScriptError> {
+        let group = self
+            .find_script_group(script_group_type, script_hash)
+            .cloned()
+            .ok_or_else(|| ScriptError::ScriptNotFound(script_hash.clone()))?;
+        if group.script.code_hash() == TYPE_ID_CODE_HASH.pack()
+            && Into::<u8>::into(group.script.hash_type()) == Into::<u8>::into(ScriptHashType::Type)
+        {
+            let verifier = TypeIdSystemScript {
+                rtx: &self.rtx,
+                script_group: &group,
+                max_cycles,
+            };
+            return Ok(ScriptProfile::single_frame("type_id", verifier.verify()?));
+        }
+
+        // remember the program loaded by the exec syscall to resolve its symbols
+        let exec_program: Arc<Mutex<Option<Byte32>>> = Default::default();
+        self.generator.child_vm_tracer = {
+            let exec_program = Arc::clone(&exec_program);
+            Some(Arc::new(move |vm: ChildVm| {
+                if vm.kind == ChildVmKind::Exec {
+                    *exec_program.lock().expect("lock exec program") = Some(vm.program_hash);
+                }
+            }))
+        };
+        let result = self.extract_script(&group.script).and_then(|program| {
+            let mut machine = self.build_machine(&group, max_cycles, Default::default())?;
+            profile_run(&mut machine, &program, max_cycles, || {
+                let data_hash = exec_program.lock().expect("lock exec program").take()?;
+                self.binaries_by_data_hash
+                    .get(&data_hash)
+                    .map(|lazy| Symbols::parse(&lazy.access(&self.data_loader)))
+            })
+        });
+        self.generator.child_vm_tracer = None;
+
+        let (code, profile) = result?;
+        if code == 0 {
+            Ok(profile)
+        } else {
+            Err(ScriptError::validation_failure(&group.script, code))
+        }
+    }
+
+    fn trace_script_group(
+        &self,
+        group: &ScriptGroup,
+        max_cycles: Cycle,
+    ) -> (Cycle, Option<i8>, Option<TransactionScriptError>) {
+        if group.script.code_hash() == TYPE_ID_CODE_HASH.pack()
+            && Into::<u8>::into(group.script.hash_type()) == Into::<u8>::into(ScriptHashType::Type)
+        {
+            let verifier = TypeIdSystemScript {
+                rtx: &self.rtx,
+                script_group: group,
+                max_cycles,
+            };
+            return match verifier.verify() {
+                Ok(cycles) => (cycles, Some(0), None),
+                Err(err) => (0, None, Some(err.source(group))),
+            };
+        }
+
+        let context: Arc<Mutex<MachineContext>> = Default::default();
+        let mut machine = match self.start_machine(group, max_cycles, Arc::clone(&context)) {
+            Ok(machine) => machine,
+            Err(err) => return (0, None, Some(err.source(group))),
+        };
+        // keep the machine after failures to report the consumed cycles
+        let result = machine.run();
+        let mut cycles = machine.machine.cycles();
+        let error = match result {
+            Ok(0) => return (cycles, Some(0), None),
+            Ok(code) => {
+                let error = ScriptError::validation_failure(&group.script, code);
+                return (cycles, Some(code), Some(error.source(group)));
+            }
+            Err(VMInternalError::CyclesExceeded) => {
+                // the spawned VMs suspended on the limit, which are resumed by the chunked
+                // verification, have not added their cycles to the callers yet
+                let suspended_cycles = context
+                    .lock()
+                    .expect("lock machine context")
+                    .suspended_machines
+                    .drain(..)
+                    .map(|machine| machine.cycles())
+                    .fold(0, Cycle::saturating_add);
+                cycles = cycles.saturating_add(suspended_cycles);
+                ScriptError::ExceededMaximumCycles(max_cycles)
+            }
+            Err(err) => ScriptError::VMInternalError(err),
+        };
+        (cycles, None, Some(error.source(group)))
+    }
+
     fn verify_script_group(
         &self,
         group: &ScriptGroup,
@@ -1012,15 +1313,14 @@ impl<DL: CellDataProvider + HeaderProvider + ExtensionProvider + Send + Sync + C
         Ok(machine)
     }
 
-    /// Runs a single program, then returns the exit code together with the entire
-    /// machine to the caller for more inspections.
-    pub fn detailed_run(
+    // Builds the machine of the script group and loads the program, ready to run.
+    fn start_machine(
         &self,
         script_group: &ScriptGroup,
         max_cycles: Cycle,
-    ) -> Result<(i8, Machine), ScriptError> {
+        context: Arc<Mutex<MachineContext>>,
+    ) -> Result<Machine, ScriptError> {
         let program = self.extract_script(&script_group.script)?;
-        let context = Default::default();
         let mut machine = self.build_machine(script_group, max_cycles, context)?;
 
         let map_vm_internal_error = |error: VMInternalError| match error {
@@ -1035,6 +1335,22 @@ impl<DL: CellDataProvider + HeaderProvider + ExtensionProvider + Send + Sync + C
             .machine
             .add_cycles_no_checking(transferred_byte_cycles(bytes))
             .map_err(map_vm_internal_error)?;
+        Ok(machine)
+    }
+
+    /// Runs a single program, then returns the exit code together with the entire
+    /// machine to the caller for more inspections.
+    pub fn detailed_run(
+        &self,
+        script_group: &ScriptGroup,
+        max_cycles: Cycle,
+    ) -> Result<(i8, Machine), ScriptError> {
+        let mut machine = self.start_machine(script_group, max_cycles, Default::default())?;
+
+        let map_vm_internal_error = |error: VMInternalError| match error {
+            VMInternalError::CyclesExceeded => ScriptError::ExceededMaximumCycles(max_cycles),
+            _ => ScriptError::VMInternalError(error),
+        };
         let code = machine.run().map_err(map_vm_internal_error)?;
 
         Ok((code, machine))
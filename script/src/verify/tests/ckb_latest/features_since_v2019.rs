@@ -15,6 +15,7 @@ use std::io::Read;
 use super::SCRIPT_VERSION;
 use crate::{
     type_id::TYPE_ID_CYCLES,
+    types::CyclesBudget,
     verify::{tests::utils::*, *},
 };
 
@@ -250,6 +251,18 @@ fn check_invalid_signature() {
         result.unwrap_err(),
         ScriptError::validation_failure(&script, -1).input_lock_script(0),
     );
+
+    // the trace reports the failed group like the verification
+    let traces = verifier.verify_map(script_version, &rtx, |mut verifier| {
+        verifier.trace(u64::MAX)
+    });
+    assert_eq!(traces.len(), 1);
+    assert_eq!(traces[0].exit_code, Some(-1));
+    assert!(traces[0].cycles > 0);
+    assert_eq!(
+        traces[0].error,
+        Some(ScriptError::validation_failure(&script, -1).input_lock_script(0))
+    );
 }
 
 #[test]
@@ -1163,6 +1176,16 @@ fn check_debugger() {
     let verifier = TransactionScriptsVerifierWithEnv::new();
     let result = verifier.verify_without_limit(script_version, &rtx);
     assert!(result.is_ok(), "result {result:?}");
+
+    let traces = verifier.verify_map(script_version, &rtx, |mut verifier| {
+        verifier.trace(u64::MAX)
+    });
+    let trace = traces
+        .iter()
+        .find(|trace| trace.group_type == ScriptGroupType::Type)
+        .unwrap();
+    assert_eq!(trace.exit_code, Some(0));
+    assert_eq!(trace.debug_messages, vec!["debugger print utf-8 string"]);
 }
 
 #[test]
@@ -1215,3 +1238,131 @@ fn _check_typical_secp256k1_blake160_2_in_2_out_resume_load_cycles(step_cycles:
     }
     assert_eq!(cycles, cycles_once, "step_cycles {step_cycles}");
 }
+
+#[test]
+fn check_parallel_verify() {
+    let script_version = SCRIPT_VERSION;
+
+    let (always_success_cell, always_success_cell_data, always_success_script) =
+        always_success_cell();
+    let always_success_cell = CellMetaBuilder::from_cell_output(
+        always_success_cell.clone(),
+        always_success_cell_data.to_owned(),
+    )
+    .transaction_info(default_transaction_info())
+    .build();
+
+    let mut file = open_cell_always_failure();
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer).unwrap();
+    let always_failure_script = Script::new_builder()
+        .code_hash(blake2b_256(&buffer).pack())
+        .hash_type(ScriptHashType::Data.into())
+        .build();
+    let data = Bytes::from(buffer);
+    let output = CellOutputBuilder::default()
+        .capacity(Capacity::bytes(data.len()).unwrap().pack())
+        .build();
+    let always_failure_cell = CellMetaBuilder::from_cell_output(output, data)
+        .transaction_info(default_transaction_info())
+        .build();
+
+    let verifier = TransactionScriptsVerifierWithEnv::new();
+    let resolve = |locks: Vec<Script>| {
+        let inputs = locks
+            .into_iter()
+            .map(|lock| {
+                create_dummy_cell(
+                    CellOutputBuilder::default()
+                        .capacity(capacity_bytes!(100).pack())
+                        .lock(lock)
+                        .build(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let transaction = TransactionBuilder::default()
+            .inputs(vec![CellInput::new(OutPoint::null(), 0); inputs.len()])
+            .build();
+        ResolvedTransaction {
+            transaction,
+            resolved_cell_deps: vec![always_success_cell.clone(), always_failure_cell.clone()],
+            resolved_inputs: inputs,
+            resolved_dep_groups: vec![],
+        }
+    };
+    let success_locks: Vec<_> = (0u8..4)
+        .map(|i| {
+            always_success_script
+                .clone()
+                .as_builder()
+                .args(Bytes::from(vec![i]).pack())
+                .build()
+        })
+        .collect();
+
+    let rtx = resolve(success_locks.clone());
+    let cycles = verifier.verify_without_limit(script_version, &rtx).unwrap();
+    let parallel_cycles = verifier.verify_map(script_version, &rtx, |verifier| {
+        verifier.parallel_verify(u64::MAX)
+    });
+    assert_eq!(parallel_cycles.unwrap(), cycles);
+
+    // the group which makes the sum exceed the limit reports the error
+    let error = verifier
+        .verify(script_version, &rtx, cycles - 1)
+        .unwrap_err();
+    let parallel_error = verifier
+        .verify_map(script_version, &rtx, |verifier| {
+            verifier.parallel_verify(cycles - 1)
+        })
+        .unwrap_err();
+    assert_eq!(parallel_error.to_string(), error.to_string());
+
+    // the transactions sharing a budget don't run to the end once it runs out
+    let budget = CyclesBudget::new(cycles * 3 / 2);
+    let parallel_cycles = verifier.verify_map(script_version, &rtx, |verifier| {
+        verifier.parallel_verify_with_budget(u64::MAX, &budget)
+    });
+    assert_eq!(parallel_cycles.unwrap(), Some(cycles));
+    assert_eq!(budget.remaining(), cycles * 3 / 2 - cycles);
+    let parallel_cycles = verifier.verify_map(script_version, &rtx, |verifier| {
+        verifier.parallel_verify_with_budget(u64::MAX, &budget)
+    });
+    assert_eq!(parallel_cycles.unwrap(), None);
+
+    // the first failing group reports the error
+    let mut locks = success_locks.clone();
+    locks.insert(2, always_failure_script.clone());
+    locks.push(
+        always_failure_script
+            .as_builder()
+            .args(Bytes::from(vec![1u8]).pack())
+            .build(),
+    );
+    let rtx = resolve(locks);
+    let error = verifier
+        .verify_without_limit(script_version, &rtx)
+        .unwrap_err();
+    for _ in 0..4 {
+        let parallel_error = verifier
+            .verify_map(script_version, &rtx, |verifier| {
+                verifier.parallel_verify(u64::MAX)
+            })
+            .unwrap_err();
+        assert_eq!(parallel_error.to_string(), error.to_string());
+    }
+
+    // the failing transaction cancels the budget, the others sharing it are not run
+    let budget = CyclesBudget::new(u64::MAX);
+    assert!(verifier
+        .verify_map(script_version, &rtx, |verifier| {
+            verifier.parallel_verify_with_budget(u64::MAX, &budget)
+        })
+        .is_err());
+    assert!(budget.is_cancelled());
+    let rtx = resolve(success_locks);
+    let parallel_cycles = verifier.verify_map(script_version, &rtx, |verifier| {
+        verifier.parallel_verify_with_budget(u64::MAX, &budget)
+    });
+    assert_eq!(parallel_cycles.unwrap(), None);
+}
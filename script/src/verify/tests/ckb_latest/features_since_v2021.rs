@@ -337,6 +337,62 @@ fn check_exec_from_cell_data() {
     assert_eq!(result.is_ok(), script_version >= ScriptVersion::V1);
 }
 
+#[test]
+fn check_exec_profile() {
+    let script_version = SCRIPT_VERSION;
+
+    let (exec_caller_cell, exec_caller_data_hash) =
+        load_cell_from_path("testdata/exec_caller_from_cell_data");
+    let (exec_callee_cell, _exec_callee_data_hash) = load_cell_from_path("testdata/exec_callee");
+
+    let exec_caller_script = Script::new_builder()
+        .hash_type(script_version.data_hash_type().into())
+        .code_hash(exec_caller_data_hash)
+        .build();
+    let output = CellOutputBuilder::default()
+        .capacity(capacity_bytes!(100).pack())
+        .lock(exec_caller_script.clone())
+        .build();
+    let input = CellInput::new(OutPoint::null(), 0);
+
+    let transaction = TransactionBuilder::default().input(input).build();
+    let dummy_cell = create_dummy_cell(output);
+
+    let rtx = ResolvedTransaction {
+        transaction,
+        resolved_cell_deps: vec![exec_caller_cell, exec_callee_cell],
+        resolved_inputs: vec![dummy_cell],
+        resolved_dep_groups: vec![],
+    };
+
+    let verifier = TransactionScriptsVerifierWithEnv::new();
+    let result = verifier.verify_without_limit(script_version, &rtx);
+    assert_eq!(result.is_ok(), script_version >= ScriptVersion::V1);
+    if script_version < ScriptVersion::V1 {
+        return;
+    }
+    let cycles = result.unwrap();
+    let profile = verifier
+        .verify_map(script_version, &rtx, |mut verifier| {
+            verifier.profile(
+                ScriptGroupType::Lock,
+                &exec_caller_script.calc_script_hash(),
+                u64::MAX,
+            )
+        })
+        .unwrap();
+
+    assert_eq!(profile.cycles, cycles);
+    let stacks = profile.stacks();
+    assert_eq!(stacks.values().sum::<Cycle>(), cycles);
+    let folded_stacks = profile.folded_stacks();
+    assert_eq!(folded_stacks.lines().count(), stacks.len());
+    for line in folded_stacks.lines() {
+        let (stack, cycles) = line.rsplit_once(' ').unwrap();
+        assert_eq!(stacks[stack], cycles.parse::<Cycle>().unwrap());
+    }
+}
+
 #[test]
 fn check_exec_from_witness() {
     let script_version = SCRIPT_VERSION;
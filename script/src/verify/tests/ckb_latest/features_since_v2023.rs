@@ -4,7 +4,10 @@ use ckb_types::{
 };
 
 use super::SCRIPT_VERSION;
-use crate::verify::{tests::utils::*, *};
+use crate::{
+    verify::{tests::utils::*, *},
+    ChildVmKind,
+};
 
 // check_vm_version: vm_version() returns 2.
 // check_get_memory_limit: get_memory_limit() returns 8 in prime script.
@@ -15,6 +18,7 @@ use crate::verify::{tests::utils::*, *};
 // check_spawn_set_content: set_content() with content < length, = length and > length.
 // check_spawn_out_of_cycles: child script out-of-cycles.
 // check_spawn_exec: A exec B spawn C.
+// check_spawn_exec_trace: A spawn B exec C, the child VMs are traced.
 // check_spawn_strcat_wrap: A spawn B spwan C.
 // check_spawn_out_of_cycles_wrap: A spawn B spwan C, but C out-of-cycles.
 // check_spawn_recursive: A spawn A spawn A ... ... spawn A
@@ -335,6 +339,71 @@ fn check_spawn_exec() {
     assert_eq!(result.is_ok(), script_version >= ScriptVersion::V2);
 }
 
+#[test]
+fn check_spawn_exec_trace() {
+    let script_version = SCRIPT_VERSION;
+
+    let (spawn_caller_cell, spawn_caller_data_hash) =
+        load_cell_from_path("testdata/spawn_caller_exec");
+    let (spawn_callee_caller_cell, spawn_callee_caller_data_hash) =
+        load_cell_from_path("testdata/spawn_callee_exec_caller");
+    let (spawn_callee_callee_cell, spawn_callee_callee_data_hash) =
+        load_cell_from_path("testdata/spawn_callee_exec_callee");
+
+    let spawn_caller_script = Script::new_builder()
+        .hash_type(script_version.data_hash_type().into())
+        .code_hash(spawn_caller_data_hash)
+        .build();
+    let output = CellOutputBuilder::default()
+        .capacity(capacity_bytes!(100).pack())
+        .lock(spawn_caller_script.clone())
+        .build();
+    let input = CellInput::new(OutPoint::null(), 0);
+
+    let transaction = TransactionBuilder::default().input(input).build();
+    let dummy_cell = create_dummy_cell(output);
+
+    let rtx = ResolvedTransaction {
+        transaction,
+        resolved_cell_deps: vec![
+            spawn_caller_cell,
+            spawn_callee_caller_cell,
+            spawn_callee_callee_cell,
+        ],
+        resolved_inputs: vec![dummy_cell],
+        resolved_dep_groups: vec![],
+    };
+    let verifier = TransactionScriptsVerifierWithEnv::new();
+    let result = verifier.verify(script_version, &rtx, 0xffffff);
+    assert_eq!(result.is_ok(), script_version >= ScriptVersion::V2);
+    if script_version < ScriptVersion::V2 {
+        return;
+    }
+    let cycles = result.unwrap();
+    let traces = verifier.verify_map(script_version, &rtx, |mut verifier| {
+        verifier.trace(0xffffff)
+    });
+
+    assert_eq!(traces.len(), 1);
+    let trace = &traces[0];
+    assert_eq!(trace.group_type, ScriptGroupType::Lock);
+    assert_eq!(trace.script_hash, spawn_caller_script.calc_script_hash());
+    assert_eq!(trace.cycles, cycles);
+    assert_eq!(trace.exit_code, Some(0));
+    assert!(trace.error.is_none());
+    let kinds: Vec<_> = trace.child_vms.iter().map(|vm| vm.kind).collect();
+    assert_eq!(kinds, vec![ChildVmKind::Exec, ChildVmKind::Spawn]);
+    assert_eq!(
+        trace.child_vms[0].program_hash,
+        spawn_callee_callee_data_hash
+    );
+    assert_eq!(
+        trace.child_vms[1].program_hash,
+        spawn_callee_caller_data_hash
+    );
+    assert_eq!(trace.child_vms[1].exit_code, Some(0));
+}
+
 #[test]
 fn check_spawn_strcat_wrap() {
     let script_version = SCRIPT_VERSION;
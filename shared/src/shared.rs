@@ -1,19 +1,21 @@
 //! TODO(doc): @quake
 use crate::{Snapshot, SnapshotMgr};
-use arc_swap::Guard;
+use arc_swap::{ArcSwap, Guard};
 use ckb_async_runtime::Handle;
 use ckb_chain_spec::consensus::Consensus;
 use ckb_constant::store::TX_INDEX_UPPER_BOUND;
 use ckb_constant::sync::MAX_TIP_AGE;
 use ckb_db::{Direction, IteratorMode};
 use ckb_db_schema::{COLUMN_BLOCK_BODY, COLUMN_NUMBER_HASH};
-use ckb_error::{AnyError, Error};
+use ckb_error::{AnyError, Error, InternalErrorKind};
 use ckb_notify::NotifyController;
 use ckb_proposal_table::ProposalView;
 use ckb_stop_handler::{new_crossbeam_exit_rx, register_thread};
-use ckb_store::{ChainDB, ChainStore};
+use ckb_store::{
+    attach_block_tx_index, clear_tx_spent, prune_block_tx_index, BlockPruned, ChainDB, ChainStore,
+};
 use ckb_systemtime::unix_time_as_millis;
-use ckb_tx_pool::{BlockTemplate, TokioRwLock, TxPoolController};
+use ckb_tx_pool::{BlockTemplate, BlockTemplateParams, TokioRwLock, TxPoolController};
 use ckb_types::{
     core::{BlockNumber, EpochExt, EpochNumber, HeaderView, Version},
     packed::{self, Byte32},
@@ -23,6 +25,8 @@ use ckb_types::{
 use ckb_verification::cache::TxVerificationCache;
 use std::cmp;
 use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
@@ -31,6 +35,47 @@ use std::time::Duration;
 const FREEZER_INTERVAL: Duration = Duration::from_secs(60);
 const THRESHOLD_EPOCH: EpochNumber = 2;
 const MAX_FREEZE_LIMIT: BlockNumber = 30_000;
+const TX_INDEX_INTERVAL: Duration = Duration::from_secs(60);
+const MAX_TX_INDEX_LIMIT: BlockNumber = 10_000;
+
+/// Returns the minimum `store.freezer_prune_depth`.
+///
+/// The blocks within the finalization delay and the reorg window kept out of the freezer must not
+/// be pruned, they are still read by the chain service when it switches forks or rewards the
+/// blocks.
+pub fn min_freezer_prune_depth(consensus: &Consensus) -> BlockNumber {
+    consensus.finalization_delay_length() + THRESHOLD_EPOCH * consensus.max_epoch_length()
+}
+
+/// The sub-directory of a backup which holds the kv-db checkpoint
+pub const BACKUP_DB_DIR: &str = "db";
+/// The sub-directory of a backup which holds the frozen blocks
+pub const BACKUP_ANCIENT_DIR: &str = "ancient";
+
+/// The progress of the transaction index, see [`Shared::spawn_tx_index`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxIndexStatus {
+    /// The transactions of all the main chain blocks are indexed
+    Complete,
+    /// The transactions of the blocks up to the number are pruned from the index
+    Pruned(BlockNumber),
+    /// The index is being rebuilt, the transactions of the blocks up to the number are not
+    /// indexed yet
+    Rebuilding(BlockNumber),
+    /// The transactions of the blocks up to the number can't be indexed, since the bodies of
+    /// the blocks are pruned by the freezer
+    Incomplete(BlockNumber),
+}
+
+/// The state of the background thread maintaining the transaction index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxIndexState {
+    /// The progress of the index
+    pub status: TxIndexStatus,
+    /// The number of the block which failed to be pruned or indexed with the error, the block
+    /// is tried again in the next round
+    pub error: Option<(BlockNumber, String)>,
+}
 
 /// An owned permission to close on a freezer thread
 pub struct FreezerClose {
@@ -54,6 +99,7 @@ pub struct Shared {
     pub(crate) snapshot_mgr: Arc<SnapshotMgr>,
     pub(crate) async_handle: Handle,
     pub(crate) ibd_finished: Arc<AtomicBool>,
+    pub(crate) tx_index_state: Arc<ArcSwap<Option<TxIndexState>>>,
 }
 
 impl Shared {
@@ -78,6 +124,7 @@ impl Shared {
             snapshot_mgr,
             async_handle,
             ibd_finished,
+            tx_index_state: Arc::new(ArcSwap::from_pointee(None)),
         }
     }
     /// Spawn freeze background thread that periodically checks and moves ancient data from the kv database into the freezer.
@@ -163,6 +210,15 @@ impl Shared {
         // Wipe out frozen data
         self.wipe_out_frozen_data(&snapshot, ret, stopped)?;
 
+        if let Some(depth) = freezer.prune_depth() {
+            if !stopped {
+                // the freezer clamps the threshold to its latest frozen block
+                let prune_threshold = snapshot.tip_number().saturating_sub(depth);
+                let tail = freezer.prune(prune_threshold)?;
+                ckb_logger::trace!("Freezer pruned, earliest kept block {}", tail);
+            }
+        }
+
         ckb_logger::trace!("Freezer completed");
 
         Ok(())
@@ -176,6 +232,11 @@ impl Shared {
     ) -> Result<(), Error> {
         let mut side = BTreeMap::new();
         let mut batch = self.store.new_write_batch();
+        // the pruned freezer drops the frozen blocks, so the extensions are kept in the db
+        let pruned = self
+            .store
+            .freezer()
+            .map_or(false, |freezer| freezer.prune_depth().is_some());
 
         ckb_logger::trace!("freezer wipe_out_frozen_data {} ", frozen.len());
 
@@ -186,6 +247,9 @@ impl Shared {
                     ckb_logger::error!("Freezer delete_block_body failed {}", e);
                     e
                 })?;
+                if !pruned {
+                    batch.delete_block_extension(hash)?;
+                }
 
                 let pack_number: packed::Uint64 = number.pack();
                 let prefix = pack_number.as_slice();
@@ -263,6 +327,177 @@ impl Shared {
         }
     }
 
+    /// Spawn the background thread that maintains the transaction index.
+    ///
+    /// When `store.tx_index_prune_depth` is set, the transactions of the blocks deeper than
+    /// the depth are pruned from the index. Otherwise, the index pruned before is rebuilt.
+    /// A failing block is tried again in the next round, the state is exposed by
+    /// [`tx_index_state`](Self::tx_index_state).
+    pub fn spawn_tx_index(&self) {
+        if self.store.tx_index_prune_depth().is_none()
+            && self.store.get_tx_index_pruned_number().is_none()
+        {
+            return;
+        }
+        let signal_receiver = new_crossbeam_exit_rx();
+        let shared = self.clone();
+        let tx_index_jh = thread::Builder::new()
+            .spawn(move || {
+                let mut rebuild_started = false;
+                loop {
+                    let ret = match shared.store.tx_index_prune_depth() {
+                        Some(depth) => shared.prune_tx_index(depth, MAX_TX_INDEX_LIMIT),
+                        None if !rebuild_started => {
+                            shared.start_rebuild_tx_index().and_then(|_| {
+                                rebuild_started = true;
+                                shared.rebuild_tx_index(MAX_TX_INDEX_LIMIT)
+                            })
+                        }
+                        None => shared.rebuild_tx_index(MAX_TX_INDEX_LIMIT),
+                    };
+                    shared.update_tx_index_state(ret);
+                    if signal_receiver.recv_timeout(TX_INDEX_INTERVAL).is_ok() {
+                        ckb_logger::info!("Transaction index closing");
+                        break;
+                    }
+                }
+            })
+            .expect("Start TxIndexService failed");
+
+        register_thread("tx-index", tx_index_jh);
+    }
+
+    fn update_tx_index_state(&self, ret: Result<TxIndexStatus, Error>) {
+        let prev = self.tx_index_state.load_full();
+        let state = match ret {
+            Ok(status) => {
+                let changed = prev.as_ref().as_ref().map(|prev| prev.status) != Some(status);
+                match status {
+                    TxIndexStatus::Complete if changed => {
+                        ckb_logger::info!("Transaction index rebuilt")
+                    }
+                    TxIndexStatus::Incomplete(number) if changed => ckb_logger::warn!(
+                        "Transaction index of the blocks up to {} can't be rebuilt, \
+                         the block bodies are pruned",
+                        number
+                    ),
+                    _ => {}
+                }
+                TxIndexState {
+                    status,
+                    error: None,
+                }
+            }
+            Err(err) => {
+                // the batches are committed block by block, the next block failed
+                let pruned = self.store.get_tx_index_pruned_number();
+                let (status, number) = match self.store.tx_index_prune_depth() {
+                    Some(_) => (
+                        pruned.map_or(TxIndexStatus::Complete, TxIndexStatus::Pruned),
+                        pruned.map_or(0, |number| number + 1),
+                    ),
+                    None => (
+                        pruned.map_or(TxIndexStatus::Complete, TxIndexStatus::Rebuilding),
+                        pruned.unwrap_or_default(),
+                    ),
+                };
+                ckb_logger::error!("Transaction index error at block {}: {}", number, err);
+                TxIndexState {
+                    status,
+                    error: Some((number, err.to_string())),
+                }
+            }
+        };
+        self.tx_index_state.store(Arc::new(Some(state)));
+    }
+
+    /// Returns the state of the transaction index thread, `None` if the thread isn't running.
+    pub fn tx_index_state(&self) -> Option<TxIndexState> {
+        self.tx_index_state.load().as_ref().clone()
+    }
+
+    /// Prunes the transaction index of at most `limit` main chain blocks deeper than `depth`.
+    ///
+    /// The transactions which still have live cells are kept, they are needed to detach blocks.
+    /// The blocks whose bodies are pruned by the freezer are skipped, their transactions stay
+    /// indexed.
+    pub fn prune_tx_index(
+        &self,
+        depth: BlockNumber,
+        limit: BlockNumber,
+    ) -> Result<TxIndexStatus, Error> {
+        let snapshot = self.snapshot();
+        let pruned = snapshot.get_tx_index_pruned_number();
+        let status = pruned.map_or(TxIndexStatus::Complete, TxIndexStatus::Pruned);
+        let threshold = match snapshot.tip_number().checked_sub(depth) {
+            Some(threshold) => threshold,
+            None => return Ok(status),
+        };
+        let start = pruned.map(|number| number + 1).unwrap_or(0);
+        let end = cmp::min(threshold, start.saturating_add(limit));
+        if start >= end {
+            return Ok(status);
+        }
+
+        for number in start..end {
+            let txn = self.store.begin_transaction();
+            let hash = txn
+                .get_block_hash(number)
+                .ok_or_else(|| missing_block(number))?;
+            match txn.get_block(&hash) {
+                Ok(Some(block)) => prune_block_tx_index(&txn, &block)?,
+                Ok(None) => return Err(missing_block(number)),
+                Err(BlockPruned(_)) => {}
+            }
+            txn.insert_tx_index_pruned_number(number)?;
+            txn.commit()?;
+        }
+        ckb_logger::trace!("Transaction index pruned {}-{}", start, end);
+        Ok(TxIndexStatus::Pruned(end - 1))
+    }
+
+    /// Clears the spent outputs counters of the pruned transaction index, which are useless
+    /// once the index is rebuilt. Call it once before the batches of `rebuild_tx_index`.
+    pub fn start_rebuild_tx_index(&self) -> Result<(), Error> {
+        let txn = self.store.begin_transaction();
+        clear_tx_spent(&txn)?;
+        txn.commit()
+    }
+
+    /// Rebuilds the pruned transaction index of at most `limit` blocks, from the latest pruned
+    /// block down to the genesis.
+    ///
+    /// The rebuilding stops at the latest block whose body is pruned by the freezer, the index
+    /// of it and the blocks below stays incomplete.
+    pub fn rebuild_tx_index(&self, limit: BlockNumber) -> Result<TxIndexStatus, Error> {
+        let pruned = match self.store.get_tx_index_pruned_number() {
+            Some(pruned) => pruned,
+            None => return Ok(TxIndexStatus::Complete),
+        };
+
+        let end = pruned.saturating_sub(limit);
+        for number in (end..=pruned).rev() {
+            let txn = self.store.begin_transaction();
+            let hash = txn
+                .get_block_hash(number)
+                .ok_or_else(|| missing_block(number))?;
+            let block = match txn.get_block(&hash) {
+                Ok(Some(block)) => block,
+                Ok(None) => return Err(missing_block(number)),
+                Err(BlockPruned(_)) => return Ok(TxIndexStatus::Incomplete(number)),
+            };
+            attach_block_tx_index(&txn, &block)?;
+            if number == 0 {
+                txn.delete_tx_index_pruned_number()?;
+                txn.commit()?;
+                return Ok(TxIndexStatus::Complete);
+            }
+            txn.insert_tx_index_pruned_number(number - 1)?;
+            txn.commit()?;
+        }
+        Ok(TxIndexStatus::Rebuilding(end - 1))
+    }
+
     /// TODO(doc): @quake
     pub fn tx_pool_controller(&self) -> &TxPoolController {
         &self.tx_pool_controller
@@ -342,6 +577,28 @@ impl Shared {
         &self.store
     }
 
+    /// Creates a consistent backup of the chain data into `target` while the node is running.
+    ///
+    /// The target directory must not exist. The kv-db checkpoint is created in the
+    /// sub-directory `db` and the frozen blocks are copied into `ancient`.
+    pub fn create_backup(&self, target: &Path) -> Result<(), Error> {
+        if target.exists() {
+            return Err(InternalErrorKind::Database
+                .other(format!("backup target {} already exists", target.display()))
+                .into());
+        }
+        fs::create_dir_all(target).map_err(|e| InternalErrorKind::Database.other(e))?;
+        let ret = self.store.create_backup(
+            &target.join(BACKUP_DB_DIR),
+            &target.join(BACKUP_ANCIENT_DIR),
+        );
+        if ret.is_err() {
+            // don't leave a partial backup behind
+            let _ignored = fs::remove_dir_all(target);
+        }
+        ret
+    }
+
     /// Return whether chain is in initial block download
     pub fn is_initial_block_download(&self) -> bool {
         // Once this function has returned false, it must remain false.
@@ -370,4 +627,26 @@ impl Shared {
             max_version.map(Into::into),
         )
     }
+
+    /// Generate and return a one-off block_template customized by the params
+    pub fn get_block_template_with_params(
+        &self,
+        bytes_limit: Option<u64>,
+        proposals_limit: Option<u64>,
+        max_version: Option<Version>,
+        params: BlockTemplateParams,
+    ) -> Result<Result<BlockTemplate, AnyError>, AnyError> {
+        self.tx_pool_controller().get_block_template_with_params(
+            bytes_limit,
+            proposals_limit,
+            max_version.map(Into::into),
+            params,
+        )
+    }
+}
+
+fn missing_block(number: BlockNumber) -> Error {
+    InternalErrorKind::Database
+        .other(format!("main chain block {number} not found"))
+        .into()
 }
@@ -8,12 +8,13 @@ use std::cmp::Ordering;
 use ckb_chain_spec::consensus::Consensus;
 use ckb_chain_spec::SpecError;
 
-use crate::Shared;
+use crate::{shared::min_freezer_prune_depth, Shared};
 use ckb_proposal_table::ProposalView;
 use ckb_snapshot::{Snapshot, SnapshotMgr};
 
 use ckb_app_config::{
-    BlockAssemblerConfig, DBConfig, ExitCode, NotifyConfig, StoreConfig, TxPoolConfig,
+    BlockAssemblerConfig, DBBackend, DBConfig, ExitCode, FreezerCompression, NotifyConfig,
+    StoreConfig, TxPoolConfig,
 };
 use ckb_async_runtime::{new_background_runtime, Handle};
 use ckb_db::RocksDB;
@@ -22,13 +23,14 @@ use ckb_error::{Error, InternalErrorKind};
 use ckb_logger::{error, info};
 use ckb_migrate::migrate::Migrate;
 use ckb_notify::{NotifyController, NotifyService};
-use ckb_store::{ChainDB, ChainStore, Freezer};
+use ckb_store::backend::{KeyValueBackend, MemoryBackend, RocksDBBackend};
+use ckb_store::{ChainDB, ChainStore, Compression, Freezer};
 use ckb_types::core::hardfork::HardForks;
 use ckb_types::core::service::PoolTransactionEntry;
-use ckb_types::core::tx_pool::Reject;
+use ckb_types::core::tx_pool::{Reject, TxConflict, TxEvent};
 use ckb_types::core::EpochExt;
 use ckb_types::core::HeaderView;
-use ckb_verification::cache::init_cache;
+use ckb_verification::cache::{init_cache, load_cache, pool_script_version, TxVerificationCache};
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
@@ -37,7 +39,7 @@ use tempfile::TempDir;
 
 /// Shared builder for construct new shared.
 pub struct SharedBuilder {
-    db: RocksDB,
+    backend: Arc<dyn KeyValueBackend>,
     ancient_path: Option<PathBuf>,
     consensus: Consensus,
     tx_pool_config: Option<TxPoolConfig>,
@@ -132,15 +134,21 @@ impl SharedBuilder {
         async_handle: Handle,
         consensus: Consensus,
     ) -> Result<SharedBuilder, ExitCode> {
-        let db = open_or_create_db(
-            bin_name,
-            root_dir,
-            db_config,
-            consensus.hardfork_switch.clone(),
-        )?;
+        let backend: Arc<dyn KeyValueBackend> = match db_config.backend {
+            DBBackend::RocksDB => Arc::new(RocksDBBackend::new(open_or_create_db(
+                bin_name,
+                root_dir,
+                db_config,
+                consensus.hardfork_switch.clone(),
+            )?)),
+            DBBackend::Memory => {
+                info!("Chain data is kept in memory and will be lost on exit");
+                Arc::new(MemoryBackend::new())
+            }
+        };
 
         Ok(SharedBuilder {
-            db,
+            backend,
             ancient_path: ancient,
             consensus,
             tx_pool_config: None,
@@ -185,7 +193,7 @@ impl SharedBuilder {
         };
 
         RUNTIME_HANDLE.with(|runtime| SharedBuilder {
-            db,
+            backend: Arc::new(RocksDBBackend::new(db)),
             ancient_path: None,
             consensus: Consensus::default(),
             tx_pool_config: None,
@@ -320,7 +328,7 @@ impl SharedBuilder {
     /// TODO(doc): @quake
     pub fn build(self) -> Result<(Shared, SharedPackage), ExitCode> {
         let SharedBuilder {
-            db,
+            backend,
             ancient_path,
             consensus,
             tx_pool_config,
@@ -337,18 +345,31 @@ impl SharedBuilder {
 
         let notify_controller = start_notify_service(notify_config, async_handle.clone());
 
-        let store = build_store(db, store_config, ancient_path).map_err(|e| {
+        if let Some(depth) = store_config.freezer_prune_depth {
+            let min_depth = min_freezer_prune_depth(&consensus);
+            if depth < min_depth {
+                eprintln!(
+                    "Config Error: store.freezer_prune_depth {depth} is less than the minimum {min_depth}"
+                );
+                return Err(ExitCode::Config);
+            }
+        }
+
+        let store = build_store(backend, store_config, ancient_path).map_err(|e| {
             eprintln!("build_store {e}");
             ExitCode::Failure
         })?;
 
-        let txs_verify_cache = Arc::new(TokioRwLock::new(init_cache()));
-
         let (snapshot, table) =
             Self::init_snapshot(&store, Arc::clone(&consensus)).map_err(|e| {
                 eprintln!("init_snapshot {e}");
                 ExitCode::Failure
             })?;
+
+        let txs_verify_cache = Arc::new(TokioRwLock::new(load_verify_cache(
+            &tx_pool_config,
+            &snapshot,
+        )));
         let snapshot = Arc::new(snapshot);
         let snapshot_mgr = Arc::new(SnapshotMgr::new(Arc::clone(&snapshot)));
 
@@ -411,12 +432,20 @@ fn register_tx_pool_callback(tx_pool_builder: &mut TxPoolServiceBuilder, notify:
         notify_proposed.notify_proposed_transaction(notify_tx_entry);
     }));
 
+    let notify_conflict = notify.clone();
+    tx_pool_builder.register_conflict(Box::new(move |entry: &TxEntry, conflict: &TxConflict| {
+        // notify
+        let notify_tx_entry = create_notify_entry(entry);
+        notify_conflict.notify_conflict_transaction(notify_tx_entry, conflict.clone());
+    }));
+
     let notify_reject = notify;
     tx_pool_builder.register_reject(Box::new(
         move |tx_pool: &mut TxPool, entry: &TxEntry, reject: Reject| {
             let tx_hash = entry.transaction().hash();
             // record recent reject
             if reject.should_recorded() {
+                tx_pool.record_tx_event(&tx_hash, TxEvent::Rejected(reject.clone()));
                 if let Some(ref mut recent_reject) = tx_pool.recent_reject {
                     if let Err(e) = recent_reject.put(&tx_hash, reject.clone()) {
                         error!("record recent_reject failed {} {} {}", tx_hash, reject, e);
@@ -442,17 +471,40 @@ fn start_notify_service(notify_config: NotifyConfig, handle: Handle) -> NotifyCo
 }
 
 fn build_store(
-    db: RocksDB,
+    backend: Arc<dyn KeyValueBackend>,
     store_config: StoreConfig,
     ancient_path: Option<PathBuf>,
 ) -> Result<ChainDB, Error> {
-    let store = if store_config.freezer_enable && ancient_path.is_some() {
-        let freezer = Freezer::open(ancient_path.expect("exist checked"))?;
-        ChainDB::new_with_freezer(db, freezer, store_config)
-    } else {
-        ChainDB::new(db, store_config)
+    // the frozen blocks must be persisted along with the database
+    let freezer = match ancient_path {
+        Some(path) if store_config.freezer_enable && backend.rocksdb().is_some() => Some(
+            Freezer::open(path)?
+                .with_prune_depth(store_config.freezer_prune_depth)
+                .with_compression(match store_config.freezer_compression {
+                    FreezerCompression::None => Compression::None,
+                    FreezerCompression::Snappy => Compression::Snappy,
+                    FreezerCompression::Zstd => Compression::Zstd,
+                }),
+        ),
+        _ => None,
     };
-    Ok(store)
+    Ok(ChainDB::with_backend(backend, freezer, store_config))
+}
+
+fn load_verify_cache(tx_pool_config: &TxPoolConfig, snapshot: &Snapshot) -> TxVerificationCache {
+    let mut cache = init_cache();
+    if tx_pool_config.persisted_verify_cache_size > 0 {
+        let script_version = pool_script_version(snapshot.consensus(), snapshot.tip_header());
+        match load_cache(
+            &mut cache,
+            &tx_pool_config.persisted_verify_cache,
+            script_version,
+        ) {
+            Ok(count) => info!("Loaded {} transaction verification cache entries", count),
+            Err(err) => error!("Failed to load the transaction verification cache: {}", err),
+        }
+    }
+    cache
 }
 
 /// SharedBuilder build returning the shared/package halves
@@ -0,0 +1,482 @@
+use super::{
+    unsupported_batch, KeyValueBackend, KeyValueSnapshot, KeyValueTransaction, StoreIter,
+    StoreSlice, WriteBatch,
+};
+use ckb_db::{Direction, IteratorMode};
+use ckb_db_schema::Col;
+use ckb_error::{Error, InternalErrorKind};
+use ckb_util::{Mutex, RwLock};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::ops::Bound;
+use std::path::Path;
+use std::sync::Arc;
+
+// the metadata of the database is stored in the default column
+const DEFAULT_COLUMN: Col = "default";
+
+// The versions of a key in ascending order of the sequence, `None` value means deletion
+type Versions = Vec<(u64, Option<Vec<u8>>)>;
+type Column = BTreeMap<Vec<u8>, Versions>;
+// The pending writes of a transaction, `None` value means deletion
+type Writes = HashMap<Col, BTreeMap<Vec<u8>, Option<Vec<u8>>>>;
+// The count of the items an iterator reads from a column at a time
+const ITER_BATCH_SIZE: usize = 128;
+
+/// The in-memory backend for tests and ephemeral dev chains, all data is lost once dropped.
+///
+/// Every write batch gets a new sequence number, and each key keeps the versions still visible
+/// to the live snapshots, so taking a snapshot or writing never copies a column.
+#[derive(Clone, Default)]
+pub struct MemoryBackend {
+    state: Arc<RwLock<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    columns: HashMap<Col, Column>,
+    // the sequence of the latest write batch
+    seq: u64,
+    // the sequences read by the live snapshots, iterators and transactions, with the counts
+    readers: BTreeMap<u64, usize>,
+}
+
+impl State {
+    // The oldest sequence which is still read, the older versions shadowed by it can be dropped
+    fn oldest_read(&self) -> u64 {
+        self.readers
+            .keys()
+            .next()
+            .copied()
+            .unwrap_or(self.seq)
+            .min(self.seq)
+    }
+
+    fn get(&self, col: Col, key: &[u8], seq: u64) -> Option<&Vec<u8>> {
+        self.columns
+            .get(col)
+            .and_then(|column| column.get(key))
+            .and_then(|versions| visible(versions, seq))
+    }
+
+    // The sequence of the latest write to the key
+    fn latest_write(&self, col: Col, key: &[u8]) -> Option<u64> {
+        self.columns
+            .get(col)
+            .and_then(|column| column.get(key))
+            .and_then(|versions| versions.last())
+            .map(|(seq, _)| *seq)
+    }
+
+    // Applies the writes of a batch under a new sequence
+    fn apply<'a, I>(&mut self, writes: I)
+    where
+        I: IntoIterator<Item = (Col, &'a [u8], Option<&'a [u8]>)>,
+    {
+        self.seq += 1;
+        let seq = self.seq;
+        let oldest_read = self.oldest_read();
+        for (col, key, value) in writes {
+            let column = self.columns.entry(col).or_default();
+            let versions = column.entry(key.to_vec()).or_default();
+            match versions.last_mut() {
+                // written twice in the same batch
+                Some(last) if last.0 == seq => last.1 = value.map(<[u8]>::to_vec),
+                _ => versions.push((seq, value.map(<[u8]>::to_vec))),
+            }
+            // keep the latest version visible to the oldest reader and the newer ones
+            let shadowed = versions
+                .iter()
+                .rposition(|(version, _)| *version <= oldest_read)
+                .unwrap_or(0);
+            versions.drain(..shadowed);
+            // the deletion is visible to all the readers
+            if versions.len() == 1 && versions[0].1.is_none() && versions[0].0 <= oldest_read {
+                column.remove(key);
+            }
+        }
+    }
+}
+
+fn visible(versions: &Versions, seq: u64) -> Option<&Vec<u8>> {
+    versions
+        .iter()
+        .rev()
+        .find(|(version, _)| *version <= seq)
+        .and_then(|(_, value)| value.as_ref())
+}
+
+/// Registers a sequence as read until dropped, which keeps the versions visible to it.
+struct ReadGuard {
+    state: Arc<RwLock<State>>,
+    seq: u64,
+}
+
+impl ReadGuard {
+    fn new(state: &Arc<RwLock<State>>) -> Self {
+        let mut guard = state.write();
+        let seq = guard.seq;
+        *guard.readers.entry(seq).or_default() += 1;
+        ReadGuard {
+            state: Arc::clone(state),
+            seq,
+        }
+    }
+}
+
+impl Drop for ReadGuard {
+    fn drop(&mut self) {
+        let mut state = self.state.write();
+        if let Some(count) = state.readers.get_mut(&self.seq) {
+            *count -= 1;
+            if *count == 0 {
+                state.readers.remove(&self.seq);
+            }
+        }
+    }
+}
+
+/// The write batch of the [`MemoryBackend`].
+#[derive(Default)]
+pub struct MemoryWriteBatch {
+    ops: Vec<(Col, Vec<u8>, Option<Vec<u8>>)>,
+    size: usize,
+}
+
+impl MemoryWriteBatch {
+    pub(crate) fn put(&mut self, col: Col, key: &[u8], value: &[u8]) {
+        self.size += key.len() + value.len();
+        self.ops.push((col, key.to_vec(), Some(value.to_vec())));
+    }
+
+    pub(crate) fn delete(&mut self, col: Col, key: &[u8]) {
+        self.size += key.len();
+        self.ops.push((col, key.to_vec(), None));
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub(crate) fn size_in_bytes(&self) -> usize {
+        self.size
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.ops.clear();
+        self.size = 0;
+    }
+}
+
+impl MemoryBackend {
+    /// Creates an empty backend.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl KeyValueBackend for MemoryBackend {
+    fn get(&self, col: Col, key: &[u8]) -> Result<Option<StoreSlice<'_>>, Error> {
+        let state = self.state.read();
+        Ok(state
+            .get(col, key, state.seq)
+            .map(|value| StoreSlice::Owned(value.clone())))
+    }
+
+    fn iter(&self, col: Col, mode: IteratorMode) -> Result<StoreIter<'_>, Error> {
+        let guard = Arc::new(ReadGuard::new(&self.state));
+        Ok(Box::new(MemoryIter::new(guard, col, mode, BTreeMap::new())))
+    }
+
+    fn get_default(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        let state = self.state.read();
+        Ok(state.get(DEFAULT_COLUMN, key, state.seq).cloned())
+    }
+
+    fn put_default(&self, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        self.state
+            .write()
+            .apply(std::iter::once((DEFAULT_COLUMN, key, Some(value))));
+        Ok(())
+    }
+
+    fn new_write_batch(&self) -> WriteBatch {
+        WriteBatch::Memory(MemoryWriteBatch::default())
+    }
+
+    fn write(&self, batch: &WriteBatch, _sync: bool) -> Result<(), Error> {
+        let WriteBatch::Memory(batch) = batch else {
+            return Err(unsupported_batch());
+        };
+        self.state.write().apply(
+            batch
+                .ops
+                .iter()
+                .map(|(col, key, value)| (*col, key.as_slice(), value.as_deref())),
+        );
+        Ok(())
+    }
+
+    fn snapshot(&self) -> Box<dyn KeyValueSnapshot> {
+        Box::new(MemorySnapshot {
+            guard: Arc::new(ReadGuard::new(&self.state)),
+        })
+    }
+
+    fn transaction(&self) -> Box<dyn KeyValueTransaction> {
+        Box::new(MemoryTransaction {
+            backend: self.clone(),
+            begin: ReadGuard::new(&self.state),
+            writes: Mutex::new(HashMap::new()),
+            reads: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn create_checkpoint(&self, _path: &Path) -> Result<(), Error> {
+        Err(InternalErrorKind::Database
+            .other("the in-memory store can not be backed up")
+            .into())
+    }
+}
+
+struct MemorySnapshot {
+    guard: Arc<ReadGuard>,
+}
+
+impl KeyValueSnapshot for MemorySnapshot {
+    fn get(&self, col: Col, key: &[u8]) -> Result<Option<StoreSlice<'_>>, Error> {
+        Ok(self
+            .guard
+            .state
+            .read()
+            .get(col, key, self.guard.seq)
+            .map(|value| StoreSlice::Owned(value.clone())))
+    }
+
+    fn iter(&self, col: Col, mode: IteratorMode) -> Result<StoreIter<'_>, Error> {
+        Ok(Box::new(MemoryIter::new(
+            Arc::clone(&self.guard),
+            col,
+            mode,
+            BTreeMap::new(),
+        )))
+    }
+}
+
+struct MemoryTransaction {
+    backend: MemoryBackend,
+    // the sequence when the transaction begins, to detect the conflicts
+    begin: ReadGuard,
+    writes: Mutex<Writes>,
+    // the keys read by `get_for_update`
+    reads: Mutex<Vec<(Col, Vec<u8>)>>,
+}
+
+impl MemoryTransaction {
+    fn is_modified(&self, state: &State, col: Col, key: &[u8]) -> bool {
+        state
+            .latest_write(col, key)
+            .map_or(false, |seq| seq > self.begin.seq)
+    }
+}
+
+impl KeyValueTransaction for MemoryTransaction {
+    fn get(&self, col: Col, key: &[u8]) -> Result<Option<StoreSlice<'_>>, Error> {
+        if let Some(value) = self
+            .writes
+            .lock()
+            .get(col)
+            .and_then(|writes| writes.get(key))
+        {
+            return Ok(value.clone().map(StoreSlice::Owned));
+        }
+        self.backend.get(col, key)
+    }
+
+    fn iter(&self, col: Col, mode: IteratorMode) -> Result<StoreIter<'_>, Error> {
+        let guard = Arc::new(ReadGuard::new(&self.backend.state));
+        let writes = self.writes.lock().get(col).cloned().unwrap_or_default();
+        Ok(Box::new(MemoryIter::new(guard, col, mode, writes)))
+    }
+
+    fn put(&self, col: Col, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        self.writes
+            .lock()
+            .entry(col)
+            .or_default()
+            .insert(key.to_vec(), Some(value.to_vec()));
+        Ok(())
+    }
+
+    fn delete(&self, col: Col, key: &[u8]) -> Result<(), Error> {
+        self.writes
+            .lock()
+            .entry(col)
+            .or_default()
+            .insert(key.to_vec(), None);
+        Ok(())
+    }
+
+    fn get_for_update(&self, col: Col, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        self.reads.lock().push((col, key.to_vec()));
+        self.get(col, key)
+            .map(|value| value.map(|value| value.to_vec()))
+    }
+
+    fn commit(&self) -> Result<(), Error> {
+        let mut state = self.backend.state.write();
+        let mut writes = self.writes.lock();
+        let mut reads = self.reads.lock();
+
+        let conflict = reads
+            .iter()
+            .any(|(col, key)| self.is_modified(&state, col, key))
+            || writes
+                .iter()
+                .any(|(col, writes)| writes.keys().any(|key| self.is_modified(&state, col, key)));
+        if conflict {
+            return Err(InternalErrorKind::Database
+                .other("Resource busy: the transaction conflicts with others")
+                .into());
+        }
+
+        let writes: Vec<_> = writes.drain().collect();
+        state.apply(writes.iter().flat_map(|(col, writes)| {
+            writes
+                .iter()
+                .map(move |(key, value)| (*col, key.as_slice(), value.as_deref()))
+        }));
+        reads.clear();
+        Ok(())
+    }
+}
+
+/// Iterates a column at the sequence of the guard without holding the lock between the reads,
+/// the pending writes of a transaction are merged over the column.
+///
+/// The column is read in batches from a cursor, the key after which the next batch starts.
+struct MemoryIter {
+    guard: Arc<ReadGuard>,
+    col: Col,
+    cursor: Bound<Vec<u8>>,
+    direction: Direction,
+    // the items read from the column but not returned yet
+    committed: VecDeque<(Vec<u8>, Vec<u8>)>,
+    // the column has no more items after the cursor
+    exhausted: bool,
+    // the pending writes in the order of the iteration
+    writes: VecDeque<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl MemoryIter {
+    fn new(
+        guard: Arc<ReadGuard>,
+        col: Col,
+        mode: IteratorMode,
+        writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    ) -> Self {
+        let (cursor, direction) = match mode {
+            IteratorMode::Start => (Bound::Unbounded, Direction::Forward),
+            IteratorMode::End => (Bound::Unbounded, Direction::Reverse),
+            IteratorMode::From(key, direction) => (Bound::Included(key.to_vec()), direction),
+        };
+        let writes = match direction {
+            Direction::Forward => writes
+                .range((cursor.clone(), Bound::Unbounded))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            Direction::Reverse => writes
+                .range((Bound::Unbounded, cursor.clone()))
+                .rev()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        };
+        MemoryIter {
+            guard,
+            col,
+            cursor,
+            direction,
+            committed: VecDeque::new(),
+            exhausted: false,
+            writes,
+        }
+    }
+
+    // Reads the next batch of the visible items from the cursor
+    fn read_batch(&mut self) {
+        let state = self.guard.state.read();
+        let column = match state.columns.get(self.col) {
+            Some(column) => column,
+            None => {
+                self.exhausted = true;
+                return;
+            }
+        };
+        let cursor = std::mem::replace(&mut self.cursor, Bound::Unbounded);
+        let range: Box<dyn Iterator<Item = (&Vec<u8>, &Versions)>> = match self.direction {
+            Direction::Forward => Box::new(column.range((cursor, Bound::Unbounded))),
+            Direction::Reverse => Box::new(column.range((Bound::Unbounded, cursor)).rev()),
+        };
+        let mut last = None;
+        for (key, versions) in range {
+            last = Some(key);
+            if let Some(value) = visible(versions, self.guard.seq) {
+                self.committed.push_back((key.clone(), value.clone()));
+                if self.committed.len() == ITER_BATCH_SIZE {
+                    break;
+                }
+            }
+        }
+        match last {
+            Some(key) if self.committed.len() == ITER_BATCH_SIZE => {
+                self.cursor = Bound::Excluded(key.clone());
+            }
+            _ => self.exhausted = true,
+        }
+    }
+
+    // Whether the key `a` comes before `b` in the direction
+    fn precedes(&self, a: &[u8], b: &[u8]) -> bool {
+        match self.direction {
+            Direction::Forward => a < b,
+            Direction::Reverse => a > b,
+        }
+    }
+}
+
+impl Iterator for MemoryIter {
+    type Item = (Box<[u8]>, Box<[u8]>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.committed.is_empty() && !self.exhausted {
+                self.read_batch();
+            }
+            let take_written = match (self.committed.front(), self.writes.front()) {
+                (None, None) => return None,
+                (Some(_), None) => false,
+                (None, Some(_)) => true,
+                (Some((key, _)), Some((written_key, _))) => !self.precedes(key, written_key),
+            };
+            if !take_written {
+                let (key, value) = self.committed.pop_front().expect("checked");
+                return Some((key.into_boxed_slice(), value.into_boxed_slice()));
+            }
+            let (key, value) = self.writes.pop_front().expect("checked");
+            // the pending write overrides the committed value
+            if self
+                .committed
+                .front()
+                .map_or(false, |(committed_key, _)| *committed_key == key)
+            {
+                self.committed.pop_front();
+            }
+            if let Some(value) = value {
+                return Some((key.into_boxed_slice(), value.into_boxed_slice()));
+            }
+        }
+    }
+}
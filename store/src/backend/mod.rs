@@ -0,0 +1,199 @@
+//! The key-value backends of the chain store.
+//!
+//! `ChainDB` accesses the data only through the [`KeyValueBackend`] trait, so the chain logic
+//! can run on other storage engines than RocksDB, e.g. the [`MemoryBackend`] for tests and
+//! ephemeral dev chains.
+use ckb_db::{DBPinnableSlice, IteratorMode, RocksDBWriteBatch};
+use ckb_db_schema::Col;
+use ckb_error::{Error, InternalErrorKind};
+use std::ops::Deref;
+use std::path::Path;
+
+mod memory;
+mod rocksdb;
+
+pub use self::memory::{MemoryBackend, MemoryWriteBatch};
+pub use self::rocksdb::RocksDBBackend;
+
+/// The iterator over the key-value pairs of a column.
+pub type StoreIter<'a> = Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + Send + 'a>;
+
+/// The value returned by the backends.
+pub enum StoreSlice<'a> {
+    /// The value pinned in the RocksDB block cache, which avoids a memory copy.
+    Pinned(DBPinnableSlice<'a>),
+    /// The value owned by the caller.
+    Owned(Vec<u8>),
+}
+
+impl<'a> Deref for StoreSlice<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            StoreSlice::Pinned(slice) => slice,
+            StoreSlice::Owned(value) => value,
+        }
+    }
+}
+
+impl<'a> AsRef<[u8]> for StoreSlice<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+/// An atomic batch of write operations, which is created by
+/// [`KeyValueBackend::new_write_batch`] and applied by [`KeyValueBackend::write`] of the same
+/// backend.
+pub enum WriteBatch {
+    /// The native write batch of RocksDB, which is written without copying.
+    RocksDB(RocksDBWriteBatch),
+    /// The write batch of the [`MemoryBackend`].
+    Memory(MemoryWriteBatch),
+}
+
+impl WriteBatch {
+    /// Write the bytes into the given column with associated key.
+    pub fn put(&mut self, col: Col, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        match self {
+            WriteBatch::RocksDB(batch) => batch.put(col, key, value),
+            WriteBatch::Memory(batch) => {
+                batch.put(col, key, value);
+                Ok(())
+            }
+        }
+    }
+
+    /// Delete the data associated with the given key and given column.
+    pub fn delete(&mut self, col: Col, key: &[u8]) -> Result<(), Error> {
+        match self {
+            WriteBatch::RocksDB(batch) => batch.delete(col, key),
+            WriteBatch::Memory(batch) => {
+                batch.delete(col, key);
+                Ok(())
+            }
+        }
+    }
+
+    /// Return the count of write batch.
+    pub fn len(&self) -> usize {
+        match self {
+            WriteBatch::RocksDB(batch) => batch.len(),
+            WriteBatch::Memory(batch) => batch.len(),
+        }
+    }
+
+    /// Return WriteBatch serialized size (in bytes).
+    pub fn size_in_bytes(&self) -> usize {
+        match self {
+            WriteBatch::RocksDB(batch) => batch.size_in_bytes(),
+            WriteBatch::Memory(batch) => batch.size_in_bytes(),
+        }
+    }
+
+    /// Returns true if the write batch contains no operations.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            WriteBatch::RocksDB(batch) => batch.is_empty(),
+            WriteBatch::Memory(batch) => batch.is_empty(),
+        }
+    }
+
+    /// Clear all updates buffered in this batch.
+    pub fn clear(&mut self) -> Result<(), Error> {
+        match self {
+            WriteBatch::RocksDB(batch) => batch.clear(),
+            WriteBatch::Memory(batch) => {
+                batch.clear();
+                Ok(())
+            }
+        }
+    }
+}
+
+fn unsupported_batch() -> Error {
+    InternalErrorKind::Database
+        .other("the write batch is created by another backend")
+        .into()
+}
+
+/// A point-in-time view of a backend.
+pub trait KeyValueSnapshot: Send + Sync {
+    /// Return the value associated with the given key and given column.
+    fn get(&self, col: Col, key: &[u8]) -> Result<Option<StoreSlice<'_>>, Error>;
+
+    /// Opens an iterator over the given column.
+    fn iter(&self, col: Col, mode: IteratorMode) -> Result<StoreIter<'_>, Error>;
+}
+
+/// An optimistic transaction of a backend.
+///
+/// The reads see the latest committed data and the writes of the transaction itself. The commit
+/// fails if the keys written or read by `get_for_update` are modified by others after the
+/// transaction begins.
+pub trait KeyValueTransaction: Send + Sync {
+    /// Return the value associated with the given key and given column.
+    fn get(&self, col: Col, key: &[u8]) -> Result<Option<StoreSlice<'_>>, Error>;
+
+    /// Opens an iterator over the given column.
+    fn iter(&self, col: Col, mode: IteratorMode) -> Result<StoreIter<'_>, Error>;
+
+    /// Write the bytes into the given column with associated key.
+    fn put(&self, col: Col, key: &[u8], value: &[u8]) -> Result<(), Error>;
+
+    /// Delete the data associated with the given key and given column.
+    fn delete(&self, col: Col, key: &[u8]) -> Result<(), Error>;
+
+    /// Read a key and make the read value a precondition for transaction commit.
+    fn get_for_update(&self, col: Col, key: &[u8]) -> Result<Option<Vec<u8>>, Error>;
+
+    /// Commit the transaction.
+    fn commit(&self) -> Result<(), Error>;
+}
+
+/// The key-value storage engine of the chain store.
+pub trait KeyValueBackend: Send + Sync {
+    /// Return the value associated with the given key and given column.
+    fn get(&self, col: Col, key: &[u8]) -> Result<Option<StoreSlice<'_>>, Error>;
+
+    /// Opens an iterator over the given column.
+    fn iter(&self, col: Col, mode: IteratorMode) -> Result<StoreIter<'_>, Error>;
+
+    /// Return the value associated with the given key in the default column, which stores the
+    /// metadata of the database.
+    fn get_default(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error>;
+
+    /// Insert a value under the given key in the default column.
+    fn put_default(&self, key: &[u8], value: &[u8]) -> Result<(), Error>;
+
+    /// Creates an empty write batch of the backend.
+    fn new_write_batch(&self) -> WriteBatch;
+
+    /// Applies the write batch atomically, `sync` flushes the write to the disk before return.
+    fn write(&self, batch: &WriteBatch, sync: bool) -> Result<(), Error>;
+
+    /// Return a point-in-time view of the backend.
+    fn snapshot(&self) -> Box<dyn KeyValueSnapshot>;
+
+    /// Begins an optimistic transaction.
+    fn transaction(&self) -> Box<dyn KeyValueTransaction>;
+
+    /// Force the data in the key range to go through the compaction.
+    fn compact_range(
+        &self,
+        _col: Col,
+        _start: Option<&[u8]>,
+        _end: Option<&[u8]>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Creates an openable copy of the data at the path while the backend is running.
+    fn create_checkpoint(&self, path: &Path) -> Result<(), Error>;
+
+    /// Return the RocksDB instance if the backend is RocksDB.
+    fn rocksdb(&self) -> Option<&ckb_db::RocksDB> {
+        None
+    }
+}
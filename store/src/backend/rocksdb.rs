@@ -0,0 +1,129 @@
+use super::{
+    unsupported_batch, KeyValueBackend, KeyValueSnapshot, KeyValueTransaction, StoreIter,
+    StoreSlice, WriteBatch,
+};
+use ckb_db::{
+    iter::{DBIterator, IteratorMode},
+    RocksDB, RocksDBSnapshot, RocksDBTransaction,
+};
+use ckb_db_schema::Col;
+use ckb_error::Error;
+use std::path::Path;
+
+/// The RocksDB backend, the default backend of the chain store.
+#[derive(Clone)]
+pub struct RocksDBBackend {
+    db: RocksDB,
+}
+
+impl RocksDBBackend {
+    /// Wraps the RocksDB instance.
+    pub fn new(db: RocksDB) -> Self {
+        RocksDBBackend { db }
+    }
+}
+
+impl KeyValueBackend for RocksDBBackend {
+    fn get(&self, col: Col, key: &[u8]) -> Result<Option<StoreSlice<'_>>, Error> {
+        self.db
+            .get_pinned(col, key)
+            .map(|value| value.map(StoreSlice::Pinned))
+    }
+
+    fn iter(&self, col: Col, mode: IteratorMode) -> Result<StoreIter<'_>, Error> {
+        self.db
+            .iter(col, mode)
+            .map(|iter| Box::new(iter) as StoreIter)
+    }
+
+    fn get_default(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        self.db
+            .get_pinned_default(key)
+            .map(|value| value.map(|slice| slice.to_vec()))
+    }
+
+    fn put_default(&self, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        self.db.put_default(key, value)
+    }
+
+    fn new_write_batch(&self) -> WriteBatch {
+        WriteBatch::RocksDB(self.db.new_write_batch())
+    }
+
+    fn write(&self, batch: &WriteBatch, sync: bool) -> Result<(), Error> {
+        let WriteBatch::RocksDB(batch) = batch else {
+            return Err(unsupported_batch());
+        };
+        if sync {
+            self.db.write_sync(batch)
+        } else {
+            self.db.write(batch)
+        }
+    }
+
+    fn snapshot(&self) -> Box<dyn KeyValueSnapshot> {
+        Box::new(self.db.get_snapshot())
+    }
+
+    fn transaction(&self) -> Box<dyn KeyValueTransaction> {
+        Box::new(self.db.transaction())
+    }
+
+    fn compact_range(
+        &self,
+        col: Col,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+    ) -> Result<(), Error> {
+        self.db.compact_range(col, start, end)
+    }
+
+    fn create_checkpoint(&self, path: &Path) -> Result<(), Error> {
+        self.db.create_checkpoint(path)
+    }
+
+    fn rocksdb(&self) -> Option<&RocksDB> {
+        Some(&self.db)
+    }
+}
+
+impl KeyValueSnapshot for RocksDBSnapshot {
+    fn get(&self, col: Col, key: &[u8]) -> Result<Option<StoreSlice<'_>>, Error> {
+        self.get_pinned(col, key)
+            .map(|value| value.map(StoreSlice::Pinned))
+    }
+
+    fn iter(&self, col: Col, mode: IteratorMode) -> Result<StoreIter<'_>, Error> {
+        DBIterator::iter(self, col, mode).map(|iter| Box::new(iter) as StoreIter)
+    }
+}
+
+impl KeyValueTransaction for RocksDBTransaction {
+    fn get(&self, col: Col, key: &[u8]) -> Result<Option<StoreSlice<'_>>, Error> {
+        self.get_pinned(col, key)
+            .map(|value| value.map(StoreSlice::Pinned))
+    }
+
+    fn iter(&self, col: Col, mode: IteratorMode) -> Result<StoreIter<'_>, Error> {
+        DBIterator::iter(self, col, mode).map(|iter| Box::new(iter) as StoreIter)
+    }
+
+    fn put(&self, col: Col, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        RocksDBTransaction::put(self, col, key, value)
+    }
+
+    fn delete(&self, col: Col, key: &[u8]) -> Result<(), Error> {
+        RocksDBTransaction::delete(self, col, key)
+    }
+
+    fn get_for_update(&self, col: Col, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        // the snapshot is set at the start of the transaction
+        let snapshot = self.get_snapshot();
+        RocksDBTransaction::get_for_update(self, col, key, &snapshot)
+            .map(|value| value.map(|value| value.to_vec()))
+    }
+
+    fn commit(&self) -> Result<(), Error> {
+        RocksDBTransaction::commit(self)
+    }
+}
@@ -6,9 +6,14 @@ use ckb_traits::{
 };
 use ckb_types::{
     bytes::Bytes,
-    core::{BlockExt, BlockNumber, EpochExt, HeaderView},
+    core::{
+        cell::{CellMeta, CellProvider, CellStatus, HeaderChecker},
+        error::OutPointError,
+        BlockExt, BlockNumber, EpochExt, HeaderView,
+    },
     packed::{self, Byte32, OutPoint},
 };
+use std::collections::HashMap;
 use std::sync::Arc;
 
 /// DataLoaderWrapper wrap`ChainStore`
@@ -154,3 +159,213 @@ impl<'a, T: ChainStore> ExtensionProvider for BorrowedDataLoaderWrapper<'a, T> {
         ChainStore::get_block_extension(self.0, hash)
     }
 }
+
+/// The cells and headers injected into the chain state, e.g. to dry run a transaction which
+/// depends on the cells not yet on chain.
+#[derive(Clone, Default)]
+pub struct StateOverrides {
+    cells: HashMap<OutPoint, CellMeta>,
+    headers: HashMap<Byte32, HeaderView>,
+    median_times: HashMap<Byte32, u64>,
+}
+
+impl StateOverrides {
+    /// Creates empty overrides.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Injects a live cell keyed by its out point, the cell data must be loaded in memory.
+    pub fn insert_cell(&mut self, cell_meta: CellMeta) {
+        self.cells.insert(cell_meta.out_point.clone(), cell_meta);
+    }
+
+    /// Injects a header, which is treated as a main chain header.
+    pub fn insert_header(&mut self, header: HeaderView) {
+        self.headers.insert(header.hash(), header);
+    }
+
+    /// Overrides the past median time of the block, which is used by the `since` checks.
+    pub fn set_block_median_time(&mut self, block_hash: Byte32, median_time: u64) {
+        self.median_times.insert(block_hash, median_time);
+    }
+
+    /// Returns the injected header.
+    pub fn header(&self, block_hash: &Byte32) -> Option<&HeaderView> {
+        self.headers.get(block_hash)
+    }
+}
+
+/// Wraps `DataLoaderWrapper` to resolve the cells and headers in the `StateOverrides` before
+/// the ones in the store.
+pub struct OverrideDataLoader<T> {
+    inner: DataLoaderWrapper<T>,
+    overrides: Arc<StateOverrides>,
+}
+
+// auto derive don't work
+impl<T> Clone for OverrideDataLoader<T> {
+    fn clone(&self) -> Self {
+        OverrideDataLoader {
+            inner: self.inner.clone(),
+            overrides: Arc::clone(&self.overrides),
+        }
+    }
+}
+
+impl<T> OverrideDataLoader<T> {
+    /// Construct new OverrideDataLoader
+    pub fn new(inner: DataLoaderWrapper<T>, overrides: StateOverrides) -> Self {
+        OverrideDataLoader {
+            inner,
+            overrides: Arc::new(overrides),
+        }
+    }
+}
+
+impl<T> CellProvider for OverrideDataLoader<T>
+where
+    T: ChainStore,
+{
+    fn cell(&self, out_point: &OutPoint, eager_load: bool) -> CellStatus {
+        if let Some(cell_meta) = self.overrides.cells.get(out_point) {
+            return CellStatus::live_cell(cell_meta.clone());
+        }
+        let store = self.inner.0.as_ref();
+        match store.get_cell(out_point) {
+            Some(mut cell_meta) => {
+                if eager_load {
+                    if let Some((data, data_hash)) = store.get_cell_data(out_point) {
+                        cell_meta.mem_cell_data = Some(data);
+                        cell_meta.mem_cell_data_hash = Some(data_hash);
+                    }
+                }
+                CellStatus::live_cell(cell_meta)
+            }
+            None => CellStatus::Unknown,
+        }
+    }
+}
+
+impl<T> HeaderChecker for OverrideDataLoader<T>
+where
+    T: ChainStore,
+{
+    fn check_valid(&self, block_hash: &Byte32) -> Result<(), OutPointError> {
+        let store = self.inner.0.as_ref();
+        if self.overrides.headers.contains_key(block_hash)
+            || (store.is_main_chain(block_hash) && store.get_block_header(block_hash).is_some())
+        {
+            Ok(())
+        } else {
+            Err(OutPointError::InvalidHeader(block_hash.clone()))
+        }
+    }
+}
+
+impl<T> CellDataProvider for OverrideDataLoader<T>
+where
+    T: ChainStore,
+{
+    fn get_cell_data(&self, out_point: &OutPoint) -> Option<Bytes> {
+        match self.overrides.cells.get(out_point) {
+            Some(cell_meta) => cell_meta.mem_cell_data.clone(),
+            None => self.inner.get_cell_data(out_point),
+        }
+    }
+
+    fn get_cell_data_hash(&self, out_point: &OutPoint) -> Option<Byte32> {
+        match self.overrides.cells.get(out_point) {
+            Some(cell_meta) => cell_meta.mem_cell_data_hash.clone(),
+            None => self.inner.get_cell_data_hash(out_point),
+        }
+    }
+}
+
+impl<T> HeaderProvider for OverrideDataLoader<T>
+where
+    T: ChainStore,
+{
+    fn get_header(&self, block_hash: &Byte32) -> Option<HeaderView> {
+        match self.overrides.headers.get(block_hash) {
+            Some(header) => Some(header.clone()),
+            None => self.inner.get_header(block_hash),
+        }
+    }
+}
+
+impl<T> HeaderFieldsProvider for OverrideDataLoader<T>
+where
+    T: ChainStore,
+{
+    fn get_header_fields(&self, hash: &Byte32) -> Option<HeaderFields> {
+        match self.overrides.headers.get(hash) {
+            Some(header) => Some(HeaderFields {
+                number: header.number(),
+                epoch: header.epoch(),
+                parent_hash: header.data().raw().parent_hash(),
+                timestamp: header.timestamp(),
+                hash: header.hash(),
+            }),
+            None => self.inner.get_header_fields(hash),
+        }
+    }
+
+    fn block_median_time(&self, block_hash: &Byte32, median_block_count: usize) -> u64 {
+        if let Some(median_time) = self.overrides.median_times.get(block_hash) {
+            return *median_time;
+        }
+        // the parents of the injected headers may be unknown, stops at them
+        let mut timestamps: Vec<u64> = Vec::with_capacity(median_block_count);
+        let mut block_hash = block_hash.clone();
+        for _ in 0..median_block_count {
+            let header_fields = match self.get_header_fields(&block_hash) {
+                Some(header_fields) => header_fields,
+                None => break,
+            };
+            timestamps.push(header_fields.timestamp);
+            block_hash = header_fields.parent_hash;
+
+            if header_fields.number == 0 {
+                break;
+            }
+        }
+
+        // return greater one if count is even.
+        timestamps.sort_unstable();
+        timestamps
+            .get(timestamps.len() >> 1)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+impl<T> EpochProvider for OverrideDataLoader<T>
+where
+    T: ChainStore,
+{
+    fn get_epoch_ext(&self, header: &HeaderView) -> Option<EpochExt> {
+        self.inner.get_epoch_ext(header)
+    }
+
+    fn get_block_hash(&self, number: BlockNumber) -> Option<Byte32> {
+        self.inner.get_block_hash(number)
+    }
+
+    fn get_block_ext(&self, block_hash: &Byte32) -> Option<BlockExt> {
+        self.inner.get_block_ext(block_hash)
+    }
+
+    fn get_block_header(&self, hash: &Byte32) -> Option<HeaderView> {
+        self.get_header(hash)
+    }
+}
+
+impl<T> ExtensionProvider for OverrideDataLoader<T>
+where
+    T: ChainStore,
+{
+    fn get_block_extension(&self, hash: &Byte32) -> Option<packed::Bytes> {
+        self.inner.get_block_extension(hash)
+    }
+}
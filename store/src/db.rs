@@ -1,3 +1,4 @@
+use crate::backend::{KeyValueBackend, RocksDBBackend, StoreIter, StoreSlice};
 use crate::cache::StoreCache;
 use crate::cell::attach_block_cell;
 use crate::store::ChainStore;
@@ -6,10 +7,7 @@ use crate::write_batch::StoreWriteBatch;
 use crate::StoreSnapshot;
 use ckb_app_config::StoreConfig;
 use ckb_chain_spec::{consensus::Consensus, versionbits::VersionbitsIndexer};
-use ckb_db::{
-    iter::{DBIter, DBIterator, IteratorMode},
-    DBPinnableSlice, RocksDB,
-};
+use ckb_db::{iter::IteratorMode, RocksDB};
 use ckb_db_schema::{Col, CHAIN_SPEC_HASH_KEY, MIGRATION_VERSION_KEY};
 use ckb_error::{Error, InternalErrorKind};
 use ckb_freezer::Freezer;
@@ -19,14 +17,16 @@ use ckb_types::{
     prelude::*,
     utilities::merkle_mountain_range::ChainRootMMR,
 };
+use std::path::Path;
 use std::sync::Arc;
 
-/// A database of the chain store based on the RocksDB wrapper `RocksDB`
+/// A database of the chain store based on a key-value backend, which is RocksDB by default
 #[derive(Clone)]
 pub struct ChainDB {
-    db: RocksDB,
+    backend: Arc<dyn KeyValueBackend>,
     freezer: Option<Freezer>,
     cache: Arc<StoreCache>,
+    tx_index_prune_depth: Option<u64>,
 }
 
 impl ChainStore for ChainDB {
@@ -38,14 +38,16 @@ impl ChainStore for ChainDB {
         self.freezer.as_ref()
     }
 
-    fn get(&self, col: Col, key: &[u8]) -> Option<DBPinnableSlice> {
-        self.db
-            .get_pinned(col, key)
+    fn get(&self, col: Col, key: &[u8]) -> Option<StoreSlice> {
+        self.backend
+            .get(col, key)
             .expect("db operation should be ok")
     }
 
-    fn get_iter(&self, col: Col, mode: IteratorMode) -> DBIter {
-        self.db.iter(col, mode).expect("db operation should be ok")
+    fn get_iter(&self, col: Col, mode: IteratorMode) -> StoreIter {
+        self.backend
+            .iter(col, mode)
+            .expect("db operation should be ok")
     }
 }
 
@@ -70,58 +72,84 @@ impl VersionbitsIndexer for ChainDB {
 impl ChainDB {
     /// Allocate a new ChainDB instance with the given config
     pub fn new(db: RocksDB, config: StoreConfig) -> Self {
-        let cache = StoreCache::from_config(config);
-        ChainDB {
-            db,
-            freezer: None,
-            cache: Arc::new(cache),
-        }
+        Self::with_backend(Arc::new(RocksDBBackend::new(db)), None, config)
     }
 
     /// Open new ChainDB with freezer instance
     pub fn new_with_freezer(db: RocksDB, freezer: Freezer, config: StoreConfig) -> Self {
+        Self::with_backend(Arc::new(RocksDBBackend::new(db)), Some(freezer), config)
+    }
+
+    /// Allocate a new ChainDB instance on the key-value backend
+    pub fn with_backend(
+        backend: Arc<dyn KeyValueBackend>,
+        freezer: Option<Freezer>,
+        config: StoreConfig,
+    ) -> Self {
         let cache = StoreCache::from_config(config);
         ChainDB {
-            db,
-            freezer: Some(freezer),
+            backend,
+            freezer,
             cache: Arc::new(cache),
+            tx_index_prune_depth: config.tx_index_prune_depth,
         }
     }
 
-    /// Return the inner RocksDB instance
-    pub fn db(&self) -> &RocksDB {
-        &self.db
+    /// Return the key-value backend
+    pub fn backend(&self) -> &dyn KeyValueBackend {
+        self.backend.as_ref()
+    }
+
+    /// Return the inner RocksDB instance, `None` if the store is not backed by RocksDB
+    pub fn db(&self) -> Option<&RocksDB> {
+        self.backend.rocksdb()
     }
 
-    /// Converts self into a `RocksDB`
-    pub fn into_inner(self) -> RocksDB {
-        self.db
+    /// Return the depth from the tip beyond which the transaction index is pruned
+    pub fn tx_index_prune_depth(&self) -> Option<u64> {
+        self.tx_index_prune_depth
     }
 
     /// Store the chain spec hash
     pub fn put_chain_spec_hash(&self, hash: &packed::Byte32) -> Result<(), Error> {
-        self.db.put_default(CHAIN_SPEC_HASH_KEY, hash.as_slice())
+        self.backend
+            .put_default(CHAIN_SPEC_HASH_KEY, hash.as_slice())
     }
 
     /// Return the chain spec hash
     pub fn get_chain_spec_hash(&self) -> Option<packed::Byte32> {
-        self.db
-            .get_pinned_default(CHAIN_SPEC_HASH_KEY)
+        self.backend
+            .get_default(CHAIN_SPEC_HASH_KEY)
             .expect("db operation should be ok")
             .map(|raw| packed::Byte32Reader::from_slice_should_be_ok(raw.as_ref()).to_entity())
     }
 
     /// Return the chain spec hash
-    pub fn get_migration_version(&self) -> Option<DBPinnableSlice> {
-        self.db
-            .get_pinned_default(MIGRATION_VERSION_KEY)
+    pub fn get_migration_version(&self) -> Option<Vec<u8>> {
+        self.backend
+            .get_default(MIGRATION_VERSION_KEY)
             .expect("db operation should be ok")
     }
 
+    /// Creates a consistent copy of the store while it's running.
+    ///
+    /// The kv-db checkpoint is created at `db_path`, the frozen blocks are copied into
+    /// `ancient_path` if the freezer is enabled.
+    pub fn create_backup(&self, db_path: &Path, ancient_path: &Path) -> Result<(), Error> {
+        match self.freezer {
+            // freezing is paused while the checkpoint is created, so all the blocks which
+            // are not in the freezer copy are still in the checkpoint
+            Some(ref freezer) => {
+                freezer.snapshot(ancient_path, || self.backend.create_checkpoint(db_path))
+            }
+            None => self.backend.create_checkpoint(db_path),
+        }
+    }
+
     /// Set this snapshot at start of transaction
     pub fn begin_transaction(&self) -> StoreTransaction {
         StoreTransaction {
-            inner: self.db.transaction(),
+            inner: self.backend.transaction(),
             freezer: self.freezer.clone(),
             cache: Arc::clone(&self.cache),
         }
@@ -130,7 +158,7 @@ impl ChainDB {
     /// Return `StoreSnapshot`
     pub fn get_snapshot(&self) -> StoreSnapshot {
         StoreSnapshot {
-            inner: self.db.get_snapshot(),
+            inner: self.backend.snapshot(),
             freezer: self.freezer.clone(),
             cache: Arc::clone(&self.cache),
         }
@@ -139,20 +167,20 @@ impl ChainDB {
     /// Construct `StoreWriteBatch` with default option.
     pub fn new_write_batch(&self) -> StoreWriteBatch {
         StoreWriteBatch {
-            inner: self.db.new_write_batch(),
+            inner: self.backend.new_write_batch(),
         }
     }
 
     /// Write batch into chain db.
     pub fn write(&self, write_batch: &StoreWriteBatch) -> Result<(), Error> {
-        self.db.write(&write_batch.inner)
+        self.backend.write(&write_batch.inner, false)
     }
 
     /// write options set_sync = true
     ///
     /// see [`RocksDB::write_sync`](ckb_db::RocksDB::write_sync).
     pub fn write_sync(&self, write_batch: &StoreWriteBatch) -> Result<(), Error> {
-        self.db.write_sync(&write_batch.inner)
+        self.backend.write(&write_batch.inner, true)
     }
 
     /// Force the data to go through the compaction in order to consolidate it
@@ -164,7 +192,7 @@ impl ChainDB {
         start: Option<&[u8]>,
         end: Option<&[u8]>,
     ) -> Result<(), Error> {
-        self.db.compact_range(col, start, end)
+        self.backend.compact_range(col, start, end)
     }
 
     /// TODO(doc): @quake
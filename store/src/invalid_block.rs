@@ -0,0 +1,51 @@
+use ckb_error::{Error, InternalErrorKind};
+use ckb_types::core::BlockNumber;
+use std::convert::TryInto;
+
+// number (8 bytes) + timestamp (8 bytes) + manual flag (1 byte)
+const FIXED_SIZE: usize = 17;
+
+/// A block rejected by the chain, recorded in `COLUMN_INVALID_BLOCK` by the block hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidBlock {
+    /// The block number.
+    pub number: BlockNumber,
+    /// The unix timestamp in milliseconds when the block is marked invalid.
+    pub timestamp: u64,
+    /// Whether the block is invalidated by the operator instead of failing the verification.
+    pub manual: bool,
+    /// The verification error, or the reason of the manual invalidation.
+    pub reason: String,
+}
+
+impl InvalidBlock {
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(FIXED_SIZE + self.reason.len());
+        bytes.extend_from_slice(&self.number.to_le_bytes());
+        bytes.extend_from_slice(&self.timestamp.to_le_bytes());
+        bytes.push(u8::from(self.manual));
+        bytes.extend_from_slice(self.reason.as_bytes());
+        bytes
+    }
+
+    /// Decodes the record, returns an error if the stored bytes are malformed.
+    pub(crate) fn from_slice(slice: &[u8]) -> Result<Self, Error> {
+        if slice.len() < FIXED_SIZE || slice[16] > 1 {
+            return Err(InternalErrorKind::DataCorrupted
+                .other(format!(
+                    "invalid block record of {} bytes is malformed",
+                    slice.len()
+                ))
+                .into());
+        }
+        let reason = std::str::from_utf8(&slice[FIXED_SIZE..]).map_err(|err| {
+            InternalErrorKind::DataCorrupted.other(format!("invalid block record reason: {err}"))
+        })?;
+        Ok(InvalidBlock {
+            number: u64::from_le_bytes(slice[0..8].try_into().expect("checked length")),
+            timestamp: u64::from_le_bytes(slice[8..16].try_into().expect("checked length")),
+            manual: slice[16] != 0,
+            reason: reason.to_owned(),
+        })
+    }
+}
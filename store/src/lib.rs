@@ -3,13 +3,16 @@
 //! This Library contains the `ChainStore` traits
 //! which provides chain data store interface
 
+pub mod backend;
 mod cache;
 mod cell;
 pub mod data_loader_wrapper;
 mod db;
+mod invalid_block;
 mod snapshot;
 mod store;
 mod transaction;
+mod tx_index;
 mod write_batch;
 
 #[cfg(test)]
@@ -18,9 +21,11 @@ mod tests;
 pub use cache::StoreCache;
 pub use cell::{attach_block_cell, detach_block_cell};
 pub use db::ChainDB;
+pub use invalid_block::InvalidBlock;
 pub use snapshot::StoreSnapshot;
-pub use store::ChainStore;
+pub use store::{BlockPruned, ChainStore};
 pub use transaction::StoreTransaction;
+pub use tx_index::{attach_block_tx_index, clear_tx_spent, prune_block_tx_index};
 pub use write_batch::StoreWriteBatch;
 
-pub use ckb_freezer::Freezer;
+pub use ckb_freezer::{Compression, Freezer};
@@ -1,16 +1,14 @@
+use crate::backend::{KeyValueSnapshot, StoreIter, StoreSlice};
 use crate::cache::StoreCache;
 use crate::store::ChainStore;
-use ckb_db::{
-    iter::{DBIter, DBIterator, IteratorMode},
-    DBPinnableSlice, RocksDBSnapshot,
-};
+use ckb_db::iter::IteratorMode;
 use ckb_db_schema::Col;
 use ckb_freezer::Freezer;
 use std::sync::Arc;
 
 /// A snapshot of the chain store.
 pub struct StoreSnapshot {
-    pub(crate) inner: RocksDBSnapshot,
+    pub(crate) inner: Box<dyn KeyValueSnapshot>,
     pub(crate) freezer: Option<Freezer>,
     pub(crate) cache: Arc<StoreCache>,
 }
@@ -24,13 +22,11 @@ impl ChainStore for StoreSnapshot {
         self.freezer.as_ref()
     }
 
-    fn get(&self, col: Col, key: &[u8]) -> Option<DBPinnableSlice> {
-        self.inner
-            .get_pinned(col, key)
-            .expect("db operation should be ok")
+    fn get(&self, col: Col, key: &[u8]) -> Option<StoreSlice> {
+        self.inner.get(col, key).expect("db operation should be ok")
     }
 
-    fn get_iter(&self, col: Col, mode: IteratorMode) -> DBIter {
+    fn get_iter(&self, col: Col, mode: IteratorMode) -> StoreIter {
         self.inner
             .iter(col, mode)
             .expect("db operation should be ok")
@@ -1,16 +1,17 @@
+use crate::backend::{StoreIter, StoreSlice};
 use crate::cache::StoreCache;
 use crate::data_loader_wrapper::BorrowedDataLoaderWrapper;
-use ckb_db::{
-    iter::{DBIter, Direction, IteratorMode},
-    DBPinnableSlice,
-};
+use crate::invalid_block::InvalidBlock;
+use ckb_db::iter::{Direction, IteratorMode};
 use ckb_db_schema::{
     Col, COLUMN_BLOCK_BODY, COLUMN_BLOCK_EPOCH, COLUMN_BLOCK_EXT, COLUMN_BLOCK_EXTENSION,
     COLUMN_BLOCK_FILTER, COLUMN_BLOCK_FILTER_HASH, COLUMN_BLOCK_HEADER, COLUMN_BLOCK_PROPOSAL_IDS,
     COLUMN_BLOCK_UNCLE, COLUMN_CELL, COLUMN_CELL_DATA, COLUMN_CELL_DATA_HASH,
-    COLUMN_CHAIN_ROOT_MMR, COLUMN_EPOCH, COLUMN_INDEX, COLUMN_META, COLUMN_TRANSACTION_INFO,
-    COLUMN_UNCLES, META_CURRENT_EPOCH_KEY, META_LATEST_BUILT_FILTER_DATA_KEY, META_TIP_HEADER_KEY,
+    COLUMN_CHAIN_ROOT_MMR, COLUMN_EPOCH, COLUMN_INDEX, COLUMN_INVALID_BLOCK, COLUMN_META,
+    COLUMN_NUMBER_HASH, COLUMN_TRANSACTION_INFO, COLUMN_UNCLES, META_CURRENT_EPOCH_KEY,
+    META_LATEST_BUILT_FILTER_DATA_KEY, META_TIP_HEADER_KEY, META_TX_INDEX_PRUNED_KEY,
 };
+use ckb_error::{Error, InternalErrorKind};
 use ckb_freezer::Freezer;
 use ckb_types::{
     bytes::Bytes,
@@ -21,6 +22,20 @@ use ckb_types::{
     packed::{self, OutPoint},
     prelude::*,
 };
+use std::fmt;
+
+/// The block body has been deleted by the freezer in the pruned mode, only the header, uncles,
+/// proposals and extension of the block are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockPruned(pub BlockNumber);
+
+impl fmt::Display for BlockPruned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the body of block #{} is pruned", self.0)
+    }
+}
+
+impl std::error::Error for BlockPruned {}
 
 /// The `ChainStore` trait provides chain data store interface
 pub trait ChainStore: Send + Sync + Sized {
@@ -29,24 +44,29 @@ pub trait ChainStore: Send + Sync + Sized {
     /// Return freezer reference
     fn freezer(&self) -> Option<&Freezer>;
     /// Return the bytes associated with a key value and the given column family.
-    fn get(&self, col: Col, key: &[u8]) -> Option<DBPinnableSlice>;
+    fn get(&self, col: Col, key: &[u8]) -> Option<StoreSlice>;
     /// Return an iterator over the database key-value pairs in the given column family.
-    fn get_iter(&self, col: Col, mode: IteratorMode) -> DBIter;
+    fn get_iter(&self, col: Col, mode: IteratorMode) -> StoreIter;
     /// Return the borrowed data loader wrapper
     fn borrow_as_data_loader(&self) -> BorrowedDataLoaderWrapper<Self> {
         BorrowedDataLoaderWrapper::new(self)
     }
 
     /// Get block by block header hash
-    fn get_block(&self, h: &packed::Byte32) -> Option<BlockView> {
-        let header = self.get_block_header(h)?;
+    ///
+    /// Returns `Err(BlockPruned)` if the block body has been deleted in the pruned mode.
+    fn get_block(&self, h: &packed::Byte32) -> Result<Option<BlockView>, BlockPruned> {
+        let header = match self.get_block_header(h) {
+            Some(header) => header,
+            None => return Ok(None),
+        };
         if let Some(freezer) = self.freezer() {
             if header.number() > 0 && header.number() < freezer.number() {
-                let raw_block = freezer.retrieve(header.number()).expect("block frozen")?;
-                let raw_block = packed::BlockReader::from_compatible_slice(&raw_block)
-                    .expect("checked data")
-                    .to_entity();
-                return Some(raw_block.into_view());
+                return match read_frozen_block(freezer, header.number()) {
+                    Some(raw_block) => Ok(Some(raw_block.into_view())),
+                    None if freezer.is_pruned(header.number()) => Err(BlockPruned(header.number())),
+                    None => Ok(None),
+                };
             }
         }
         let body = self.get_block_body(h);
@@ -63,7 +83,14 @@ pub trait ChainStore: Send + Sync + Sized {
         } else {
             BlockView::new_unchecked(header, uncles, body, proposals)
         };
-        Some(block)
+        Ok(Some(block))
+    }
+
+    /// Return whether the body of the block with the given number has been pruned
+    fn is_block_body_pruned(&self, number: BlockNumber) -> bool {
+        self.freezer()
+            .map(|freezer| freezer.is_pruned(number))
+            .unwrap_or(false)
     }
 
     /// Get header by block header hash
@@ -322,12 +349,8 @@ pub trait ChainStore: Send + Sync + Sized {
         let tx_info = self.get_transaction_info(hash)?;
         if let Some(freezer) = self.freezer() {
             if tx_info.block_number > 0 && tx_info.block_number < freezer.number() {
-                let raw_block = freezer
-                    .retrieve(tx_info.block_number)
-                    .expect("block frozen")?;
-                let raw_block_reader =
-                    packed::BlockReader::from_compatible_slice(&raw_block).expect("checked data");
-                let tx_reader = raw_block_reader.transactions().get(tx_info.index)?;
+                let raw_block = read_frozen_block(freezer, tx_info.block_number)?;
+                let tx_reader = raw_block.as_reader().transactions().get(tx_info.index)?;
                 return Some((tx_reader.to_entity().into_view(), tx_info));
             }
         }
@@ -481,6 +504,14 @@ pub trait ChainStore: Send + Sync + Sized {
             .map(|raw| packed::Byte32Reader::from_slice_should_be_ok(raw.as_ref()).to_entity())
     }
 
+    /// Gets the number of the latest block whose transactions are pruned from the index.
+    ///
+    /// Returns `None` if the transaction index is complete.
+    fn get_tx_index_pruned_number(&self) -> Option<BlockNumber> {
+        self.get(COLUMN_META, META_TX_INDEX_PRUNED_KEY)
+            .map(|raw| packed::Uint64Reader::from_slice_should_be_ok(raw.as_ref()).unpack())
+    }
+
     /// Gets block filter data by block hash
     fn get_block_filter(&self, hash: &packed::Byte32) -> Option<packed::Bytes> {
         self.get(COLUMN_BLOCK_FILTER, hash.as_slice())
@@ -493,6 +524,45 @@ pub trait ChainStore: Send + Sync + Sized {
             .map(|slice| packed::Byte32Reader::from_slice_should_be_ok(slice.as_ref()).to_entity())
     }
 
+    /// Gets the invalid block record by block hash, returns an error if the record is malformed
+    fn get_invalid_block(&self, hash: &packed::Byte32) -> Result<Option<InvalidBlock>, Error> {
+        self.get(COLUMN_INVALID_BLOCK, hash.as_slice())
+            .map(|slice| InvalidBlock::from_slice(slice.as_ref()))
+            .transpose()
+    }
+
+    /// Gets all the invalid block records, returns an error if any record is malformed
+    fn get_invalid_blocks(&self) -> Result<Vec<(packed::Byte32, InvalidBlock)>, Error> {
+        self.get_iter(COLUMN_INVALID_BLOCK, IteratorMode::Start)
+            .map(|(key, value)| {
+                let hash = packed::Byte32Reader::from_slice(&key)
+                    .map_err(|err| {
+                        InternalErrorKind::DataCorrupted
+                            .other(format!("invalid block record key: {err}"))
+                    })?
+                    .to_entity();
+                Ok((hash, InvalidBlock::from_slice(&value)?))
+            })
+            .collect()
+    }
+
+    /// Gets the hashes of all the stored blocks of the number, including the side chain blocks
+    fn get_block_hashes_by_number(&self, number: BlockNumber) -> Vec<packed::Byte32> {
+        let pack_number: packed::Uint64 = number.pack();
+        let prefix = pack_number.as_slice();
+        self.get_iter(
+            COLUMN_NUMBER_HASH,
+            IteratorMode::From(prefix, Direction::Forward),
+        )
+        .take_while(|(key, _)| key.starts_with(prefix))
+        .map(|(key, _)| {
+            packed::NumberHashReader::from_slice_should_be_ok(key.as_ref())
+                .block_hash()
+                .to_entity()
+        })
+        .collect()
+    }
+
     /// Gets block bytes by block hash
     fn get_packed_block(&self, hash: &packed::Byte32) -> Option<packed::Block> {
         let header = self
@@ -589,3 +659,22 @@ fn build_cell_meta_from_reader(out_point: OutPoint, reader: packed::CellEntryRea
         mem_cell_data_hash: None,
     }
 }
+
+// Reads the frozen block, an unreadable one is logged and treated as missing instead of
+// panicking, e.g. its checksum mismatches. `ckb freezer --verify` reports all of them.
+fn read_frozen_block(freezer: &Freezer, number: BlockNumber) -> Option<packed::Block> {
+    let raw_block = match freezer.retrieve(number) {
+        Ok(raw_block) => raw_block?,
+        Err(e) => {
+            ckb_logger::error!("Freezer retrieve block {} error {}", number, e);
+            return None;
+        }
+    };
+    match packed::BlockReader::from_compatible_slice(&raw_block) {
+        Ok(reader) => Some(reader.to_entity()),
+        Err(e) => {
+            ckb_logger::error!("Freezer block {} is malformed {}", number, e);
+            None
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use ckb_chain_spec::consensus::ConsensusBuilder;
+use ckb_db::{Direction, IteratorMode};
+use ckb_db_schema::{COLUMN_BLOCK_HEADER, COLUMN_META};
+use std::sync::Arc;
+
+use crate::{
+    backend::{KeyValueBackend, MemoryBackend},
+    db::ChainDB,
+    store::ChainStore,
+};
+
+fn keys(iter: crate::backend::StoreIter) -> Vec<Vec<u8>> {
+    iter.map(|(key, _)| key.to_vec()).collect()
+}
+
+#[test]
+fn memory_iter() {
+    let backend = MemoryBackend::new();
+    let mut batch = backend.new_write_batch();
+    for key in [b"1", b"3", b"5"] {
+        batch.put(COLUMN_META, key, b"v").unwrap();
+    }
+    backend.write(&batch, false).unwrap();
+
+    let forward = backend
+        .iter(COLUMN_META, IteratorMode::From(b"2", Direction::Forward))
+        .unwrap();
+    assert_eq!(keys(forward), vec![b"3".to_vec(), b"5".to_vec()]);
+    let reverse = backend
+        .iter(COLUMN_META, IteratorMode::From(b"3", Direction::Reverse))
+        .unwrap();
+    assert_eq!(keys(reverse), vec![b"3".to_vec(), b"1".to_vec()]);
+    let end = backend.iter(COLUMN_META, IteratorMode::End).unwrap();
+    assert_eq!(end.count(), 3);
+    let empty = backend
+        .iter(COLUMN_BLOCK_HEADER, IteratorMode::Start)
+        .unwrap();
+    assert_eq!(empty.count(), 0);
+}
+
+#[test]
+fn memory_snapshot_and_transaction() {
+    let backend = MemoryBackend::new();
+    let snapshot = backend.snapshot();
+
+    let txn = backend.transaction();
+    txn.put(COLUMN_META, b"key", b"value").unwrap();
+    assert_eq!(
+        txn.get(COLUMN_META, b"key").unwrap().as_deref(),
+        Some(&b"value"[..])
+    );
+    assert!(backend.get(COLUMN_META, b"key").unwrap().is_none());
+    txn.commit().unwrap();
+
+    assert!(backend.get(COLUMN_META, b"key").unwrap().is_some());
+    assert!(snapshot.get(COLUMN_META, b"key").unwrap().is_none());
+
+    // the key read for update is modified by another transaction
+    let txn1 = backend.transaction();
+    let txn2 = backend.transaction();
+    txn1.get_for_update(COLUMN_META, b"key").unwrap();
+    txn1.put(COLUMN_META, b"other", b"1").unwrap();
+    txn2.delete(COLUMN_META, b"key").unwrap();
+    txn2.commit().unwrap();
+    assert!(txn1.commit().is_err());
+    assert!(backend.get(COLUMN_META, b"other").unwrap().is_none());
+}
+
+#[test]
+fn memory_snapshot_versions() {
+    let backend = MemoryBackend::new();
+    let mut batch = backend.new_write_batch();
+    batch.put(COLUMN_META, b"1", b"a").unwrap();
+    batch.put(COLUMN_META, b"2", b"a").unwrap();
+    backend.write(&batch, false).unwrap();
+
+    let snapshot = backend.snapshot();
+    let mut batch = backend.new_write_batch();
+    batch.put(COLUMN_META, b"1", b"b").unwrap();
+    batch.delete(COLUMN_META, b"2").unwrap();
+    batch.put(COLUMN_META, b"3", b"b").unwrap();
+    backend.write(&batch, false).unwrap();
+
+    // the snapshot keeps the versions when it's taken
+    assert_eq!(
+        snapshot.get(COLUMN_META, b"1").unwrap().as_deref(),
+        Some(&b"a"[..])
+    );
+    assert!(snapshot.get(COLUMN_META, b"3").unwrap().is_none());
+    let iter = snapshot.iter(COLUMN_META, IteratorMode::Start).unwrap();
+    assert_eq!(keys(iter), vec![b"1".to_vec(), b"2".to_vec()]);
+    assert_eq!(
+        backend.get(COLUMN_META, b"1").unwrap().as_deref(),
+        Some(&b"b"[..])
+    );
+    drop(snapshot);
+
+    // the iterator of a transaction merges its writes over the latest data
+    let txn = backend.transaction();
+    txn.delete(COLUMN_META, b"1").unwrap();
+    txn.put(COLUMN_META, b"4", b"c").unwrap();
+    let iter = txn.iter(COLUMN_META, IteratorMode::End).unwrap();
+    assert_eq!(keys(iter), vec![b"4".to_vec(), b"3".to_vec()]);
+    txn.commit().unwrap();
+    let iter = backend.iter(COLUMN_META, IteratorMode::Start).unwrap();
+    assert_eq!(keys(iter), vec![b"3".to_vec(), b"4".to_vec()]);
+}
+
+#[test]
+fn memory_iter_in_batches() {
+    let backend = MemoryBackend::new();
+    let mut batch = backend.new_write_batch();
+    for i in 0u32..1000 {
+        batch.put(COLUMN_META, &i.to_be_bytes(), b"v").unwrap();
+    }
+    backend.write(&batch, false).unwrap();
+    // keep the odd keys only
+    let mut batch = backend.new_write_batch();
+    for i in (0u32..1000).step_by(2) {
+        batch.delete(COLUMN_META, &i.to_be_bytes()).unwrap();
+    }
+    backend.write(&batch, false).unwrap();
+
+    let odd: Vec<_> = (0u32..1000)
+        .filter(|i| i % 2 == 1)
+        .map(|i| i.to_be_bytes().to_vec())
+        .collect();
+    let forward = backend.iter(COLUMN_META, IteratorMode::Start).unwrap();
+    assert_eq!(keys(forward), odd);
+    let reverse = backend.iter(COLUMN_META, IteratorMode::End).unwrap();
+    assert_eq!(keys(reverse), odd.iter().rev().cloned().collect::<Vec<_>>());
+
+    // the writes of a transaction are merged in every batch
+    let txn = backend.transaction();
+    txn.put(COLUMN_META, &500u32.to_be_bytes(), b"v").unwrap();
+    txn.delete(COLUMN_META, &999u32.to_be_bytes()).unwrap();
+    let from = 400u32.to_be_bytes();
+    let iter = txn
+        .iter(COLUMN_META, IteratorMode::From(&from, Direction::Forward))
+        .unwrap();
+    let mut expected: Vec<_> = (400u32..999)
+        .filter(|i| i % 2 == 1 || *i == 500)
+        .map(|i| i.to_be_bytes().to_vec())
+        .collect();
+    assert_eq!(keys(iter), expected);
+    let iter = txn
+        .iter(COLUMN_META, IteratorMode::From(&from, Direction::Reverse))
+        .unwrap();
+    expected = (0u32..400)
+        .rev()
+        .filter(|i| i % 2 == 1)
+        .map(|i| i.to_be_bytes().to_vec())
+        .collect();
+    assert_eq!(keys(iter), expected);
+}
+
+#[test]
+fn save_and_get_block_in_memory() {
+    let store = ChainDB::with_backend(Arc::new(MemoryBackend::new()), None, Default::default());
+    let consensus = ConsensusBuilder::default().build();
+    store.init(&consensus).unwrap();
+
+    let block = consensus.genesis_block();
+    assert_eq!(block, &store.get_block(&block.hash()).unwrap().unwrap());
+    assert_eq!(store.get_tip_header().unwrap(), block.header());
+    assert!(store.db().is_none());
+}
@@ -0,0 +1,77 @@
+use ckb_chain_spec::consensus::ConsensusBuilder;
+use ckb_traits::{CellDataProvider, HeaderFieldsProvider, HeaderProvider};
+use ckb_types::{
+    bytes::Bytes,
+    core::{
+        cell::{CellMetaBuilder, CellProvider, CellStatus, HeaderChecker},
+        EpochNumberWithFraction, HeaderBuilder,
+    },
+    packed::{CellOutput, OutPoint},
+    prelude::*,
+};
+use std::sync::Arc;
+
+use crate::{
+    backend::MemoryBackend,
+    data_loader_wrapper::{AsDataLoader, OverrideDataLoader, StateOverrides},
+    db::ChainDB,
+};
+
+#[test]
+fn override_cells_and_headers() {
+    let store = Arc::new(ChainDB::with_backend(
+        Arc::new(MemoryBackend::new()),
+        None,
+        Default::default(),
+    ));
+    let consensus = ConsensusBuilder::default().build();
+    store.init(&consensus).unwrap();
+    let genesis = consensus.genesis_block().header();
+
+    let data = Bytes::from_static(b"override");
+    let out_point = OutPoint::new(Default::default(), 0);
+    let cell_meta = CellMetaBuilder::from_cell_output(CellOutput::default(), data.clone())
+        .out_point(out_point.clone())
+        .build();
+    let header = HeaderBuilder::default()
+        .parent_hash(genesis.hash())
+        .number(1.pack())
+        .epoch(EpochNumberWithFraction::new(0, 1, 1000).pack())
+        .timestamp((genesis.timestamp() + 1).pack())
+        .build();
+    let mut overrides = StateOverrides::new();
+    overrides.insert_cell(cell_meta);
+    overrides.insert_header(header.clone());
+    overrides.set_block_median_time(header.hash(), 42);
+    let data_loader = OverrideDataLoader::new(store.as_data_loader(), overrides);
+
+    match data_loader.cell(&out_point, true) {
+        CellStatus::Live(cell_meta) => assert_eq!(cell_meta.mem_cell_data, Some(data.clone())),
+        _ => panic!("the injected cell should be live"),
+    }
+    assert_eq!(data_loader.get_cell_data(&out_point), Some(data));
+    assert!(data_loader
+        .cell(&OutPoint::new(Default::default(), 1), true)
+        .is_unknown());
+
+    assert!(data_loader.check_valid(&header.hash()).is_ok());
+    assert!(data_loader.check_valid(&genesis.hash()).is_ok());
+    assert_eq!(data_loader.get_header(&header.hash()), Some(header.clone()));
+    assert_eq!(data_loader.block_median_time(&header.hash(), 37), 42);
+
+    // the median time of the other blocks are calculated from the headers
+    let child = HeaderBuilder::default()
+        .parent_hash(header.hash())
+        .number(2.pack())
+        .epoch(EpochNumberWithFraction::new(0, 2, 1000).pack())
+        .timestamp((genesis.timestamp() + 2).pack())
+        .build();
+    let mut overrides = StateOverrides::new();
+    overrides.insert_header(header);
+    overrides.insert_header(child.clone());
+    let data_loader = OverrideDataLoader::new(store.as_data_loader(), overrides);
+    assert_eq!(
+        data_loader.block_median_time(&child.hash(), 37),
+        genesis.timestamp() + 1
+    );
+}
@@ -19,7 +19,7 @@ fn save_and_get_block() {
     let txn = store.begin_transaction();
     txn.insert_block(block).unwrap();
     txn.commit().unwrap();
-    assert_eq!(block, &store.get_block(&hash).unwrap());
+    assert_eq!(block, &store.get_block(&hash).unwrap().unwrap());
 }
 
 #[test]
@@ -41,7 +41,7 @@ fn save_and_get_block_with_transactions() {
     let txn = store.begin_transaction();
     txn.insert_block(&block).unwrap();
     txn.commit().unwrap();
-    assert_eq!(block, store.get_block(&hash).unwrap());
+    assert_eq!(block, store.get_block(&hash).unwrap().unwrap());
 }
 
 #[test]
@@ -120,7 +120,7 @@ fn freeze_blockv0() {
         .freeze(2, |_number| Some(block.clone()))
         .expect("freeze");
 
-    assert_eq!(store.get_block(&block_hash), Some(block));
+    assert_eq!(store.get_block(&block_hash), Ok(Some(block)));
 }
 
 #[test]
@@ -156,6 +156,6 @@ fn freeze_blockv1_with_extension() {
         .freeze(2, |_number| Some(block.clone()))
         .expect("freeze");
 
-    let block = store.get_block(&block_hash).expect("get_block");
-    assert_eq!(store.get_block(&block_hash), Some(block));
+    let block = store.get_block(&block_hash).unwrap().expect("get_block");
+    assert_eq!(store.get_block(&block_hash), Ok(Some(block)));
 }
@@ -0,0 +1,48 @@
+use ckb_db_schema::COLUMN_INVALID_BLOCK;
+use ckb_types::{h256, packed::Byte32, prelude::*};
+use std::sync::Arc;
+
+use crate::{backend::MemoryBackend, db::ChainDB, store::ChainStore, InvalidBlock};
+
+#[test]
+fn insert_and_delete_invalid_block() {
+    let store = ChainDB::with_backend(Arc::new(MemoryBackend::new()), None, Default::default());
+    let hash: Byte32 = h256!("0x1").pack();
+    let invalid_block = InvalidBlock {
+        number: 42,
+        timestamp: 1_700_000_000_000,
+        manual: false,
+        reason: "Header(Timestamp(BlockTimeTooOld))".to_owned(),
+    };
+
+    let txn = store.begin_transaction();
+    txn.insert_invalid_block(&hash, &invalid_block).unwrap();
+    txn.commit().unwrap();
+    assert_eq!(
+        store.get_invalid_block(&hash).unwrap(),
+        Some(invalid_block.clone())
+    );
+    assert_eq!(
+        store.get_invalid_blocks().unwrap(),
+        vec![(hash.clone(), invalid_block)]
+    );
+
+    let txn = store.begin_transaction();
+    txn.delete_invalid_block(&hash).unwrap();
+    txn.commit().unwrap();
+    assert!(store.get_invalid_block(&hash).unwrap().is_none());
+    assert!(store.get_invalid_blocks().unwrap().is_empty());
+}
+
+#[test]
+fn malformed_invalid_block() {
+    let store = ChainDB::with_backend(Arc::new(MemoryBackend::new()), None, Default::default());
+    let hash: Byte32 = h256!("0x1").pack();
+
+    let txn = store.begin_transaction();
+    txn.insert_raw(COLUMN_INVALID_BLOCK, hash.as_slice(), &[0u8; 16])
+        .unwrap();
+    txn.commit().unwrap();
+    assert!(store.get_invalid_block(&hash).is_err());
+    assert!(store.get_invalid_blocks().is_err());
+}
@@ -1 +1,5 @@
+mod backend;
+mod data_loader_wrapper;
 mod db;
+mod invalid_block;
+mod tx_index;
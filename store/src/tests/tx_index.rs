@@ -0,0 +1,116 @@
+use ckb_db::RocksDB;
+use ckb_db_schema::{COLUMNS, COLUMN_TRANSACTION_SPENT};
+use ckb_types::{
+    core::{
+        BlockBuilder, BlockView, EpochNumberWithFraction, HeaderBuilder, TransactionBuilder,
+        TransactionView,
+    },
+    packed::{self, CellInput, CellOutput, OutPoint},
+    prelude::*,
+};
+use tempfile::TempDir;
+
+use crate::{
+    attach_block_tx_index, clear_tx_spent, db::ChainDB, prune_block_tx_index, store::ChainStore,
+};
+
+fn build_tx(inputs: &[OutPoint], outputs: usize) -> TransactionView {
+    TransactionBuilder::default()
+        .inputs(
+            inputs
+                .iter()
+                .map(|out_point| CellInput::new(out_point.clone(), 0)),
+        )
+        .outputs((0..outputs).map(|_| CellOutput::new_builder().build()))
+        .outputs_data((0..outputs).map(|_| packed::Bytes::default()))
+        .build()
+}
+
+fn build_block(parent: &BlockView, txs: Vec<TransactionView>) -> BlockView {
+    let cellbase = TransactionBuilder::default()
+        .input(CellInput::new_cellbase_input(parent.number() + 1))
+        .build();
+    BlockBuilder::default()
+        .header(
+            HeaderBuilder::default()
+                .parent_hash(parent.hash())
+                .number((parent.number() + 1).pack())
+                .epoch(EpochNumberWithFraction::new(0, parent.number() + 1, 1000).pack())
+                .build(),
+        )
+        .transaction(cellbase)
+        .transactions(txs)
+        .build()
+}
+
+#[test]
+fn prune_and_rebuild() {
+    let tmp_dir = TempDir::new().unwrap();
+    let db = RocksDB::open_in(&tmp_dir, COLUMNS);
+    let store = ChainDB::new(db, Default::default());
+
+    let genesis = BlockBuilder::default().build();
+    let tx_a = build_tx(&[], 2);
+    let block_1 = build_block(&genesis, vec![tx_a.clone()]);
+    let tx_b = build_tx(&[OutPoint::new(tx_a.hash(), 0)], 1);
+    let block_2 = build_block(&block_1, vec![tx_b.clone()]);
+    let tx_c = build_tx(
+        &[OutPoint::new(tx_a.hash(), 1), OutPoint::new(tx_b.hash(), 0)],
+        1,
+    );
+    let block_3 = build_block(&block_2, vec![tx_c.clone()]);
+    let blocks = [genesis, block_1, block_2, block_3];
+
+    let txn = store.begin_transaction();
+    for block in &blocks {
+        txn.insert_block(block).unwrap();
+        txn.attach_block(block).unwrap();
+    }
+    txn.commit().unwrap();
+
+    let txn = store.begin_transaction();
+    prune_block_tx_index(&txn, &blocks[1]).unwrap();
+    prune_block_tx_index(&txn, &blocks[2]).unwrap();
+    txn.insert_tx_index_pruned_number(2).unwrap();
+    txn.commit().unwrap();
+
+    // the cellbases without outputs are pruned, tx_a still has a live cell
+    assert!(!store.transaction_exists(&blocks[1].transactions()[0].hash()));
+    assert!(store.transaction_exists(&tx_a.hash()));
+    assert!(store.transaction_exists(&tx_b.hash()));
+    assert!(store
+        .get(COLUMN_TRANSACTION_SPENT, tx_a.hash().as_slice())
+        .is_some());
+
+    let txn = store.begin_transaction();
+    prune_block_tx_index(&txn, &blocks[3]).unwrap();
+    txn.insert_tx_index_pruned_number(3).unwrap();
+    txn.commit().unwrap();
+
+    assert!(!store.transaction_exists(&tx_a.hash()));
+    assert!(!store.transaction_exists(&tx_b.hash()));
+    assert!(store.transaction_exists(&tx_c.hash()));
+    assert!(store
+        .get(COLUMN_TRANSACTION_SPENT, tx_a.hash().as_slice())
+        .is_none());
+    assert_eq!(store.get_tx_index_pruned_number(), Some(3));
+
+    let txn = store.begin_transaction();
+    clear_tx_spent(&txn).unwrap();
+    for block in &blocks {
+        attach_block_tx_index(&txn, block).unwrap();
+    }
+    txn.delete_tx_index_pruned_number().unwrap();
+    txn.commit().unwrap();
+
+    for block in &blocks {
+        for tx in block.transactions() {
+            assert!(store.transaction_exists(&tx.hash()));
+        }
+    }
+    assert!(store
+        .get_iter(COLUMN_TRANSACTION_SPENT, ckb_db::IteratorMode::Start)
+        .next()
+        .is_none());
+    assert_eq!(store.get_tx_index_pruned_number(), None);
+}
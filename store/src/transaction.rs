@@ -1,17 +1,17 @@
+use crate::backend::{KeyValueTransaction, StoreIter, StoreSlice};
 use crate::cache::StoreCache;
+use crate::invalid_block::InvalidBlock;
 use crate::store::ChainStore;
+use crate::tx_index::attach_block_tx_index;
 use ckb_chain_spec::versionbits::VersionbitsIndexer;
-use ckb_db::{
-    iter::{DBIter, DBIterator, IteratorMode},
-    DBPinnableSlice, RocksDBTransaction, RocksDBTransactionSnapshot,
-};
+use ckb_db::iter::IteratorMode;
 use ckb_db_schema::{
     Col, COLUMN_BLOCK_BODY, COLUMN_BLOCK_EPOCH, COLUMN_BLOCK_EXT, COLUMN_BLOCK_EXTENSION,
     COLUMN_BLOCK_FILTER, COLUMN_BLOCK_FILTER_HASH, COLUMN_BLOCK_HEADER, COLUMN_BLOCK_PROPOSAL_IDS,
     COLUMN_BLOCK_UNCLE, COLUMN_CELL, COLUMN_CELL_DATA, COLUMN_CELL_DATA_HASH,
-    COLUMN_CHAIN_ROOT_MMR, COLUMN_EPOCH, COLUMN_INDEX, COLUMN_META, COLUMN_NUMBER_HASH,
-    COLUMN_TRANSACTION_INFO, COLUMN_UNCLES, META_CURRENT_EPOCH_KEY,
-    META_LATEST_BUILT_FILTER_DATA_KEY, META_TIP_HEADER_KEY,
+    COLUMN_CHAIN_ROOT_MMR, COLUMN_EPOCH, COLUMN_INDEX, COLUMN_INVALID_BLOCK, COLUMN_META,
+    COLUMN_NUMBER_HASH, COLUMN_TRANSACTION_INFO, COLUMN_UNCLES, META_CURRENT_EPOCH_KEY,
+    META_LATEST_BUILT_FILTER_DATA_KEY, META_TIP_HEADER_KEY, META_TX_INDEX_PRUNED_KEY,
 };
 use ckb_error::Error;
 use ckb_freezer::Freezer;
@@ -19,7 +19,7 @@ use ckb_merkle_mountain_range::{Error as MMRError, MMRStore, Result as MMRResult
 use ckb_types::{
     core::{
         cell::{CellChecker, CellProvider, CellStatus},
-        BlockExt, BlockView, EpochExt, HeaderView, TransactionView,
+        BlockExt, BlockNumber, BlockView, EpochExt, HeaderView, TransactionView,
     },
     packed::{self, Byte32, OutPoint},
     prelude::*,
@@ -29,7 +29,7 @@ use std::sync::Arc;
 
 /// A Transaction DB
 pub struct StoreTransaction {
-    pub(crate) inner: RocksDBTransaction,
+    pub(crate) inner: Box<dyn KeyValueTransaction>,
     pub(crate) freezer: Option<Freezer>,
     pub(crate) cache: Arc<StoreCache>,
 }
@@ -43,13 +43,11 @@ impl ChainStore for StoreTransaction {
         self.freezer.as_ref()
     }
 
-    fn get(&self, col: Col, key: &[u8]) -> Option<DBPinnableSlice<'_>> {
-        self.inner
-            .get_pinned(col, key)
-            .expect("db operation should be ok")
+    fn get(&self, col: Col, key: &[u8]) -> Option<StoreSlice<'_>> {
+        self.inner.get(col, key).expect("db operation should be ok")
     }
 
-    fn get_iter(&self, col: Col, mode: IteratorMode) -> DBIter {
+    fn get_iter(&self, col: Col, mode: IteratorMode) -> StoreIter {
         self.inner
             .iter(col, mode)
             .expect("db operation should be ok")
@@ -101,34 +99,6 @@ impl CellChecker for StoreTransaction {
     }
 }
 
-pub struct StoreTransactionSnapshot<'a> {
-    pub(crate) inner: RocksDBTransactionSnapshot<'a>,
-    pub(crate) freezer: Option<Freezer>,
-    pub(crate) cache: Arc<StoreCache>,
-}
-
-impl<'a> ChainStore for StoreTransactionSnapshot<'a> {
-    fn cache(&self) -> Option<&StoreCache> {
-        Some(&self.cache)
-    }
-
-    fn freezer(&self) -> Option<&Freezer> {
-        self.freezer.as_ref()
-    }
-
-    fn get(&self, col: Col, key: &[u8]) -> Option<DBPinnableSlice> {
-        self.inner
-            .get_pinned(col, key)
-            .expect("db operation should be ok")
-    }
-
-    fn get_iter(&self, col: Col, mode: IteratorMode) -> DBIter {
-        self.inner
-            .iter(col, mode)
-            .expect("db operation should be ok")
-    }
-}
-
 impl StoreTransaction {
     /// TODO(doc): @quake
     pub fn insert_raw(&self, col: Col, key: &[u8], value: &[u8]) -> Result<(), Error> {
@@ -146,21 +116,9 @@ impl StoreTransaction {
     }
 
     /// TODO(doc): @quake
-    pub fn get_snapshot(&self) -> StoreTransactionSnapshot<'_> {
-        StoreTransactionSnapshot {
-            inner: self.inner.get_snapshot(),
-            freezer: self.freezer.clone(),
-            cache: Arc::clone(&self.cache),
-        }
-    }
-
-    /// TODO(doc): @quake
-    pub fn get_update_for_tip_hash(
-        &self,
-        snapshot: &StoreTransactionSnapshot<'_>,
-    ) -> Option<packed::Byte32> {
+    pub fn get_update_for_tip_hash(&self) -> Option<packed::Byte32> {
         self.inner
-            .get_for_update(COLUMN_META, META_TIP_HEADER_KEY, &snapshot.inner)
+            .get_for_update(COLUMN_META, META_TIP_HEADER_KEY)
             .expect("db operation should be ok")
             .map(|slice| packed::Byte32Reader::from_slice_should_be_ok(slice.as_ref()).to_entity())
     }
@@ -255,20 +213,8 @@ impl StoreTransaction {
 
     /// TODO(doc): @quake
     pub fn attach_block(&self, block: &BlockView) -> Result<(), Error> {
-        let header = block.data().header();
         let block_hash = block.hash();
-        for (index, tx_hash) in block.tx_hashes().iter().enumerate() {
-            let key = packed::TransactionKey::new_builder()
-                .block_hash(block_hash.clone())
-                .index(index.pack())
-                .build();
-            let info = packed::TransactionInfo::new_builder()
-                .key(key)
-                .block_number(header.raw().number())
-                .block_epoch(header.raw().epoch())
-                .build();
-            self.insert_raw(COLUMN_TRANSACTION_INFO, tx_hash.as_slice(), info.as_slice())?;
-        }
+        attach_block_tx_index(self, block)?;
         let block_number: packed::Uint64 = block.number().pack();
         self.insert_raw(COLUMN_INDEX, block_number.as_slice(), block_hash.as_slice())?;
         for uncle in block.uncles().into_iter() {
@@ -406,6 +352,35 @@ impl StoreTransaction {
             block_hash.as_slice(),
         )
     }
+
+    /// Records the latest block whose transactions are pruned from the index.
+    pub fn insert_tx_index_pruned_number(&self, number: BlockNumber) -> Result<(), Error> {
+        let number: packed::Uint64 = number.pack();
+        self.insert_raw(COLUMN_META, META_TX_INDEX_PRUNED_KEY, number.as_slice())
+    }
+
+    /// Marks the transaction index as complete.
+    pub fn delete_tx_index_pruned_number(&self) -> Result<(), Error> {
+        self.delete(COLUMN_META, META_TX_INDEX_PRUNED_KEY)
+    }
+
+    /// Records the block as invalid.
+    pub fn insert_invalid_block(
+        &self,
+        hash: &packed::Byte32,
+        invalid_block: &InvalidBlock,
+    ) -> Result<(), Error> {
+        self.insert_raw(
+            COLUMN_INVALID_BLOCK,
+            hash.as_slice(),
+            &invalid_block.to_bytes(),
+        )
+    }
+
+    /// Removes the invalid record of the block.
+    pub fn delete_invalid_block(&self, hash: &packed::Byte32) -> Result<(), Error> {
+        self.delete(COLUMN_INVALID_BLOCK, hash.as_slice())
+    }
 }
 
 impl MMRStore<packed::HeaderDigest> for &StoreTransaction {
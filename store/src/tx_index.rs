@@ -0,0 +1,83 @@
+use crate::{ChainStore, StoreTransaction};
+use ckb_db::IteratorMode;
+use ckb_db_schema::{COLUMN_TRANSACTION_INFO, COLUMN_TRANSACTION_SPENT};
+use ckb_error::Error;
+use ckb_types::{core::BlockView, packed, prelude::*};
+use std::collections::HashMap;
+
+/// Indexes all the transactions of this block.
+pub fn attach_block_tx_index(txn: &StoreTransaction, block: &BlockView) -> Result<(), Error> {
+    let header = block.data().header();
+    let block_hash = block.hash();
+    for (index, tx_hash) in block.tx_hashes().iter().enumerate() {
+        let key = packed::TransactionKey::new_builder()
+            .block_hash(block_hash.clone())
+            .index(index.pack())
+            .build();
+        let info = packed::TransactionInfo::new_builder()
+            .key(key)
+            .block_number(header.raw().number())
+            .block_epoch(header.raw().epoch())
+            .build();
+        txn.insert_raw(COLUMN_TRANSACTION_INFO, tx_hash.as_slice(), info.as_slice())?;
+    }
+    Ok(())
+}
+
+/// Removes the index of the transactions whose outputs are all spent once this block is pruned.
+///
+/// The blocks must be pruned in order. The spent outputs of a transaction are counted in
+/// `COLUMN_TRANSACTION_SPENT`, the transactions which still have live cells are kept in the
+/// index because they are required to restore the cells when a block is detached.
+pub fn prune_block_tx_index(txn: &StoreTransaction, block: &BlockView) -> Result<(), Error> {
+    let mut spent: HashMap<packed::Byte32, u32> = HashMap::new();
+    for tx in block.transactions().iter() {
+        if tx.outputs().is_empty() {
+            txn.delete(COLUMN_TRANSACTION_INFO, tx.hash().as_slice())?;
+        }
+        // skip cellbase
+        if tx.is_cellbase() {
+            continue;
+        }
+        for pts in tx.input_pts_iter() {
+            *spent.entry(pts.tx_hash()).or_default() += 1;
+        }
+    }
+
+    for (tx_hash, count) in spent {
+        let outputs_len = match txn.get_transaction_with_info(&tx_hash) {
+            Some((tx, _)) => tx.outputs().len() as u32,
+            // not indexed, or the block body has been pruned
+            None => continue,
+        };
+        let total = txn
+            .get(COLUMN_TRANSACTION_SPENT, tx_hash.as_slice())
+            .map(|slice| packed::Uint32Reader::from_slice_should_be_ok(slice.as_ref()).unpack())
+            .unwrap_or(0u32)
+            + count;
+        if total >= outputs_len {
+            txn.delete(COLUMN_TRANSACTION_INFO, tx_hash.as_slice())?;
+            txn.delete(COLUMN_TRANSACTION_SPENT, tx_hash.as_slice())?;
+        } else {
+            let total: packed::Uint32 = total.pack();
+            txn.insert_raw(
+                COLUMN_TRANSACTION_SPENT,
+                tx_hash.as_slice(),
+                total.as_slice(),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Clears the spent outputs counters, which are only maintained while the index is pruned.
+pub fn clear_tx_spent(txn: &StoreTransaction) -> Result<(), Error> {
+    let keys: Vec<_> = txn
+        .get_iter(COLUMN_TRANSACTION_SPENT, IteratorMode::Start)
+        .map(|(key, _)| key)
+        .collect();
+    for key in keys {
+        txn.delete(COLUMN_TRANSACTION_SPENT, &key)?;
+    }
+    Ok(())
+}
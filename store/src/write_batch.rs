@@ -1,4 +1,4 @@
-use ckb_db::RocksDBWriteBatch;
+use crate::backend::WriteBatch;
 use ckb_db_schema::{
     Col, COLUMN_BLOCK_BODY, COLUMN_BLOCK_EXTENSION, COLUMN_BLOCK_HEADER, COLUMN_BLOCK_PROPOSAL_IDS,
     COLUMN_BLOCK_UNCLE, COLUMN_CELL, COLUMN_CELL_DATA, COLUMN_CELL_DATA_HASH, COLUMN_NUMBER_HASH,
@@ -6,9 +6,9 @@ use ckb_db_schema::{
 use ckb_error::Error;
 use ckb_types::{core::BlockNumber, packed, prelude::*};
 
-/// Wrapper of `RocksDBWriteBatch`, provides atomic batch of write operations.
+/// Wrapper of `WriteBatch`, provides atomic batch of write operations.
 pub struct StoreWriteBatch {
-    pub(crate) inner: RocksDBWriteBatch,
+    pub(crate) inner: WriteBatch,
 }
 
 impl StoreWriteBatch {
@@ -87,17 +87,17 @@ impl StoreWriteBatch {
     }
 
     /// Removes the block body from database with corresponding hash, number and txs number
+    ///
+    /// The block extension is kept, see `delete_block_extension`.
     pub fn delete_block_body(
         &mut self,
         number: BlockNumber,
         hash: &packed::Byte32,
         txs_len: u32,
     ) -> Result<(), Error> {
-        self.inner.delete(COLUMN_BLOCK_UNCLE, hash.as_slice())?;
-        self.inner.delete(COLUMN_BLOCK_EXTENSION, hash.as_slice())?;
-        self.inner
-            .delete(COLUMN_BLOCK_PROPOSAL_IDS, hash.as_slice())?;
-        self.inner.delete(
+        self.delete(COLUMN_BLOCK_UNCLE, hash.as_slice())?;
+        self.delete(COLUMN_BLOCK_PROPOSAL_IDS, hash.as_slice())?;
+        self.delete(
             COLUMN_NUMBER_HASH,
             packed::NumberHash::new_builder()
                 .number(number.pack())
@@ -106,14 +106,13 @@ impl StoreWriteBatch {
                 .as_slice(),
         )?;
 
-        let key_range = (0u32..txs_len).map(|i| {
-            packed::TransactionKey::new_builder()
+        for i in 0u32..txs_len {
+            let key = packed::TransactionKey::new_builder()
                 .block_hash(hash.clone())
                 .index(i.pack())
-                .build()
-        });
-
-        self.inner.delete_range(COLUMN_BLOCK_BODY, key_range)?;
+                .build();
+            self.delete(COLUMN_BLOCK_BODY, key.as_slice())?;
+        }
         Ok(())
     }
 
@@ -124,7 +123,13 @@ impl StoreWriteBatch {
         hash: &packed::Byte32,
         txs_len: u32,
     ) -> Result<(), Error> {
-        self.inner.delete(COLUMN_BLOCK_HEADER, hash.as_slice())?;
+        self.delete(COLUMN_BLOCK_HEADER, hash.as_slice())?;
+        self.delete_block_extension(hash)?;
         self.delete_block_body(number, hash, txs_len)
     }
+
+    /// Removes the block extension from database with corresponding hash
+    pub fn delete_block_extension(&mut self, hash: &packed::Byte32) -> Result<(), Error> {
+        self.delete(COLUMN_BLOCK_EXTENSION, hash.as_slice())
+    }
 }
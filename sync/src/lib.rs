@@ -16,6 +16,7 @@ mod utils;
 #[cfg(test)]
 mod tests;
 
+pub use crate::block_status::BlockStatus;
 pub use crate::filter::BlockFilter;
 pub use crate::net_time_checker::NetTimeProtocol;
 pub use crate::relayer::Relayer;
@@ -57,7 +57,8 @@ impl<'a> GetBlockTransactionsProcess<'a> {
             block_hash
         );
 
-        if let Some(block) = shared.store().get_block(&block_hash) {
+        // a pruned block is too old to be relayed
+        if let Ok(Some(block)) = shared.store().get_block(&block_hash) {
             let transactions = self
                 .message
                 .indexes()
@@ -500,7 +500,7 @@ impl Relayer {
             match status {
                 BlockStatus::UNKNOWN | BlockStatus::HEADER_VALID => missing_uncles.push(i),
                 BlockStatus::BLOCK_STORED | BlockStatus::BLOCK_VALID => {
-                    if let Some(uncle) = active_chain.get_block(&uncle_hash) {
+                    if let Ok(Some(uncle)) = active_chain.get_block(&uncle_hash) {
                         uncles.push(uncle.as_uncle().data());
                     } else {
                         debug_target!(
@@ -225,6 +225,7 @@ fn test_collision_and_send_missing_indexes() {
         .shared
         .store()
         .get_block(&active_chain.tip_hash())
+        .unwrap()
         .unwrap();
     let last_cellbase = last_block.transactions().first().cloned().unwrap();
 
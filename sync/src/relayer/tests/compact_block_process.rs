@@ -28,7 +28,7 @@ fn test_in_block_status_map() {
         let parent = shared
             .store()
             .get_block_hash(4)
-            .and_then(|block_hash| shared.store().get_block(&block_hash))
+            .and_then(|block_hash| shared.store().get_block(&block_hash).unwrap())
             .unwrap();
         new_header_builder(relayer.shared.shared(), &parent.header()).build()
     };
@@ -496,6 +496,7 @@ fn test_collision() {
         .shared
         .store()
         .get_block(&relayer.shared.active_chain().tip_hash())
+        .unwrap()
         .unwrap();
     let last_cellbase = last_block.transactions().first().cloned().unwrap();
 
@@ -79,6 +79,7 @@ pub(crate) fn new_transaction(
             .shared()
             .store()
             .get_block(&tip_hash)
+            .unwrap()
             .expect("getting tip block");
         let txs = block.transactions();
         let cellbase = txs.first().expect("getting cellbase from tip block");
@@ -181,7 +182,7 @@ pub(crate) fn build_chain(tip: BlockNumber) -> (Relayer, OutPoint) {
         let parent = shared
             .store()
             .get_block_hash(i)
-            .and_then(|block_hash| shared.store().get_block(&block_hash))
+            .and_then(|block_hash| shared.store().get_block(&block_hash).unwrap())
             .unwrap();
         let cellbase = TransactionBuilder::default()
             .input(CellInput::new_cellbase_input(parent.header().number() + 1))
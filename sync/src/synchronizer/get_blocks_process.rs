@@ -65,7 +65,15 @@ impl<'a> GetBlocksProcess<'a> {
                 continue;
             }
 
-            if let Some(block) = active_chain.get_block(&block_hash) {
+            let block = match active_chain.get_block(&block_hash) {
+                Ok(block) => block,
+                Err(err) => {
+                    // The older blocks behind are pruned too
+                    debug!("Stopping getblocks, since {}", err);
+                    break;
+                }
+            };
+            if let Some(block) = block {
                 debug!(
                     "respond_block {} {} to peer {:?}",
                     block.number(),
@@ -10,6 +10,7 @@ use ckb_traits::HeaderFieldsProvider;
 use ckb_types::{core, packed, prelude::*};
 use ckb_verification::{HeaderError, HeaderVerifier};
 use ckb_verification_traits::Verifier;
+use std::time::Instant;
 
 pub struct HeadersProcess<'a> {
     message: packed::SendHeadersReader<'a>,
@@ -248,7 +249,15 @@ impl<'a, DL: HeaderFieldsProvider> HeaderAcceptor<'a, DL> {
     }
 
     pub fn non_contextual_check(&self, state: &mut ValidationResult) -> Result<(), bool> {
-        self.verifier.verify(self.header).map_err(|error| {
+        let instant = Instant::now();
+        let verified = self.verifier.verify(self.header);
+        if let Some(metrics) = ckb_metrics::handle() {
+            metrics
+                .ckb_block_process_stage_duration
+                .with_label_values(&["header"])
+                .observe(instant.elapsed().as_secs_f64());
+        }
+        verified.map_err(|error| {
             debug!(
                 "HeadersProcess accepted {:?} error {:?}",
                 self.header.number(),
@@ -35,7 +35,7 @@ use ckb_constant::sync::{
 use ckb_error::Error as CKBError;
 use ckb_logger::{debug, error, info, trace, warn};
 use ckb_network::{
-    async_trait, bytes::Bytes, tokio, CKBProtocolContext, CKBProtocolHandler, PeerIndex,
+    async_trait, bytes::Bytes, tokio, CKBProtocolContext, CKBProtocolHandler, Flags, PeerIndex,
     ServiceControl, SupportProtocols,
 };
 use ckb_stop_handler::{new_crossbeam_exit_rx, register_thread};
@@ -428,19 +428,23 @@ impl Synchronizer {
 
     pub(crate) fn on_connected(&self, nc: &dyn CKBProtocolContext, peer: PeerIndex) {
         let pid = SupportProtocols::Sync.protocol_id();
-        let (is_outbound, is_whitelist, is_2023edition) = nc
+        let (is_outbound, is_whitelist, is_2023edition, is_pruned) = nc
             .get_peer(peer)
             .map(|peer| {
                 (
                     peer.is_outbound(),
                     peer.is_whitelist,
                     peer.protocols.get(&pid).map(|v| v == "3").unwrap_or(false),
+                    peer.identify_info
+                        .as_ref()
+                        .map(|info| info.flags.contains(Flags::PRUNED))
+                        .unwrap_or(false),
                 )
             })
-            .unwrap_or((false, false, false));
+            .unwrap_or((false, false, false, false));
 
         self.peers()
-            .sync_connected(peer, is_outbound, is_whitelist, is_2023edition);
+            .sync_connected(peer, is_outbound, is_whitelist, is_2023edition, is_pruned);
     }
 
     /// Regularly check and eject some nodes that do not respond in time
@@ -622,10 +626,12 @@ impl Synchronizer {
                     return false;
                 };
                 match ibd {
+                    // pruned peers cannot serve the ancient blocks
                     IBDState::In => {
-                        state.peer_flags.is_outbound
-                            || state.peer_flags.is_whitelist
-                            || state.peer_flags.is_protect
+                        !state.peer_flags.is_pruned
+                            && (state.peer_flags.is_outbound
+                                || state.peer_flags.is_whitelist
+                                || state.peer_flags.is_protect)
                     }
                     IBDState::Out => state.started_or_tip_synced(),
                 }
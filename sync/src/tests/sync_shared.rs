@@ -67,11 +67,13 @@ fn test_insert_parent_unknown_block() {
     let block = shared1
         .store()
         .get_block(&shared1.active_chain().tip_header().hash())
+        .unwrap()
         .unwrap();
     let parent = {
         let parent = shared1
             .store()
             .get_block(&block.header().parent_hash())
+            .unwrap()
             .unwrap();
         Arc::new(parent)
     };
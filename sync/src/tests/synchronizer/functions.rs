@@ -660,7 +660,7 @@ fn test_sync_process() {
 
     let mut fetched_blocks = Vec::new();
     for block_hash in &blocks_to_fetch[0] {
-        fetched_blocks.push(shared2.store().get_block(block_hash).unwrap());
+        fetched_blocks.push(shared2.store().get_block(block_hash).unwrap().unwrap());
     }
 
     for block in &fetched_blocks {
@@ -1060,7 +1060,7 @@ fn test_fix_last_common_header() {
         }
         for number in 0..=main_tip_number {
             let block_hash = shared.snapshot().get_block_hash(number).unwrap();
-            let block = shared.snapshot().get_block(&block_hash).unwrap();
+            let block = shared.snapshot().get_block(&block_hash).unwrap().unwrap();
             let block_ext = shared.snapshot().get_block_ext(&block_hash).unwrap();
             graph.insert(m_(number), block);
             graph_exts.insert(m_(number), block_ext);
@@ -1079,7 +1079,7 @@ fn test_fix_last_common_header() {
         }
         for number in 0..=fork_tip_number {
             let block_hash = shared.snapshot().get_block_hash(number).unwrap();
-            let block = shared.snapshot().get_block(&block_hash).unwrap();
+            let block = shared.snapshot().get_block(&block_hash).unwrap().unwrap();
             let block_ext = shared.snapshot().get_block_ext(&block_hash).unwrap();
             graph.insert(f_(number), block);
             graph_exts.insert(f_(number), block_ext);
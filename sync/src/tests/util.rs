@@ -47,7 +47,7 @@ pub fn generate_blocks(
 
 pub fn inherit_block(shared: &Shared, parent_hash: &Byte32) -> BlockBuilder {
     let snapshot = shared.snapshot();
-    let parent = snapshot.get_block(parent_hash).unwrap();
+    let parent = snapshot.get_block(parent_hash).unwrap().unwrap();
     let parent_number = parent.header().number();
     let epoch = snapshot
         .consensus()
@@ -17,7 +17,7 @@ use ckb_error::Error as CKBError;
 use ckb_logger::{debug, error, info, trace};
 use ckb_network::{CKBProtocolContext, PeerIndex, SupportProtocols};
 use ckb_shared::{shared::Shared, Snapshot};
-use ckb_store::{ChainDB, ChainStore};
+use ckb_store::{BlockPruned, ChainDB, ChainStore};
 use ckb_systemtime::unix_time_as_millis;
 use ckb_traits::{HeaderFields, HeaderFieldsProvider};
 use ckb_tx_pool::service::TxVerificationResult;
@@ -136,6 +136,7 @@ pub struct PeerFlags {
     pub is_protect: bool,
     pub is_whitelist: bool,
     pub is_2023edition: bool,
+    pub is_pruned: bool,
 }
 
 #[derive(Clone, Default, Debug, Copy)]
@@ -816,6 +817,7 @@ impl Peers {
         is_outbound: bool,
         is_whitelist: bool,
         is_2023edition: bool,
+        is_pruned: bool,
     ) {
         let protect_outbound = is_outbound
             && self
@@ -833,6 +835,7 @@ impl Peers {
             is_outbound,
             is_whitelist,
             is_2023edition,
+            is_pruned,
             is_protect: protect_outbound,
         };
         self.state
@@ -1283,10 +1286,21 @@ impl SyncShared {
             shared.async_handle(),
         );
 
+        // The blocks rejected in the previous runs stay invalid until reconsidered
+        let block_status_map = DashMap::new();
+        match shared.store().get_invalid_blocks() {
+            Ok(invalid_blocks) => {
+                for (hash, _) in invalid_blocks {
+                    block_status_map.insert(hash, BlockStatus::BLOCK_INVALID);
+                }
+            }
+            Err(err) => error!("load invalid blocks error {}", err),
+        }
+
         let state = SyncState {
             shared_best_header,
             header_map,
-            block_status_map: DashMap::new(),
+            block_status_map,
             tx_filter: Mutex::new(TtlFilter::default()),
             unknown_tx_hashes: Mutex::new(KeyedPriorityQueue::new()),
             peers: Peers::default(),
@@ -2038,7 +2052,7 @@ impl ActiveChain {
         self.snapshot().get_block_hash(number)
     }
 
-    pub fn get_block(&self, h: &packed::Byte32) -> Option<core::BlockView> {
+    pub fn get_block(&self, h: &packed::Byte32) -> Result<Option<core::BlockView>, BlockPruned> {
         self.store().get_block(h)
     }
 
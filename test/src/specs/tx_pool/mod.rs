@@ -80,5 +80,6 @@ fn new_block_assembler_config(lock_arg: Bytes, hash_type: ScriptHashType) -> Blo
         notify: vec![],
         notify_scripts: vec![],
         notify_timeout_millis: 800,
+        policy: Default::default(),
     }
 }
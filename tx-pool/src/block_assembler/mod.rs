@@ -1,6 +1,7 @@
 //! Generate a new block
 
 mod candidate_uncles;
+mod policy;
 mod process;
 
 #[cfg(test)]
@@ -13,7 +14,8 @@ use ckb_app_config::BlockAssemblerConfig;
 use ckb_dao::DaoCalculator;
 use ckb_error::{AnyError, InternalErrorKind};
 use ckb_jsonrpc_types::{
-    BlockTemplate as JsonBlockTemplate, CellbaseTemplate, TransactionTemplate, UncleTemplate,
+    BlockTemplate as JsonBlockTemplate, BlockTemplateParams, CellbaseTemplate, TransactionTemplate,
+    UncleTemplate,
 };
 use ckb_logger::{debug, error, trace};
 use ckb_reward_calculator::RewardCalculator;
@@ -33,6 +35,7 @@ use ckb_types::{
     prelude::*,
 };
 use hyper::{client::HttpConnector, Body, Client, Method, Request};
+pub use policy::{build_policy, BlockTemplatePolicy, ConfiguredPolicy, GreedyPolicy, TxSelector};
 use std::collections::HashSet;
 use std::sync::{
     atomic::{AtomicU64, Ordering},
@@ -100,6 +103,7 @@ pub struct BlockAssembler {
     pub(crate) candidate_uncles: Arc<Mutex<CandidateUncles>>,
     pub(crate) current: Arc<Mutex<CurrentTemplate>>,
     pub(crate) poster: Arc<Client<HttpConnector, Body>>,
+    pub(crate) policy: Arc<dyn BlockTemplatePolicy>,
 }
 
 impl BlockAssembler {
@@ -159,9 +163,16 @@ impl BlockAssembler {
             candidate_uncles: Arc::new(Mutex::new(CandidateUncles::new())),
             current: Arc::new(Mutex::new(current)),
             poster: Arc::new(Client::new()),
+            policy: Arc::new(GreedyPolicy),
         }
     }
 
+    /// Replaces the policy to select the transactions in the block template, it's the
+    /// [`GreedyPolicy`] by default.
+    pub fn set_policy(&mut self, policy: Arc<dyn BlockTemplatePolicy>) {
+        self.policy = policy;
+    }
+
     pub(crate) async fn update_full(&self, tx_pool: &RwLock<TxPool>) -> Result<(), AnyError> {
         let mut current = self.current.lock().await;
         let consensus = current.snapshot.consensus();
@@ -191,7 +202,7 @@ impl BlockAssembler {
 
             let max_block_cycles = consensus.max_block_cycles();
             let (txs, _txs_size, _cycles) =
-                tx_pool_reader.package_txs(max_block_cycles, txs_size_limit);
+                tx_pool_reader.package_txs(self.policy.as_ref(), max_block_cycles, txs_size_limit);
             (proposals, txs, basic_size)
         };
 
@@ -407,8 +418,11 @@ impl BlockAssembler {
             }
 
             let max_block_cycles = consensus.max_block_cycles();
-            let (txs, _txs_size, _cycles) = tx_pool_reader
-                .package_txs(max_block_cycles, txs_size_limit.expect("overflow checked"));
+            let (txs, _txs_size, _cycles) = tx_pool_reader.package_txs(
+                self.policy.as_ref(),
+                max_block_cycles,
+                txs_size_limit.expect("overflow checked"),
+            );
             txs
         };
 
@@ -453,6 +467,127 @@ impl BlockAssembler {
         (&current.template).into()
     }
 
+    /// Builds a one-off template from the current one with the params of a single request.
+    ///
+    /// The current template is left untouched, and the invalid txs found are not removed
+    /// from the pool, which is done by the next regular update.
+    pub(crate) async fn get_custom(
+        &self,
+        tx_pool: &RwLock<TxPool>,
+        bytes_limit: Option<u64>,
+        proposals_limit: Option<u64>,
+        params: BlockTemplateParams,
+    ) -> Result<JsonBlockTemplate, AnyError> {
+        let current = self.current.lock().await.clone();
+        let consensus = current.snapshot.consensus();
+        let current_template = &current.template;
+
+        let cellbase = if params.cellbase_lock.is_some() || params.cellbase_message.is_some() {
+            let mut config = BlockAssemblerConfig::clone(&self.config);
+            if let Some(lock) = params.cellbase_lock {
+                config.code_hash = lock.code_hash;
+                config.hash_type = lock.hash_type;
+                config.args = lock.args;
+            }
+            if let Some(message) = params.cellbase_message {
+                config.message = message;
+            }
+            Self::build_cellbase(&config, &current.snapshot)?
+        } else {
+            current_template.cellbase.clone()
+        };
+
+        let bytes_limit = bytes_limit.map_or(current_template.bytes_limit, |limit| {
+            cmp::min(limit, current_template.bytes_limit)
+        });
+        let cycles_limit = params
+            .cycles_limit
+            .map_or(current_template.cycles_limit, |limit| {
+                cmp::min(limit.value(), current_template.cycles_limit)
+            });
+        let max_proposals_limit = consensus.max_block_proposals_limit();
+        let proposals_limit = proposals_limit.map_or(max_proposals_limit, |limit| {
+            cmp::min(limit, max_proposals_limit)
+        });
+        let include: Vec<ProposalShortId> = params
+            .include_transactions
+            .iter()
+            .map(|hash| ProposalShortId::from_tx_hash(&hash.pack()))
+            .collect();
+        let exclude: HashSet<ProposalShortId> = params
+            .exclude_transactions
+            .iter()
+            .map(|hash| ProposalShortId::from_tx_hash(&hash.pack()))
+            .collect();
+
+        let (proposals, txs) = {
+            let tx_pool_reader = tx_pool.read().await;
+            if current.snapshot.tip_hash() != tx_pool_reader.snapshot().tip_hash() {
+                return Err(InternalErrorKind::BlockAssembler
+                    .other("the block template is being updated to the new tip")
+                    .into());
+            }
+            let exclusion: HashSet<ProposalShortId> = current_template
+                .uncles
+                .iter()
+                .flat_map(|u| u.data().proposals().into_iter())
+                .chain(exclude.iter().cloned())
+                .collect();
+            let proposals = tx_pool_reader.get_proposals(proposals_limit as usize, &exclusion);
+
+            let basic_size = Self::basic_block_size(
+                cellbase.data(),
+                &current_template.uncles,
+                proposals.iter(),
+                current_template.extension.clone(),
+            );
+            let txs_size_limit =
+                (bytes_limit as usize)
+                    .checked_sub(basic_size)
+                    .ok_or_else(|| {
+                        BlockAssemblerError::InvalidParams(format!(
+                        "bytes_limit {bytes_limit} is less than the basic block size {basic_size}"
+                    ))
+                    })?;
+
+            let (txs, _txs_size, _cycles) = tx_pool_reader.package_txs_with(
+                self.policy.as_ref(),
+                cycles_limit,
+                txs_size_limit,
+                &include,
+                exclude,
+            );
+            (proposals, txs)
+        };
+
+        let (dao, checked_txs, _failed_txs) =
+            Self::calc_dao(&current.snapshot, &current.epoch, cellbase.clone(), txs)?;
+
+        let mut builder = BlockTemplateBuilder::from_template(current_template);
+        builder.bytes_limit = bytes_limit;
+        builder.cycles_limit = cycles_limit;
+        builder
+            .set_proposals(Vec::from_iter(proposals))
+            .set_transactions(checked_txs)
+            .cellbase(cellbase)
+            .work_id(self.work_id.fetch_add(1, Ordering::SeqCst))
+            .current_time(cmp::max(
+                unix_time_as_millis(),
+                current_template.current_time,
+            ))
+            .dao(dao);
+        let template = builder.build();
+
+        trace!(
+            "[BlockAssembler] get_custom {} uncles-{} proposals-{} txs-{}",
+            template.number,
+            template.uncles.len(),
+            template.proposals.len(),
+            template.transactions.len(),
+        );
+        Ok((&template).into())
+    }
+
     pub(crate) fn build_cellbase_witness(
         config: &BlockAssemblerConfig,
         snapshot: &Snapshot,
@@ -0,0 +1,223 @@
+use crate::component::commit_txs_scanner::CommitTxsScanner;
+use crate::component::entry::TxEntry;
+use ckb_app_config::BlockTemplatePolicyConfig;
+use ckb_types::{
+    core::Cycle,
+    packed::{Byte32, ProposalShortId},
+    prelude::*,
+};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Selects the proposed transactions to commit in the block template.
+///
+/// The policy is called with the space left in the block after the cellbase, uncles and
+/// proposals. It packs the transactions via the [`TxSelector`], which keeps the packages
+/// consistent: a transaction is always packed after all its ancestors in the pool.
+pub trait BlockTemplatePolicy: Send + Sync {
+    /// Packs the transactions into the selector within the limits.
+    fn select_txs(&self, selector: &mut TxSelector<'_>, size_limit: usize, cycles_limit: Cycle);
+}
+
+/// Packs the proposed transactions with their ancestors by the ancestors fee rate.
+pub struct TxSelector<'a> {
+    scanner: CommitTxsScanner<'a>,
+    excluded: HashSet<ProposalShortId>,
+}
+
+impl<'a> TxSelector<'a> {
+    pub(crate) fn new(scanner: CommitTxsScanner<'a>) -> Self {
+        TxSelector {
+            scanner,
+            excluded: HashSet::new(),
+        }
+    }
+
+    /// Never packs the transactions, and so their descendants, whatever the filter is.
+    pub(crate) fn exclude(&mut self, ids: HashSet<ProposalShortId>) {
+        self.excluded = ids;
+    }
+
+    /// Packs the transactions with their ancestors ahead of the others, skipping the ones not
+    /// proposed or excluded.
+    pub(crate) fn select_required(
+        &mut self,
+        ids: &[ProposalShortId],
+        size_limit: usize,
+        cycles_limit: Cycle,
+    ) {
+        if ids.is_empty() {
+            return;
+        }
+        let ids: HashSet<&ProposalShortId> = ids.iter().collect();
+        self.select_packages(
+            size_limit,
+            cycles_limit,
+            |entry| ids.contains(&entry.proposal_short_id()),
+            |_| true,
+        );
+    }
+
+    /// Packs the best transactions accepted by `root` together with their ancestors, whether
+    /// the ancestors are accepted by `root` or not.
+    ///
+    /// A package is skipped if `filter` rejects any transaction in it not packed yet.
+    pub fn select_packages(
+        &mut self,
+        size_limit: usize,
+        cycles_limit: Cycle,
+        root: impl Fn(&TxEntry) -> bool,
+        filter: impl Fn(&TxEntry) -> bool,
+    ) {
+        let pool_map = self.scanner.pool_map();
+        let packages: HashSet<ProposalShortId> = pool_map
+            .sorted_proposed_iter()
+            .filter(|entry| root(entry))
+            .flat_map(|entry| {
+                let id = entry.proposal_short_id();
+                pool_map.calc_ancestors(&id).into_iter().chain(Some(id))
+            })
+            .collect();
+        self.select(size_limit, cycles_limit, |entry| {
+            packages.contains(&entry.proposal_short_id()) && filter(entry)
+        });
+    }
+
+    /// Packs the best transactions accepted by the filter after the ones already packed, until
+    /// the total size or cycles of all the packed transactions reach the limits.
+    ///
+    /// A transaction is skipped if the filter rejects it or any of its ancestors not packed yet.
+    /// It can be packed in a later call with another filter.
+    pub fn select(
+        &mut self,
+        size_limit: usize,
+        cycles_limit: Cycle,
+        filter: impl Fn(&TxEntry) -> bool,
+    ) {
+        let excluded = &self.excluded;
+        self.scanner.scan(size_limit, cycles_limit, &|entry| {
+            !excluded.contains(&entry.proposal_short_id()) && filter(entry)
+        });
+    }
+
+    /// The total size of the packed transactions.
+    pub fn size(&self) -> usize {
+        self.scanner.size()
+    }
+
+    /// The total cycles of the packed transactions.
+    pub fn cycles(&self) -> Cycle {
+        self.scanner.cycles()
+    }
+
+    pub(crate) fn into_parts(self) -> (Vec<TxEntry>, usize, Cycle) {
+        self.scanner.into_parts()
+    }
+}
+
+/// The default policy, packs the transactions by the ancestors fee rate greedily.
+#[derive(Default)]
+pub struct GreedyPolicy;
+
+impl BlockTemplatePolicy for GreedyPolicy {
+    fn select_txs(&self, selector: &mut TxSelector<'_>, size_limit: usize, cycles_limit: Cycle) {
+        selector.select(size_limit, cycles_limit, |_| true);
+    }
+}
+
+/// The policy configured by `[block_assembler.policy]`.
+pub struct ConfiguredPolicy {
+    max_cycles: Option<Cycle>,
+    exclude_script_hashes: HashSet<Byte32>,
+    priority_lock_hashes: HashSet<Byte32>,
+    priority_reserved_bytes: usize,
+}
+
+impl ConfiguredPolicy {
+    /// Creates the policy from the config.
+    pub fn new(config: &BlockTemplatePolicyConfig) -> Self {
+        ConfiguredPolicy {
+            max_cycles: config.max_cycles,
+            exclude_script_hashes: config
+                .exclude_script_hashes
+                .iter()
+                .map(|hash| hash.pack())
+                .collect(),
+            priority_lock_hashes: config
+                .priority_lock_hashes
+                .iter()
+                .map(|hash| hash.pack())
+                .collect(),
+            priority_reserved_bytes: config.priority_reserved_bytes as usize,
+        }
+    }
+
+    fn is_excluded(&self, entry: &TxEntry) -> bool {
+        !self.exclude_script_hashes.is_empty()
+            && script_hashes(entry).any(|hash| self.exclude_script_hashes.contains(&hash))
+    }
+
+    fn is_priority(&self, entry: &TxEntry) -> bool {
+        lock_hashes(entry).any(|hash| self.priority_lock_hashes.contains(&hash))
+    }
+}
+
+impl BlockTemplatePolicy for ConfiguredPolicy {
+    fn select_txs(&self, selector: &mut TxSelector<'_>, size_limit: usize, cycles_limit: Cycle) {
+        let cycles_limit = self
+            .max_cycles
+            .map_or(cycles_limit, |max_cycles| max_cycles.min(cycles_limit));
+
+        if !self.priority_lock_hashes.is_empty() {
+            selector.select_packages(
+                size_limit,
+                cycles_limit,
+                |entry| self.is_priority(entry),
+                |entry| !self.is_excluded(entry),
+            );
+        }
+        let reserved = self.priority_reserved_bytes.saturating_sub(selector.size());
+        selector.select(size_limit.saturating_sub(reserved), cycles_limit, |entry| {
+            !self.is_excluded(entry)
+        });
+    }
+}
+
+/// Builds the policy from the config, the greedy policy is used when all the options are default.
+pub fn build_policy(config: &BlockTemplatePolicyConfig) -> Arc<dyn BlockTemplatePolicy> {
+    if *config == BlockTemplatePolicyConfig::default() {
+        Arc::new(GreedyPolicy)
+    } else {
+        Arc::new(ConfiguredPolicy::new(config))
+    }
+}
+
+fn lock_hashes(entry: &TxEntry) -> impl Iterator<Item = Byte32> + '_ {
+    let rtx = &entry.rtx;
+    rtx.resolved_inputs
+        .iter()
+        .map(|cell| cell.cell_output.calc_lock_hash())
+        .chain(
+            rtx.transaction
+                .outputs()
+                .into_iter()
+                .map(|output| output.calc_lock_hash()),
+        )
+}
+
+fn script_hashes(entry: &TxEntry) -> impl Iterator<Item = Byte32> + '_ {
+    let rtx = &entry.rtx;
+    let type_hashes = rtx
+        .resolved_inputs
+        .iter()
+        .map(|cell| &cell.cell_output)
+        .filter_map(|output| output.type_().to_opt())
+        .chain(
+            rtx.transaction
+                .outputs()
+                .into_iter()
+                .filter_map(|output| output.type_().to_opt()),
+        )
+        .map(|script| script.calc_script_hash());
+    lock_hashes(entry).chain(type_hashes)
+}
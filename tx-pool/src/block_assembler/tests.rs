@@ -1,11 +1,21 @@
-use ckb_types::{
-    core::{BlockBuilder, BlockNumber, EpochNumberWithFraction},
-    prelude::*,
-};
-
 use crate::block_assembler::candidate_uncles::{
     CandidateUncles, MAX_CANDIDATE_UNCLES, MAX_PER_HEIGHT,
 };
+use crate::block_assembler::{BlockTemplatePolicy, ConfiguredPolicy, GreedyPolicy, TxSelector};
+use crate::component::commit_txs_scanner::CommitTxsScanner;
+use crate::component::{entry::TxEntry, pool_map::PoolMap};
+use ckb_app_config::BlockTemplatePolicyConfig;
+use ckb_types::{
+    bytes::Bytes,
+    core::{
+        BlockBuilder, BlockNumber, Capacity, EpochNumberWithFraction, TransactionBuilder,
+        TransactionView,
+    },
+    h256,
+    packed::{CellInput, CellOutput, OutPoint, Script},
+    prelude::*,
+    H256,
+};
 
 #[test]
 fn test_candidate_uncles_basic() {
@@ -72,3 +82,143 @@ fn test_candidate_uncles_max_per_height() {
     assert_eq!(candidate_uncles.map.len(), 1);
     assert_eq!(candidate_uncles.len(), MAX_PER_HEIGHT);
 }
+
+fn build_tx_with_lock(input: H256, lock_args: &'static [u8]) -> TransactionView {
+    TransactionBuilder::default()
+        .input(CellInput::new(OutPoint::new(input.pack(), 0), 0))
+        .output(
+            CellOutput::new_builder()
+                .lock(
+                    Script::new_builder()
+                        .args(Bytes::from_static(lock_args).pack())
+                        .build(),
+                )
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .build()
+}
+
+fn lock_hash(tx: &TransactionView) -> H256 {
+    tx.output(0).unwrap().calc_lock_hash().unpack()
+}
+
+#[test]
+fn test_block_template_policy() {
+    let tx_a = build_tx_with_lock(h256!("0x1"), b"a");
+    let tx_priority = build_tx_with_lock(h256!("0x2"), b"priority");
+    let tx_excluded = build_tx_with_lock(h256!("0x3"), b"excluded");
+
+    let mut pool_map = PoolMap::new(125);
+    for (tx, fee) in [(&tx_a, 100), (&tx_priority, 10), (&tx_excluded, 50)] {
+        let entry = TxEntry::dummy_resolve(tx.clone(), 0, Capacity::shannons(fee), 100);
+        pool_map.add_proposed(entry).unwrap();
+    }
+    let select = |policy: &dyn BlockTemplatePolicy| {
+        let mut selector = TxSelector::new(CommitTxsScanner::new(&pool_map));
+        policy.select_txs(&mut selector, 250, u64::MAX);
+        let (entries, size, _cycles) = selector.into_parts();
+        assert_eq!(size, entries.len() * 100);
+        entries
+            .into_iter()
+            .map(|entry| entry.transaction().hash())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(select(&GreedyPolicy), vec![tx_a.hash(), tx_excluded.hash()]);
+
+    let config = BlockTemplatePolicyConfig {
+        max_cycles: None,
+        exclude_script_hashes: vec![lock_hash(&tx_excluded)],
+        priority_lock_hashes: vec![lock_hash(&tx_priority)],
+        priority_reserved_bytes: 150,
+    };
+    assert_eq!(
+        select(&ConfiguredPolicy::new(&config)),
+        vec![tx_priority.hash(), tx_a.hash()]
+    );
+
+    // the unused reserved space is not filled by the others
+    let config = BlockTemplatePolicyConfig {
+        priority_lock_hashes: vec![h256!("0x4")],
+        priority_reserved_bytes: 100,
+        ..config
+    };
+    assert_eq!(select(&ConfiguredPolicy::new(&config)), vec![tx_a.hash()]);
+}
+
+#[test]
+fn test_block_template_policy_priority_package() {
+    let tx_a = build_tx_with_lock(h256!("0x1"), b"a");
+    let tx_parent = build_tx_with_lock(h256!("0x2"), b"parent");
+    let tx_priority = build_tx_with_lock(tx_parent.hash().unpack(), b"priority");
+
+    let mut pool_map = PoolMap::new(125);
+    for (tx, fee) in [(&tx_a, 100), (&tx_parent, 10), (&tx_priority, 10)] {
+        let entry = TxEntry::dummy_resolve(tx.clone(), 0, Capacity::shannons(fee), 100);
+        pool_map.add_proposed(entry).unwrap();
+    }
+    let select = |config: &BlockTemplatePolicyConfig| {
+        let mut selector = TxSelector::new(CommitTxsScanner::new(&pool_map));
+        ConfiguredPolicy::new(config).select_txs(&mut selector, 250, u64::MAX);
+        let (entries, _size, _cycles) = selector.into_parts();
+        entries
+            .into_iter()
+            .map(|entry| entry.transaction().hash())
+            .collect::<Vec<_>>()
+    };
+
+    // the priority tx is packed with its ancestor which is not priority
+    let config = BlockTemplatePolicyConfig {
+        max_cycles: None,
+        exclude_script_hashes: vec![],
+        priority_lock_hashes: vec![lock_hash(&tx_priority)],
+        priority_reserved_bytes: 200,
+    };
+    assert_eq!(select(&config), vec![tx_parent.hash(), tx_priority.hash()]);
+
+    // the package with an excluded ancestor is skipped
+    let config = BlockTemplatePolicyConfig {
+        exclude_script_hashes: vec![lock_hash(&tx_parent)],
+        ..config
+    };
+    assert_eq!(select(&config), Vec::<ckb_types::packed::Byte32>::new());
+}
+
+#[test]
+fn test_block_template_include_exclude() {
+    let tx_a = build_tx_with_lock(h256!("0x1"), b"a");
+    let tx_b = build_tx_with_lock(h256!("0x2"), b"b");
+    let tx_c = build_tx_with_lock(h256!("0x3"), b"c");
+    let tx_d = build_tx_with_lock(tx_c.hash().unpack(), b"d");
+
+    let mut pool_map = PoolMap::new(125);
+    for (tx, fee) in [(&tx_a, 100), (&tx_b, 10), (&tx_c, 50), (&tx_d, 200)] {
+        let entry = TxEntry::dummy_resolve(tx.clone(), 0, Capacity::shannons(fee), 100);
+        pool_map.add_proposed(entry).unwrap();
+    }
+    let select = |include: &[&TransactionView], exclude: &[&TransactionView], size_limit| {
+        let include: Vec<_> = include.iter().map(|tx| tx.proposal_short_id()).collect();
+        let exclude = exclude.iter().map(|tx| tx.proposal_short_id()).collect();
+        let mut selector = TxSelector::new(CommitTxsScanner::new(&pool_map));
+        selector.exclude(exclude);
+        selector.select_required(&include, size_limit, u64::MAX);
+        GreedyPolicy.select_txs(&mut selector, size_limit, u64::MAX);
+        let (entries, _size, _cycles) = selector.into_parts();
+        entries
+            .into_iter()
+            .map(|entry| entry.transaction().hash())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(select(&[], &[], 250), vec![tx_c.hash(), tx_d.hash()]);
+    // the included tx is packed before the better ones
+    assert_eq!(select(&[&tx_b], &[], 250), vec![tx_b.hash(), tx_a.hash()]);
+    // the included tx is packed with its ancestors
+    assert_eq!(select(&[&tx_d], &[], 250), vec![tx_c.hash(), tx_d.hash()]);
+    // the descendants of the excluded tx are excluded as well
+    assert_eq!(
+        select(&[&tx_d], &[&tx_c], 400),
+        vec![tx_a.hash(), tx_b.hash()]
+    );
+}
@@ -1,6 +1,7 @@
 use super::component::TxEntry;
 use crate::error::Reject;
 use crate::pool::TxPool;
+use ckb_types::core::tx_pool::TxConflict;
 
 /// Callback boxed fn pointer wrapper
 pub type PendingCallback = Box<dyn Fn(&TxEntry) + Sync + Send>;
@@ -8,12 +9,15 @@ pub type PendingCallback = Box<dyn Fn(&TxEntry) + Sync + Send>;
 pub type ProposedCallback = Box<dyn Fn(&TxEntry) + Sync + Send>;
 /// Reject Callback boxed fn pointer wrapper
 pub type RejectCallback = Box<dyn Fn(&mut TxPool, &TxEntry, Reject) + Sync + Send>;
+/// Conflict Callback boxed fn pointer wrapper
+pub type ConflictCallback = Box<dyn Fn(&TxEntry, &TxConflict) + Sync + Send>;
 
 /// Struct hold callbacks
 pub struct Callbacks {
     pub(crate) pending: Option<PendingCallback>,
     pub(crate) proposed: Option<ProposedCallback>,
     pub(crate) reject: Option<RejectCallback>,
+    pub(crate) conflict: Option<ConflictCallback>,
 }
 
 impl Default for Callbacks {
@@ -29,6 +33,7 @@ impl Callbacks {
             pending: None,
             proposed: None,
             reject: None,
+            conflict: None,
         }
     }
 
@@ -47,6 +52,11 @@ impl Callbacks {
         self.reject = Some(callback);
     }
 
+    /// Register a new conflict callback
+    pub fn register_conflict(&mut self, callback: ConflictCallback) {
+        self.conflict = Some(callback);
+    }
+
     /// Call on after pending
     pub fn call_pending(&self, entry: &TxEntry) {
         if let Some(call) = &self.pending {
@@ -67,4 +77,12 @@ impl Callbacks {
             call(tx_pool, entry, reject)
         }
     }
+
+    /// Call on after removed for conflicting with another tx, the reject callback is called
+    /// first with the same entry
+    pub fn call_conflict(&self, entry: &TxEntry, conflict: &TxConflict) {
+        if let Some(call) = &self.conflict {
+            call(entry, conflict)
+        }
+    }
 }
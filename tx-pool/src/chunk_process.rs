@@ -217,7 +217,7 @@ impl ChunkProcess {
         let Entry { tx, remote } = entry;
         let tx_hash = tx.hash();
 
-        let (ret, snapshot) = self.service.pre_check(&tx).await;
+        let (ret, snapshot) = self.service.pre_check(&tx, remote.is_none()).await;
         let (tip_hash, rtx, status, fee, tx_size) = try_or_return_with_snapshot!(ret, snapshot);
 
         let cached = self.service.fetch_tx_verify_cache(&tx).await;
@@ -242,7 +242,8 @@ impl ChunkProcess {
                     .map_err(Reject::Verification);
                     let completed = try_or_return_with_snapshot!(ret, snapshot);
 
-                    let entry = TxEntry::new(rtx, completed.cycles, fee, tx_size);
+                    let mut entry = TxEntry::new(rtx, completed.cycles, fee, tx_size);
+                    entry.local = remote.is_none();
                     let (ret, submit_snapshot) =
                         self.service.submit_entry(tip_hash, entry, status).await;
                     try_or_return_with_snapshot!(ret, submit_snapshot);
@@ -321,7 +322,8 @@ impl ChunkProcess {
             }
         }
 
-        let entry = TxEntry::new(rtx, completed.cycles, fee, tx_size);
+        let mut entry = TxEntry::new(rtx, completed.cycles, fee, tx_size);
+        entry.local = remote.is_none();
         let (ret, submit_snapshot) = self.service.submit_entry(tip_hash, entry, status).await;
         try_or_return_with_snapshot!(ret, snapshot);
 
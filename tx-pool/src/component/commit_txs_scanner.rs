@@ -4,7 +4,7 @@ use crate::component::{entry::TxEntry, sort_key::AncestorsScoreSortKey};
 use ckb_types::{core::Cycle, packed::ProposalShortId};
 use ckb_util::LinkedHashMap;
 use multi_index_map::MultiIndexMap;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 // A template data struct used to store modified entries when package txs
 #[derive(MultiIndexMap, Clone)]
@@ -52,6 +52,9 @@ const MAX_CONSECUTIVE_FAILURES: usize = 500;
 pub struct CommitTxsScanner<'a> {
     pool_map: &'a PoolMap,
     entries: Vec<TxEntry>,
+    // total size and cycles of the packaged txs
+    size: usize,
+    cycles: Cycle,
     // modified_entries will store sorted packages after they are modified
     // because some of their txs are already in the block
     modified_entries: MultiIndexModifiedTxMap,
@@ -59,16 +62,21 @@ pub struct CommitTxsScanner<'a> {
     fetched_txs: HashSet<ProposalShortId>,
     // Keep track of entries that failed inclusion, to avoid duplicate work
     failed_txs: HashSet<ProposalShortId>,
+    // The modified entries that failed inclusion in this scan, restored in the next scan
+    failed_modified_entries: HashMap<ProposalShortId, TxEntry>,
 }
 
 impl<'a> CommitTxsScanner<'a> {
     pub fn new(pool_map: &'a PoolMap) -> CommitTxsScanner<'a> {
         CommitTxsScanner {
             entries: Vec::new(),
+            size: 0,
+            cycles: 0,
             pool_map,
             modified_entries: MultiIndexModifiedTxMap::default(),
             fetched_txs: HashSet::default(),
             failed_txs: HashSet::default(),
+            failed_modified_entries: HashMap::default(),
         }
     }
 
@@ -78,11 +86,46 @@ impl<'a> CommitTxsScanner<'a> {
         size_limit: usize,
         cycles_limit: Cycle,
     ) -> (Vec<TxEntry>, usize, Cycle) {
-        let mut size: usize = 0;
-        let mut cycles: Cycle = 0;
+        self.scan(size_limit, cycles_limit, &|_| true);
+        self.into_parts()
+    }
+
+    pub(crate) fn pool_map(&self) -> &'a PoolMap {
+        self.pool_map
+    }
+
+    /// The total size of the packaged txs
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The total cycles of the packaged txs
+    pub fn cycles(&self) -> Cycle {
+        self.cycles
+    }
+
+    /// Returns the packaged txs in order, total_size and total_cycles.
+    pub fn into_parts(self) -> (Vec<TxEntry>, usize, Cycle) {
+        (self.entries, self.size, self.cycles)
+    }
+
+    /// Packages the txs after the ones already packaged, until the total size or cycles reach
+    /// the limits. A tx is packaged only when the filter accepts it and all its ancestors not
+    /// packaged yet.
+    pub fn scan(
+        &mut self,
+        size_limit: usize,
+        cycles_limit: Cycle,
+        filter: &dyn Fn(&TxEntry) -> bool,
+    ) {
         let mut consecutive_failed = 0;
+        self.failed_txs.clear();
+        for (_, entry) in self.failed_modified_entries.drain() {
+            self.modified_entries.insert_entry(entry);
+        }
 
-        let mut iter = self.pool_map.sorted_proposed_iter().peekable();
+        let pool_map = self.pool_map;
+        let mut iter = pool_map.sorted_proposed_iter().peekable();
         loop {
             let mut using_modified = false;
 
@@ -119,14 +162,13 @@ impl<'a> CommitTxsScanner<'a> {
             };
 
             let short_id = tx_entry.proposal_short_id();
-            let next_size = size.saturating_add(tx_entry.ancestors_size);
-            let next_cycles = cycles.saturating_add(tx_entry.ancestors_cycles);
+            let next_size = self.size.saturating_add(tx_entry.ancestors_size);
+            let next_cycles = self.cycles.saturating_add(tx_entry.ancestors_cycles);
 
             if next_cycles > cycles_limit || next_size > size_limit {
                 consecutive_failed += 1;
                 if using_modified {
-                    self.modified_entries.remove(&short_id);
-                    self.failed_txs.insert(short_id.clone());
+                    self.mark_failed_modified(&short_id);
                 }
                 if consecutive_failed > MAX_CONSECUTIVE_FAILURES {
                     break;
@@ -134,6 +176,11 @@ impl<'a> CommitTxsScanner<'a> {
                 continue;
             }
 
+            if !filter(&tx_entry) {
+                self.mark_failed(&short_id, using_modified);
+                continue;
+            }
+
             let only_unconfirmed = |short_id| {
                 if self.fetched_txs.contains(short_id) {
                     None
@@ -153,6 +200,11 @@ impl<'a> CommitTxsScanner<'a> {
                 .cloned()
                 .collect::<Vec<TxEntry>>();
 
+            if !ancestors.iter().all(filter) {
+                self.mark_failed(&short_id, using_modified);
+                continue;
+            }
+
             // sort ancestors by ancestors_count,
             // if A is an ancestor of B, B.ancestors_count must large than A
             ancestors.sort_unstable_by_key(|entry| entry.ancestors_count);
@@ -166,8 +218,8 @@ impl<'a> CommitTxsScanner<'a> {
             for (short_id, entry) in &ancestors {
                 let is_inserted = self.fetched_txs.insert(short_id.clone());
                 debug_assert!(is_inserted, "package duplicate txs");
-                cycles = cycles.saturating_add(entry.cycles);
-                size = size.saturating_add(entry.size);
+                self.cycles = self.cycles.saturating_add(entry.cycles);
+                self.size = self.size.saturating_add(entry.size);
                 self.entries.push(entry.to_owned());
                 // try remove from modified
                 self.modified_entries.remove(short_id);
@@ -175,12 +227,28 @@ impl<'a> CommitTxsScanner<'a> {
 
             self.update_modified_entries(&ancestors);
         }
-        (self.entries, size, cycles)
+    }
+
+    fn mark_failed(&mut self, short_id: &ProposalShortId, using_modified: bool) {
+        if using_modified {
+            self.mark_failed_modified(short_id);
+        } else {
+            self.failed_txs.insert(short_id.clone());
+        }
+    }
+
+    // Move the failed entry out of `modified_entries` so it isn't evaluated again in this scan
+    fn mark_failed_modified(&mut self, short_id: &ProposalShortId) {
+        if let Some(entry) = self.modified_entries.remove(short_id) {
+            self.failed_modified_entries.insert(short_id.clone(), entry);
+        }
+        self.failed_txs.insert(short_id.clone());
     }
 
     fn retrieve_entry(&self, short_id: &ProposalShortId) -> Option<&TxEntry> {
         self.modified_entries
             .get(short_id)
+            .or_else(|| self.failed_modified_entries.get(short_id))
             .or_else(|| self.pool_map.get_proposed(short_id))
     }
 
@@ -208,6 +276,7 @@ impl<'a> CommitTxsScanner<'a> {
                 if let Some(mut desc) = self
                     .modified_entries
                     .remove(desc_id)
+                    .or_else(|| self.failed_modified_entries.remove(desc_id))
                     .or_else(|| self.pool_map.get(desc_id).cloned())
                 {
                     desc.sub_ancestor_weight(entry);
@@ -23,6 +23,8 @@ pub struct TxEntry {
     pub size: usize,
     /// fee
     pub fee: Capacity,
+    /// virtual fee delta set by `prioritise_transaction`, only affects the ordering
+    pub fee_delta: i64,
     /// ancestors txs size
     pub ancestors_size: usize,
     /// ancestors txs fee
@@ -41,6 +43,8 @@ pub struct TxEntry {
     pub descendants_count: usize,
     /// The unix timestamp when entering the Txpool, unit: Millisecond
     pub timestamp: u64,
+    /// Whether the tx is submitted locally, e.g. via RPC
+    pub local: bool,
 }
 
 impl TxEntry {
@@ -62,7 +66,9 @@ impl TxEntry {
             cycles,
             size,
             fee,
+            fee_delta: 0,
             timestamp,
+            local: false,
             ancestors_size: size,
             ancestors_fee: fee,
             ancestors_cycles: cycles,
@@ -111,6 +117,35 @@ impl TxEntry {
         EvictKey::from(self)
     }
 
+    /// Returns the fee with the fee delta applied
+    pub fn modified_fee(&self) -> Capacity {
+        let fee = self.fee.as_u64();
+        if self.fee_delta >= 0 {
+            Capacity::shannons(fee.saturating_add(self.fee_delta.unsigned_abs()))
+        } else {
+            Capacity::shannons(fee.saturating_sub(self.fee_delta.unsigned_abs()))
+        }
+    }
+
+    /// Update the fee delta and the aggregated fees include this entry
+    pub fn set_fee_delta(&mut self, fee_delta: i64) {
+        let old_fee = self.modified_fee().as_u64();
+        self.fee_delta = fee_delta;
+        let new_fee = self.modified_fee().as_u64();
+        self.ancestors_fee = Capacity::shannons(
+            self.ancestors_fee
+                .as_u64()
+                .saturating_sub(old_fee)
+                .saturating_add(new_fee),
+        );
+        self.descendants_fee = Capacity::shannons(
+            self.descendants_fee
+                .as_u64()
+                .saturating_sub(old_fee)
+                .saturating_add(new_fee),
+        );
+    }
+
     /// Returns fee rate
     pub fn fee_rate(&self) -> FeeRate {
         let weight = get_transaction_weight(self.size, self.cycles);
@@ -125,7 +160,7 @@ impl TxEntry {
         self.descendants_fee = Capacity::shannons(
             self.descendants_fee
                 .as_u64()
-                .saturating_add(entry.fee.as_u64()),
+                .saturating_add(entry.modified_fee().as_u64()),
         );
     }
 
@@ -137,7 +172,7 @@ impl TxEntry {
         self.descendants_fee = Capacity::shannons(
             self.descendants_fee
                 .as_u64()
-                .saturating_sub(entry.fee.as_u64()),
+                .saturating_sub(entry.modified_fee().as_u64()),
         );
     }
 
@@ -149,7 +184,7 @@ impl TxEntry {
         self.ancestors_fee = Capacity::shannons(
             self.ancestors_fee
                 .as_u64()
-                .saturating_add(entry.fee.as_u64()),
+                .saturating_add(entry.modified_fee().as_u64()),
         );
     }
 
@@ -161,7 +196,7 @@ impl TxEntry {
         self.ancestors_fee = Capacity::shannons(
             self.ancestors_fee
                 .as_u64()
-                .saturating_sub(entry.fee.as_u64()),
+                .saturating_sub(entry.modified_fee().as_u64()),
         );
     }
 
@@ -170,12 +205,12 @@ impl TxEntry {
         self.ancestors_count = 1;
         self.ancestors_size = self.size;
         self.ancestors_cycles = self.cycles;
-        self.ancestors_fee = self.fee;
+        self.ancestors_fee = self.modified_fee();
 
         self.descendants_count = 1;
         self.descendants_size = self.size;
         self.descendants_cycles = self.cycles;
-        self.descendants_fee = self.fee;
+        self.descendants_fee = self.modified_fee();
     }
 
     /// Converts entry to a `TxEntryInfo`.
@@ -223,7 +258,7 @@ impl From<&TxEntry> for AncestorsScoreSortKey {
         let weight = get_transaction_weight(entry.size, entry.cycles);
         let ancestors_weight = get_transaction_weight(entry.ancestors_size, entry.ancestors_cycles);
         AncestorsScoreSortKey {
-            fee: entry.fee,
+            fee: entry.modified_fee(),
             weight,
             ancestors_fee: entry.ancestors_fee,
             ancestors_weight,
@@ -238,8 +273,9 @@ impl From<&TxEntry> for EvictKey {
             get_transaction_weight(entry.descendants_size, entry.descendants_cycles);
 
         let descendants_feerate = FeeRate::calculate(entry.descendants_fee, descendants_weight);
-        let feerate = FeeRate::calculate(entry.fee, weight);
+        let feerate = FeeRate::calculate(entry.modified_fee(), weight);
         EvictKey {
+            local: entry.local,
             fee_rate: descendants_feerate.max(feerate),
             timestamp: entry.timestamp,
             descendants_count: entry.descendants_count,
@@ -8,6 +8,7 @@ pub(crate) mod orphan;
 pub(crate) mod pool_map;
 pub(crate) mod recent_reject;
 pub(crate) mod sort_key;
+pub(crate) mod tx_history;
 
 #[cfg(test)]
 mod tests;
@@ -9,7 +9,8 @@ use crate::error::Reject;
 use crate::TxEntry;
 use ckb_logger::{debug, error, trace};
 use ckb_types::core::error::OutPointError;
-use ckb_types::core::Cycle;
+use ckb_types::core::tx_pool::{ConflictKind, TxConflict};
+use ckb_types::core::{Cycle, FeeRate};
 use ckb_types::packed::OutPoint;
 use ckb_types::prelude::*;
 use ckb_types::{
@@ -17,9 +18,17 @@ use ckb_types::{
     core::TransactionView,
     packed::{Byte32, CellOutput, ProposalShortId},
 };
+use lru::LruCache;
 use multi_index_map::MultiIndexMap;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 type ConflictEntry = (TxEntry, Reject);
+type DoubleSpentEntry = (TxEntry, Reject, TxConflict);
+
+// The max interval between the rebroadcasts of a local tx, 1 day
+const MAX_LOCAL_REBROADCAST_INTERVAL: u64 = 24 * 60 * 60 * 1000;
+
+// The max count of the fee deltas kept for the txs not in the pool
+pub(crate) const MAX_FEE_DELTAS: usize = 10_000;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Status {
@@ -38,6 +47,24 @@ impl ToString for Status {
     }
 }
 
+/// The rebroadcast schedule of a local tx
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LocalRebroadcast {
+    /// The times the tx has been rebroadcast
+    pub(crate) count: u32,
+    /// The time of the next rebroadcast in milliseconds
+    pub(crate) next_at: u64,
+}
+
+/// The fee delta set by `prioritise_transaction` for a tx not in the pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FeeDelta {
+    /// The accumulated fee delta
+    pub(crate) value: i64,
+    /// The time when the fee delta is set in milliseconds
+    pub(crate) timestamp: u64,
+}
+
 #[derive(Copy, Clone)]
 enum EntryOp {
     Add,
@@ -66,13 +93,37 @@ pub struct PoolMap {
     /// All the parent/children relationships
     pub(crate) links: TxLinksMap,
     pub(crate) max_ancestors_count: usize,
+    /// The max count of the txs referencing the same cell dep, no limit when it's 0
+    pub(crate) max_cell_dep_references: usize,
+    /// The tx consuming a cell referenced as cell dep by at least this many txs must pay more
+    /// than the total fee of the txs invalidated, no fee bump is required when it's 0
+    pub(crate) heavy_cell_dep_references: usize,
+    /// The txs whose outputs are not limited by `max_cell_dep_references` when referenced as cell
+    /// deps, which are the genesis txs holding the system scripts and dep groups
+    pub(crate) unlimited_cell_dep_txs: HashSet<Byte32>,
     // sum of all tx_pool tx's virtual sizes.
     pub(crate) total_tx_size: usize,
     // sum of all tx_pool tx's cycles.
     pub(crate) total_tx_cycles: Cycle,
+    /// The size budget of the local txs, they're evicted after all the other txs while their
+    /// total size is within it, no budget when it's 0
+    pub(crate) max_local_tx_size: usize,
+    // sum of the local txs' virtual sizes.
+    pub(crate) local_tx_size: usize,
     pub(crate) pending_count: usize,
     pub(crate) gap_count: usize,
     pub(crate) proposed_count: usize,
+    /// The fee deltas set by `prioritise_transaction` for the txs not in the pool yet, the least
+    /// recently set ones are dropped beyond `MAX_FEE_DELTAS`. The fee delta moves into the entry
+    /// once the tx enters the pool, and it's dropped when the tx leaves the pool.
+    pub(crate) fee_deltas: LruCache<ProposalShortId, FeeDelta>,
+    /// The txs below the min fee rate waiting for a child to pay their fee, with the deadlines
+    /// in milliseconds
+    pub(crate) low_fee_parents: HashMap<ProposalShortId, u64>,
+    /// The txs below the min fee rate which are paid by their descendants
+    pub(crate) paid_low_fee_parents: HashSet<ProposalShortId>,
+    /// The rebroadcast schedules of the local txs, created at the first check
+    pub(crate) local_rebroadcasts: HashMap<ProposalShortId, LocalRebroadcast>,
 }
 
 impl PoolMap {
@@ -82,14 +133,37 @@ impl PoolMap {
             edges: Edges::default(),
             links: TxLinksMap::new(),
             max_ancestors_count,
+            max_cell_dep_references: 0,
+            heavy_cell_dep_references: 0,
+            unlimited_cell_dep_txs: HashSet::new(),
             total_tx_size: 0,
             total_tx_cycles: 0,
+            max_local_tx_size: 0,
+            local_tx_size: 0,
             pending_count: 0,
             gap_count: 0,
             proposed_count: 0,
+            fee_deltas: LruCache::new(MAX_FEE_DELTAS),
+            low_fee_parents: HashMap::new(),
+            paid_low_fee_parents: HashSet::new(),
+            local_rebroadcasts: HashMap::new(),
         }
     }
 
+    /// Sets the limits protecting the pool from the churn of consuming the cell deps, which
+    /// invalidates all the txs referencing them. The outputs of `unlimited_txs` can be referenced
+    /// by any number of txs.
+    pub(crate) fn set_cell_dep_limits(
+        &mut self,
+        max_references: usize,
+        heavy_references: usize,
+        unlimited_txs: HashSet<Byte32>,
+    ) {
+        self.max_cell_dep_references = max_references;
+        self.heavy_cell_dep_references = heavy_references;
+        self.unlimited_cell_dep_txs = unlimited_txs;
+    }
+
     #[cfg(test)]
     pub(crate) fn header_deps_len(&self) -> usize {
         self.edges.header_deps_len()
@@ -209,12 +283,20 @@ impl PoolMap {
             return Ok((false, evicts));
         }
         trace!("pool_map.add_{:?} {}", status, entry.transaction().hash());
+        if let Some(fee_delta) = self.fee_deltas.peek(&tx_short_id) {
+            entry.set_fee_delta(fee_delta.value);
+        }
+        self.check_cell_dep_churn_fee(&entry)?;
         evicts = self.check_and_record_ancestors(&mut entry)?;
         self.record_entry_edges(&entry)?;
         self.insert_entry(&entry, status);
+        self.fee_deltas.pop(&tx_short_id);
         self.record_entry_descendants(&entry);
         self.track_entry_statics(None, Some(status));
         self.update_stat_for_add_tx(entry.size, entry.cycles);
+        if entry.local {
+            self.local_tx_size = self.local_tx_size.saturating_add(entry.size);
+        }
         Ok((true, evicts))
     }
 
@@ -230,6 +312,196 @@ impl PoolMap {
         self.track_entry_statics(old_status, Some(status));
     }
 
+    /// Adds the fee delta to the tx, and updates the sort keys of the tx and its relatives if
+    /// it's in the pool. Returns the accumulated fee delta of the tx.
+    pub(crate) fn prioritise(&mut self, id: &ProposalShortId, fee_delta: i64, now_ms: u64) -> i64 {
+        if let Some(entry) = self.get(id).cloned() {
+            let total = entry.fee_delta.saturating_add(fee_delta);
+            self.update_ancestors_index_key(&entry, EntryOp::Remove);
+            self.update_descendants_index_key(&entry, EntryOp::Remove);
+            self.entries
+                .modify_by_id(id, |e| {
+                    e.inner.set_fee_delta(total);
+                    e.score = e.inner.as_score_key();
+                    e.evict_key = e.inner.as_evict_key();
+                })
+                .expect("unconsistent pool");
+            let entry = self.get_by_id_checked(id).inner.clone();
+            self.update_ancestors_index_key(&entry, EntryOp::Add);
+            self.update_descendants_index_key(&entry, EntryOp::Add);
+            return total;
+        }
+
+        let total = self
+            .fee_deltas
+            .peek(id)
+            .map_or(0, |fee_delta| fee_delta.value)
+            .saturating_add(fee_delta);
+        if total == 0 {
+            self.fee_deltas.pop(id);
+        } else {
+            self.fee_deltas.put(
+                id.clone(),
+                FeeDelta {
+                    value: total,
+                    timestamp: now_ms,
+                },
+            );
+        }
+        total
+    }
+
+    pub(crate) fn remove_fee_delta(&mut self, id: &ProposalShortId) {
+        self.fee_deltas.pop(id);
+    }
+
+    /// Drops the fee deltas of the txs not in the pool, which are set before the deadline.
+    pub(crate) fn remove_expired_fee_deltas(&mut self, deadline: u64) {
+        let expired: Vec<_> = self
+            .fee_deltas
+            .iter()
+            .filter(|(_, fee_delta)| fee_delta.timestamp < deadline)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in expired {
+            self.fee_deltas.pop(&id);
+        }
+    }
+
+    /// Returns the rebroadcast schedule of the local tx, the first rebroadcast is one interval
+    /// after the tx enters the pool.
+    pub(crate) fn local_rebroadcast(&self, entry: &TxEntry, interval: u64) -> LocalRebroadcast {
+        self.local_rebroadcasts
+            .get(&entry.proposal_short_id())
+            .copied()
+            .unwrap_or(LocalRebroadcast {
+                count: 0,
+                next_at: entry.timestamp.saturating_add(interval),
+            })
+    }
+
+    /// Returns the hashes of the local txs due to rebroadcast, and schedules their next
+    /// rebroadcasts, the interval is doubled after each rebroadcast up to 1 day.
+    pub(crate) fn take_local_txs_to_rebroadcast(
+        &mut self,
+        now_ms: u64,
+        interval: u64,
+    ) -> Vec<Byte32> {
+        let mut due = Vec::new();
+        for (_, entry) in self.entries.iter() {
+            if !entry.inner.local {
+                continue;
+            }
+            let mut schedule = self.local_rebroadcast(&entry.inner, interval);
+            if schedule.next_at <= now_ms {
+                schedule.count = schedule.count.saturating_add(1);
+                let backoff = interval
+                    .saturating_mul(1 << schedule.count.min(16))
+                    .min(MAX_LOCAL_REBROADCAST_INTERVAL);
+                schedule.next_at = now_ms.saturating_add(backoff);
+                due.push(entry.inner.transaction().hash());
+            }
+            self.local_rebroadcasts.insert(entry.id.clone(), schedule);
+        }
+        due
+    }
+
+    /// Restores the persisted timestamp and origin of the entry, returns false if the entry is
+    /// not in the pool.
+    pub(crate) fn restore_entry(
+        &mut self,
+        id: &ProposalShortId,
+        timestamp: u64,
+        local: bool,
+    ) -> bool {
+        let mut local_size_change = None;
+        let restored = self
+            .entries
+            .modify_by_id(id, |e| {
+                if e.inner.local != local {
+                    local_size_change = Some(e.inner.size);
+                }
+                e.inner.timestamp = timestamp;
+                e.inner.local = local;
+                e.evict_key = e.inner.as_evict_key();
+            })
+            .is_some();
+        if let Some(size) = local_size_change {
+            self.local_tx_size = if local {
+                self.local_tx_size.saturating_add(size)
+            } else {
+                self.local_tx_size.saturating_sub(size)
+            };
+        }
+        restored
+    }
+
+    /// Tracks the tx below the min fee rate until the deadline.
+    pub(crate) fn track_low_fee_parent(&mut self, id: ProposalShortId, deadline: u64) {
+        self.low_fee_parents.insert(id, deadline);
+    }
+
+    /// Stops tracking the ancestors of the tx whose package fee rate with their descendants
+    /// reaches the min fee rate.
+    pub(crate) fn settle_low_fee_ancestors(&mut self, id: &ProposalShortId, min_fee_rate: FeeRate) {
+        if self.low_fee_parents.is_empty() {
+            return;
+        }
+        for anc_id in self.calc_ancestors(id) {
+            if self.low_fee_parents.contains_key(&anc_id)
+                && self
+                    .get(&anc_id)
+                    .map_or(true, |entry| is_package_paid(entry, min_fee_rate))
+            {
+                debug!("low fee parent {:?} is paid by descendants", anc_id);
+                self.low_fee_parents.remove(&anc_id);
+                self.paid_low_fee_parents.insert(anc_id);
+            }
+        }
+    }
+
+    /// Tracks the paid txs below the min fee rate again until the deadline, once the descendants
+    /// paying their fee are removed.
+    pub(crate) fn retrack_unpaid_low_fee_parents(&mut self, deadline: u64, min_fee_rate: FeeRate) {
+        let unpaid: Vec<_> = self
+            .paid_low_fee_parents
+            .iter()
+            .filter(|id| {
+                self.get(id)
+                    .map_or(false, |entry| !is_package_paid(entry, min_fee_rate))
+            })
+            .cloned()
+            .collect();
+        for id in unpaid {
+            debug!("low fee parent {:?} is not paid anymore", id);
+            self.paid_low_fee_parents.remove(&id);
+            self.low_fee_parents.insert(id, deadline);
+        }
+    }
+
+    /// Stops tracking the txs whose deadline has passed, returns the ones whose package fee rate
+    /// is still lower than the min fee rate.
+    pub(crate) fn take_unpaid_low_fee_parents(
+        &mut self,
+        now_ms: u64,
+        min_fee_rate: FeeRate,
+    ) -> Vec<ProposalShortId> {
+        let expired: Vec<_> = self
+            .low_fee_parents
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now_ms)
+            .map(|(id, _)| id.clone())
+            .collect();
+        expired
+            .into_iter()
+            .filter(|id| {
+                self.low_fee_parents.remove(id);
+                self.get(id)
+                    .map_or(false, |entry| !is_package_paid(entry, min_fee_rate))
+            })
+            .collect()
+    }
+
     pub(crate) fn remove_entry(&mut self, id: &ProposalShortId) -> Option<TxEntry> {
         self.entries.remove_by_id(id).map(|entry| {
             debug!(
@@ -243,6 +515,12 @@ impl PoolMap {
             self.remove_entry_links(id);
             self.track_entry_statics(Some(entry.status), None);
             self.update_stat_for_remove_tx(entry.inner.size, entry.inner.cycles);
+            if entry.inner.local {
+                self.local_tx_size = self.local_tx_size.saturating_sub(entry.inner.size);
+            }
+            self.low_fee_parents.remove(id);
+            self.paid_low_fee_parents.remove(id);
+            self.local_rebroadcasts.remove(id);
             entry.inner
         })
     }
@@ -251,15 +529,34 @@ impl PoolMap {
         let mut removed_ids = vec![id.to_owned()];
         removed_ids.extend(self.calc_descendants(id));
 
+        // the ancestors staying in the pool must still lose the weight of the removed txs
+        let kept_ancestors: Vec<HashSet<ProposalShortId>> = removed_ids
+            .iter()
+            .map(|id| {
+                let mut ancestors = self.calc_ancestors(id);
+                ancestors.retain(|anc_id| !removed_ids.contains(anc_id));
+                ancestors
+            })
+            .collect();
+
         // update links state for remove, so that we won't update_descendants_index_key in remove_entry
         for id in &removed_ids {
             self.remove_entry_links(id);
         }
 
-        removed_ids
-            .iter()
-            .filter_map(|id| self.remove_entry(id))
-            .collect()
+        let mut removed = Vec::with_capacity(removed_ids.len());
+        for (id, ancestors) in removed_ids.iter().zip(kept_ancestors) {
+            if let Some(entry) = self.remove_entry(id) {
+                for anc_id in &ancestors {
+                    self.entries.modify_by_id(anc_id, |e| {
+                        e.inner.sub_descendant_weight(&entry);
+                        e.evict_key = e.inner.as_evict_key();
+                    });
+                }
+                removed.push(entry);
+            }
+        }
+        removed
     }
 
     pub(crate) fn resolve_conflict_header_dep(
@@ -300,28 +597,44 @@ impl PoolMap {
             .find_map(|out_point| self.edges.get_input_ref(&out_point).map(|_| out_point))
     }
 
-    pub(crate) fn resolve_conflict(&mut self, tx: &TransactionView) -> Vec<ConflictEntry> {
+    /// Removes the txs double spent by the committed tx along with their descendants.
+    pub(crate) fn resolve_conflict(&mut self, tx: &TransactionView) -> Vec<DoubleSpentEntry> {
         let mut conflicts = Vec::new();
+        let spent: HashSet<OutPoint> = tx.input_pts_iter().collect();
 
         for i in tx.input_pts_iter() {
-            if let Some(id) = self.edges.remove_input(&i) {
+            let input_ids = self
+                .edges
+                .remove_input(&i)
+                .into_iter()
+                .map(|id| (id, false));
+            // deps consumed
+            let dep_ids = self
+                .edges
+                .remove_deps(&i)
+                .into_iter()
+                .flatten()
+                .map(|id| (id, true));
+            let ids: Vec<_> = input_ids.chain(dep_ids).collect();
+
+            for (id, is_dep) in ids {
+                let out_points = self.contested_out_points(&id, &spent);
                 let entries = self.remove_entry_and_descendants(&id);
                 if !entries.is_empty() {
-                    let reject = Reject::Resolve(OutPointError::Dead(i.clone()));
-                    let rejects = std::iter::repeat(reject).take(entries.len());
-                    conflicts.extend(entries.into_iter().zip(rejects));
-                }
-            }
-
-            // deps consumed
-            if let Some(x) = self.edges.remove_deps(&i) {
-                for id in x {
-                    let entries = self.remove_entry_and_descendants(&id);
-                    if !entries.is_empty() {
-                        let reject = Reject::Resolve(OutPointError::Dead(i.clone()));
-                        let rejects = std::iter::repeat(reject).take(entries.len());
-                        conflicts.extend(entries.into_iter().zip(rejects));
+                    if is_dep {
+                        track_cell_dep_evicted(entries.len());
                     }
+                    let reject = Reject::Resolve(OutPointError::Dead(i.clone()));
+                    let conflict = TxConflict {
+                        kind: ConflictKind::DoubleSpent,
+                        conflicting_tx_hash: tx.hash(),
+                        out_points,
+                    };
+                    conflicts.extend(
+                        entries
+                            .into_iter()
+                            .map(|entry| (entry, reject.clone(), conflict.clone())),
+                    );
                 }
             }
         }
@@ -329,6 +642,48 @@ impl PoolMap {
         conflicts
     }
 
+    /// Removes the txs replaced by the tx via RBF along with their descendants.
+    pub(crate) fn remove_replaced(
+        &mut self,
+        ids: &HashSet<ProposalShortId>,
+        tx: &TransactionView,
+    ) -> Vec<(TxEntry, TxConflict)> {
+        let spent: HashSet<OutPoint> = tx.input_pts_iter().collect();
+        let mut replaced = Vec::new();
+        for id in ids {
+            let conflict = TxConflict {
+                kind: ConflictKind::Replaced,
+                conflicting_tx_hash: tx.hash(),
+                out_points: self.contested_out_points(id, &spent),
+            };
+            replaced.extend(
+                self.remove_entry_and_descendants(id)
+                    .into_iter()
+                    .map(|entry| (entry, conflict.clone())),
+            );
+        }
+        replaced
+    }
+
+    // The inputs and the cell deps of the tx which are spent by another tx
+    fn contested_out_points(
+        &self,
+        id: &ProposalShortId,
+        spent: &HashSet<OutPoint>,
+    ) -> Vec<OutPoint> {
+        self.get_by_id(id)
+            .map(|entry| {
+                entry
+                    .inner
+                    .transaction()
+                    .input_pts_iter()
+                    .chain(entry.inner.related_dep_out_points().cloned())
+                    .filter(|out_point| spent.contains(out_point))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     // find the pending txs sorted by score, and return their proposal short ids
     pub(crate) fn get_proposals(
         &self,
@@ -355,15 +710,42 @@ impl PoolMap {
             .map(|entry| entry.id.clone())
     }
 
+    /// Returns the next entry to evict when the pool is full, the pending ones first, then the
+    /// gap and proposed ones. While the total size of the local txs is within
+    /// `max_local_tx_size`, they're evicted after all the other txs.
+    pub(crate) fn next_evict_entry_for_size(&self) -> Option<ProposalShortId> {
+        const STATUSES: [Status; 3] = [Status::Pending, Status::Gap, Status::Proposed];
+        let protect_local =
+            self.max_local_tx_size > 0 && self.local_tx_size <= self.max_local_tx_size;
+        if protect_local {
+            let remote = STATUSES.iter().find_map(|status| {
+                self.entries
+                    .iter_by_evict_key()
+                    .find(|entry| entry.status == *status && !entry.inner.local)
+                    .map(|entry| entry.id.clone())
+            });
+            if remote.is_some() {
+                return remote;
+            }
+        }
+        STATUSES
+            .iter()
+            .find_map(|status| self.next_evict_entry(*status))
+    }
+
     pub(crate) fn clear(&mut self) {
         self.entries = MultiIndexPoolEntryMap::default();
         self.edges.clear();
         self.links.clear();
         self.total_tx_size = 0;
         self.total_tx_cycles = 0;
+        self.local_tx_size = 0;
         self.pending_count = 0;
         self.gap_count = 0;
         self.proposed_count = 0;
+        self.low_fee_parents.clear();
+        self.paid_low_fee_parents.clear();
+        self.local_rebroadcasts.clear();
     }
 
     pub(crate) fn score_sorted_iter_by_status(
@@ -550,6 +932,71 @@ impl PoolMap {
         );
     }
 
+    /// Rejects the new tx if any of its cell deps is referenced by too many txs in the pool
+    /// already. It's not checked in `add_entry`, so the txs added back on reorg are kept.
+    pub(crate) fn check_cell_dep_references(&self, entry: &TxEntry) -> Result<(), Reject> {
+        if self.max_cell_dep_references == 0 {
+            return Ok(());
+        }
+        let exceeded = entry.related_dep_out_points().any(|out_point| {
+            !self.unlimited_cell_dep_txs.contains(&out_point.tx_hash())
+                && self
+                    .edges
+                    .get_deps_ref(out_point)
+                    .map_or(0, |ids| ids.len())
+                    >= self.max_cell_dep_references
+        });
+        if exceeded {
+            if let Some(metrics) = ckb_metrics::handle() {
+                metrics.ckb_tx_pool_cell_dep_churn.exceeded_references.inc();
+            }
+            return Err(Reject::ExceededCellDepReferencesLimit(
+                self.max_cell_dep_references,
+            ));
+        }
+        Ok(())
+    }
+
+    // The tx consuming the cells referenced as cell deps by many txs invalidates all of them and
+    // their descendants once committed, so it must pay more than the total fee of them
+    fn check_cell_dep_churn_fee(&self, entry: &TxEntry) -> Result<(), Reject> {
+        if self.heavy_cell_dep_references == 0 {
+            return Ok(());
+        }
+        let mut invalidated = HashSet::new();
+        for out_point in entry.transaction().input_pts_iter() {
+            match self.edges.get_deps_ref(&out_point) {
+                Some(ids) if ids.len() >= self.heavy_cell_dep_references => {
+                    for id in ids {
+                        invalidated.extend(self.calc_descendants(id));
+                        invalidated.insert(id.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+        if invalidated.is_empty() {
+            return Ok(());
+        }
+
+        let total_fee = invalidated
+            .iter()
+            .filter_map(|id| self.get(id))
+            .fold(0u64, |acc, e| acc.saturating_add(e.fee.as_u64()));
+        let fee = entry.fee.as_u64();
+        if fee <= total_fee {
+            if let Some(metrics) = ckb_metrics::handle() {
+                metrics.ckb_tx_pool_cell_dep_churn.low_fee.inc();
+            }
+            return Err(Reject::LowCellDepChurnFee(
+                invalidated.len(),
+                total_fee,
+                fee,
+            ));
+        }
+        Ok(())
+    }
+
     /// Check ancestors and record for entry
     // FIXME: In the scenario that a transaction passed all RBF rules, and then removed the conflicted
     // transaction in txpool, then failed with max ancestor limits, we now need to rollback the removing.
@@ -707,3 +1154,18 @@ impl PoolMap {
         self.total_tx_cycles = total_tx_cycles;
     }
 }
+
+// Whether the fee of the tx and its descendants reaches the min fee rate
+fn is_package_paid(entry: &TxEntry, min_fee_rate: FeeRate) -> bool {
+    entry.descendants_fee >= min_fee_rate.fee(entry.descendants_size as u64)
+}
+
+// Counts the txs evicted because their cell deps are consumed
+fn track_cell_dep_evicted(count: usize) {
+    if let Some(metrics) = ckb_metrics::handle() {
+        metrics
+            .ckb_tx_pool_cell_dep_churn
+            .evicted
+            .inc_by(count as u64);
+    }
+}
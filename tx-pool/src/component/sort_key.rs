@@ -72,11 +72,12 @@ impl ToString for AncestorsScoreSortKey {
     }
 }
 
-/// First compare fee_rate, select the smallest fee_rate,
+/// First compare fee_rate, select the smallest fee_rate, then select the non-local txs,
 /// and then select the latest timestamp, for eviction,
 /// the latest timestamp which also means that the fewer descendants may exist.
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub struct EvictKey {
+    pub local: bool,
     pub fee_rate: FeeRate,
     pub timestamp: u64,
     pub descendants_count: usize,
@@ -91,7 +92,9 @@ impl PartialOrd for EvictKey {
 impl Ord for EvictKey {
     fn cmp(&self, other: &Self) -> Ordering {
         if self.fee_rate == other.fee_rate {
-            if self.descendants_count == other.descendants_count {
+            if self.local != other.local {
+                self.local.cmp(&other.local)
+            } else if self.descendants_count == other.descendants_count {
                 self.timestamp.cmp(&other.timestamp)
             } else {
                 self.descendants_count.cmp(&other.descendants_count)
@@ -6,6 +6,7 @@ fn test_min_fee_and_weight_evict() {
     let mut result = vec![(500, 10, 30), (10, 10, 31), (100, 10, 32)]
         .into_iter()
         .map(|(fee, weight, timestamp)| EvictKey {
+            local: false,
             fee_rate: FeeRate::calculate(Capacity::shannons(fee), weight),
             timestamp,
             descendants_count: 0,
@@ -23,6 +24,7 @@ fn test_min_timestamp_evict() {
     let mut result = vec![(500, 10, 30), (500, 10, 31), (500, 10, 32)]
         .into_iter()
         .map(|(fee, weight, timestamp)| EvictKey {
+            local: false,
             fee_rate: FeeRate::calculate(Capacity::shannons(fee), weight),
             timestamp,
             descendants_count: 0,
@@ -40,6 +42,7 @@ fn test_min_weight_evict() {
     let mut result = vec![(500, 10, 30), (500, 12, 31), (500, 13, 32)]
         .into_iter()
         .map(|(fee, weight, timestamp)| EvictKey {
+            local: false,
             fee_rate: FeeRate::calculate(Capacity::shannons(fee), weight),
             timestamp,
             descendants_count: 0,
@@ -51,3 +54,25 @@ fn test_min_weight_evict() {
         vec![32, 31, 30]
     );
 }
+
+#[test]
+fn test_local_evict_after_same_fee_rate() {
+    let mut result = vec![
+        (500, 10, 30, true),
+        (500, 10, 31, false),
+        (100, 10, 32, true),
+    ]
+    .into_iter()
+    .map(|(fee, weight, timestamp, local)| EvictKey {
+        local,
+        fee_rate: FeeRate::calculate(Capacity::shannons(fee), weight),
+        timestamp,
+        descendants_count: 0,
+    })
+    .collect::<Vec<_>>();
+    result.sort();
+    assert_eq!(
+        result.iter().map(|key| key.timestamp).collect::<Vec<_>>(),
+        vec![32, 31, 30]
+    );
+}
@@ -3,7 +3,9 @@ mod entry;
 mod links;
 mod orphan;
 mod pending;
+mod persisted;
 mod proposed;
 mod recent_reject;
 mod score_key;
+mod tx_history;
 mod util;
@@ -4,11 +4,16 @@ use crate::component::tests::util::{
 };
 use crate::component::{
     entry::TxEntry,
-    pool_map::{PoolMap, Status},
+    pool_map::{PoolMap, Status, MAX_FEE_DELTAS},
 };
-use ckb_types::core::Capacity;
+use crate::error::Reject;
+use ckb_types::core::{tx_pool::ConflictKind, Capacity, FeeRate};
 use ckb_types::packed::OutPoint;
-use ckb_types::{h256, packed::Byte32, prelude::*};
+use ckb_types::{
+    h256,
+    packed::{Byte32, ProposalShortId},
+    prelude::*,
+};
 use std::collections::HashSet;
 use std::time::Duration;
 
@@ -110,6 +115,66 @@ fn test_resolve_conflict_descendants() {
     );
 }
 
+#[test]
+fn test_conflict_out_points() {
+    let mut pool = PoolMap::new(1000);
+    let tx1 = build_tx(vec![(&Byte32::zero(), 1), (&h256!("0x1").pack(), 1)], 1);
+    let tx2 = build_tx(vec![(&tx1.hash(), 0)], 1);
+    let tx3 = build_tx_with_dep(
+        vec![(&h256!("0x2").pack(), 1)],
+        vec![(&h256!("0x3").pack(), 1)],
+        1,
+    );
+    for tx in [tx1.clone(), tx2.clone(), tx3.clone()] {
+        let entry = TxEntry::dummy_resolve(tx, MOCK_CYCLES, MOCK_FEE, MOCK_SIZE);
+        assert!(pool.add_entry(entry, Status::Pending).is_ok());
+    }
+
+    // tx1 is double spent, and tx2 is removed along with it
+    let committed = build_tx(
+        vec![
+            (&Byte32::zero(), 1),
+            (&h256!("0x1").pack(), 1),
+            (&h256!("0x3").pack(), 1),
+        ],
+        1,
+    );
+    let conflicts = pool.resolve_conflict(&committed);
+    assert_eq!(conflicts.len(), 3);
+    for (entry, _, conflict) in conflicts {
+        assert_eq!(conflict.kind, ConflictKind::DoubleSpent);
+        assert_eq!(conflict.conflicting_tx_hash, committed.hash());
+        let out_points = if entry.transaction().hash() == tx3.hash() {
+            // the consumed cell dep
+            vec![OutPoint::new(h256!("0x3").pack(), 1)]
+        } else {
+            vec![
+                OutPoint::new(Byte32::zero(), 1),
+                OutPoint::new(h256!("0x1").pack(), 1),
+            ]
+        };
+        assert_eq!(conflict.out_points, out_points);
+    }
+
+    // tx4 is replaced by tx5
+    let tx4 = build_tx(vec![(&h256!("0x4").pack(), 1)], 1);
+    let tx5 = build_tx(vec![(&h256!("0x4").pack(), 1)], 2);
+    let entry4 = TxEntry::dummy_resolve(tx4, MOCK_CYCLES, MOCK_FEE, MOCK_SIZE);
+    let id4 = entry4.proposal_short_id();
+    assert!(pool.add_entry(entry4.clone(), Status::Pending).is_ok());
+    let replaced = pool.remove_replaced(&HashSet::from_iter(vec![id4]), &tx5);
+    assert_eq!(replaced.len(), 1);
+    let (entry, conflict) = &replaced[0];
+    assert_eq!(entry, &entry4);
+    assert_eq!(conflict.kind, ConflictKind::Replaced);
+    assert_eq!(conflict.conflicting_tx_hash, tx5.hash());
+    assert_eq!(
+        conflict.out_points,
+        vec![OutPoint::new(h256!("0x4").pack(), 1)]
+    );
+    assert_eq!(pool.size(), 0);
+}
+
 #[test]
 fn test_resolve_conflict_header_dep() {
     let mut pool = PoolMap::new(1000);
@@ -418,3 +483,286 @@ fn test_pool_min_descendants_evict() {
 
     assert!(pool.next_evict_entry(Status::Pending).is_none());
 }
+
+#[test]
+fn test_prioritise() {
+    let mut pool = PoolMap::new(1000);
+    let tx1 = build_tx(vec![(&Byte32::zero(), 1)], 1);
+    let tx2 = build_tx(vec![(&tx1.hash(), 0)], 1);
+    let id1 = tx1.proposal_short_id();
+    let id2 = tx2.proposal_short_id();
+
+    // the fee delta can be set before the tx enters the pool
+    assert_eq!(pool.prioritise(&id2, 100, 0), 100);
+    assert_eq!(pool.prioritise(&id2, 50, 0), 150);
+
+    let entry1 = TxEntry::dummy_resolve(tx1, MOCK_CYCLES, Capacity::shannons(100), MOCK_SIZE);
+    let entry2 = TxEntry::dummy_resolve(tx2, MOCK_CYCLES, Capacity::shannons(100), MOCK_SIZE);
+    assert!(pool.add_entry(entry1, Status::Pending).is_ok());
+    assert!(pool.add_entry(entry2, Status::Pending).is_ok());
+    // moved into the entry
+    assert!(pool.fee_deltas.is_empty());
+
+    let child = pool.get(&id2).unwrap();
+    assert_eq!(child.fee, Capacity::shannons(100));
+    assert_eq!(child.modified_fee(), Capacity::shannons(250));
+    assert_eq!(child.ancestors_fee, Capacity::shannons(350));
+    let parent = pool.get(&id1).unwrap();
+    assert_eq!(parent.descendants_fee, Capacity::shannons(350));
+
+    // the negative delta penalizes the tx and its descendants
+    assert_eq!(pool.prioritise(&id1, -60, 0), -60);
+    let parent = pool.get(&id1).unwrap();
+    assert_eq!(parent.modified_fee(), Capacity::shannons(40));
+    assert_eq!(parent.descendants_fee, Capacity::shannons(290));
+    let child = pool.get(&id2).unwrap();
+    assert_eq!(child.ancestors_fee, Capacity::shannons(290));
+    assert_eq!(
+        pool.get_by_id(&id2).unwrap().score,
+        pool.get(&id2).unwrap().as_score_key()
+    );
+
+    // cancel the delta
+    assert_eq!(pool.prioritise(&id1, 60, 0), 0);
+    assert_eq!(
+        pool.get(&id2).unwrap().ancestors_fee,
+        Capacity::shannons(350)
+    );
+
+    // dropped with the entry
+    pool.remove_entry(&id2);
+    assert_eq!(pool.prioritise(&id2, 10, 0), 10);
+}
+
+#[test]
+fn test_fee_deltas_not_in_pool() {
+    let mut pool = PoolMap::new(1000);
+    let id =
+        |i: usize| ProposalShortId::new([(i % 256) as u8, (i / 256) as u8, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+    // the least recently set ones are dropped beyond the cap
+    for i in 0..=MAX_FEE_DELTAS {
+        pool.prioritise(&id(i), 100, i as u64);
+    }
+    assert_eq!(pool.fee_deltas.len(), MAX_FEE_DELTAS);
+    assert!(!pool.fee_deltas.contains(&id(0)));
+
+    // expired
+    pool.remove_expired_fee_deltas(MAX_FEE_DELTAS as u64);
+    assert_eq!(pool.fee_deltas.len(), 1);
+    assert!(pool.fee_deltas.contains(&id(MAX_FEE_DELTAS)));
+
+    // moved into the entry and dropped with it
+    let tx = build_tx(vec![(&Byte32::zero(), 1)], 1);
+    let tx_id = tx.proposal_short_id();
+    pool.prioritise(&tx_id, 100, 0);
+    let entry = TxEntry::dummy_resolve(tx, MOCK_CYCLES, MOCK_FEE, MOCK_SIZE);
+    assert!(pool.add_entry(entry, Status::Pending).is_ok());
+    assert!(!pool.fee_deltas.contains(&tx_id));
+    assert_eq!(pool.get(&tx_id).unwrap().fee_delta, 100);
+    pool.remove_entry(&tx_id);
+    assert!(!pool.fee_deltas.contains(&tx_id));
+}
+
+#[test]
+fn test_low_fee_parents() {
+    let min_fee_rate = FeeRate::from_u64(1000);
+    let mut pool = PoolMap::new(1000);
+    let parent1 = build_tx(vec![(&Byte32::zero(), 1)], 1);
+    let parent2 = build_tx(vec![(&Byte32::zero(), 2)], 1);
+    let child1 = build_tx(vec![(&parent1.hash(), 0)], 1);
+    let child2 = build_tx(vec![(&parent2.hash(), 0)], 1);
+    let id1 = parent1.proposal_short_id();
+    let id2 = parent2.proposal_short_id();
+
+    for parent in [parent1, parent2] {
+        let entry = TxEntry::dummy_resolve(parent, MOCK_CYCLES, Capacity::zero(), 1000);
+        let id = entry.proposal_short_id();
+        assert!(pool.add_entry(entry, Status::Pending).is_ok());
+        pool.track_low_fee_parent(id, 100);
+    }
+
+    // the package fee rate of parent1 and child1 reaches the min fee rate
+    let entry = TxEntry::dummy_resolve(child1, MOCK_CYCLES, Capacity::shannons(2000), 1000);
+    let id = entry.proposal_short_id();
+    assert!(pool.add_entry(entry, Status::Pending).is_ok());
+    pool.settle_low_fee_ancestors(&id, min_fee_rate);
+    assert!(!pool.low_fee_parents.contains_key(&id1));
+    assert!(pool.paid_low_fee_parents.contains(&id1));
+    let child1_id = id;
+
+    // the package fee rate of parent2 and child2 is still too low
+    let entry = TxEntry::dummy_resolve(child2, MOCK_CYCLES, Capacity::shannons(1000), 1000);
+    let id = entry.proposal_short_id();
+    assert!(pool.add_entry(entry, Status::Pending).is_ok());
+    pool.settle_low_fee_ancestors(&id, min_fee_rate);
+    assert!(pool.low_fee_parents.contains_key(&id2));
+
+    assert!(pool
+        .take_unpaid_low_fee_parents(99, min_fee_rate)
+        .is_empty());
+    assert_eq!(
+        pool.take_unpaid_low_fee_parents(100, min_fee_rate),
+        vec![id2.clone()]
+    );
+    assert!(pool.low_fee_parents.is_empty());
+
+    // parent1 waits again once child1 is removed
+    pool.retrack_unpaid_low_fee_parents(300, min_fee_rate);
+    assert!(pool.low_fee_parents.is_empty());
+    pool.remove_entry(&child1_id);
+    pool.retrack_unpaid_low_fee_parents(300, min_fee_rate);
+    assert_eq!(pool.low_fee_parents.get(&id1), Some(&300));
+    assert!(pool.paid_low_fee_parents.is_empty());
+
+    // the removed tx is not tracked anymore
+    pool.track_low_fee_parent(id2.clone(), 200);
+    pool.remove_entry_and_descendants(&id2);
+    pool.remove_entry(&id1);
+    assert!(pool.low_fee_parents.is_empty());
+}
+
+#[test]
+fn test_local_rebroadcast_backoff() {
+    let mut pool = PoolMap::new(1000);
+    let local = build_tx(vec![(&Byte32::zero(), 1)], 1);
+    let remote = build_tx(vec![(&Byte32::zero(), 2)], 1);
+    let local_hash = local.hash();
+    let local_id = local.proposal_short_id();
+
+    let mut entry = TxEntry::dummy_resolve(local, MOCK_CYCLES, MOCK_FEE, MOCK_SIZE);
+    entry.timestamp = 0;
+    entry.local = true;
+    assert!(pool.add_entry(entry, Status::Pending).is_ok());
+    let mut entry = TxEntry::dummy_resolve(remote, MOCK_CYCLES, MOCK_FEE, MOCK_SIZE);
+    entry.timestamp = 0;
+    assert!(pool.add_entry(entry, Status::Pending).is_ok());
+
+    assert!(pool.take_local_txs_to_rebroadcast(99, 100).is_empty());
+    assert_eq!(
+        pool.take_local_txs_to_rebroadcast(100, 100),
+        vec![local_hash.clone()]
+    );
+
+    // the interval is doubled after each rebroadcast
+    assert!(pool.take_local_txs_to_rebroadcast(299, 100).is_empty());
+    assert_eq!(
+        pool.take_local_txs_to_rebroadcast(300, 100),
+        vec![local_hash]
+    );
+    let schedule = pool.local_rebroadcasts[&local_id];
+    assert_eq!(schedule.count, 2);
+    assert_eq!(schedule.next_at, 700);
+
+    // the removed tx is not tracked anymore
+    pool.remove_entry(&local_id);
+    assert!(pool.local_rebroadcasts.is_empty());
+}
+
+#[test]
+fn test_local_evict_last_within_budget() {
+    let mut pool = PoolMap::new(1000);
+    let local = build_tx(vec![(&Byte32::zero(), 1)], 1);
+    let remote = build_tx(vec![(&Byte32::zero(), 2)], 1);
+    let local_id = local.proposal_short_id();
+    let remote_id = remote.proposal_short_id();
+
+    let mut entry = TxEntry::dummy_resolve(local, MOCK_CYCLES, Capacity::shannons(1000), 100);
+    entry.local = true;
+    assert!(pool.add_entry(entry, Status::Pending).is_ok());
+    // pays a slightly higher fee rate
+    let entry = TxEntry::dummy_resolve(remote, MOCK_CYCLES, Capacity::shannons(1010), 100);
+    assert!(pool.add_entry(entry, Status::Pending).is_ok());
+    assert_eq!(pool.local_tx_size, 100);
+
+    // no budget
+    assert_eq!(pool.next_evict_entry_for_size(), Some(local_id.clone()));
+
+    // the local tx is kept within the budget
+    pool.max_local_tx_size = 100;
+    assert_eq!(pool.next_evict_entry_for_size(), Some(remote_id.clone()));
+    pool.remove_entry(&remote_id);
+    assert_eq!(pool.next_evict_entry_for_size(), Some(local_id.clone()));
+
+    // over the budget
+    pool.max_local_tx_size = 99;
+    let remote = build_tx(vec![(&Byte32::zero(), 2)], 1);
+    let entry = TxEntry::dummy_resolve(remote, MOCK_CYCLES, Capacity::shannons(1010), 100);
+    assert!(pool.add_entry(entry, Status::Pending).is_ok());
+    assert_eq!(pool.next_evict_entry_for_size(), Some(local_id.clone()));
+
+    pool.remove_entry(&local_id);
+    assert_eq!(pool.local_tx_size, 0);
+}
+
+#[test]
+fn test_cell_dep_references_limit() {
+    let mut pool = PoolMap::new(1000);
+    let genesis_dep = h256!("0x2").pack();
+    pool.set_cell_dep_limits(2, 0, HashSet::from_iter([genesis_dep.clone()]));
+    let dep = h256!("0x1").pack();
+
+    for i in 0..2 {
+        let tx = build_tx_with_dep(
+            vec![(&Byte32::zero(), i)],
+            vec![(&dep, 0), (&genesis_dep, 0)],
+            1,
+        );
+        let entry = TxEntry::dummy_resolve(tx, MOCK_CYCLES, MOCK_FEE, MOCK_SIZE);
+        assert!(pool.check_cell_dep_references(&entry).is_ok());
+        assert!(pool.add_entry(entry, Status::Pending).is_ok());
+    }
+
+    let tx = build_tx_with_dep(vec![(&Byte32::zero(), 2)], vec![(&dep, 0)], 1);
+    let over_limit = TxEntry::dummy_resolve(tx, MOCK_CYCLES, MOCK_FEE, MOCK_SIZE);
+    assert!(matches!(
+        pool.check_cell_dep_references(&over_limit),
+        Err(Reject::ExceededCellDepReferencesLimit(2))
+    ));
+
+    // the other cell deps and the genesis cell deps are not limited
+    let tx = build_tx_with_dep(
+        vec![(&Byte32::zero(), 3)],
+        vec![(&dep, 1), (&genesis_dep, 0)],
+        1,
+    );
+    let entry = TxEntry::dummy_resolve(tx, MOCK_CYCLES, MOCK_FEE, MOCK_SIZE);
+    assert!(pool.check_cell_dep_references(&entry).is_ok());
+    assert!(pool.add_entry(entry, Status::Pending).is_ok());
+
+    // the txs added back on reorg are not limited
+    assert!(pool.add_entry(over_limit, Status::Pending).is_ok());
+    assert_eq!(pool.size(), 4);
+}
+
+#[test]
+fn test_cell_dep_churn_fee() {
+    let mut pool = PoolMap::new(1000);
+    pool.set_cell_dep_limits(0, 2, HashSet::new());
+    let heavy_dep = h256!("0x1").pack();
+    let light_dep = h256!("0x2").pack();
+
+    for i in 0..2 {
+        let tx = build_tx_with_dep(vec![(&Byte32::zero(), i)], vec![(&heavy_dep, 0)], 1);
+        let entry = TxEntry::dummy_resolve(tx, MOCK_CYCLES, Capacity::shannons(100), MOCK_SIZE);
+        assert!(pool.add_entry(entry, Status::Pending).is_ok());
+    }
+    let tx = build_tx_with_dep(vec![(&Byte32::zero(), 2)], vec![(&light_dep, 0)], 1);
+    let entry = TxEntry::dummy_resolve(tx, MOCK_CYCLES, Capacity::shannons(100), MOCK_SIZE);
+    assert!(pool.add_entry(entry, Status::Pending).is_ok());
+
+    // consuming the cell referenced by only 1 tx requires no fee bump
+    let tx = build_tx(vec![(&light_dep, 0)], 1);
+    let entry = TxEntry::dummy_resolve(tx, MOCK_CYCLES, MOCK_FEE, MOCK_SIZE);
+    assert!(pool.add_entry(entry, Status::Pending).is_ok());
+
+    // consuming the heavily referenced cell must pay more than the 2 txs invalidated
+    let tx = build_tx(vec![(&heavy_dep, 0)], 1);
+    let entry = TxEntry::dummy_resolve(tx.clone(), MOCK_CYCLES, Capacity::shannons(200), 0);
+    assert!(matches!(
+        pool.add_entry(entry, Status::Pending),
+        Err(Reject::LowCellDepChurnFee(2, 200, 200))
+    ));
+    let entry = TxEntry::dummy_resolve(tx, MOCK_CYCLES, Capacity::shannons(201), 0);
+    assert!(pool.add_entry(entry, Status::Pending).is_ok());
+}
@@ -0,0 +1,100 @@
+use crate::component::pool_map::FeeDelta;
+use crate::component::tests::util::build_tx;
+use crate::persisted::{
+    load_from_file, next_generation, save_into_file, PersistedData, PersistedEntry,
+};
+use ckb_types::{
+    h256,
+    packed::{ProposalShortId, TransactionVec},
+    prelude::*,
+};
+
+#[test]
+fn test_persisted_data_roundtrip() {
+    let tx1 = build_tx(vec![(&h256!("0x1").pack(), 0)], 2);
+    let tx2 = build_tx(vec![(&tx1.hash(), 0)], 1);
+    let data = PersistedData {
+        entries: vec![
+            PersistedEntry {
+                transaction: tx1.clone(),
+                timestamp: 100,
+                fee_delta: -50,
+                local: true,
+            },
+            PersistedEntry {
+                transaction: tx2,
+                timestamp: 200,
+                fee_delta: 0,
+                local: false,
+            },
+        ],
+        fee_deltas: vec![
+            (
+                ProposalShortId::new([1u8; 10]),
+                FeeDelta {
+                    value: 30,
+                    timestamp: 300,
+                },
+            ),
+            (
+                ProposalShortId::new([2u8; 10]),
+                FeeDelta {
+                    value: -20,
+                    timestamp: 400,
+                },
+            ),
+        ]
+        .into_iter()
+        .collect(),
+    };
+
+    let buffer = data.encode();
+    assert_eq!(PersistedData::decode(&buffer).unwrap(), data);
+    assert!(PersistedData::decode(&buffer[..buffer.len() - 1]).is_err());
+    assert!(PersistedData::decode(&buffer[..10]).is_err());
+    // a huge entries count doesn't allocate beyond the buffer
+    let mut huge_count = buffer.clone();
+    huge_count[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(PersistedData::decode(&huge_count).is_err());
+
+    let tmp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let path = tmp_dir.path().join("tx_pool_persisted_data");
+    assert_eq!(load_from_file(&path).unwrap(), PersistedData::default());
+    let stale_generation = next_generation();
+    assert!(save_into_file(&path, &data, next_generation()).unwrap());
+    assert_eq!(load_from_file(&path).unwrap(), data);
+
+    // the stale snapshot doesn't overwrite the newer one
+    assert!(!save_into_file(&path, &PersistedData::default(), stale_generation).unwrap());
+    assert_eq!(load_from_file(&path).unwrap(), data);
+}
+
+#[test]
+fn test_load_legacy_persisted_data() {
+    let tx1 = build_tx(vec![(&h256!("0x1").pack(), 0)], 2);
+    let tx2 = build_tx(vec![(&tx1.hash(), 0)], 1);
+
+    let tmp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let path = tmp_dir.path().join("tx_pool_persisted_data");
+    let txs = TransactionVec::new_builder()
+        .push(tx1.data())
+        .push(tx2.data())
+        .build();
+    std::fs::write(path.with_extension("v1"), txs.as_slice()).unwrap();
+
+    let data = load_from_file(&path).unwrap();
+    let txs: Vec<_> = data
+        .entries
+        .iter()
+        .map(|entry| entry.transaction.clone())
+        .collect();
+    assert_eq!(txs, vec![tx1, tx2]);
+    assert!(data.entries.iter().all(|entry| !entry.local));
+    assert!(data.fee_deltas.is_empty());
+
+    // the legacy file is kept until the data is saved in the current version
+    assert!(path.with_extension("v1").exists());
+    assert!(save_into_file(&path, &data, next_generation()).unwrap());
+    assert!(!path.with_extension("v1").exists());
+    assert_eq!(load_from_file(&path).unwrap(), data);
+}
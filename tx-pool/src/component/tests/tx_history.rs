@@ -0,0 +1,137 @@
+use ckb_hash::blake2b_256;
+use ckb_jsonrpc_types::TransactionEvent;
+use ckb_types::{
+    core::{
+        tx_pool::{Reject, TxEvent},
+        Capacity,
+    },
+    packed::Byte32,
+    prelude::*,
+};
+
+use crate::component::tx_history::{TxHistory, MAX_EVENTS_PER_TX};
+
+#[test]
+fn test_basic() {
+    let tmp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let tx_history = TxHistory::build(tmp_dir.path(), 2, 100, -1).unwrap();
+
+    let hash = Byte32::new(blake2b_256(0u64.to_le_bytes()));
+    tx_history.record(&hash, TxEvent::Received(None));
+    tx_history.record(&hash, TxEvent::Verified(100, Capacity::shannons(10)));
+    tx_history.record(&hash, TxEvent::Pending);
+    tx_history.record(&hash, TxEvent::Committed(1, hash.clone()));
+    tx_history.record(&hash, TxEvent::Rejected(Reject::Expiry(0)));
+    tx_history.flush();
+    let events: Vec<_> = tx_history
+        .get(&hash)
+        .unwrap()
+        .into_iter()
+        .map(|event| event.event)
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            TransactionEvent::Received { peer: None },
+            TransactionEvent::Verified {
+                cycles: 100.into(),
+                fee: 10.into(),
+            },
+            TransactionEvent::Pending,
+            TransactionEvent::Committed {
+                block_number: 1.into(),
+                block_hash: hash.unpack(),
+            },
+            TransactionEvent::Rejected {
+                reason: Reject::Expiry(0).into()
+            },
+        ]
+    );
+
+    // the unknown tx is not recorded by `record_if_known`
+    let unknown = Byte32::new(blake2b_256(1u64.to_le_bytes()));
+    tx_history.record_if_known(&unknown, TxEvent::Proposed);
+    tx_history.flush();
+    assert!(tx_history.get(&unknown).unwrap().is_empty());
+    tx_history.record_if_known(&hash, TxEvent::Proposed);
+    tx_history.flush();
+    assert_eq!(tx_history.get(&hash).unwrap().len(), 6);
+
+    // the events following the first one of an unknown tx in the same batch are recorded
+    let received = Byte32::new(blake2b_256(2u64.to_le_bytes()));
+    tx_history.record(&received, TxEvent::Received(Some(1)));
+    tx_history.record_if_known(&received, TxEvent::Pending);
+    tx_history.flush();
+    let events: Vec<_> = tx_history
+        .get(&received)
+        .unwrap()
+        .into_iter()
+        .map(|event| event.event)
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            TransactionEvent::Received {
+                peer: Some(1.into())
+            },
+            TransactionEvent::Pending,
+        ]
+    );
+
+    // the earliest events are dropped
+    for _ in 0..MAX_EVENTS_PER_TX {
+        tx_history.record(&hash, TxEvent::Gap);
+    }
+    tx_history.flush();
+    let events = tx_history.get(&hash).unwrap();
+    assert_eq!(events.len(), MAX_EVENTS_PER_TX);
+    assert!(events.iter().all(|e| e.event == TransactionEvent::Gap));
+}
+
+#[test]
+fn test_count_limit() {
+    let tmp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let tx_history = TxHistory::build(tmp_dir.path(), 2, 100, -1).unwrap();
+
+    for i in 0..160u64 {
+        let hash = Byte32::new(blake2b_256(i.to_le_bytes()));
+        tx_history.record(&hash, TxEvent::Pending);
+    }
+    tx_history.flush();
+    assert!(tx_history.total_keys_num() <= tx_history.count_limit());
+}
+
+#[test]
+fn test_dropped_events() {
+    let tmp_dir = tempfile::Builder::new().tempdir().unwrap();
+    let tx_history = TxHistory::build(tmp_dir.path(), 2, 100, -1).unwrap();
+
+    let hash = Byte32::new(blake2b_256(0u64.to_le_bytes()));
+    tx_history.record(&hash, TxEvent::Received(None));
+    tx_history.flush();
+    // the writer is busy
+    tx_history.drop_event(&hash, true);
+    tx_history.drop_event(&hash, false);
+    let unknown = Byte32::new(blake2b_256(1u64.to_le_bytes()));
+    tx_history.drop_event(&unknown, false);
+    assert_eq!(tx_history.dropped_events(), 3);
+
+    tx_history.record(&hash, TxEvent::Pending);
+    tx_history.flush();
+    let events: Vec<_> = tx_history
+        .get(&hash)
+        .unwrap()
+        .into_iter()
+        .map(|event| event.event)
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            TransactionEvent::Received { peer: None },
+            TransactionEvent::Pending,
+            TransactionEvent::Dropped { count: 2.into() },
+        ]
+    );
+    // the dropped event of `record_if_known` does not create the history
+    assert!(tx_history.get(&unknown).unwrap().is_empty());
+}
@@ -0,0 +1,490 @@
+use ckb_channel::{bounded, Receiver, Sender, TrySendError};
+use ckb_db::DBWithTTL;
+use ckb_error::AnyError;
+use ckb_jsonrpc_types::{PoolTransactionReject, TransactionEvent, TransactionHistoryEvent};
+use ckb_logger::{debug, error};
+use ckb_types::{core::tx_pool::TxEvent, packed::Byte32, prelude::*};
+use ckb_util::Mutex;
+use lru::LruCache;
+use rand::distributions::Uniform;
+use rand::{thread_rng, Rng};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+const DEFAULT_SHARDS: u32 = 5;
+// The max count of the events kept for a tx, the earliest ones are dropped first
+pub(crate) const MAX_EVENTS_PER_TX: usize = 64;
+// The capacity of the channel to the writer, the events are dropped when it's full
+const CHANNEL_SIZE: usize = 65536;
+// The max count of the events written in one batch
+const MAX_BATCH_SIZE: usize = 4096;
+// The max count of the txs whose dropped events are waiting to be marked in the history
+const MAX_DROPPED_TXS: usize = 65536;
+
+// An encoded event: the timestamp, the tag and the payload of the tag
+const TIMESTAMP_SIZE: usize = 8;
+const TAG_RECEIVED: u8 = 0;
+const TAG_VERIFIED: u8 = 1;
+const TAG_PENDING: u8 = 2;
+const TAG_GAP: u8 = 3;
+const TAG_PROPOSED: u8 = 4;
+const TAG_COMMITTED: u8 = 5;
+const TAG_REJECTED: u8 = 6;
+const TAG_REMOVED: u8 = 7;
+const TAG_DROPPED: u8 = 8;
+
+/// The lifecycle events of the txs, which are kept after the txs leave the pool.
+///
+/// The events are encoded and sent to a background writer, which appends them to the
+/// database in batches, so recording never touches the database on the caller's thread.
+///
+/// The events are dropped when the writer is too busy, then the writer appends a `dropped`
+/// event to the history of the tx, which marks the history as incomplete.
+#[derive(Clone)]
+pub struct TxHistory {
+    shard_num: u32,
+    sender: Sender<Message>,
+    inner: Arc<Mutex<Inner>>,
+    dropped: Arc<Dropped>,
+}
+
+// The events dropped by the busy writer
+struct Dropped {
+    // the total count of the dropped events
+    count: AtomicU64,
+    // the txs whose dropped events are not marked in the history yet
+    txs: Mutex<LruCache<Byte32, DroppedEvents>>,
+}
+
+struct DroppedEvents {
+    // the time of the first dropped event
+    timestamp: u64,
+    count: u64,
+    // whether any of the dropped events creates the history
+    create: bool,
+}
+
+struct Inner {
+    db: DBWithTTL,
+    ttl: i32,
+    count_limit: u64,
+    total_keys_num: u64,
+}
+
+enum Message {
+    Record {
+        hash: Byte32,
+        event: Vec<u8>,
+        create: bool,
+    },
+    // only sent by the tests to wait for the writer
+    #[allow(dead_code)]
+    Flush(Sender<()>),
+}
+
+impl TxHistory {
+    pub fn new<P>(path: P, count_limit: u64, ttl: i32) -> Result<TxHistory, AnyError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::build(path, DEFAULT_SHARDS, count_limit, ttl)
+    }
+
+    pub(crate) fn build<P>(
+        path: P,
+        shard_num: u32,
+        count_limit: u64,
+        ttl: i32,
+    ) -> Result<TxHistory, AnyError>
+    where
+        P: AsRef<Path>,
+    {
+        let cf_names: Vec<_> = (0..shard_num).map(|c| c.to_string()).collect();
+        let db = DBWithTTL::open_cf(path, cf_names.clone(), ttl)?;
+        let estimate_keys_num = cf_names
+            .iter()
+            .map(|cf| db.estimate_num_keys_cf(cf))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let total_keys_num = estimate_keys_num.iter().map(|num| num.unwrap_or(0)).sum();
+
+        let inner = Arc::new(Mutex::new(Inner {
+            db,
+            ttl,
+            count_limit,
+            total_keys_num,
+        }));
+        let dropped = Arc::new(Dropped {
+            count: AtomicU64::new(0),
+            txs: Mutex::new(LruCache::new(MAX_DROPPED_TXS)),
+        });
+        let (sender, receiver) = bounded(CHANNEL_SIZE);
+        let writer = Writer {
+            shard_num,
+            inner: Arc::clone(&inner),
+            dropped: Arc::clone(&dropped),
+        };
+        thread::Builder::new()
+            .name("TxHistoryWriter".to_string())
+            .spawn(move || writer.run(receiver))?;
+
+        Ok(TxHistory {
+            shard_num,
+            sender,
+            inner,
+            dropped,
+        })
+    }
+
+    /// Appends the event to the history of the tx.
+    pub fn record(&self, hash: &Byte32, event: TxEvent) {
+        self.send(hash, event, true)
+    }
+
+    /// Appends the event only if the tx has a history already.
+    pub fn record_if_known(&self, hash: &Byte32, event: TxEvent) {
+        self.send(hash, event, false)
+    }
+
+    /// Returns the events of the tx in order, it's empty if the tx is unknown.
+    ///
+    /// The events still queued for the writer are not included.
+    pub fn get(&self, hash: &Byte32) -> Result<Vec<TransactionHistoryEvent>, AnyError> {
+        let slice = hash.as_slice();
+        let shard = get_shard(self.shard_num, slice).to_string();
+        let inner = self.inner.lock();
+        let events = match inner.db.get_pinned(&shard, slice)? {
+            Some(bytes) => decode_events(&bytes)?,
+            None => Vec::new(),
+        };
+        Ok(events)
+    }
+
+    /// Returns the total count of the events dropped because the writer is busy or stopped.
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped.count.load(Ordering::Relaxed)
+    }
+
+    /// Blocks until all the events recorded before are written.
+    #[cfg(test)]
+    pub(crate) fn flush(&self) {
+        let (sender, receiver) = bounded(1);
+        if self.sender.send(Message::Flush(sender)).is_ok() {
+            let _ = receiver.recv();
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn total_keys_num(&self) -> u64 {
+        self.inner.lock().total_keys_num
+    }
+
+    #[cfg(test)]
+    pub(crate) fn count_limit(&self) -> u64 {
+        self.inner.lock().count_limit
+    }
+
+    fn send(&self, hash: &Byte32, event: TxEvent, create: bool) {
+        let message = Message::Record {
+            hash: hash.clone(),
+            event: encode_event(ckb_systemtime::unix_time_as_millis(), event),
+            create,
+        };
+        match self.sender.try_send(message) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                debug!(
+                    "The tx history writer is busy, drop the event of tx {}",
+                    hash
+                );
+                self.drop_event(hash, create);
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                error!(
+                    "The tx history writer is stopped, drop the event of tx {}",
+                    hash
+                );
+                self.dropped.count.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    // Counts the dropped event and remembers the tx to mark its history as incomplete
+    pub(crate) fn drop_event(&self, hash: &Byte32, create: bool) {
+        self.dropped.count.fetch_add(1, Ordering::Relaxed);
+        if let Some(metrics) = ckb_metrics::handle() {
+            metrics.ckb_tx_pool_history_dropped.inc();
+        }
+        let mut txs = self.dropped.txs.lock();
+        if let Some(events) = txs.get_mut(hash) {
+            events.count += 1;
+            events.create |= create;
+        } else {
+            txs.put(
+                hash.clone(),
+                DroppedEvents {
+                    timestamp: ckb_systemtime::unix_time_as_millis(),
+                    count: 1,
+                    create,
+                },
+            );
+        }
+    }
+}
+
+struct Writer {
+    shard_num: u32,
+    inner: Arc<Mutex<Inner>>,
+    dropped: Arc<Dropped>,
+}
+
+impl Writer {
+    fn run(self, receiver: Receiver<Message>) {
+        while let Ok(message) = receiver.recv() {
+            let mut records: HashMap<Byte32, Vec<(Vec<u8>, bool)>> = HashMap::new();
+            let mut flushes = Vec::new();
+            for message in std::iter::once(message).chain(receiver.try_iter().take(MAX_BATCH_SIZE))
+            {
+                match message {
+                    Message::Record {
+                        hash,
+                        event,
+                        create,
+                    } => records.entry(hash).or_default().push((event, create)),
+                    Message::Flush(sender) => flushes.push(sender),
+                }
+            }
+            // the events are dropped after the ones queued in the channel
+            let dropped: Vec<_> = {
+                let mut txs = self.dropped.txs.lock();
+                let dropped = txs
+                    .iter()
+                    .map(|(hash, events)| {
+                        let event = encode_dropped(events.timestamp, events.count);
+                        (hash.clone(), event, events.create)
+                    })
+                    .collect();
+                txs.clear();
+                dropped
+            };
+            for (hash, event, create) in dropped {
+                records.entry(hash).or_default().push((event, create));
+            }
+            if !records.is_empty() {
+                if let Err(e) = self.write(records) {
+                    error!("Failed to write the tx history {}", e);
+                }
+            }
+            for sender in flushes {
+                let _ = sender.send(());
+            }
+        }
+    }
+
+    fn write(&self, records: HashMap<Byte32, Vec<(Vec<u8>, bool)>>) -> Result<(), AnyError> {
+        let mut inner = self.inner.lock();
+        let mut values = Vec::with_capacity(records.len());
+        let mut new_keys_num = 0u64;
+        for (hash, events) in records {
+            let hash_slice = hash.as_slice();
+            let shard = get_shard(self.shard_num, hash_slice).to_string();
+            let existing = inner.db.get_pinned(&shard, hash_slice)?;
+            let is_new = existing.is_none();
+            let mut value = existing.map(|bytes| bytes.to_vec()).unwrap_or_default();
+            let mut appended = false;
+            for (event, create) in events {
+                if create || !is_new || appended {
+                    value.extend_from_slice(&event);
+                    appended = true;
+                }
+            }
+            if appended {
+                truncate_events(&mut value);
+                if is_new {
+                    new_keys_num += 1;
+                }
+                values.push((shard, hash, value));
+            }
+        }
+        inner.db.put_batch(
+            values
+                .iter()
+                .map(|(shard, hash, value)| (shard.as_str(), hash.as_slice(), value)),
+        )?;
+
+        if new_keys_num > 0 {
+            match inner.total_keys_num.checked_add(new_keys_num) {
+                Some(total_keys_num) if total_keys_num <= inner.count_limit => {
+                    inner.total_keys_num = total_keys_num;
+                }
+                // overflow occurred or the limit is exceeded, try shrink
+                _ => self.shrink(&mut inner)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn shrink(&self, inner: &mut Inner) -> Result<(), AnyError> {
+        let mut rng = thread_rng();
+        let shard = rng.sample(Uniform::new(0, self.shard_num)).to_string();
+        let ttl = inner.ttl;
+        inner.db.drop_cf(&shard)?;
+        inner.db.create_cf_with_ttl(&shard, ttl)?;
+
+        let estimate_keys_num = (0..self.shard_num)
+            .map(|num| inner.db.estimate_num_keys_cf(&num.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        inner.total_keys_num = estimate_keys_num.iter().map(|num| num.unwrap_or(0)).sum();
+        Ok(())
+    }
+}
+
+fn get_shard(shard_num: u32, hash: &[u8]) -> u32 {
+    let mut low_u32 = [0u8; 4];
+    low_u32.copy_from_slice(&hash[0..4]);
+    u32::from_le_bytes(low_u32) % shard_num
+}
+
+fn encode_event(timestamp: u64, event: TxEvent) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(TIMESTAMP_SIZE + 1 + 40);
+    bytes.extend_from_slice(&timestamp.to_le_bytes());
+    match event {
+        TxEvent::Received(peer) => {
+            bytes.push(TAG_RECEIVED);
+            let peer = peer.map(|peer| peer as u64).unwrap_or(u64::MAX);
+            bytes.extend_from_slice(&peer.to_le_bytes());
+        }
+        TxEvent::Verified(cycles, fee) => {
+            bytes.push(TAG_VERIFIED);
+            bytes.extend_from_slice(&cycles.to_le_bytes());
+            bytes.extend_from_slice(&fee.as_u64().to_le_bytes());
+        }
+        TxEvent::Pending => bytes.push(TAG_PENDING),
+        TxEvent::Gap => bytes.push(TAG_GAP),
+        TxEvent::Proposed => bytes.push(TAG_PROPOSED),
+        TxEvent::Committed(number, hash) => {
+            bytes.push(TAG_COMMITTED);
+            bytes.extend_from_slice(&number.to_le_bytes());
+            bytes.extend_from_slice(hash.as_slice());
+        }
+        TxEvent::Rejected(reject) => {
+            bytes.push(TAG_REJECTED);
+            let reason =
+                serde_json::to_vec(&PoolTransactionReject::from(reject)).expect("serialize reject");
+            bytes.extend_from_slice(&(reason.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&reason);
+        }
+        TxEvent::Removed => bytes.push(TAG_REMOVED),
+    }
+    bytes
+}
+
+fn encode_dropped(timestamp: u64, count: u64) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(TIMESTAMP_SIZE + 1 + 8);
+    bytes.extend_from_slice(&timestamp.to_le_bytes());
+    bytes.push(TAG_DROPPED);
+    bytes.extend_from_slice(&count.to_le_bytes());
+    bytes
+}
+
+// Returns the length of the encoded event at the start of the bytes
+fn event_len(bytes: &[u8]) -> Result<usize, AnyError> {
+    let payload_len = match bytes.get(TIMESTAMP_SIZE) {
+        Some(&TAG_RECEIVED) | Some(&TAG_DROPPED) => 8,
+        Some(&TAG_VERIFIED) => 16,
+        Some(&TAG_PENDING) | Some(&TAG_GAP) | Some(&TAG_PROPOSED) | Some(&TAG_REMOVED) => 0,
+        Some(&TAG_COMMITTED) => 8 + 32,
+        Some(&TAG_REJECTED) => {
+            let start = TIMESTAMP_SIZE + 1;
+            let len = bytes
+                .get(start..start + 4)
+                .ok_or_else(|| corrupted("truncated reject reason"))?;
+            4 + u32::from_le_bytes(len.try_into().expect("checked length")) as usize
+        }
+        Some(tag) => return Err(corrupted(format!("unknown event tag {tag}"))),
+        None => return Err(corrupted("truncated event")),
+    };
+    let len = TIMESTAMP_SIZE + 1 + payload_len;
+    if bytes.len() < len {
+        return Err(corrupted("truncated event"));
+    }
+    Ok(len)
+}
+
+// Drops the earliest events beyond `MAX_EVENTS_PER_TX`
+fn truncate_events(bytes: &mut Vec<u8>) {
+    let mut offsets = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        match event_len(&bytes[offset..]) {
+            Ok(len) => {
+                offsets.push(offset);
+                offset += len;
+            }
+            // keep the bytes as they are, reading them reports the corruption
+            Err(_) => return,
+        }
+    }
+    if offsets.len() > MAX_EVENTS_PER_TX {
+        bytes.drain(..offsets[offsets.len() - MAX_EVENTS_PER_TX]);
+    }
+}
+
+fn decode_events(mut bytes: &[u8]) -> Result<Vec<TransactionHistoryEvent>, AnyError> {
+    let mut events = Vec::new();
+    while !bytes.is_empty() {
+        let len = event_len(bytes)?;
+        let (timestamp, rest) = bytes[..len].split_at(TIMESTAMP_SIZE);
+        let timestamp = u64::from_le_bytes(timestamp.try_into().expect("checked length"));
+        let payload = &rest[1..];
+        let read_u64 = |start: usize| {
+            u64::from_le_bytes(
+                payload[start..start + 8]
+                    .try_into()
+                    .expect("checked length"),
+            )
+        };
+        let event = match rest[0] {
+            TAG_RECEIVED => TransactionEvent::Received {
+                peer: Some(read_u64(0))
+                    .filter(|peer| *peer != u64::MAX)
+                    .map(Into::into),
+            },
+            TAG_VERIFIED => TransactionEvent::Verified {
+                cycles: read_u64(0).into(),
+                fee: read_u64(8).into(),
+            },
+            TAG_PENDING => TransactionEvent::Pending,
+            TAG_GAP => TransactionEvent::Gap,
+            TAG_PROPOSED => TransactionEvent::Proposed,
+            TAG_COMMITTED => TransactionEvent::Committed {
+                block_number: read_u64(0).into(),
+                block_hash: Byte32::from_slice(&payload[8..])?.unpack(),
+            },
+            TAG_REJECTED => TransactionEvent::Rejected {
+                reason: serde_json::from_slice(&payload[4..])?,
+            },
+            TAG_REMOVED => TransactionEvent::Removed,
+            TAG_DROPPED => TransactionEvent::Dropped {
+                count: read_u64(0).into(),
+            },
+            tag => unreachable!("checked event tag {}", tag),
+        };
+        events.push(TransactionHistoryEvent {
+            timestamp: timestamp.into(),
+            event,
+        });
+        bytes = &bytes[len..];
+    }
+    Ok(events)
+}
+
+fn corrupted<S: ToString>(reason: S) -> AnyError {
+    ckb_error::InternalErrorKind::Database
+        .other(format!("corrupted tx history: {}", reason.to_string()))
+        .into()
+}
@@ -13,8 +13,9 @@ mod process;
 pub mod service;
 mod util;
 
-pub use ckb_jsonrpc_types::BlockTemplate;
+pub use ckb_jsonrpc_types::{BlockTemplate, BlockTemplateParams};
 pub use component::entry::TxEntry;
+pub use persisted::PersistedEntry;
 pub use pool::TxPool;
 pub use process::PlugTarget;
 pub use service::{TxPoolController, TxPoolServiceBuilder};
@@ -1,91 +1,316 @@
+use crate::component::pool_map::{FeeDelta, Status};
 use crate::TxPool;
 use ckb_error::{AnyError, OtherError};
 use ckb_types::{
     core::TransactionView,
-    packed::{TransactionVec, TransactionVecReader},
+    packed::{ProposalShortId, Transaction, TransactionReader, TransactionVecReader},
     prelude::*,
 };
 use std::{
-    fs::OpenOptions,
-    io::{Read as _, Write as _},
+    collections::{BTreeMap, HashMap},
+    fs::{self, OpenOptions},
+    io::Write as _,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
 };
 
 /// The version of the persisted tx-pool data.
-pub(crate) const VERSION: u32 = 1;
+pub(crate) const VERSION: u32 = 2;
 
-impl TxPool {
-    pub(crate) fn load_from_file(&self) -> Result<Vec<TransactionView>, AnyError> {
-        let mut persisted_data_file = self.config.persisted_data.clone();
-        persisted_data_file.set_extension(format!("v{VERSION}"));
-
-        if persisted_data_file.exists() {
-            let mut file = OpenOptions::new()
-                .read(true)
-                .open(&persisted_data_file)
-                .map_err(|err| {
-                    let errmsg = format!(
-                        "Failed to open the tx-pool persisted data file [{persisted_data_file:?}], cause: {err}"
-                    );
-                    OtherError::new(errmsg)
-                })?;
-            let mut buffer = Vec::new();
-            file.read_to_end(&mut buffer).map_err(|err| {
-                let errmsg = format!(
-                    "Failed to read the tx-pool persisted data file [{persisted_data_file:?}], cause: {err}"
-                );
-                OtherError::new(errmsg)
-            })?;
-
-            let persisted_data = TransactionVecReader::from_slice(&buffer)
-                .map_err(|err| {
-                    let errmsg = format!(
-                        "The tx-pool persisted data file [{persisted_data_file:?}] is broken, cause: {err}"
-                    );
-                    OtherError::new(errmsg)
-                })?
-                .to_entity();
-
-            Ok(persisted_data
-                .into_iter()
-                .map(|tx| tx.into_view())
-                .collect())
-        } else {
-            Ok(Vec::new())
+// The version of the persisted data which only contains the txs.
+const LEGACY_VERSION: u32 = 1;
+
+// proposal short id (10 bytes) + fee delta (8 bytes) + timestamp (8 bytes)
+const FEE_DELTA_RECORD_SIZE: usize = 26;
+
+// Serializes the saves, e.g. the periodic save and the save on shutdown, and holds the generation
+// of the last saved data by the path.
+static SAVE_LOCK: Mutex<BTreeMap<PathBuf, u64>> = Mutex::new(BTreeMap::new());
+
+// The generation of the data snapshots, a save is skipped if a newer snapshot has been saved.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+// timestamp (8 bytes) + fee delta (8 bytes) + local (1 byte) + tx size (4 bytes)
+const ENTRY_HEADER_SIZE: usize = 21;
+
+/// A tx-pool entry with the metadata to persist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PersistedEntry {
+    /// The transaction
+    pub transaction: TransactionView,
+    /// The unix timestamp when entering the tx-pool, unit: Millisecond
+    pub timestamp: u64,
+    /// The virtual fee delta set by `prioritise_transaction`
+    pub fee_delta: i64,
+    /// Whether the transaction is submitted locally
+    pub local: bool,
+}
+
+/// The persisted tx-pool data.
+///
+/// The data is encoded as below, all the numbers are in little endian:
+///
+/// - entries count (4 bytes)
+/// - the entries, each is timestamp (8 bytes), fee delta (8 bytes), local (1 byte), tx size
+///   (4 bytes) and the molecule encoded tx, the txs are verified again when loaded
+/// - the fee deltas of the txs not in the pool till the end, each is proposal short id
+///   (10 bytes), fee delta (8 bytes) and the timestamp when it's set (8 bytes)
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct PersistedData {
+    /// The entries sorted by the dependencies, a tx is always after its ancestors
+    pub(crate) entries: Vec<PersistedEntry>,
+    /// The fee deltas of the txs not in the pool
+    pub(crate) fee_deltas: HashMap<ProposalShortId, FeeDelta>,
+}
+
+impl PersistedData {
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for entry in &self.entries {
+            let tx = entry.transaction.data();
+            buffer.extend_from_slice(&entry.timestamp.to_le_bytes());
+            buffer.extend_from_slice(&entry.fee_delta.to_le_bytes());
+            buffer.push(u8::from(entry.local));
+            buffer.extend_from_slice(&(tx.as_slice().len() as u32).to_le_bytes());
+            buffer.extend_from_slice(tx.as_slice());
+        }
+        for (id, fee_delta) in &self.fee_deltas {
+            buffer.extend_from_slice(id.as_slice());
+            buffer.extend_from_slice(&fee_delta.value.to_le_bytes());
+            buffer.extend_from_slice(&fee_delta.timestamp.to_le_bytes());
         }
+        buffer
     }
 
-    pub(crate) fn save_into_file(&mut self) -> Result<(), AnyError> {
-        let mut persisted_data_file = self.config.persisted_data.clone();
-        persisted_data_file.set_extension(format!("v{VERSION}"));
+    pub(crate) fn decode(buffer: &[u8]) -> Result<Self, String> {
+        if buffer.len() < 4 {
+            return Err("the entries count is missing".to_owned());
+        }
+        let (count, mut rest) = buffer.split_at(4);
+        let count = u32::from_le_bytes(count.try_into().expect("checked length"));
+
+        // the count is untrusted, every entry takes at least the header
+        let mut entries = Vec::with_capacity((count as usize).min(rest.len() / ENTRY_HEADER_SIZE));
+        for _ in 0..count {
+            if rest.len() < ENTRY_HEADER_SIZE {
+                return Err("the entry is truncated".to_owned());
+            }
+            let (header, body) = rest.split_at(ENTRY_HEADER_SIZE);
+            let tx_size = read_u32(&header[17..]) as usize;
+            if body.len() < tx_size {
+                return Err("the transaction is truncated".to_owned());
+            }
+            let (tx, body) = body.split_at(tx_size);
+            let transaction = TransactionReader::from_slice(tx)
+                .map_err(|err| err.to_string())?
+                .to_entity()
+                .into_view();
+            entries.push(PersistedEntry {
+                transaction,
+                timestamp: read_u64(&header[0..]),
+                fee_delta: read_u64(&header[8..]) as i64,
+                local: header[16] != 0,
+            });
+            rest = body;
+        }
+
+        let fee_deltas =
+            decode_fee_deltas(rest).ok_or_else(|| "the fee deltas are truncated".to_owned())?;
+        Ok(PersistedData {
+            entries,
+            fee_deltas,
+        })
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes[..4].try_into().expect("checked length"))
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().expect("checked length"))
+}
+
+fn decode_fee_deltas(buffer: &[u8]) -> Option<HashMap<ProposalShortId, FeeDelta>> {
+    if buffer.len() % FEE_DELTA_RECORD_SIZE != 0 {
+        return None;
+    }
+    Some(
+        buffer
+            .chunks_exact(FEE_DELTA_RECORD_SIZE)
+            .map(|record| {
+                let (id, fee_delta) = record.split_at(10);
+                (
+                    ProposalShortId::from_slice(id).expect("checked length"),
+                    FeeDelta {
+                        value: read_u64(fee_delta) as i64,
+                        timestamp: read_u64(&fee_delta[8..]),
+                    },
+                )
+            })
+            .collect(),
+    )
+}
 
-        let mut file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&persisted_data_file)
+fn read_file(path: &Path) -> Result<Vec<u8>, AnyError> {
+    fs::read(path).map_err(|err| {
+        let errmsg =
+            format!("Failed to read the tx-pool persisted data file [{path:?}], cause: {err}");
+        OtherError::new(errmsg).into()
+    })
+}
+
+fn versioned_file(path: &Path, version: u32) -> PathBuf {
+    let mut file = path.to_path_buf();
+    file.set_extension(format!("v{version}"));
+    file
+}
+
+/// Loads the persisted data, the legacy file is loaded if the data of the current version doesn't
+/// exist. The legacy file is removed once the data of the current version is saved.
+pub(crate) fn load_from_file(path: &Path) -> Result<PersistedData, AnyError> {
+    let persisted_data_file = versioned_file(path, VERSION);
+    if persisted_data_file.exists() {
+        let buffer = read_file(&persisted_data_file)?;
+        return PersistedData::decode(&buffer).map_err(|err| {
+            let errmsg = format!(
+                "The tx-pool persisted data file [{persisted_data_file:?}] is broken, cause: {err}"
+            );
+            OtherError::new(errmsg).into()
+        });
+    }
+    load_legacy_from_file(path)
+}
+
+fn load_legacy_from_file(path: &Path) -> Result<PersistedData, AnyError> {
+    let mut data = PersistedData::default();
+    let persisted_data_file = versioned_file(path, LEGACY_VERSION);
+    if persisted_data_file.exists() {
+        let buffer = read_file(&persisted_data_file)?;
+        let txs = TransactionVecReader::from_slice(&buffer)
             .map_err(|err| {
                 let errmsg = format!(
-                    "Failed to open the tx-pool persisted data file [{persisted_data_file:?}], cause: {err}"
+                    "The tx-pool persisted data file [{persisted_data_file:?}] is broken, cause: {err}"
                 );
                 OtherError::new(errmsg)
-            })?;
+            })?
+            .to_entity();
+        let now = ckb_systemtime::unix_time_as_millis();
+        data.entries = txs
+            .into_iter()
+            .map(|tx: Transaction| PersistedEntry {
+                transaction: tx.into_view(),
+                timestamp: now,
+                fee_delta: 0,
+                local: false,
+            })
+            .collect();
+    }
+    Ok(data)
+}
 
-        let txs = TransactionVec::new_builder()
-            .extend(self.drain_all_transactions().iter().map(|tx| tx.data()))
-            .build();
+/// Returns the generation of a data snapshot, it must be called while holding the lock of the
+/// tx-pool which the snapshot is taken from.
+pub(crate) fn next_generation() -> u64 {
+    GENERATION.fetch_add(1, Ordering::SeqCst) + 1
+}
 
-        file.write_all(txs.as_slice()).map_err(|err| {
-            let errmsg = format!(
-                "Failed to write the tx-pool persisted data into file [{persisted_data_file:?}], cause: {err}"
-            );
-            OtherError::new(errmsg)
-        })?;
-        file.sync_all().map_err(|err| {
+/// Saves the persisted data into a temporary file first, then renames it, so the previous data
+/// is kept if the node crashes while saving. The legacy file is removed after the data is saved.
+///
+/// Returns false and skips saving if the data of a newer generation has been saved.
+pub(crate) fn save_into_file(
+    path: &Path,
+    data: &PersistedData,
+    generation: u64,
+) -> Result<bool, AnyError> {
+    let mut saved_generations = SAVE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    if saved_generations
+        .get(path)
+        .map_or(false, |saved| generation < *saved)
+    {
+        return Ok(false);
+    }
+    let persisted_data_file = versioned_file(path, VERSION);
+    let mut tmp_file = persisted_data_file.clone();
+    tmp_file.set_extension(format!("v{VERSION}.tmp"));
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(&tmp_file)
+        .map_err(|err| {
             let errmsg = format!(
-                "Failed to sync the tx-pool persisted data file [{persisted_data_file:?}], cause: {err}"
+                "Failed to open the tx-pool persisted data file [{tmp_file:?}], cause: {err}"
             );
             OtherError::new(errmsg)
         })?;
-        Ok(())
+    file.write_all(&data.encode()).map_err(|err| {
+        let errmsg = format!(
+            "Failed to write the tx-pool persisted data into file [{tmp_file:?}], cause: {err}"
+        );
+        OtherError::new(errmsg)
+    })?;
+    file.sync_all().map_err(|err| {
+        let errmsg =
+            format!("Failed to sync the tx-pool persisted data file [{tmp_file:?}], cause: {err}");
+        OtherError::new(errmsg)
+    })?;
+    fs::rename(&tmp_file, &persisted_data_file).map_err(|err| {
+        let errmsg = format!(
+            "Failed to rename the tx-pool persisted data file [{tmp_file:?}] to [{persisted_data_file:?}], cause: {err}"
+        );
+        OtherError::new(errmsg)
+    })?;
+    saved_generations.insert(path.to_path_buf(), generation);
+
+    let legacy_file = versioned_file(path, LEGACY_VERSION);
+    if legacy_file.exists() {
+        let _ = fs::remove_file(&legacy_file);
+    }
+    Ok(true)
+}
+
+impl TxPool {
+    /// Returns the entries with the metadata, a tx is always after its ancestors.
+    pub(crate) fn persisted_entries(&self) -> Vec<PersistedEntry> {
+        let mut entries: Vec<_> = self
+            .pool_map
+            .entries
+            .get_by_status(&Status::Proposed)
+            .into_iter()
+            .chain(self.pool_map.entries.get_by_status(&Status::Gap))
+            .chain(self.pool_map.entries.get_by_status(&Status::Pending))
+            .map(|e| &e.inner)
+            .collect();
+        // the ancestors count of a tx is always greater than the ones of its ancestors
+        entries.sort_by_key(|entry| entry.ancestors_count);
+        entries
+            .into_iter()
+            .map(|entry| PersistedEntry {
+                transaction: entry.transaction().clone(),
+                timestamp: entry.timestamp,
+                fee_delta: entry.fee_delta,
+                local: entry.local,
+            })
+            .collect()
+    }
+
+    /// Returns the data to persist, which is saved without holding the lock.
+    pub(crate) fn persisted_data(&self) -> PersistedData {
+        PersistedData {
+            entries: self.persisted_entries(),
+            fee_deltas: self
+                .pool_map
+                .fee_deltas
+                .iter()
+                .map(|(id, fee_delta)| (id.clone(), *fee_delta))
+                .collect(),
+        }
     }
 }
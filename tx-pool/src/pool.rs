@@ -2,16 +2,18 @@
 extern crate rustc_hash;
 extern crate slab;
 use super::component::{commit_txs_scanner::CommitTxsScanner, TxEntry};
+use crate::block_assembler::{BlockTemplatePolicy, TxSelector};
 use crate::callback::Callbacks;
 use crate::component::pool_map::{PoolEntry, PoolMap, Status};
 use crate::component::recent_reject::RecentReject;
+use crate::component::tx_history::TxHistory;
 use crate::error::Reject;
 use crate::pool_cell::PoolCell;
 use ckb_app_config::TxPoolConfig;
 use ckb_logger::{debug, error, warn};
 use ckb_snapshot::Snapshot;
 use ckb_store::ChainStore;
-use ckb_types::core::tx_pool::PoolTxDetailInfo;
+use ckb_types::core::tx_pool::{LocalTxInfo, PoolTxDetailInfo, TxEvent};
 use ckb_types::core::CapacityError;
 use ckb_types::packed::OutPoint;
 use ckb_types::{
@@ -40,6 +42,8 @@ pub struct TxPool {
     pub(crate) snapshot: Arc<Snapshot>,
     /// record recent reject
     pub recent_reject: Option<RecentReject>,
+    /// record the lifecycle events of txs
+    pub tx_history: Option<TxHistory>,
     // expiration milliseconds,
     pub(crate) expiry: u64,
     // conflicted transaction cache
@@ -50,13 +54,28 @@ impl TxPool {
     /// Create new TxPool
     pub fn new(config: TxPoolConfig, snapshot: Arc<Snapshot>) -> TxPool {
         let recent_reject = Self::build_recent_reject(&config);
+        let tx_history = Self::build_tx_history(&config);
         let expiry = config.expiry_hours as u64 * 60 * 60 * 1000;
+        let mut pool_map = PoolMap::new(config.max_ancestors_count);
+        pool_map.set_cell_dep_limits(
+            config.max_cell_dep_references,
+            config.heavy_cell_dep_references,
+            snapshot
+                .consensus()
+                .genesis_block()
+                .transactions()
+                .iter()
+                .map(|tx| tx.hash())
+                .collect(),
+        );
+        pool_map.max_local_tx_size = config.max_local_tx_pool_size;
         TxPool {
-            pool_map: PoolMap::new(config.max_ancestors_count),
+            pool_map,
             committed_txs_hash_cache: LruCache::new(COMMITTED_HASH_CACHE_SIZE),
             config,
             snapshot,
             recent_reject,
+            tx_history,
             expiry,
             conflicts_cache: LruCache::new(CONFLICTES_CACHE_SIZE),
         }
@@ -149,11 +168,33 @@ impl TxPool {
     }
 
     pub(crate) fn set_entry_proposed(&mut self, short_id: &ProposalShortId) {
-        self.pool_map.set_entry(short_id, Status::Proposed)
+        self.pool_map.set_entry(short_id, Status::Proposed);
+        self.record_entry_event(short_id, TxEvent::Proposed);
     }
 
     pub(crate) fn set_entry_gap(&mut self, short_id: &ProposalShortId) {
-        self.pool_map.set_entry(short_id, Status::Gap)
+        self.pool_map.set_entry(short_id, Status::Gap);
+        self.record_entry_event(short_id, TxEvent::Gap);
+    }
+
+    /// Records the lifecycle event of the tx.
+    pub fn record_tx_event(&self, tx_hash: &Byte32, event: TxEvent) {
+        if let Some(ref tx_history) = self.tx_history {
+            tx_history.record(tx_hash, event);
+        }
+    }
+
+    /// Records the lifecycle event only for the tx with recorded events.
+    pub(crate) fn record_known_tx_event(&self, tx_hash: &Byte32, event: TxEvent) {
+        if let Some(ref tx_history) = self.tx_history {
+            tx_history.record_if_known(tx_hash, event);
+        }
+    }
+
+    fn record_entry_event(&self, short_id: &ProposalShortId, event: TxEvent) {
+        if let Some(tx) = self.get_tx_from_pool(short_id) {
+            self.record_tx_event(&tx.hash(), event);
+        }
     }
 
     pub(crate) fn record_conflict(&mut self, tx: TransactionView) {
@@ -230,14 +271,16 @@ impl TxPool {
         if let Some(_entry) = self.pool_map.remove_entry(&short_id) {
             debug!("remove_committed_tx for {}", tx.hash());
         }
+        self.pool_map.remove_fee_delta(&short_id);
         {
-            for (entry, reject) in self.pool_map.resolve_conflict(tx) {
+            for (entry, reject, conflict) in self.pool_map.resolve_conflict(tx) {
                 debug!(
                     "removed {} for committed: {}",
                     entry.transaction().hash(),
                     tx.hash()
                 );
                 callbacks.call_reject(self, &entry, reject);
+                callbacks.call_conflict(&entry, &conflict);
             }
         }
     }
@@ -260,6 +303,79 @@ impl TxPool {
             let reject = Reject::Expiry(entry.timestamp);
             callbacks.call_reject(self, &entry, reject);
         }
+        // the fee deltas of the txs not in the pool expire as the entries
+        self.pool_map
+            .remove_expired_fee_deltas(now_ms.saturating_sub(self.expiry));
+
+        self.remove_unpaid_low_fee_parents(callbacks);
+    }
+
+    /// Returns an error if no more txs below the min fee rate can wait for a child.
+    pub(crate) fn check_low_fee_parents_limit(&self) -> Result<(), Reject> {
+        let limit = self.config.max_low_fee_parents;
+        if self.pool_map.low_fee_parents.len() >= limit {
+            return Err(Reject::ExceededLowFeeParentsLimit(limit));
+        }
+        Ok(())
+    }
+
+    /// Tracks the new entry if it's below the min fee rate, and stops tracking its ancestors
+    /// paid by it.
+    pub(crate) fn update_low_fee_parents(&mut self, entry: &TxEntry) {
+        let min_fee_rate = self.config.min_fee_rate;
+        let short_id = entry.proposal_short_id();
+        if entry.fee < min_fee_rate.fee(entry.size as u64) {
+            let deadline = entry
+                .timestamp
+                .saturating_add(self.config.low_fee_parent_window_secs.saturating_mul(1000));
+            debug!(
+                "low fee parent {} waits for a child until {}",
+                entry.transaction().hash(),
+                deadline
+            );
+            self.pool_map
+                .track_low_fee_parent(short_id.clone(), deadline);
+        }
+        self.pool_map
+            .settle_low_fee_ancestors(&short_id, min_fee_rate);
+    }
+
+    // Remove the txs below the min fee rate whose window has passed without a child paying
+    // enough fee for them, and their descendants.
+    //
+    // The paid ones get a new window once the descendants paying their fee are removed.
+    pub(crate) fn remove_unpaid_low_fee_parents(&mut self, callbacks: &Callbacks) {
+        let now_ms = ckb_systemtime::unix_time_as_millis();
+        let min_fee_rate = self.config.min_fee_rate;
+        if !self.pool_map.paid_low_fee_parents.is_empty() {
+            let deadline =
+                now_ms.saturating_add(self.config.low_fee_parent_window_secs.saturating_mul(1000));
+            self.pool_map
+                .retrack_unpaid_low_fee_parents(deadline, min_fee_rate);
+        }
+        if self.pool_map.low_fee_parents.is_empty() {
+            return;
+        }
+        for id in self
+            .pool_map
+            .take_unpaid_low_fee_parents(now_ms, min_fee_rate)
+        {
+            let reject = match self.pool_map.get(&id) {
+                Some(parent) => Reject::LowPackageFeeRate(
+                    min_fee_rate,
+                    min_fee_rate.fee(parent.descendants_size as u64).as_u64(),
+                    parent.descendants_fee.as_u64(),
+                ),
+                None => continue,
+            };
+            for entry in self.pool_map.remove_entry_and_descendants(&id) {
+                debug!(
+                    "remove_unpaid_low_fee_parents {}",
+                    entry.transaction().hash()
+                );
+                callbacks.call_reject(self, &entry, reject.clone());
+            }
+        }
     }
 
     // Remove transactions from the pool until total size <= size_limit.
@@ -271,14 +387,7 @@ impl TxPool {
     ) -> Option<Reject> {
         let mut ret = None;
         while self.pool_map.total_tx_size > self.config.max_tx_pool_size {
-            let next_evict_entry = || {
-                self.pool_map
-                    .next_evict_entry(Status::Pending)
-                    .or_else(|| self.pool_map.next_evict_entry(Status::Gap))
-                    .or_else(|| self.pool_map.next_evict_entry(Status::Proposed))
-            };
-
-            if let Some(id) = next_evict_entry() {
+            if let Some(id) = self.pool_map.next_evict_entry_for_size() {
                 let removed = self.pool_map.remove_entry_and_descendants(&id);
                 for entry in removed {
                     let tx_hash = entry.transaction().hash();
@@ -321,6 +430,9 @@ impl TxPool {
                     let tx_hash = entry.transaction().hash();
                     entry.reset_statistic_state();
                     let ret = self.add_pending(entry);
+                    if matches!(ret, Ok((true, _))) {
+                        self.record_tx_event(&tx_hash, TxEvent::Pending);
+                    }
                     debug!(
                         "remove_by_detached_proposal from {:?} {} add_pending {:?}",
                         status, tx_hash, ret
@@ -330,6 +442,13 @@ impl TxPool {
         }
     }
 
+    /// Adds a virtual fee delta to the tx, the tx doesn't need to be in the pool yet.
+    /// Returns the accumulated fee delta.
+    pub(crate) fn prioritise_tx(&mut self, id: &ProposalShortId, fee_delta: i64) -> i64 {
+        let now_ms = ckb_systemtime::unix_time_as_millis();
+        self.pool_map.prioritise(id, fee_delta, now_ms)
+    }
+
     pub(crate) fn remove_tx(&mut self, id: &ProposalShortId) -> bool {
         let entries = self.pool_map.remove_entry_and_descendants(id);
         !entries.is_empty()
@@ -509,11 +628,34 @@ impl TxPool {
 
     pub(crate) fn package_txs(
         &self,
+        policy: &dyn BlockTemplatePolicy,
         max_block_cycles: Cycle,
         txs_size_limit: usize,
     ) -> (Vec<TxEntry>, usize, Cycle) {
-        let (entries, size, cycles) =
-            CommitTxsScanner::new(&self.pool_map).txs_to_commit(txs_size_limit, max_block_cycles);
+        self.package_txs_with(
+            policy,
+            max_block_cycles,
+            txs_size_limit,
+            &[],
+            HashSet::new(),
+        )
+    }
+
+    /// Packages the included txs with their ancestors first, then the ones selected by the
+    /// policy. The excluded txs and their descendants are never packaged.
+    pub(crate) fn package_txs_with(
+        &self,
+        policy: &dyn BlockTemplatePolicy,
+        max_block_cycles: Cycle,
+        txs_size_limit: usize,
+        include: &[ProposalShortId],
+        exclude: HashSet<ProposalShortId>,
+    ) -> (Vec<TxEntry>, usize, Cycle) {
+        let mut selector = TxSelector::new(CommitTxsScanner::new(&self.pool_map));
+        selector.exclude(exclude);
+        selector.select_required(include, txs_size_limit, max_block_cycles);
+        policy.select_txs(&mut selector, txs_size_limit, max_block_cycles);
+        let (entries, size, cycles) = selector.into_parts();
 
         if !entries.is_empty() {
             ckb_logger::info!(
@@ -667,6 +809,48 @@ impl TxPool {
         }
     }
 
+    fn local_rebroadcast_interval(&self) -> u64 {
+        self.config
+            .local_rebroadcast_interval_secs
+            .saturating_mul(1000)
+    }
+
+    /// Returns the hashes of the local txs due to rebroadcast, and schedules their next
+    /// rebroadcasts.
+    pub(crate) fn take_local_txs_to_rebroadcast(&mut self) -> Vec<Byte32> {
+        let interval = self.local_rebroadcast_interval();
+        if interval == 0 {
+            return Vec::new();
+        }
+        let now_ms = ckb_systemtime::unix_time_as_millis();
+        self.pool_map
+            .take_local_txs_to_rebroadcast(now_ms, interval)
+    }
+
+    /// Returns the local txs in the pool sorted by the time added into the pool.
+    pub(crate) fn get_local_txs(&self) -> Vec<LocalTxInfo> {
+        let interval = self.local_rebroadcast_interval();
+        let mut txs: Vec<_> = self
+            .pool_map
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.inner.local)
+            .map(|(_, entry)| {
+                let schedule = self.pool_map.local_rebroadcast(&entry.inner, interval);
+                LocalTxInfo {
+                    tx_hash: entry.inner.transaction().hash(),
+                    entry_status: entry.status.to_string(),
+                    timestamp: entry.inner.timestamp,
+                    fee: entry.inner.fee,
+                    rebroadcast_count: schedule.count,
+                    next_rebroadcast_at: (interval > 0).then_some(schedule.next_at),
+                }
+            })
+            .collect();
+        txs.sort_by_key(|tx| tx.timestamp);
+        txs
+    }
+
     fn build_recent_reject(config: &TxPoolConfig) -> Option<RecentReject> {
         if !config.recent_reject.as_os_str().is_empty() {
             let recent_reject_ttl =
@@ -690,4 +874,23 @@ impl TxPool {
             None
         }
     }
+
+    fn build_tx_history(config: &TxPoolConfig) -> Option<TxHistory> {
+        if !config.tx_history.as_os_str().is_empty() && config.keep_tx_history_count > 0 {
+            let ttl = u8::max(1, config.keep_tx_history_days) as i32 * 24 * 60 * 60;
+            match TxHistory::new(&config.tx_history, config.keep_tx_history_count, ttl) {
+                Ok(tx_history) => Some(tx_history),
+                Err(err) => {
+                    error!(
+                        "Failed to open the tx history database {:?} {}",
+                        config.tx_history, err
+                    );
+                    None
+                }
+            }
+        } else {
+            warn!("Tx history database is disabled!");
+            None
+        }
+    }
 }
@@ -3,6 +3,7 @@ use crate::component::entry::TxEntry;
 use crate::component::orphan::Entry as OrphanEntry;
 use crate::component::pool_map::Status;
 use crate::error::Reject;
+use crate::persisted::{self, PersistedEntry};
 use crate::pool::TxPool;
 use crate::service::{BlockAssemblerMessage, TxPoolService, TxVerificationResult};
 use crate::try_or_return_with_snapshot;
@@ -12,20 +13,21 @@ use crate::util::{
 };
 use ckb_chain_spec::consensus::MAX_BLOCK_PROPOSALS_LIMIT;
 use ckb_error::{AnyError, InternalErrorKind};
-use ckb_jsonrpc_types::BlockTemplate;
+use ckb_jsonrpc_types::{BlockTemplate, BlockTemplateParams};
 use ckb_logger::Level::Trace;
 use ckb_logger::{debug, error, info, log_enabled_target, trace_target};
 use ckb_network::PeerIndex;
 use ckb_snapshot::Snapshot;
 use ckb_store::data_loader_wrapper::AsDataLoader;
 use ckb_types::core::error::OutPointError;
+use ckb_types::core::tx_pool::TxEvent;
 use ckb_types::{
     core::{cell::ResolvedTransaction, BlockView, Capacity, Cycle, HeaderView, TransactionView},
     packed::{Byte32, ProposalShortId},
 };
 use ckb_util::LinkedHashSet;
 use ckb_verification::{
-    cache::{CacheEntry, Completed},
+    cache::{pool_script_version, save_cache, CacheEntry, Completed},
     ContextualTransactionVerifier, DaoScriptSizeVerifier, ScriptVerifyResult,
     TimeRelativeTransactionVerifier, TxVerifyEnv,
 };
@@ -79,6 +81,23 @@ impl TxPoolService {
         }
     }
 
+    pub(crate) async fn get_custom_block_template(
+        &self,
+        bytes_limit: Option<u64>,
+        proposals_limit: Option<u64>,
+        params: BlockTemplateParams,
+    ) -> Result<BlockTemplate, AnyError> {
+        if let Some(ref block_assembler) = self.block_assembler {
+            block_assembler
+                .get_custom(&self.tx_pool, bytes_limit, proposals_limit, params)
+                .await
+        } else {
+            Err(InternalErrorKind::Config
+                .other("BlockAssembler disabled")
+                .into())
+        }
+    }
+
     pub(crate) async fn fetch_tx_verify_cache(&self, tx: &TransactionView) -> Option<CacheEntry> {
         let guard = self.txs_verify_cache.read().await;
         guard.peek(&tx.witness_hash()).cloned()
@@ -107,6 +126,13 @@ impl TxPoolService {
     ) -> (Result<(), Reject>, Arc<Snapshot>) {
         let (ret, snapshot) = self
             .with_tx_pool_write_lock(move |tx_pool, snapshot| {
+                tx_pool.remove_unpaid_low_fee_parents(&self.callbacks);
+                if entry.fee < tx_pool.config.min_fee_rate.fee(entry.size as u64) {
+                    // the limit may be reached since pre-check
+                    tx_pool.check_low_fee_parents_limit()?;
+                }
+                tx_pool.pool_map.check_cell_dep_references(&entry)?;
+
                 // check_rbf must be invoked in `write` lock to avoid concurrent issues.
                 let conflicts = if tx_pool.enable_rbf() {
                     tx_pool.check_rbf(&snapshot, &entry)?
@@ -140,23 +166,24 @@ impl TxPoolService {
                 }
 
                 // try to remove conflicted tx here
-                for id in conflicts.iter() {
-                    let removed = tx_pool.pool_map.remove_entry_and_descendants(id);
-                    for old in removed {
-                        debug!(
-                            "remove conflict tx {} for RBF by new tx {}",
-                            old.transaction().hash(),
-                            entry.transaction().hash()
-                        );
-                        let reject = Reject::RBFRejected(format!(
-                            "replaced by tx {}",
-                            entry.transaction().hash()
-                        ));
-                        // RBF replace successfully, put old transactions into conflicts pool
-                        tx_pool.record_conflict(old.transaction().clone());
-                        // after removing old tx from tx_pool, we call reject callbacks manually
-                        self.callbacks.call_reject(tx_pool, &old, reject);
-                    }
+                let replaced = tx_pool
+                    .pool_map
+                    .remove_replaced(&conflicts, entry.transaction());
+                for (old, conflict) in replaced {
+                    debug!(
+                        "remove conflict tx {} for RBF by new tx {}",
+                        old.transaction().hash(),
+                        entry.transaction().hash()
+                    );
+                    let reject = Reject::RBFRejected(format!(
+                        "replaced by tx {}",
+                        entry.transaction().hash()
+                    ));
+                    // RBF replace successfully, put old transactions into conflicts pool
+                    tx_pool.record_conflict(old.transaction().clone());
+                    // after removing old tx from tx_pool, we call reject callbacks manually
+                    self.callbacks.call_reject(tx_pool, &old, reject);
+                    self.callbacks.call_conflict(&old, &conflict);
                 }
                 let evicted = _submit_entry(tx_pool, status, entry.clone(), &self.callbacks)?;
                 for evict in evicted {
@@ -242,6 +269,7 @@ impl TxPoolService {
     pub(crate) async fn pre_check(
         &self,
         tx: &TransactionView,
+        local: bool,
     ) -> (Result<PreCheckedTx, Reject>, Arc<Snapshot>) {
         // Acquire read lock for cheap check
         let tx_size = tx.data().serialized_size_in_block();
@@ -259,7 +287,16 @@ impl TxPoolService {
                 let res = resolve_tx(tx_pool, &snapshot, tx.clone(), false);
                 match res {
                     Ok((rtx, status)) => {
-                        let fee = check_tx_fee(tx_pool, &snapshot, &rtx, tx_size)?;
+                        let fee = match check_tx_fee(tx_pool, &snapshot, &rtx, tx_size) {
+                            // the local tx below the min fee rate may wait for a child to pay its fee
+                            Err(Reject::LowFeeRate(_, _, fee))
+                                if local && tx_pool.config.max_low_fee_parents > 0 =>
+                            {
+                                tx_pool.check_low_fee_parents_limit()?;
+                                Capacity::shannons(fee)
+                            }
+                            ret => ret?,
+                        };
                         Ok((tip_hash, rtx, status, fee, tx_size))
                     }
                     Err(Reject::Resolve(OutPointError::Dead(out))) => {
@@ -320,6 +357,7 @@ impl TxPoolService {
             debug!("reject tx {} already in orphan pool", tx.hash());
             return Err(Reject::Duplicated(tx.hash()));
         }
+        self.record_received_tx(&tx, remote);
 
         if let Some((ret, snapshot)) = self._resumeble_process_tx(tx.clone(), remote).await {
             match ret {
@@ -367,6 +405,7 @@ impl TxPoolService {
         if self.chunk_contains(&tx).await || self.orphan_contains(&tx).await {
             return Err(Reject::Duplicated(tx.hash()));
         }
+        self.record_received_tx(&tx, remote);
 
         if let Some((ret, snapshot)) = self._process_tx(tx.clone(), remote.map(|r| r.0)).await {
             self.after_process(tx, remote, &snapshot, &ret).await;
@@ -380,8 +419,16 @@ impl TxPoolService {
         }
     }
 
+    fn record_received_tx(&self, tx: &TransactionView, remote: Option<(Cycle, PeerIndex)>) {
+        if let Some(ref tx_history) = self.tx_history {
+            let peer = remote.map(|(_, peer)| peer.value());
+            tx_history.record(&tx.hash(), TxEvent::Received(peer));
+        }
+    }
+
     pub(crate) async fn put_recent_reject(&self, tx_hash: &Byte32, reject: &Reject) {
         let mut tx_pool = self.tx_pool.write().await;
+        tx_pool.record_tx_event(tx_hash, TxEvent::Rejected(reject.clone()));
         if let Some(ref mut recent_reject) = tx_pool.recent_reject {
             if let Err(e) = recent_reject.put(tx_hash, reject.clone()) {
                 error!(
@@ -407,7 +454,31 @@ impl TxPoolService {
             }
         }
         let mut tx_pool = self.tx_pool.write().await;
-        tx_pool.remove_tx(&id)
+        let removed = tx_pool.remove_tx(&id);
+        if removed {
+            tx_pool.record_tx_event(&tx_hash, TxEvent::Removed);
+        }
+        removed
+    }
+
+    pub(crate) async fn prioritise_tx(&self, tx_hash: Byte32, fee_delta: i64) -> i64 {
+        let id = ProposalShortId::from_tx_hash(&tx_hash);
+        let (total, status) = {
+            let mut tx_pool = self.tx_pool.write().await;
+            let total = tx_pool.prioritise_tx(&id, fee_delta);
+            let status = tx_pool.get_pool_entry(&id).map(|entry| entry.status);
+            (total, status)
+        };
+        info!(
+            "prioritise tx {} by fee delta {}, accumulated fee delta {}",
+            tx_hash, fee_delta, total
+        );
+        match status {
+            Some(Status::Proposed) => self.notify_block_assembler(TxStatus::Proposed).await,
+            Some(_) => self.notify_block_assembler(TxStatus::Fresh).await,
+            None => {}
+        }
+        total
     }
 
     pub(crate) async fn after_process(
@@ -421,17 +492,7 @@ impl TxPoolService {
 
         // The network protocol is switched after tx-pool confirms the cache,
         // there will be no problem with the current state as the choice of the broadcast protocol.
-        let with_vm_2023 = {
-            let epoch = snapshot
-                .tip_header()
-                .epoch()
-                .minimum_epoch_number_after_n_blocks(1);
-
-            self.consensus
-                .hardfork_switch
-                .ckb2023
-                .is_vm_version_2_and_syscalls_3_enabled(epoch)
-        };
+        let with_vm_2023 = self.with_vm_2023(snapshot);
 
         // log tx verification result for monitor node
         if log_enabled_target!("ckb_tx_monitor", Trace) {
@@ -526,6 +587,35 @@ impl TxPoolService {
         }
     }
 
+    fn with_vm_2023(&self, snapshot: &Snapshot) -> bool {
+        let epoch = snapshot
+            .tip_header()
+            .epoch()
+            .minimum_epoch_number_after_n_blocks(1);
+
+        self.consensus
+            .hardfork_switch
+            .ckb2023
+            .is_vm_version_2_and_syscalls_3_enabled(epoch)
+    }
+
+    /// Rebroadcasts the local txs which are due, until they are committed.
+    pub(crate) async fn rebroadcast_local_txs(&self) {
+        let (tx_hashes, with_vm_2023) = {
+            let mut tx_pool = self.tx_pool.write().await;
+            let tx_hashes = tx_pool.take_local_txs_to_rebroadcast();
+            (tx_hashes, self.with_vm_2023(tx_pool.snapshot()))
+        };
+        for tx_hash in tx_hashes {
+            debug!("rebroadcast local tx {}", tx_hash);
+            self.send_result_to_relayer(TxVerificationResult::Ok {
+                original_peer: None,
+                with_vm_2023,
+                tx_hash,
+            });
+        }
+    }
+
     pub(crate) async fn add_orphan(
         &self,
         tx: TransactionView,
@@ -676,7 +766,7 @@ impl TxPoolService {
         let limit_cycles = self.tx_pool_config.max_tx_verify_cycles;
         let wtx_hash = tx.witness_hash();
 
-        let (ret, snapshot) = self.pre_check(&tx).await;
+        let (ret, snapshot) = self.pre_check(&tx, remote.is_none()).await;
         let (tip_hash, rtx, status, fee, tx_size) = try_or_return_with_snapshot!(ret, snapshot);
 
         if self.is_in_delay_window(&snapshot) {
@@ -768,7 +858,8 @@ impl TxPoolService {
             }
         };
 
-        let entry = TxEntry::new(rtx, completed.cycles, fee, tx_size);
+        let mut entry = TxEntry::new(rtx, completed.cycles, fee, tx_size);
+        entry.local = remote.is_none();
 
         let (ret, submit_snapshot) = self.submit_entry(tip_hash, entry, status).await;
         try_or_return_with_snapshot!(ret, submit_snapshot);
@@ -813,7 +904,7 @@ impl TxPoolService {
     ) -> Option<(Result<Completed, Reject>, Arc<Snapshot>)> {
         let wtx_hash = tx.witness_hash();
 
-        let (ret, snapshot) = self.pre_check(&tx).await;
+        let (ret, snapshot) = self.pre_check(&tx, declared_cycles.is_none()).await;
 
         let (tip_hash, rtx, status, fee, tx_size) = try_or_return_with_snapshot!(ret, snapshot);
 
@@ -849,7 +940,8 @@ impl TxPoolService {
             }
         }
 
-        let entry = TxEntry::new(rtx, verified.cycles, fee, tx_size);
+        let mut entry = TxEntry::new(rtx, verified.cycles, fee, tx_size);
+        entry.local = declared_cycles.is_none();
 
         let (ret, submit_snapshot) = self.submit_entry(tip_hash, entry, status).await;
         try_or_return_with_snapshot!(ret, submit_snapshot);
@@ -869,7 +961,7 @@ impl TxPoolService {
     }
 
     pub(crate) async fn _test_accept_tx(&self, tx: TransactionView) -> Result<Completed, Reject> {
-        let (pre_check_ret, snapshot) = self.pre_check(&tx).await;
+        let (pre_check_ret, snapshot) = self.pre_check(&tx, true).await;
 
         let (_tip_hash, rtx, status, _fee, _tx_size) = pre_check_ret?;
 
@@ -917,7 +1009,14 @@ impl TxPoolService {
             detached.extend(blk.transactions().into_iter().skip(1))
         }
 
+        let mut committed = Vec::new();
         for blk in attached_blocks {
+            committed.extend(
+                blk.transactions()
+                    .iter()
+                    .skip(1)
+                    .map(|tx| (tx.hash(), TxEvent::Committed(blk.number(), blk.hash()))),
+            );
             attached.extend(blk.transactions().into_iter().skip(1));
         }
         let retain: Vec<TransactionView> = detached.difference(&attached).cloned().collect();
@@ -936,6 +1035,9 @@ impl TxPoolService {
         let txs_opt = {
             // This closure is used to limit the lifetime of mutable tx_pool.
             let mut tx_pool = self.tx_pool.write().await;
+            for (tx_hash, event) in committed {
+                tx_pool.record_known_tx_event(&tx_hash, event);
+            }
 
             let txs_opt = if is_in_delay_window {
                 {
@@ -1075,11 +1177,85 @@ impl TxPoolService {
     }
 
     pub(crate) async fn save_pool(&self) {
-        let mut tx_pool = self.tx_pool.write().await;
-        if let Err(err) = tx_pool.save_into_file() {
-            error!("failed to save pool, error: {:?}", err)
-        } else {
-            info!("TxPool saved successfully")
+        let (data, generation) = {
+            let tx_pool = self.tx_pool.read().await;
+            (tx_pool.persisted_data(), persisted::next_generation())
+        };
+        match persisted::save_into_file(&self.tx_pool_config.persisted_data, &data, generation) {
+            Ok(true) => info!(
+                "TxPool saved successfully, total {} txs",
+                data.entries.len()
+            ),
+            Ok(false) => debug!("TxPool save skipped, a newer snapshot has been saved"),
+            Err(err) => error!("failed to save pool, error: {:?}", err),
+        }
+    }
+
+    pub(crate) async fn dump_pool(&self) -> Vec<PersistedEntry> {
+        self.tx_pool.read().await.persisted_entries()
+    }
+
+    /// Loads the entries into the pool and restores the metadata, assume that the entries are
+    /// sorted. The timestamps in the future are clamped to now. Returns the count of the entries
+    /// loaded.
+    pub(crate) async fn load_entries(&self, entries: Vec<PersistedEntry>) -> usize {
+        let now_ms = ckb_systemtime::unix_time_as_millis();
+        let mut loaded = 0;
+        for entry in entries {
+            let tx_hash = entry.transaction.hash();
+            let short_id = entry.transaction.proposal_short_id();
+            let timestamp = entry.timestamp.min(now_ms);
+            {
+                let tx_pool = self.tx_pool.read().await;
+                if tx_pool.expiry.saturating_add(timestamp) < now_ms {
+                    debug!("ignore the expired persisted tx {:#x}", tx_hash);
+                    continue;
+                }
+            }
+            if let Err(err) = self.process_tx(entry.transaction, None).await {
+                debug!(
+                    "failed to load the persisted tx {:#x}, error: {}",
+                    tx_hash, err
+                );
+                continue;
+            }
+            let mut tx_pool = self.tx_pool.write().await;
+            if tx_pool
+                .pool_map
+                .restore_entry(&short_id, timestamp, entry.local)
+            {
+                // the fee delta set on this node is kept
+                let prioritised = tx_pool
+                    .pool_map
+                    .get(&short_id)
+                    .map_or(false, |pool_entry| pool_entry.fee_delta != 0);
+                if entry.fee_delta != 0 && !prioritised {
+                    tx_pool.prioritise_tx(&short_id, entry.fee_delta);
+                }
+                loaded += 1;
+            }
+        }
+        loaded
+    }
+
+    pub(crate) async fn save_verify_cache(&self) {
+        let limit = self.tx_pool_config.persisted_verify_cache_size;
+        if limit == 0 {
+            return;
+        }
+        let script_version = {
+            let tx_pool = self.tx_pool.read().await;
+            pool_script_version(&self.consensus, tx_pool.snapshot().tip_header())
+        };
+        let cache = self.txs_verify_cache.read().await;
+        match save_cache(
+            &cache,
+            &self.tx_pool_config.persisted_verify_cache,
+            script_version,
+            limit,
+        ) {
+            Ok(count) => info!("Saved {} transaction verification cache entries", count),
+            Err(err) => error!("failed to save verification cache, error: {:?}", err),
         }
     }
 
@@ -1161,12 +1337,20 @@ fn _submit_entry(
 ) -> Result<HashSet<TxEntry>, Reject> {
     let tx_hash = entry.transaction().hash();
     debug!("submit_entry {:?} {}", status, tx_hash);
+    tx_pool.record_tx_event(&tx_hash, TxEvent::Verified(entry.cycles, entry.fee));
     let (succ, evicts) = match status {
         TxStatus::Fresh => tx_pool.add_pending(entry.clone())?,
         TxStatus::Gap => tx_pool.add_gap(entry.clone())?,
         TxStatus::Proposed => tx_pool.add_proposed(entry.clone())?,
     };
     if succ {
+        tx_pool.update_low_fee_parents(&entry);
+        let event = match status {
+            TxStatus::Fresh => TxEvent::Pending,
+            TxStatus::Gap => TxEvent::Gap,
+            TxStatus::Proposed => TxEvent::Proposed,
+        };
+        tx_pool.record_tx_event(&tx_hash, event);
         match status {
             TxStatus::Fresh => callbacks.call_pending(&entry),
             TxStatus::Gap => callbacks.call_pending(&entry),
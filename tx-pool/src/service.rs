@@ -1,26 +1,31 @@
 //! Tx-pool background service
 
-use crate::block_assembler::{self, BlockAssembler};
-use crate::callback::{Callbacks, PendingCallback, ProposedCallback, RejectCallback};
+use crate::block_assembler::{self, BlockAssembler, BlockTemplatePolicy};
+use crate::callback::{
+    Callbacks, ConflictCallback, PendingCallback, ProposedCallback, RejectCallback,
+};
 use crate::chunk_process::ChunkCommand;
 use crate::component::pool_map::{PoolEntry, Status};
-use crate::component::{chunk::ChunkQueue, orphan::OrphanPool};
+use crate::component::{chunk::ChunkQueue, orphan::OrphanPool, tx_history::TxHistory};
 use crate::error::{handle_recv_error, handle_send_cmd_error, handle_try_send_error};
+use crate::persisted::{self, PersistedEntry};
 use crate::pool::TxPool;
 use crate::util::after_delay_window;
 use ckb_app_config::{BlockAssemblerConfig, TxPoolConfig};
 use ckb_async_runtime::Handle;
 use ckb_chain_spec::consensus::Consensus;
 use ckb_channel::oneshot;
-use ckb_error::AnyError;
-use ckb_jsonrpc_types::BlockTemplate;
+use ckb_error::{AnyError, InternalErrorKind};
+use ckb_jsonrpc_types::{BlockTemplate, BlockTemplateParams, TransactionHistoryEvent};
 use ckb_logger::{error, info};
 use ckb_network::{NetworkController, PeerIndex};
 use ckb_snapshot::Snapshot;
 use ckb_stop_handler::new_tokio_exit_rx;
 use ckb_store::ChainStore;
 use ckb_types::core::cell::{CellProvider, CellStatus, OverlayCellProvider};
-use ckb_types::core::tx_pool::{EntryCompleted, PoolTxDetailInfo, TransactionWithStatus, TxStatus};
+use ckb_types::core::tx_pool::{
+    EntryCompleted, LocalTxInfo, PoolTxDetailInfo, TransactionWithStatus, TxStatus,
+};
 use ckb_types::packed::OutPoint;
 use ckb_types::{
     core::{
@@ -74,7 +79,12 @@ impl<A> Notify<A> {
 }
 
 pub(crate) type BlockTemplateResult = Result<BlockTemplate, AnyError>;
-type BlockTemplateArgs = (Option<u64>, Option<u64>, Option<Version>);
+type BlockTemplateArgs = (
+    Option<u64>,
+    Option<u64>,
+    Option<Version>,
+    Option<BlockTemplateParams>,
+);
 
 pub(crate) type SubmitTxResult = Result<(), Reject>;
 
@@ -82,6 +92,7 @@ pub(crate) type TestAcceptTxResult = Result<EntryCompleted, Reject>;
 
 type GetTxStatusResult = Result<(TxStatus, Option<Cycle>), AnyError>;
 type GetTransactionWithStatusResult = Result<TransactionWithStatus, AnyError>;
+type GetTxHistoryResult = Result<Vec<TransactionHistoryEvent>, AnyError>;
 type FetchTxsWithCyclesResult = Vec<(ProposalShortId, (TransactionView, Cycle))>;
 
 pub(crate) type ChainReorgArgs = (
@@ -110,7 +121,12 @@ pub(crate) enum Message {
     GetAllEntryInfo(Request<(), TxPoolEntryInfo>),
     GetAllIds(Request<(), TxPoolIds>),
     SavePool(Request<(), ()>),
+    DumpPool(Request<(), Vec<PersistedEntry>>),
+    GetLocalTxs(Request<(), Vec<LocalTxInfo>>),
+    LoadPool(Request<Vec<PersistedEntry>, usize>),
+    PrioritiseTx(Request<(Byte32, i64), i64>),
     GetPoolTxDetails(Request<Byte32, PoolTxDetailInfo>),
+    GetTxHistory(Request<Byte32, GetTxHistoryResult>),
 
     // test
     #[cfg(feature = "internal")]
@@ -196,7 +212,22 @@ impl TxPoolController {
         send_message!(
             self,
             BlockTemplate,
-            (bytes_limit, proposals_limit, max_version)
+            (bytes_limit, proposals_limit, max_version, None)
+        )
+    }
+
+    /// Generate and return a one-off block_template customized by the params
+    pub fn get_block_template_with_params(
+        &self,
+        bytes_limit: Option<u64>,
+        proposals_limit: Option<u64>,
+        max_version: Option<Version>,
+        params: BlockTemplateParams,
+    ) -> Result<BlockTemplateResult, AnyError> {
+        send_message!(
+            self,
+            BlockTemplate,
+            (bytes_limit, proposals_limit, max_version, Some(params))
         )
     }
 
@@ -333,12 +364,42 @@ impl TxPoolController {
         send_message!(self, GetPoolTxDetails, tx_hash)
     }
 
+    /// Returns the recorded lifecycle events of the transaction in order.
+    pub fn get_tx_history(&self, tx_hash: Byte32) -> Result<GetTxHistoryResult, AnyError> {
+        send_message!(self, GetTxHistory, tx_hash)
+    }
+
+    /// Adds a virtual fee delta to the transaction, which changes the priority of the transaction
+    /// in block assembly and eviction without changing the actual fee.
+    ///
+    /// The deltas are accumulated and kept until the transaction is committed, the transaction
+    /// doesn't need to be in the pool yet. Returns the accumulated fee delta.
+    pub fn prioritise_tx(&self, tx_hash: Byte32, fee_delta: i64) -> Result<i64, AnyError> {
+        send_message!(self, PrioritiseTx, (tx_hash, fee_delta))
+    }
+
     /// Saves tx pool into disk.
     pub fn save_pool(&self) -> Result<(), AnyError> {
         info!("Please be patient, tx-pool are saving data into disk ...");
         send_message!(self, SavePool, ())
     }
 
+    /// Returns the pool entries with the metadata, a tx is always after its ancestors.
+    pub fn dump_pool(&self) -> Result<Vec<PersistedEntry>, AnyError> {
+        send_message!(self, DumpPool, ())
+    }
+
+    /// Loads the entries into the pool with the metadata restored, assume that the entries are
+    /// sorted. The expired entries are ignored. Returns the count of the entries loaded.
+    pub fn load_pool(&self, entries: Vec<PersistedEntry>) -> Result<usize, AnyError> {
+        send_message!(self, LoadPool, entries)
+    }
+
+    /// Returns the txs submitted locally in the pool, which are rebroadcast until committed.
+    pub fn get_local_txs(&self) -> Result<Vec<LocalTxInfo>, AnyError> {
+        send_message!(self, GetLocalTxs, ())
+    }
+
     /// Sends suspend chunk process cmd
     pub fn suspend_chunk_process(&self) -> Result<(), AnyError> {
         self.chunk_tx
@@ -355,22 +416,18 @@ impl TxPoolController {
             .map_err(Into::into)
     }
 
-    /// Load persisted txs into pool, assume that all txs are sorted
-    fn load_persisted_data(&self, txs: Vec<TransactionView>) -> Result<(), AnyError> {
-        if !txs.is_empty() {
-            info!("Loading persistent tx-pool data, total {} txs", txs.len());
-            let mut failed_txs = 0;
-            for tx in txs {
-                if self.submit_local_tx(tx)?.is_err() {
-                    failed_txs += 1;
-                }
-            }
-            if failed_txs == 0 {
+    /// Load persisted entries into pool, assume that all entries are sorted
+    fn load_persisted_data(&self, entries: Vec<PersistedEntry>) -> Result<(), AnyError> {
+        if !entries.is_empty() {
+            let total = entries.len();
+            info!("Loading persistent tx-pool data, total {} txs", total);
+            let loaded = self.load_pool(entries)?;
+            if loaded == total {
                 info!("Persistent tx-pool data is loaded");
             } else {
                 info!(
                     "Persistent tx-pool data is loaded, {} stale txs are ignored",
-                    failed_txs
+                    total - loaded
                 );
             }
         }
@@ -461,6 +518,14 @@ impl TxPoolServiceBuilder {
         (builder, controller)
     }
 
+    /// Replaces the policy to select the transactions in the block template, it's ignored when
+    /// the block assembler is not configured.
+    pub fn set_block_template_policy(&mut self, policy: Arc<dyn BlockTemplatePolicy>) {
+        if let Some(ref mut block_assembler) = self.block_assembler {
+            block_assembler.set_policy(policy);
+        }
+    }
+
     /// Register new pending callback
     pub fn register_pending(&mut self, callback: PendingCallback) {
         self.callbacks.register_pending(callback);
@@ -481,14 +546,24 @@ impl TxPoolServiceBuilder {
         self.callbacks.register_reject(callback);
     }
 
+    /// Register new conflict callback
+    pub fn register_conflict(&mut self, callback: ConflictCallback) {
+        self.callbacks.register_conflict(callback);
+    }
+
     /// Start a background thread tx-pool service by taking ownership of the Builder, and returns a TxPoolController.
     pub fn start(self, network: NetworkController) {
         let consensus = self.snapshot.cloned_consensus();
         let after_delay_window = after_delay_window(&self.snapshot);
 
-        let tx_pool = TxPool::new(self.tx_pool_config, self.snapshot);
-        let txs = match tx_pool.load_from_file() {
-            Ok(txs) => txs,
+        let mut tx_pool = TxPool::new(self.tx_pool_config, self.snapshot);
+        let entries = match persisted::load_from_file(&tx_pool.config.persisted_data) {
+            Ok(data) => {
+                for (id, fee_delta) in data.fee_deltas {
+                    tx_pool.pool_map.fee_deltas.put(id, fee_delta);
+                }
+                data.entries
+            }
             Err(e) => {
                 error!("{}", e.to_string());
                 error!("Failed to load txs from tx-pool persistent data file, all txs are ignored");
@@ -499,6 +574,7 @@ impl TxPoolServiceBuilder {
         let (block_assembler_sender, mut block_assembler_receiver) = self.block_assembler_channel;
         let service = TxPoolService {
             tx_pool_config: Arc::new(tx_pool.config.clone()),
+            tx_history: tx_pool.tx_history.clone(),
             tx_pool: Arc::new(RwLock::new(tx_pool)),
             orphan: Arc::new(RwLock::new(OrphanPool::new())),
             block_assembler: self.block_assembler,
@@ -537,6 +613,7 @@ impl TxPoolServiceBuilder {
                     _ = signal_receiver.cancelled() => {
                         info!("TxPool is saving, please wait...");
                         process_service.save_pool().await;
+                        process_service.save_verify_cache().await;
                         info!("TxPool process_service exit now");
                         break
                     },
@@ -609,6 +686,49 @@ impl TxPoolServiceBuilder {
             }
         }
 
+        let persist_interval = Duration::from_secs(service.tx_pool_config.persist_interval_secs);
+        if !persist_interval.is_zero() {
+            let process_service = service.clone();
+            let signal_receiver = self.signal_receiver.clone();
+            self.handle.spawn(async move {
+                // the first save is delayed, so the persisted data is not overwritten before it's loaded
+                let start = tokio::time::Instant::now() + persist_interval;
+                let mut interval = tokio::time::interval_at(start, persist_interval);
+                loop {
+                    tokio::select! {
+                        _ = interval.tick() => {
+                            process_service.save_pool().await;
+                        },
+                        _ = signal_receiver.cancelled() => {
+                            info!("TxPool persist service received exit signal, exit now");
+                            break
+                        },
+                    }
+                }
+            });
+        }
+
+        let rebroadcast_interval =
+            Duration::from_secs(service.tx_pool_config.local_rebroadcast_interval_secs);
+        if !rebroadcast_interval.is_zero() {
+            let process_service = service.clone();
+            let signal_receiver = self.signal_receiver.clone();
+            self.handle.spawn(async move {
+                let mut interval = tokio::time::interval(rebroadcast_interval);
+                loop {
+                    tokio::select! {
+                        _ = interval.tick() => {
+                            process_service.rebroadcast_local_txs().await;
+                        },
+                        _ = signal_receiver.cancelled() => {
+                            info!("TxPool rebroadcast service received exit signal, exit now");
+                            break
+                        },
+                    }
+                }
+            });
+        }
+
         let signal_receiver = self.signal_receiver;
         self.handle.spawn(async move {
             loop {
@@ -645,7 +765,7 @@ impl TxPoolServiceBuilder {
             }
         });
         self.started.store(true, Ordering::Relaxed);
-        if let Err(err) = self.tx_pool_controller.load_persisted_data(txs) {
+        if let Err(err) = self.tx_pool_controller.load_persisted_data(entries) {
             error!("Failed to import persistent txs, cause: {}", err);
         }
     }
@@ -657,6 +777,7 @@ pub(crate) struct TxPoolService {
     pub(crate) orphan: Arc<RwLock<OrphanPool>>,
     pub(crate) consensus: Arc<Consensus>,
     pub(crate) tx_pool_config: Arc<TxPoolConfig>,
+    pub(crate) tx_history: Option<TxHistory>,
     pub(crate) block_assembler: Option<BlockAssembler>,
     pub(crate) txs_verify_cache: Arc<RwLock<TxVerificationCache>>,
     pub(crate) callbacks: Arc<Callbacks>,
@@ -713,9 +834,16 @@ async fn process(mut service: TxPoolService, message: Message) {
         }
         Message::BlockTemplate(Request {
             responder,
-            arguments: (_bytes_limit, _proposals_limit, _max_version),
+            arguments: (bytes_limit, proposals_limit, _max_version, params),
         }) => {
-            let block_template_result = service.get_block_template().await;
+            let block_template_result = match params {
+                Some(params) => {
+                    service
+                        .get_custom_block_template(bytes_limit, proposals_limit, params)
+                        .await
+                }
+                None => service.get_block_template().await,
+            };
             if let Err(e) = responder.send(block_template_result) {
                 error!("Responder sending block_template_result failed {:?}", e);
             };
@@ -917,6 +1045,20 @@ async fn process(mut service: TxPoolService, message: Message) {
                 error!("responder send get_pool_tx_details failed {:?}", e)
             };
         }
+        Message::GetTxHistory(Request {
+            responder,
+            arguments: tx_hash,
+        }) => {
+            let ret = match service.tx_history {
+                Some(ref tx_history) => tx_history.get(&tx_hash),
+                None => Err(InternalErrorKind::Config
+                    .other("tx history is disabled")
+                    .into()),
+            };
+            if let Err(e) = responder.send(ret) {
+                error!("Responder sending get_tx_history failed {:?}", e)
+            };
+        }
         Message::GetAllEntryInfo(Request { responder, .. }) => {
             let tx_pool = service.tx_pool.read().await;
             let info = tx_pool.get_all_entry_info();
@@ -931,12 +1073,42 @@ async fn process(mut service: TxPoolService, message: Message) {
                 error!("Responder sending get_ids failed {:?}", e)
             };
         }
+        Message::PrioritiseTx(Request {
+            responder,
+            arguments: (tx_hash, fee_delta),
+        }) => {
+            let total = service.prioritise_tx(tx_hash, fee_delta).await;
+            if let Err(e) = responder.send(total) {
+                error!("Responder sending prioritise_tx failed {:?}", e)
+            };
+        }
         Message::SavePool(Request { responder, .. }) => {
             service.save_pool().await;
             if let Err(e) = responder.send(()) {
                 error!("Responder sending save_pool failed {:?}", e)
             };
         }
+        Message::DumpPool(Request { responder, .. }) => {
+            let entries = service.dump_pool().await;
+            if let Err(e) = responder.send(entries) {
+                error!("Responder sending dump_pool failed {:?}", e)
+            };
+        }
+        Message::GetLocalTxs(Request { responder, .. }) => {
+            let txs = service.tx_pool.read().await.get_local_txs();
+            if let Err(e) = responder.send(txs) {
+                error!("Responder sending get_local_txs failed {:?}", e)
+            };
+        }
+        Message::LoadPool(Request {
+            responder,
+            arguments: entries,
+        }) => {
+            let loaded = service.load_entries(entries).await;
+            if let Err(e) = responder.send(loaded) {
+                error!("Responder sending load_pool failed {:?}", e)
+            };
+        }
         #[cfg(feature = "internal")]
         Message::PlugEntry(Request {
             responder,
@@ -957,6 +1129,7 @@ async fn process(mut service: TxPoolService, message: Message) {
             let max_block_bytes = service.consensus.max_block_bytes();
             let tx_pool = service.tx_pool.read().await;
             let (txs, _size, _cycles) = tx_pool.package_txs(
+                &block_assembler::GreedyPolicy,
                 max_block_cycles,
                 bytes_limit.unwrap_or(max_block_bytes) as usize,
             );
@@ -3,7 +3,7 @@ use ckb_chain_spec::consensus::Consensus;
 use ckb_jsonrpc_types::ScriptHashType;
 use ckb_pow::PowEngine;
 use ckb_systemtime::unix_time_as_millis;
-use ckb_types::packed::Byte32;
+use ckb_types::{packed::Byte32, H256};
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -80,6 +80,8 @@ pub struct ReplayArgs {
     pub sanity_check: bool,
     /// Enable full verification.
     pub full_verification: bool,
+    /// Verify only the block with this hash and report the time of each stage.
+    pub block: Option<H256>,
 }
 
 /// Parsed command line arguments for `ckb miner`.
@@ -212,6 +214,42 @@ pub struct MigrateArgs {
     pub include_background: bool,
 }
 
+/// Parsed command line arguments for `ckb rebuild-tx-index`.
+pub struct RebuildTxIndexArgs {
+    /// The parsed `ckb.toml.`
+    pub config: Box<CKBAppConfig>,
+    /// Loaded consensus.
+    pub consensus: Consensus,
+}
+
+/// Parsed command line arguments for `ckb backup`.
+pub struct BackupArgs {
+    /// The parsed `ckb.toml.`
+    pub config: Box<CKBAppConfig>,
+    /// Loaded consensus.
+    pub consensus: Consensus,
+    /// Hash of the chain spec, the restored backup must be created with the same chain spec.
+    pub chain_spec_hash: Byte32,
+    /// The path to create the backup in.
+    pub target: Option<PathBuf>,
+    /// The path of the backup to restore.
+    pub restore: Option<PathBuf>,
+    /// Overwrite the existing data without interactive prompt.
+    pub force: bool,
+}
+
+/// Parsed command line arguments for `ckb freezer`.
+pub struct FreezerArgs {
+    /// The parsed `ckb.toml.`
+    pub config: Box<CKBAppConfig>,
+    /// Verify all frozen blocks.
+    pub verify: bool,
+    /// Rebuild the index file from the data files.
+    pub repair: bool,
+    /// Drop the blocks which can't be recovered when repairing.
+    pub force: bool,
+}
+
 impl CustomizeSpec {
     /// No specified parameters for chain spec.
     pub fn is_unset(&self) -> bool {
@@ -34,6 +34,12 @@ pub const CMD_FROM_SECRET: &str = "from-secret";
 pub const CMD_MIGRATE: &str = "migrate";
 /// Subcommand `daemon`
 pub const CMD_DAEMON: &str = "daemon";
+/// Subcommand `freezer`.
+pub const CMD_FREEZER: &str = "freezer";
+/// Subcommand `backup`.
+pub const CMD_BACKUP: &str = "backup";
+/// Subcommand `rebuild-tx-index`.
+pub const CMD_REBUILD_TX_INDEX: &str = "rebuild-tx-index";
 /// Command line argument `--config-dir`.
 pub const ARG_CONFIG_DIR: &str = "config-dir";
 /// Command line argument `--format`.
@@ -110,6 +116,8 @@ pub const ARG_PROFILE: &str = "profile";
 pub const ARG_SANITY_CHECK: &str = "sanity-check";
 /// Command line argument `--full-verification`.
 pub const ARG_FULL_VERIFICATION: &str = "full-verification";
+/// Command line argument `--block`.
+pub const ARG_BLOCK: &str = "block";
 /// Command line argument `--skip-spec-check`.
 pub const ARG_SKIP_CHAIN_SPEC_CHECK: &str = "skip-spec-check";
 /// Present `overwrite-spec` arg to force overriding the chain spec in the database with the present configured chain spec
@@ -122,6 +130,12 @@ pub const ARG_MIGRATE_CHECK: &str = "check";
 pub const ARG_DAEMON_CHECK: &str = "check";
 /// Command line argument `daemon --stop`
 pub const ARG_DAEMON_STOP: &str = "stop";
+/// Command line argument `freezer --verify`
+pub const ARG_FREEZER_VERIFY: &str = "verify";
+/// Command line argument `freezer --repair`
+pub const ARG_FREEZER_REPAIR: &str = "repair";
+/// Command line argument `backup --restore`
+pub const ARG_RESTORE: &str = "restore";
 
 /// Command line arguments group `ba` for block assembler.
 const GROUP_BA: &str = "ba";
@@ -154,7 +168,10 @@ pub fn basic_app() -> Command {
         .subcommand(stats())
         .subcommand(reset_data())
         .subcommand(peer_id())
-        .subcommand(migrate());
+        .subcommand(migrate())
+        .subcommand(freezer())
+        .subcommand(backup())
+        .subcommand(rebuild_tx_index());
 
     #[cfg(not(target_os = "windows"))]
     let command = command.subcommand(daemon());
@@ -349,6 +366,7 @@ fn replay() -> Command {
         .override_help("
             --tmp-target <tmp> --profile 1 10,\n
             --tmp-target <tmp> --sanity-check,\n
+            --tmp-target <tmp> --block <hash>,\n
         ")
         .arg(Arg::new(ARG_TMP_TARGET).long(ARG_TMP_TARGET).value_parser(clap::builder::PathBufValueParser::new()).action(clap::ArgAction::Set).required(true).help(
             "Specify a target path. The profile command makes a temporary directory within the specified target path. This temporary directory will be automatically deleted when the command completes.",
@@ -372,9 +390,16 @@ fn replay() -> Command {
         .arg(
             Arg::new(ARG_FULL_VERIFICATION).long(ARG_FULL_VERIFICATION).action(clap::ArgAction::SetTrue).help("Enable sanity check")
         )
+        .arg(
+            Arg::new(ARG_BLOCK)
+                .long(ARG_BLOCK)
+                .value_parser(is_h256)
+                .action(clap::ArgAction::Set)
+                .help("Verify only the block with the specified hash on top of its parent state, and print a JSON report with the time spent in each verification stage"),
+        )
         .group(
             ArgGroup::new("mode")
-                .args([ARG_PROFILE, ARG_SANITY_CHECK])
+                .args([ARG_PROFILE, ARG_SANITY_CHECK, ARG_BLOCK])
                 .required(true)
         )
 }
@@ -430,6 +455,88 @@ fn migrate() -> Command {
         )
 }
 
+fn freezer() -> Command {
+    Command::new(CMD_FREEZER)
+        .about("Verify or repair the freezer files of the ancient blocks")
+        .arg(
+            Arg::new(ARG_FREEZER_VERIFY)
+                .long(ARG_FREEZER_VERIFY)
+                .action(clap::ArgAction::SetTrue)
+                .help(
+                    "Check the checksums and the encoding of all frozen blocks. \
+                    If any block is corrupted, ExitCode(113) is returned",
+                ),
+        )
+        .arg(
+            Arg::new(ARG_FREEZER_REPAIR)
+                .long(ARG_FREEZER_REPAIR)
+                .action(clap::ArgAction::SetTrue)
+                .help(
+                    "Rebuild the index file from the data files. The data files are never modified, \
+                    the repair fails if any block can't be recovered",
+                ),
+        )
+        .arg(
+            Arg::new(ARG_FORCE)
+                .long(ARG_FORCE)
+                .action(clap::ArgAction::SetTrue)
+                .requires(ARG_FREEZER_REPAIR)
+                .help(
+                    "Drop the blocks from the first one which can't be recovered when repairing. \
+                    The dropped blocks are lost, back up the freezer directory first",
+                ),
+        )
+        .group(
+            ArgGroup::new("mode")
+                .args([ARG_FREEZER_VERIFY, ARG_FREEZER_REPAIR])
+                .multiple(true)
+                .required(true),
+        )
+}
+
+fn backup() -> Command {
+    Command::new(CMD_BACKUP)
+        .about("Back up the chain data, or restore the chain data from a backup")
+        .arg(
+            Arg::new(ARG_TARGET)
+                .short('t')
+                .long(ARG_TARGET)
+                .value_name("path")
+                .value_parser(clap::builder::PathBufValueParser::new())
+                .help(
+                    "Create a backup in the target path, which must not exist. \
+                    The node must be stopped, use the RPC `create_backup` for a running node",
+                ),
+        )
+        .arg(
+            Arg::new(ARG_RESTORE)
+                .long(ARG_RESTORE)
+                .value_name("path")
+                .value_parser(clap::builder::PathBufValueParser::new())
+                .help(
+                    "Validate the backup in the path against the chain spec, \
+                    then restore it into the data directory",
+                ),
+        )
+        .arg(
+            Arg::new(ARG_FORCE)
+                .long(ARG_FORCE)
+                .action(clap::ArgAction::SetTrue)
+                .requires(ARG_RESTORE)
+                .help("Overwrite the existing database and freezer without interactive prompt"),
+        )
+        .group(
+            ArgGroup::new("mode")
+                .args([ARG_TARGET, ARG_RESTORE])
+                .required(true),
+        )
+}
+
+fn rebuild_tx_index() -> Command {
+    Command::new(CMD_REBUILD_TX_INDEX)
+        .about("Rebuild the transaction index pruned by the option `store.tx_index_prune_depth`")
+}
+
 #[cfg(not(target_os = "windows"))]
 fn daemon() -> Command {
     Command::new(CMD_DAEMON)
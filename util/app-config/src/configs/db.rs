@@ -23,6 +23,23 @@ pub struct Config {
     ///
     /// More details can be found in [the official tuning guide](https://github.com/facebook/rocksdb/wiki/RocksDB-Tuning-Guide).
     pub options_file: Option<PathBuf>,
+    /// The key-value backend of the chain database.
+    ///
+    /// The `memory` backend keeps all data in memory and loses it on exit, it's meant for
+    /// tests and ephemeral dev chains.
+    #[serde(default)]
+    pub backend: Backend,
+}
+
+/// The key-value backend of the chain database.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// RocksDB persisted in `path`.
+    #[default]
+    RocksDB,
+    /// The in-memory backend.
+    Memory,
 }
 
 impl Config {
@@ -37,6 +37,8 @@ pub enum WorkerConfig {
     Dummy(DummyConfig),
     /// Eaglesong worker which solves Eaglesong PoW.
     EaglesongSimple(EaglesongSimpleConfig),
+    /// Stratum server which distributes Eaglesong work to the downstream miners.
+    Stratum(StratumConfig),
 }
 
 /// Dummy worker config options.
@@ -83,6 +85,37 @@ pub struct EaglesongSimpleConfig {
     pub extra_hash_function: Option<ExtraHashFunction>,
 }
 
+/// Stratum server config options.
+///
+/// The server speaks Stratum v1 to the downstream miners, validates their shares, and submits
+/// the shares which meet the block target as new blocks.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct StratumConfig {
+    /// The address to listen for the downstream miners.
+    pub listen: SocketAddr,
+    /// The expected hashes to find a share.
+    ///
+    /// A share is accepted when its PoW output is not larger than `U256::MAX / share_difficulty`,
+    /// or the block target if it is easier.
+    #[serde(default = "default_share_difficulty")]
+    pub share_difficulty: u64,
+    /// Whether to perform an extra round of hash function on the Eaglesong output.
+    #[serde(default)]
+    pub extra_hash_function: Option<ExtraHashFunction>,
+    /// The max count of the downstream connections, the new connections are closed once reached.
+    #[serde(default = "default_max_connections")]
+    pub max_connections: usize,
+}
+
+const fn default_share_difficulty() -> u64 {
+    1 << 32
+}
+
+const fn default_max_connections() -> usize {
+    256
+}
+
 /// Specifies the hash function.
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Eq)]
 pub enum ExtraHashFunction {
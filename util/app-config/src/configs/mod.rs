@@ -10,12 +10,12 @@ mod rpc;
 mod store;
 mod tx_pool;
 
-pub use db::Config as DBConfig;
+pub use db::{Backend as DBBackend, Config as DBConfig};
 pub use indexer::{IndexerConfig, IndexerSyncConfig};
 pub use memory_tracker::Config as MemoryTrackerConfig;
 pub use miner::{
     ClientConfig as MinerClientConfig, Config as MinerConfig, DummyConfig, EaglesongSimpleConfig,
-    ExtraHashFunction, WorkerConfig as MinerWorkerConfig,
+    ExtraHashFunction, StratumConfig as MinerStratumConfig, WorkerConfig as MinerWorkerConfig,
 };
 pub use network::{
     default_support_all_protocols, Config as NetworkConfig, HeaderMapConfig, SupportProtocol,
@@ -25,7 +25,7 @@ pub use network_alert::Config as NetworkAlertConfig;
 pub use notify::Config as NotifyConfig;
 pub use rich_indexer::{DBDriver, RichIndexerConfig};
 pub use rpc::{Config as RpcConfig, Module as RpcModule};
-pub use store::Config as StoreConfig;
-pub use tx_pool::{BlockAssemblerConfig, TxPoolConfig};
+pub use store::{Config as StoreConfig, FreezerCompression};
+pub use tx_pool::{BlockAssemblerConfig, BlockTemplatePolicyConfig, TxPoolConfig};
 
 pub(crate) use network::{generate_random_key, read_secret_key, write_secret_to_file};
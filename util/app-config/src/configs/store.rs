@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 // The default values are set in the legacy version.
 /// Store config options.
@@ -18,4 +18,34 @@ pub struct Config {
     pub block_extensions_cache_size: usize,
     /// whether enable freezer
     pub freezer_enable: bool,
+    /// Enables pruned mode when set, the frozen block bodies older than this depth from
+    /// the tip are deleted. Headers, epochs, block extensions and live cells are kept.
+    ///
+    /// Only takes effect when the freezer is enabled. It must cover the finalization delay and
+    /// the reorg window, which is 3611 blocks on the mainnet.
+    pub freezer_prune_depth: Option<u64>,
+    /// The compression algorithm of the newly frozen blocks.
+    ///
+    /// The blocks frozen before are still readable after the algorithm is changed.
+    pub freezer_compression: FreezerCompression,
+    /// Disables the full transaction index when set.
+    ///
+    /// Only the transactions committed in the latest `tx_index_prune_depth` blocks and the
+    /// transactions which still have live cells are indexed, the others can not be queried by
+    /// `get_transaction` anymore. Remove the option to rebuild the full index in background,
+    /// or use `ckb rebuild-tx-index` to rebuild it offline.
+    pub tx_index_prune_depth: Option<u64>,
+}
+
+/// Freezer compression algorithm.
+#[derive(Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Hash, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FreezerCompression {
+    /// Store blocks without compression.
+    None,
+    /// Snappy compression.
+    #[default]
+    Snappy,
+    /// Zstd compression, with a shared dictionary trained on the frozen blocks.
+    Zstd,
 }
@@ -11,6 +11,10 @@ use url::Url;
 pub struct TxPoolConfig {
     /// Keep the transaction pool below <max_tx_pool_size> mb
     pub max_tx_pool_size: usize,
+    /// The size budget in bytes of the txs submitted locally. While their total size is within
+    /// it, they're evicted after all the other txs when the pool is full, otherwise they're
+    /// evicted as the others. No budget when it's 0.
+    pub max_local_tx_pool_size: usize,
     /// txs with lower fee rate than this will not be relayed or be mined
     #[serde(with = "FeeRateDef")]
     pub min_fee_rate: FeeRate,
@@ -30,6 +34,9 @@ pub struct TxPoolConfig {
     /// By default, it is a subdirectory of 'tx-pool' subdirectory under the data directory.
     #[serde(default)]
     pub persisted_data: PathBuf,
+    /// The interval in seconds to save the tx pool into `persisted_data` periodically, besides
+    /// the save on shutdown. The tx pool is only saved on shutdown when it's 0.
+    pub persist_interval_secs: u64,
     /// The recent reject record database directory path.
     ///
     /// By default, it is a subdirectory of 'tx-pool' subdirectory under the data directory.
@@ -37,6 +44,41 @@ pub struct TxPoolConfig {
     pub recent_reject: PathBuf,
     /// The expiration time for pool transactions in hours
     pub expiry_hours: u8,
+    /// The file to persist the transaction verification cache when the node has been shutdown,
+    /// which is loaded on start to avoid verifying the same transactions again.
+    ///
+    /// By default, it is a subdirectory of 'tx-pool' subdirectory under the data directory.
+    #[serde(default)]
+    pub persisted_verify_cache: PathBuf,
+    /// The max count of the verification cache entries to persist, the most recently used ones
+    /// are kept. The cache is not persisted when it's 0.
+    pub persisted_verify_cache_size: usize,
+    /// The max count of the local txs below `min_fee_rate` waiting in the pool for a child to pay
+    /// their fee. Such txs are rejected when it's 0, and the remote ones are always rejected.
+    pub max_low_fee_parents: usize,
+    /// The seconds a tx below `min_fee_rate` can wait in the pool, it's evicted then unless the
+    /// package fee rate of the tx and its descendants reaches `min_fee_rate`.
+    pub low_fee_parent_window_secs: u64,
+    /// The database directory path of the transaction lifecycle events.
+    ///
+    /// By default, it is a subdirectory of 'tx-pool' subdirectory under the data directory.
+    #[serde(default)]
+    pub tx_history: PathBuf,
+    /// The lifecycle events time to live by days
+    pub keep_tx_history_days: u8,
+    /// The count limit of the txs with lifecycle events
+    pub keep_tx_history_count: u64,
+    /// The interval in seconds to rebroadcast the txs submitted locally until they're committed,
+    /// it's doubled after each rebroadcast up to 1 day. The txs are not rebroadcast when it's 0,
+    /// which is the default.
+    pub local_rebroadcast_interval_secs: u64,
+    /// The max count of the txs in the pool referencing the same cell dep, since they're all
+    /// invalidated when the cell is consumed. The cell deps in the genesis block, such as the system
+    /// scripts, are not limited. No limit when it's 0.
+    pub max_cell_dep_references: usize,
+    /// A tx consuming a cell referenced as cell dep by at least this many txs in the pool must pay
+    /// a fee higher than the total fee of the txs invalidated. No fee bump is required when it's 0.
+    pub heavy_cell_dep_references: usize,
 }
 
 /// Block assembler config options.
@@ -71,6 +113,34 @@ pub struct BlockAssemblerConfig {
     /// Notify timeout
     #[serde(default = "default_notify_timeout_millis")]
     pub notify_timeout_millis: u64,
+    /// The rules to select the transactions in the block template.
+    #[serde(default)]
+    pub policy: BlockTemplatePolicyConfig,
+}
+
+/// The rules to select the transactions in the block template.
+///
+/// The transactions are packed by the ancestors fee rate greedily when all the options are
+/// default.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct BlockTemplatePolicyConfig {
+    /// The max total cycles of the transactions in a template, the consensus limit is used when
+    /// it's not set or larger than the consensus limit.
+    #[serde(default)]
+    pub max_cycles: Option<Cycle>,
+    /// The transactions whose inputs or outputs use any of these lock or type script hashes are
+    /// never packed.
+    #[serde(default)]
+    pub exclude_script_hashes: Vec<H256>,
+    /// The transactions whose inputs or outputs use any of these lock script hashes are packed
+    /// before the others.
+    #[serde(default)]
+    pub priority_lock_hashes: Vec<H256>,
+    /// The bytes reserved for the priority transactions, the others can't use the reserved
+    /// space even if it's not filled.
+    #[serde(default)]
+    pub priority_reserved_bytes: u64,
 }
 
 const fn default_use_binary_version_as_message_prefix() -> bool {
@@ -105,6 +175,18 @@ impl TxPoolConfig {
             &mut self.recent_reject,
             "recent_reject",
         );
+        _adjust(
+            root_dir,
+            tx_pool_dir.as_ref(),
+            &mut self.persisted_verify_cache,
+            "persisted_verify_cache",
+        );
+        _adjust(
+            root_dir,
+            tx_pool_dir.as_ref(),
+            &mut self.tx_history,
+            "tx_history",
+        );
     }
 }
 
@@ -1,3 +1,4 @@
+use crate::FreezerCompression;
 use serde::Deserialize;
 
 #[derive(Clone, Debug, Deserialize)]
@@ -13,6 +14,12 @@ pub(crate) struct StoreConfig {
     block_extensions_cache_size: usize,
     #[serde(default = "default_freezer_enable")]
     freezer_enable: bool,
+    #[serde(default)]
+    freezer_prune_depth: Option<u64>,
+    #[serde(default)]
+    freezer_compression: FreezerCompression,
+    #[serde(default)]
+    tx_index_prune_depth: Option<u64>,
 }
 
 const fn default_block_extensions_cache_size() -> usize {
@@ -40,6 +47,9 @@ impl Default for StoreConfig {
             cellbase_cache_size: None,
             block_extensions_cache_size: default_block_extensions_cache_size(),
             freezer_enable: default_freezer_enable(),
+            freezer_prune_depth: None,
+            freezer_compression: FreezerCompression::default(),
+            tx_index_prune_depth: None,
         }
     }
 }
@@ -55,6 +65,9 @@ impl From<StoreConfig> for crate::StoreConfig {
             cellbase_cache_size: _,
             block_extensions_cache_size,
             freezer_enable,
+            freezer_prune_depth,
+            freezer_compression,
+            tx_index_prune_depth,
         } = input;
         Self {
             header_cache_size,
@@ -64,6 +77,9 @@ impl From<StoreConfig> for crate::StoreConfig {
             block_uncles_cache_size,
             block_extensions_cache_size,
             freezer_enable,
+            freezer_prune_depth,
+            freezer_compression,
+            tx_index_prune_depth,
         }
     }
 }
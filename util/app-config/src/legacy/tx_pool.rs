@@ -17,6 +17,12 @@ const DEFAULT_MAX_ANCESTORS_COUNT: usize = 2_000;
 const DEFAULT_EXPIRY_HOURS: u8 = 12;
 // Default max_tx_pool_size 180mb
 const DEFAULT_MAX_TX_POOL_SIZE: usize = 180_000_000;
+// Default max_local_tx_pool_size 18mb
+const DEFAULT_MAX_LOCAL_TX_POOL_SIZE: usize = 18_000_000;
+// Default interval in seconds to save the tx pool periodically
+const DEFAULT_PERSIST_INTERVAL_SECS: u64 = 600;
+// Default seconds a tx below the min fee rate waits for a child to pay its fee
+const DEFAULT_LOW_FEE_PARENT_WINDOW_SECS: u64 = 60;
 
 #[derive(Clone, Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -24,6 +30,8 @@ const DEFAULT_MAX_TX_POOL_SIZE: usize = 180_000_000;
 pub(crate) struct TxPoolConfig {
     #[serde(default = "default_max_tx_pool_size")]
     max_tx_pool_size: usize,
+    #[serde(default = "default_max_local_tx_pool_size")]
+    max_local_tx_pool_size: usize,
     max_mem_size: Option<usize>,
     max_cycles: Option<Cycle>,
     pub(crate) max_verify_cache_size: Option<usize>,
@@ -41,10 +49,32 @@ pub(crate) struct TxPoolConfig {
     max_ancestors_count: usize,
     #[serde(default)]
     persisted_data: PathBuf,
+    #[serde(default = "default_persist_interval_secs")]
+    persist_interval_secs: u64,
     #[serde(default)]
     recent_reject: PathBuf,
     #[serde(default = "default_expiry_hours")]
     expiry_hours: u8,
+    #[serde(default)]
+    persisted_verify_cache: PathBuf,
+    #[serde(default)]
+    persisted_verify_cache_size: usize,
+    #[serde(default)]
+    max_low_fee_parents: usize,
+    #[serde(default = "default_low_fee_parent_window_secs")]
+    low_fee_parent_window_secs: u64,
+    #[serde(default)]
+    tx_history: PathBuf,
+    #[serde(default = "default_keep_tx_history_days")]
+    keep_tx_history_days: u8,
+    #[serde(default = "default_keep_tx_history_count")]
+    keep_tx_history_count: u64,
+    #[serde(default)]
+    local_rebroadcast_interval_secs: u64,
+    #[serde(default)]
+    max_cell_dep_references: usize,
+    #[serde(default)]
+    heavy_cell_dep_references: usize,
 }
 
 fn default_keep_rejected_tx_hashes_days() -> u8 {
@@ -55,6 +85,14 @@ fn default_keep_rejected_tx_hashes_count() -> u64 {
     10_000_000
 }
 
+fn default_keep_tx_history_days() -> u8 {
+    7
+}
+
+fn default_keep_tx_history_count() -> u64 {
+    1_000_000
+}
+
 fn default_expiry_hours() -> u8 {
     DEFAULT_EXPIRY_HOURS
 }
@@ -63,10 +101,22 @@ fn default_max_tx_pool_size() -> usize {
     DEFAULT_MAX_TX_POOL_SIZE
 }
 
+fn default_max_local_tx_pool_size() -> usize {
+    DEFAULT_MAX_LOCAL_TX_POOL_SIZE
+}
+
 fn default_min_rbf_rate() -> FeeRate {
     DEFAULT_MIN_RBF_RATE
 }
 
+fn default_persist_interval_secs() -> u64 {
+    DEFAULT_PERSIST_INTERVAL_SECS
+}
+
+fn default_low_fee_parent_window_secs() -> u64 {
+    DEFAULT_LOW_FEE_PARENT_WINDOW_SECS
+}
+
 impl Default for crate::TxPoolConfig {
     fn default() -> Self {
         TxPoolConfig::default().into()
@@ -78,6 +128,7 @@ impl Default for TxPoolConfig {
         Self {
             max_mem_size: None,
             max_tx_pool_size: DEFAULT_MAX_TX_POOL_SIZE,
+            max_local_tx_pool_size: DEFAULT_MAX_LOCAL_TX_POOL_SIZE,
             max_cycles: None,
             max_verify_cache_size: None,
             max_conflict_cache_size: None,
@@ -89,8 +140,19 @@ impl Default for TxPoolConfig {
             max_tx_verify_cycles: DEFAULT_MAX_TX_VERIFY_CYCLES,
             max_ancestors_count: DEFAULT_MAX_ANCESTORS_COUNT,
             persisted_data: Default::default(),
+            persist_interval_secs: DEFAULT_PERSIST_INTERVAL_SECS,
             recent_reject: Default::default(),
             expiry_hours: DEFAULT_EXPIRY_HOURS,
+            persisted_verify_cache: Default::default(),
+            persisted_verify_cache_size: 0,
+            max_low_fee_parents: 0,
+            low_fee_parent_window_secs: DEFAULT_LOW_FEE_PARENT_WINDOW_SECS,
+            tx_history: Default::default(),
+            keep_tx_history_days: default_keep_tx_history_days(),
+            keep_tx_history_count: default_keep_tx_history_count(),
+            local_rebroadcast_interval_secs: 0,
+            max_cell_dep_references: 0,
+            heavy_cell_dep_references: 0,
         }
     }
 }
@@ -100,6 +162,7 @@ impl From<TxPoolConfig> for crate::TxPoolConfig {
         let TxPoolConfig {
             max_mem_size: _,
             max_tx_pool_size,
+            max_local_tx_pool_size,
             max_cycles: _,
             max_verify_cache_size: _,
             max_conflict_cache_size: _,
@@ -111,12 +174,24 @@ impl From<TxPoolConfig> for crate::TxPoolConfig {
             max_tx_verify_cycles,
             max_ancestors_count,
             persisted_data,
+            persist_interval_secs,
             recent_reject,
             expiry_hours,
+            persisted_verify_cache,
+            persisted_verify_cache_size,
+            max_low_fee_parents,
+            low_fee_parent_window_secs,
+            tx_history,
+            keep_tx_history_days,
+            keep_tx_history_count,
+            local_rebroadcast_interval_secs,
+            max_cell_dep_references,
+            heavy_cell_dep_references,
         } = input;
 
         Self {
             max_tx_pool_size,
+            max_local_tx_pool_size,
             min_fee_rate,
             min_rbf_rate,
             max_tx_verify_cycles,
@@ -124,8 +199,19 @@ impl From<TxPoolConfig> for crate::TxPoolConfig {
             keep_rejected_tx_hashes_days,
             keep_rejected_tx_hashes_count,
             persisted_data,
+            persist_interval_secs,
             recent_reject,
             expiry_hours,
+            persisted_verify_cache,
+            persisted_verify_cache_size,
+            max_low_fee_parents,
+            low_fee_parent_window_secs,
+            tx_history,
+            keep_tx_history_days,
+            keep_tx_history_count,
+            local_rebroadcast_interval_secs,
+            max_cell_dep_references,
+            heavy_cell_dep_references,
         }
     }
 }
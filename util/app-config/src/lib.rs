@@ -15,8 +15,8 @@ pub use app_config::{
     AppConfig, CKBAppConfig, ChainConfig, LogConfig, MetricsConfig, MinerAppConfig,
 };
 pub use args::{
-    DaemonArgs, ExportArgs, ImportArgs, InitArgs, MigrateArgs, MinerArgs, PeerIDArgs, ReplayArgs,
-    ResetDataArgs, RunArgs, StatsArgs,
+    BackupArgs, DaemonArgs, ExportArgs, FreezerArgs, ImportArgs, InitArgs, MigrateArgs, MinerArgs,
+    PeerIDArgs, RebuildTxIndexArgs, ReplayArgs, ResetDataArgs, RunArgs, StatsArgs,
 };
 use ckb_logger::info;
 pub use configs::*;
@@ -147,6 +147,48 @@ impl Setup {
         })
     }
 
+    /// Executes `ckb freezer`.
+    pub fn freezer(self, matches: &ArgMatches) -> Result<FreezerArgs, ExitCode> {
+        let config = self.config.into_ckb()?;
+        let verify = matches.get_flag(cli::ARG_FREEZER_VERIFY);
+        let repair = matches.get_flag(cli::ARG_FREEZER_REPAIR);
+        let force = matches.get_flag(cli::ARG_FORCE);
+
+        Ok(FreezerArgs {
+            config,
+            verify,
+            repair,
+            force,
+        })
+    }
+
+    /// Executes `ckb backup`.
+    pub fn backup(self, matches: &ArgMatches) -> Result<BackupArgs, ExitCode> {
+        let consensus = self.consensus()?;
+        let chain_spec_hash = self.chain_spec()?.hash;
+        let config = self.config.into_ckb()?;
+        let target = matches.get_one::<PathBuf>(cli::ARG_TARGET).cloned();
+        let restore = matches.get_one::<PathBuf>(cli::ARG_RESTORE).cloned();
+        let force = matches.get_flag(cli::ARG_FORCE);
+
+        Ok(BackupArgs {
+            config,
+            consensus,
+            chain_spec_hash,
+            target,
+            restore,
+            force,
+        })
+    }
+
+    /// Executes `ckb rebuild-tx-index`.
+    pub fn rebuild_tx_index(self) -> Result<RebuildTxIndexArgs, ExitCode> {
+        let consensus = self.consensus()?;
+        let config = self.config.into_ckb()?;
+
+        Ok(RebuildTxIndexArgs { config, consensus })
+    }
+
     /// Executes `ckb miner`.
     pub fn miner(self, matches: &ArgMatches) -> Result<MinerArgs, ExitCode> {
         let spec = self.chain_spec()?;
@@ -185,6 +227,9 @@ impl Setup {
         };
         let sanity_check = matches.get_flag(cli::ARG_SANITY_CHECK);
         let full_verification = matches.get_flag(cli::ARG_FULL_VERIFICATION);
+        let block = matches
+            .get_one::<String>(cli::ARG_BLOCK)
+            .and_then(|s| H256::from_str(&s[2..]).ok());
         Ok(ReplayArgs {
             config,
             consensus,
@@ -192,6 +237,7 @@ impl Setup {
             profile,
             sanity_check,
             full_verification,
+            block,
         })
     }
 
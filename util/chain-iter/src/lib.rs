@@ -1,19 +1,23 @@
 //! TODO(doc): @quake
-use ckb_store::ChainStore;
+use ckb_store::{BlockPruned, ChainStore};
 use ckb_types::{core::BlockNumber, core::BlockView};
 
 /// TODO(doc): @quake
 // An iterator over the entries of a `Chain`.
+//
+// The iteration stops after yielding an error at the first block whose body is pruned.
 pub struct ChainIterator<'a, S: ChainStore> {
     store: &'a S,
-    current: Option<BlockView>,
+    current: Option<Result<BlockView, BlockPruned>>,
     tip: BlockNumber,
 }
 
 impl<'a, S: ChainStore> ChainIterator<'a, S> {
     /// TODO(doc): @quake
     pub fn new(store: &'a S) -> Self {
-        let current = store.get_block_hash(0).and_then(|h| store.get_block(&h));
+        let current = store
+            .get_block_hash(0)
+            .and_then(|h| store.get_block(&h).transpose());
         let tip = store.get_tip_header().expect("store inited").number();
         ChainIterator {
             store,
@@ -36,27 +40,28 @@ impl<'a, S: ChainStore> ChainIterator<'a, S> {
 }
 
 impl<'a, S: ChainStore> Iterator for ChainIterator<'a, S> {
-    type Item = BlockView;
+    type Item = Result<BlockView, BlockPruned>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let current = self.current.take();
 
         self.current = match current {
-            Some(ref b) => {
+            Some(Ok(ref b)) => {
                 if let Some(block_hash) = self.store.get_block_hash(b.header().number() + 1) {
-                    self.store.get_block(&block_hash)
+                    self.store.get_block(&block_hash).transpose()
                 } else {
                     None
                 }
             }
-            None => None,
+            Some(Err(_)) | None => None,
         };
         current
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
         match self.current {
-            Some(ref b) => (1, Some((self.tip - b.header().number() + 1) as usize)),
+            Some(Ok(ref b)) => (1, Some((self.tip - b.header().number() + 1) as usize)),
+            Some(Err(_)) => (1, Some(1)),
             //The default implementation returns (0, None) which is correct for any iterator.
             None => (0, None),
         }
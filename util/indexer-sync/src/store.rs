@@ -1,7 +1,10 @@
 use crate::error::Error;
 
 use ckb_db_schema::Col;
-use ckb_store::{ChainStore, Freezer, StoreCache};
+use ckb_store::{
+    backend::{StoreIter, StoreSlice},
+    ChainStore, Freezer, StoreCache,
+};
 use rocksdb::{
     ops::OpenCF, prelude::*, ColumnFamilyDescriptor, DBIterator, DBPinnableSlice, IteratorMode,
     SecondaryDB as SecondaryRocksDB, SecondaryOpenDescriptor,
@@ -112,12 +115,13 @@ impl ChainStore for SecondaryDB {
         None
     }
 
-    fn get(&self, col: Col, key: &[u8]) -> Option<DBPinnableSlice> {
+    fn get(&self, col: Col, key: &[u8]) -> Option<StoreSlice> {
         self.get_pinned(col, key)
             .expect("db operation should be ok")
+            .map(StoreSlice::Pinned)
     }
 
-    fn get_iter(&self, col: Col, mode: IteratorMode) -> DBIterator {
-        self.iter(col, mode).expect("db operation should be ok")
+    fn get_iter(&self, col: Col, mode: IteratorMode) -> StoreIter {
+        Box::new(self.iter(col, mode).expect("db operation should be ok"))
     }
 }
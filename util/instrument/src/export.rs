@@ -45,7 +45,7 @@ impl Export {
         let snapshot = self.shared.snapshot();
 
         for block in ChainIterator::new(snapshot.as_ref()) {
-            let block: JsonBlock = block.into();
+            let block: JsonBlock = block?.into();
             let encoded = serde_json::to_vec(&block)?;
             writer.write_all(&encoded)?;
             writer.write_all(b"\n")?;
@@ -72,7 +72,7 @@ impl Export {
                 .progress_chars("##-"),
         );
         for block in blocks_iter {
-            let block: JsonBlock = block.into();
+            let block: JsonBlock = block?.into();
             let encoded = serde_json::to_vec(&block)?;
             writer.write_all(&encoded)?;
             writer.write_all(b"\n")?;
@@ -1,6 +1,6 @@
 use crate::{
     BlockNumber, Byte32, Cycle, EpochNumberWithFraction, Header, JsonBytes, ProposalShortId,
-    Timestamp, Transaction, Uint32, Uint64, Version,
+    Script, Timestamp, Transaction, Uint32, Uint64, Version,
 };
 use ckb_types::{packed, prelude::*, H256};
 use schemars::JsonSchema;
@@ -174,6 +174,41 @@ impl From<BlockTemplate> for packed::Block {
     }
 }
 
+/// The extra parameters to customize a block template for a single request.
+///
+/// The node builds a one-off template from the current one, which is not shared with other
+/// miners and not pushed to the configured notify URLs.
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BlockTemplateParams {
+    /// The lock script which receives the block reward.
+    ///
+    /// **Optional:** the default is the lock configured in `[block_assembler]`.
+    #[serde(default)]
+    pub cellbase_lock: Option<Script>,
+    /// The message appended to the cellbase witness.
+    ///
+    /// **Optional:** the default is the message configured in `[block_assembler]`.
+    #[serde(default)]
+    pub cellbase_message: Option<JsonBytes>,
+    /// The max cycles of the committed transactions.
+    ///
+    /// **Optional:** the default is the consensus limit. A larger value is capped to it.
+    #[serde(default)]
+    pub cycles_limit: Option<Cycle>,
+    /// The transactions that must be committed, if they are proposed and still valid.
+    ///
+    /// Their ancestors in the pool are committed as well. They are packed before the other
+    /// transactions, as long as the limits allow.
+    #[serde(default)]
+    pub include_transactions: Vec<H256>,
+    /// The transactions that must not be committed or proposed.
+    ///
+    /// Their descendants in the pool are not committed either.
+    #[serde(default)]
+    pub exclude_transactions: Vec<H256>,
+}
+
 /// The uncle block template of the new block for miners.
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 pub struct UncleTemplate {
@@ -1,3 +1,5 @@
+use crate::{BlockNumber, Timestamp};
+use ckb_types::H256;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -56,3 +58,49 @@ pub struct MainLoggerConfig {
     /// `null` means keeping the current option unchanged.
     pub color: Option<bool>,
 }
+
+/// A block marked invalid by the chain.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+pub struct InvalidBlock {
+    /// The block hash.
+    pub hash: H256,
+    /// The block number.
+    pub number: BlockNumber,
+    /// The unix timestamp in milliseconds when the block was marked invalid.
+    pub timestamp: Timestamp,
+    /// Whether the block was invalidated manually via the RPC `invalidate_block`.
+    pub manual: bool,
+    /// The verification error, or the reason of the manual invalidation.
+    pub reason: String,
+}
+
+/// The progress of the transaction index.
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TxIndexStatus {
+    /// The transactions of all the main chain blocks are indexed.
+    Complete,
+    /// The transactions of the blocks up to `number` are pruned from the index.
+    Pruned,
+    /// The index is being rebuilt, the transactions of the blocks up to `number` are not indexed
+    /// yet.
+    Rebuilding,
+    /// The transactions of the blocks up to `number` can't be indexed, since the block bodies
+    /// are pruned.
+    Incomplete,
+}
+
+/// The state of the background thread maintaining the transaction index.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+pub struct TxIndexState {
+    /// The progress of the index.
+    pub status: TxIndexStatus,
+    /// The number of the latest block whose transactions are not indexed, `null` if the index
+    /// is complete.
+    pub number: Option<BlockNumber>,
+    /// The number of the block which failed to be indexed or pruned, it's tried again in the
+    /// next round.
+    pub error_number: Option<BlockNumber>,
+    /// The error of the failed block.
+    pub error: Option<String>,
+}
@@ -1,4 +1,4 @@
-use crate::{Cycle, OutPoint};
+use crate::{CellOutput, Cycle, EpochNumberWithFraction, Header, JsonBytes, OutPoint, Timestamp};
 use ckb_types::H256;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -23,3 +23,121 @@ pub enum DaoWithdrawingCalculationKind {
     /// the out point of the withdrawing phase 1 transaction
     WithdrawingOutPoint(OutPoint),
 }
+
+/// A cell injected by the RPC method `dry_run_transaction_with_overrides`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+pub struct CellOverride {
+    /// The out point of the cell, which is usually not on chain yet.
+    pub out_point: OutPoint,
+    /// The cell fields.
+    pub output: CellOutput,
+    /// The cell data.
+    pub data: JsonBytes,
+    /// The hash of the block which the cell is created in, which must be a block in the
+    /// canonical chain or an injected header. It is required by the relative `since` checks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_hash: Option<H256>,
+}
+
+/// The chain state overrides of the RPC method `dry_run_transaction_with_overrides`.
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+pub struct StateOverrides {
+    /// The cells which are resolved before the live cells in the chain.
+    #[serde(default)]
+    pub cells: Vec<CellOverride>,
+    /// The headers which can be used as header deps, as if they are in the canonical chain.
+    #[serde(default)]
+    pub headers: Vec<Header>,
+    /// Overrides the epoch of the tip block. (**Optional**)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<EpochNumberWithFraction>,
+    /// Overrides the median time of the past blocks as of the tip block, which is used by the
+    /// `since` checks in timestamps. (**Optional**)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<Timestamp>,
+}
+
+/// The type of a script group.
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptGroupType {
+    /// The group of the inputs which have the same lock script.
+    Lock,
+    /// The group of the inputs and outputs which have the same type script.
+    Type,
+}
+
+/// The syscall which starts a child VM.
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChildVmKind {
+    /// The `exec` syscall, which replaces the program of the current VM.
+    Exec,
+    /// The `spawn` syscall, which runs the program in a new VM and waits for its exit.
+    Spawn,
+}
+
+/// A child VM started by a script.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+pub struct ChildVmTrace {
+    /// The syscall which starts the VM.
+    pub kind: ChildVmKind,
+    /// The data hash of the loaded program.
+    pub program_hash: H256,
+    /// The arguments passed to the program.
+    pub argv: Vec<JsonBytes>,
+    /// The exit code of a spawned VM, null for exec or when the VM fails.
+    pub exit_code: Option<i8>,
+    /// The cycles consumed by a spawned VM, null for exec.
+    pub cycles: Option<Cycle>,
+}
+
+/// The execution details of a script group.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+pub struct ScriptGroupTrace {
+    /// The type of the script group.
+    pub group_type: ScriptGroupType,
+    /// The script hash of the script group.
+    pub script_hash: H256,
+    /// The cycles consumed by the script group.
+    pub cycles: Cycle,
+    /// The exit code of the root VM, null if the VM fails before exit.
+    pub exit_code: Option<i8>,
+    /// The messages printed by the debug syscall, in order.
+    pub debug_messages: Vec<String>,
+    /// The child VMs. The exec'd VMs are listed when the program is loaded and the spawned VMs
+    /// are listed when they exit.
+    pub child_vms: Vec<ChildVmTrace>,
+    /// The error of the script group, which names the index and the script type of its first
+    /// cell, null if the script group succeeds.
+    pub error: Option<String>,
+}
+
+/// Response result of the RPC method `trace_transaction`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+pub struct TransactionTrace {
+    /// The total cycles consumed by all the script groups.
+    pub cycles: Cycle,
+    /// The execution details of the lock script groups followed by the type script groups.
+    pub script_groups: Vec<ScriptGroupTrace>,
+}
+
+/// The cycles profile of a script group, response element of the RPC method
+/// `profile_transaction`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+pub struct ScriptGroupProfile {
+    /// The type of the script group.
+    pub group_type: ScriptGroupType,
+    /// The script hash of the script group.
+    pub script_hash: H256,
+    /// The cycles consumed by the script group.
+    pub cycles: Cycle,
+    /// The consumed cycles by call stacks in the folded format, one `frame;frame cycles` line
+    /// per stack, which can be rendered by flame graph tools.
+    ///
+    /// The frames are the function symbols of the script binary, or the function addresses when
+    /// the binary is stripped.
+    pub folded_stacks: String,
+    /// The error of the script group, null if the script group succeeds.
+    pub error: Option<String>,
+}
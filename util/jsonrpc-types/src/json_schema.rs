@@ -1,4 +1,4 @@
-use crate::{Byte32, Uint128, Uint32, Uint64};
+use crate::{Byte32, Int64, Uint128, Uint32, Uint64};
 use schemars::JsonSchema;
 
 macro_rules! impl_json_schema_for_type {
@@ -18,6 +18,7 @@ impl_json_schema_for_type!(Byte32, [u8; 32], "Byte32");
 impl_json_schema_for_type!(Uint32, u32, "Uint32");
 impl_json_schema_for_type!(Uint64, u64, "Uint64");
 impl_json_schema_for_type!(Uint128, u128, "Uint128");
+impl_json_schema_for_type!(Int64, i64, "Int64");
 
 pub fn u256_json_schema(
     _schemars: &mut schemars::gen::SchemaGenerator,
@@ -23,7 +23,7 @@ mod tests;
 
 pub use self::alert::{Alert, AlertId, AlertMessage, AlertPriority};
 pub use self::block_template::{
-    BlockTemplate, CellbaseTemplate, TransactionTemplate, UncleTemplate,
+    BlockTemplate, BlockTemplateParams, CellbaseTemplate, TransactionTemplate, UncleTemplate,
 };
 pub use self::blockchain::{
     Block, BlockEconomicState, BlockFilter, BlockIssuance, BlockResponse, BlockView,
@@ -35,8 +35,13 @@ pub use self::blockchain::{
 };
 pub use self::bytes::JsonBytes;
 pub use self::cell::{CellData, CellInfo, CellWithStatus};
-pub use self::debug::{ExtraLoggerConfig, MainLoggerConfig};
-pub use self::experiment::{DaoWithdrawingCalculationKind, EstimateCycles};
+pub use self::debug::{
+    ExtraLoggerConfig, InvalidBlock, MainLoggerConfig, TxIndexState, TxIndexStatus,
+};
+pub use self::experiment::{
+    CellOverride, ChildVmKind, ChildVmTrace, DaoWithdrawingCalculationKind, EstimateCycles,
+    ScriptGroupProfile, ScriptGroupTrace, ScriptGroupType, StateOverrides, TransactionTrace,
+};
 pub use self::fee_rate::FeeRateDef;
 pub use self::fixed_bytes::Byte32;
 pub use self::info::{ChainInfo, DeploymentInfo, DeploymentPos, DeploymentState, DeploymentsInfo};
@@ -45,13 +50,14 @@ pub use self::net::{
     RemoteNodeProtocol, SyncState,
 };
 pub use self::pool::{
-    AncestorsScoreSortKey, EntryCompleted, OutputsValidator, PoolTransactionEntry,
-    PoolTransactionReject, PoolTxDetailInfo, RawTxPool, TxPoolEntries, TxPoolEntry, TxPoolIds,
-    TxPoolInfo,
+    AncestorsScoreSortKey, ConflictKind, EntryCompleted, LocalTransaction, OutputsValidator,
+    PoolTransactionConflict, PoolTransactionEntry, PoolTransactionReject, PoolTxDetailInfo,
+    RawTxPool, TransactionEvent, TransactionHistoryEvent, TxPoolDump, TxPoolDumpEntry,
+    TxPoolEntries, TxPoolEntry, TxPoolIds, TxPoolInfo,
 };
 pub use self::proposal_short_id::ProposalShortId;
 pub use self::subscription::Topic;
-pub use self::uints::{Uint128, Uint32, Uint64};
+pub use self::uints::{Int64, Uint128, Uint32, Uint64};
 pub use ckb_types::core::RationalU256;
 pub use indexer::{
     IndexerCell, IndexerCellType, IndexerCellsCapacity, IndexerOrder, IndexerPagination,
@@ -1,8 +1,12 @@
-use crate::{BlockNumber, Capacity, Cycle, Timestamp, TransactionView, Uint64};
+use crate::{
+    BlockNumber, Capacity, Cycle, Int64, OutPoint, Timestamp, Transaction, TransactionView, Uint32,
+    Uint64,
+};
 use ckb_types::core::service::PoolTransactionEntry as CorePoolTransactionEntry;
 use ckb_types::core::tx_pool::{
-    AncestorsScoreSortKey as CoreAncestorsScoreSortKey, PoolTxDetailInfo as CorePoolTxDetailInfo,
-    Reject, TxEntryInfo, TxPoolEntryInfo, TxPoolIds as CoreTxPoolIds, TxPoolInfo as CoreTxPoolInfo,
+    AncestorsScoreSortKey as CoreAncestorsScoreSortKey, ConflictKind as CoreConflictKind,
+    LocalTxInfo, PoolTxDetailInfo as CorePoolTxDetailInfo, Reject, TxConflict, TxEntryInfo,
+    TxEvent, TxPoolEntryInfo, TxPoolIds as CoreTxPoolIds, TxPoolInfo as CoreTxPoolInfo,
 };
 use ckb_types::prelude::Unpack;
 use ckb_types::H256;
@@ -285,6 +289,36 @@ impl From<CorePoolTxDetailInfo> for PoolTxDetailInfo {
     }
 }
 
+/// A transaction submitted locally, which is rebroadcast until committed.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+pub struct LocalTransaction {
+    /// The transaction hash.
+    pub tx_hash: H256,
+    /// The detailed status in tx-pool, `pending`, `gap`, `proposed`
+    pub entry_status: String,
+    /// The time added into tx-pool
+    pub timestamp: Timestamp,
+    /// The transaction fee.
+    pub fee: Capacity,
+    /// The times the transaction has been rebroadcast.
+    pub rebroadcast_count: Uint32,
+    /// The time of the next rebroadcast, null if the rebroadcast is disabled.
+    pub next_rebroadcast_at: Option<Timestamp>,
+}
+
+impl From<LocalTxInfo> for LocalTransaction {
+    fn from(info: LocalTxInfo) -> Self {
+        Self {
+            tx_hash: info.tx_hash.unpack(),
+            entry_status: info.entry_status,
+            timestamp: info.timestamp.into(),
+            fee: info.fee.into(),
+            rebroadcast_count: info.rebroadcast_count.into(),
+            next_rebroadcast_at: info.next_rebroadcast_at.map(Into::into),
+        }
+    }
+}
+
 /// TX reject message, `PoolTransactionReject` is a JSON object with following fields.
 ///    * `type`:  the Reject type with following enum values
 ///    * `description`: `string` - Detailed description about why the transaction is rejected.
@@ -326,6 +360,21 @@ pub enum PoolTransactionReject {
 
     /// Invalidated rejected
     Invalidated(String),
+
+    /// Transaction fee lower than config, and too many such transactions are waiting for a child
+    /// to pay their fee
+    ExceededLowFeeParentsLimit(String),
+
+    /// The fee of the transaction and its descendants is lower than config when the waiting
+    /// window ends
+    LowPackageFeeRate(String),
+
+    /// Too many transactions in the pool are referencing the same cell dep
+    ExceededCellDepReferencesLimit(String),
+
+    /// The fee of the transaction consuming the cells referenced as cell deps by many
+    /// transactions in the pool is lower than the total fee of them
+    LowCellDepChurnFee(String),
 }
 
 impl From<Reject> for PoolTransactionReject {
@@ -347,6 +396,57 @@ impl From<Reject> for PoolTransactionReject {
             Reject::Expiry(_) => Self::Expiry(format!("{reject}")),
             Reject::RBFRejected(_) => Self::RBFRejected(format!("{reject}")),
             Reject::Invalidated(_) => Self::Invalidated(format!("{reject}")),
+            Reject::ExceededLowFeeParentsLimit(_) => {
+                Self::ExceededLowFeeParentsLimit(format!("{reject}"))
+            }
+            Reject::LowPackageFeeRate(..) => Self::LowPackageFeeRate(format!("{reject}")),
+            Reject::ExceededCellDepReferencesLimit(..) => {
+                Self::ExceededCellDepReferencesLimit(format!("{reject}"))
+            }
+            Reject::LowCellDepChurnFee(..) => Self::LowCellDepChurnFee(format!("{reject}")),
+        }
+    }
+}
+
+/// The kind of a conflict which removes a transaction from the pool.
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// Replaced by a transaction paying a higher fee via RBF.
+    Replaced,
+    /// Double spent by a transaction committed in the chain.
+    DoubleSpent,
+}
+
+impl From<CoreConflictKind> for ConflictKind {
+    fn from(kind: CoreConflictKind) -> Self {
+        match kind {
+            CoreConflictKind::Replaced => Self::Replaced,
+            CoreConflictKind::DoubleSpent => Self::DoubleSpent,
+        }
+    }
+}
+
+/// A conflict which removes a transaction from the pool.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+pub struct PoolTransactionConflict {
+    /// The kind of the conflict.
+    pub kind: ConflictKind,
+    /// The hash of the transaction which wins the conflict.
+    pub conflicting_tx_hash: H256,
+    /// The out points contested by both transactions.
+    ///
+    /// For a transaction removed because its ancestor conflicts, these are the out points
+    /// contested by the ancestor.
+    pub out_points: Vec<OutPoint>,
+}
+
+impl From<TxConflict> for PoolTransactionConflict {
+    fn from(conflict: TxConflict) -> Self {
+        Self {
+            kind: conflict.kind.into(),
+            conflicting_tx_hash: conflict.conflicting_tx_hash.unpack(),
+            out_points: conflict.out_points.into_iter().map(Into::into).collect(),
         }
     }
 }
@@ -368,3 +468,106 @@ impl From<ckb_types::core::EntryCompleted> for EntryCompleted {
         }
     }
 }
+
+/// An event in the lifecycle of a transaction in the tx-pool.
+///
+/// `TransactionEvent` is a JSON object with the field `type` and the fields of the event type.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransactionEvent {
+    /// Received from a peer, or submitted locally via RPC.
+    Received {
+        /// The session index of the peer, `null` for local submission.
+        peer: Option<Uint64>,
+    },
+    /// Passed the verification.
+    Verified {
+        /// The consumed cycles.
+        cycles: Cycle,
+        /// The transaction fee.
+        fee: Capacity,
+    },
+    /// Entered the pending pool.
+    Pending,
+    /// Entered the gap pool, proposed but not committable yet.
+    Gap,
+    /// Entered the proposed pool.
+    Proposed,
+    /// Committed in a block of the canonical chain.
+    Committed {
+        /// The block number.
+        block_number: BlockNumber,
+        /// The block hash.
+        block_hash: H256,
+    },
+    /// Rejected by the pool, or removed from the pool, e.g., evicted or replaced by another
+    /// transaction.
+    Rejected {
+        /// The reason.
+        reason: PoolTransactionReject,
+    },
+    /// Removed from the pool via the RPC `remove_transaction`.
+    Removed,
+    /// Some events were dropped here because the node was too busy to record them, so the
+    /// history is incomplete.
+    Dropped {
+        /// The count of the dropped events.
+        count: Uint64,
+    },
+}
+
+impl From<TxEvent> for TransactionEvent {
+    fn from(event: TxEvent) -> Self {
+        match event {
+            TxEvent::Received(peer) => Self::Received {
+                peer: peer.map(|peer| (peer as u64).into()),
+            },
+            TxEvent::Verified(cycles, fee) => Self::Verified {
+                cycles: cycles.into(),
+                fee: fee.into(),
+            },
+            TxEvent::Pending => Self::Pending,
+            TxEvent::Gap => Self::Gap,
+            TxEvent::Proposed => Self::Proposed,
+            TxEvent::Committed(number, hash) => Self::Committed {
+                block_number: number.into(),
+                block_hash: hash.unpack(),
+            },
+            TxEvent::Rejected(reject) => Self::Rejected {
+                reason: reject.into(),
+            },
+            TxEvent::Removed => Self::Removed,
+        }
+    }
+}
+
+/// A timestamped event in the lifecycle of a transaction in the tx-pool.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+pub struct TransactionHistoryEvent {
+    /// The time when the event happened, in milliseconds.
+    pub timestamp: Timestamp,
+    /// The event.
+    pub event: TransactionEvent,
+}
+
+/// A transaction in the tx-pool dump with the metadata.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+pub struct TxPoolDumpEntry {
+    /// The transaction.
+    pub transaction: Transaction,
+    /// The unix timestamp when entering the tx-pool, in milliseconds.
+    pub timestamp: Timestamp,
+    /// The virtual fee delta set by `prioritise_transaction`.
+    pub fee_delta: Int64,
+    /// Whether the transaction is submitted locally.
+    pub local: bool,
+}
+
+/// The dump of the tx-pool, which can be loaded into another node.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
+pub struct TxPoolDump {
+    /// The version of the dump format.
+    pub version: Uint32,
+    /// The entries sorted by the dependencies, a transaction is always after its ancestors.
+    pub entries: Vec<TxPoolDumpEntry>,
+}
@@ -14,4 +14,6 @@ pub enum Topic {
     ProposedTransaction,
     /// Subscribe transactions which are abandoned by tx-pool.
     RejectedTransaction,
+    /// Subscribe in-pool transactions which are replaced or double spent by other transactions.
+    ConflictedTransaction,
 }
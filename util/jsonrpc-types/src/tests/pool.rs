@@ -8,3 +8,29 @@ fn test_outputs_validator_json_display() {
     );
     assert_eq!("passthrough", OutputsValidator::Passthrough.json_display());
 }
+
+#[test]
+fn test_transaction_event_serialize() {
+    use crate::pool::TransactionEvent;
+    use ckb_types::core::{tx_pool::TxEvent, Capacity};
+
+    let event: TransactionEvent = TxEvent::Received(Some(3)).into();
+    assert_eq!(
+        r#"{"type":"received","peer":"0x3"}"#,
+        serde_json::to_string(&event).unwrap()
+    );
+    let event: TransactionEvent = TxEvent::Verified(0x10, Capacity::shannons(0x20)).into();
+    assert_eq!(
+        r#"{"type":"verified","cycles":"0x10","fee":"0x20"}"#,
+        serde_json::to_string(&event).unwrap()
+    );
+    let json = r#"{"type":"pending"}"#;
+    assert_eq!(
+        json,
+        serde_json::to_string(&TransactionEvent::Pending).unwrap()
+    );
+    assert_eq!(
+        TransactionEvent::Pending,
+        serde_json::from_str::<TransactionEvent>(json).unwrap()
+    );
+}
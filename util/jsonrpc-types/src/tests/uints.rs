@@ -0,0 +1,27 @@
+use crate::Int64;
+
+#[test]
+fn test_int64() {
+    for (json, value) in [
+        ("\"0x0\"", 0),
+        ("\"0x10\"", 16),
+        ("\"-0x10\"", -16),
+        ("\"0x7fffffffffffffff\"", i64::MAX),
+        ("\"-0x8000000000000000\"", i64::MIN),
+    ] {
+        let int: Int64 = serde_json::from_str(json).unwrap();
+        assert_eq!(int.value(), value);
+        assert_eq!(serde_json::to_string(&int).unwrap(), json);
+    }
+
+    for json in [
+        "\"10\"",
+        "\"-0x0\"",
+        "\"0x01\"",
+        "\"--0x1\"",
+        "\"0x8000000000000000\"",
+        "\"-0x8000000000000001\"",
+    ] {
+        assert!(serde_json::from_str::<Int64>(json).is_err(), "{json}");
+    }
+}
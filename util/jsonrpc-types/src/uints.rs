@@ -185,6 +185,102 @@ impl From<JsonUint<u64>> for core::Capacity {
     }
 }
 
+/// The 64-bit signed integer type encoded as the 0x-prefixed hex string in JSON, with a leading
+/// `-` for the negative values.
+///
+/// ## Examples
+///
+/// | JSON    | Decimal Value                |
+/// | --------| ---------------------------- |
+/// | "0x10"  | 16                           |
+/// | "-0x10" | -16                          |
+/// | "-0x0"  | Invalid, negative zero       |
+/// | "0x01"  | Invalid, redundant leading 0 |
+#[derive(Copy, Clone, Default, PartialEq, PartialOrd, Ord, Eq, Hash, Debug)]
+pub struct Int64(pub(crate) i64);
+
+impl Int64 {
+    /// Returns the inner value.
+    pub fn value(self) -> i64 {
+        self.0
+    }
+}
+
+impl fmt::Display for Int64 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0 < 0 {
+            write!(f, "-0x{:x}", self.0.unsigned_abs())
+        } else {
+            write!(f, "0x{:x}", self.0)
+        }
+    }
+}
+
+impl From<i64> for Int64 {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Int64> for i64 {
+    fn from(value: Int64) -> Self {
+        value.value()
+    }
+}
+
+impl Serialize for Int64 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'a> Deserialize<'a> for Int64 {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'a>,
+    {
+        deserializer.deserialize_str(Int64Visitor)
+    }
+}
+
+struct Int64Visitor;
+
+impl<'a> Visitor<'a> for Int64Visitor {
+    type Value = Int64;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a hex-encoded, 0x-prefixed Int64")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        let (negative, abs) = match value.strip_prefix('-') {
+            Some(abs) => (true, abs),
+            None => (false, value),
+        };
+        let abs = JsonUintVisitor::<u64>::visit_str::<E>(abs)
+            .map_err(|e| Error::custom(format!("Invalid Int64 {value}: {e}")))?
+            .value();
+        if negative {
+            if abs == 0 {
+                return Err(Error::custom(format!(
+                    "Invalid Int64 {value}: negative zero"
+                )));
+            }
+            0i64.checked_sub_unsigned(abs)
+        } else {
+            i64::try_from(abs).ok()
+        }
+        .map(Int64)
+        .ok_or_else(|| Error::custom(format!("Invalid Int64 {value}: overflow")))
+    }
+}
+
 #[cfg(tests)]
 mod tests {
     macro_rules! test_json_uint {
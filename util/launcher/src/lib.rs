@@ -26,9 +26,11 @@ use ckb_rpc::ServiceBuilder;
 use ckb_shared::Shared;
 
 use ckb_shared::shared_builder::{SharedBuilder, SharedPackage};
-use ckb_store::ChainDB;
+use ckb_store::{ChainDB, ChainStore};
 use ckb_sync::{BlockFilter, NetTimeProtocol, Relayer, SyncShared, Synchronizer};
+use ckb_tx_pool::block_assembler::build_policy;
 use ckb_tx_pool::service::TxVerificationResult;
+use ckb_tx_pool::TxPoolServiceBuilder;
 use ckb_types::prelude::*;
 use ckb_verification::GenesisVerifier;
 use ckb_verification_traits::Verifier;
@@ -137,7 +139,10 @@ impl Launcher {
     // panic immediately if migration_version is none
     fn assert_migrate_version_is_some(&self, shared: &Shared) {
         let store = shared.store();
-        assert!(store.get_migration_version().is_some());
+        // the in-memory store is never migrated
+        if store.db().is_some() {
+            assert!(store.get_migration_version().is_some());
+        }
     }
 
     fn check_spec(&self, shared: &Shared) -> Result<(), ExitCode> {
@@ -227,6 +232,13 @@ impl Launcher {
         chain_controller
     }
 
+    /// Set the block template policy configured by `[block_assembler.policy]`
+    pub fn set_block_template_policy(&self, tx_pool_builder: &mut TxPoolServiceBuilder) {
+        if let Some(ref block_assembler) = self.args.config.block_assembler {
+            tx_pool_builder.set_block_template_policy(build_policy(&block_assembler.policy));
+        }
+    }
+
     fn adjust_rpc_config(&self) -> RpcConfig {
         let mut config = self.args.config.rpc.clone();
         if self.args.indexer && !config.indexer_enable() {
@@ -353,6 +365,15 @@ impl Launcher {
             flags.remove(Flags::LIGHT_CLIENT);
         }
 
+        if shared
+            .store()
+            .freezer()
+            .and_then(|freezer| freezer.prune_depth())
+            .is_none()
+        {
+            flags.remove(Flags::PRUNED);
+        }
+
         let alert_signature_config = self.args.config.alert_signature.clone().unwrap_or_default();
         let alert_relayer = AlertRelayer::new(
             self.version.short(),
@@ -407,17 +428,21 @@ impl Launcher {
                 chain_controller.clone(),
                 miner_enable,
             )
-            .enable_net(network_controller.clone(), sync_shared)
+            .enable_net(network_controller.clone(), Arc::clone(&sync_shared))
             .enable_stats(shared.clone(), Arc::clone(&alert_notifier))
             .enable_experiment(shared.clone())
-            .enable_integration_test(shared.clone(), network_controller.clone(), chain_controller)
+            .enable_integration_test(
+                shared.clone(),
+                network_controller.clone(),
+                chain_controller.clone(),
+            )
             .enable_alert(alert_verifier, alert_notifier, network_controller.clone())
             .enable_indexer(
                 shared.clone(),
                 &self.args.config.db,
                 &self.args.config.indexer,
             )
-            .enable_debug();
+            .enable_debug(shared.clone(), chain_controller, sync_shared);
         builder.enable_subscription(shared.clone());
         let io_handler = builder.build();
 
@@ -46,9 +46,10 @@ impl<'a> GetBlocksProofProcess<'a> {
                 .protocol
                 .reply_tip_state::<packed::SendBlocksProof>(self.peer, self.nc);
         }
-        let last_block = snapshot
-            .get_block(&last_block_hash)
-            .expect("block should be in store");
+        let last_block = match snapshot.get_block(&last_block_hash) {
+            Ok(block) => block.expect("block should be in store"),
+            Err(err) => return StatusCode::InternalError.with_context(err),
+        };
 
         let block_hashes: Vec<_> = self
             .message
@@ -131,9 +131,10 @@ impl<'a> BlockSampler<'a> {
                 let position = leaf_index_to_pos(*number);
                 positions.push(position);
 
-                let ancestor_block = self
+                // the uncles and the extension are kept when the block body is pruned
+                let uncles = self
                     .snapshot
-                    .get_block(&ancestor_header.hash())
+                    .get_block_uncles(&ancestor_header.hash())
                     .ok_or_else(|| {
                         format!(
                             "failed to find block for header#{} (hash: {:#x})",
@@ -141,8 +142,8 @@ impl<'a> BlockSampler<'a> {
                             ancestor_header.hash()
                         )
                     })?;
-                let uncles_hash = ancestor_block.calc_uncles_hash();
-                let extension = ancestor_block.extension();
+                let uncles_hash = uncles.data().calc_uncles_hash();
+                let extension = self.snapshot.get_block_extension(&ancestor_header.hash());
 
                 let parent_chain_root = if *number == 0 {
                     Default::default()
@@ -209,9 +210,10 @@ impl<'a> GetLastStateProofProcess<'a> {
                 .protocol
                 .reply_tip_state::<packed::SendLastStateProof>(self.peer, self.nc);
         }
-        let last_block = snapshot
-            .get_block(&last_block_hash)
-            .expect("block should be in store");
+        let last_block = match snapshot.get_block(&last_block_hash) {
+            Ok(block) => block.expect("block should be in store"),
+            Err(err) => return StatusCode::InternalError.with_context(err),
+        };
 
         let start_block_hash = self.message.start_hash().to_entity();
         let start_block_number: BlockNumber = self.message.start_number().unpack();
@@ -46,9 +46,10 @@ impl<'a> GetTransactionsProofProcess<'a> {
                 .protocol
                 .reply_tip_state::<packed::SendTransactionsProof>(self.peer, self.nc);
         }
-        let last_block = snapshot
-            .get_block(&last_block_hash)
-            .expect("block should be in store");
+        let last_block = match snapshot.get_block(&last_block_hash) {
+            Ok(block) => block.expect("block should be in store"),
+            Err(err) => return StatusCode::InternalError.with_context(err),
+        };
 
         let (found, missing): (Vec<_>, Vec<_>) = self
             .message
@@ -80,9 +81,10 @@ impl<'a> GetTransactionsProofProcess<'a> {
         let ckb2023 = self.nc.ckb2023();
 
         for (block_hash, txs_and_tx_indices) in txs_in_blocks.into_iter() {
-            let block = snapshot
-                .get_block(&block_hash)
-                .expect("block should be in store");
+            let block = match snapshot.get_block(&block_hash) {
+                Ok(block) => block.expect("block should be in store"),
+                Err(err) => return StatusCode::InternalError.with_context(err),
+            };
             let merkle_proof = CBMT::build_merkle_proof(
                 &block
                     .transactions()
@@ -122,6 +122,7 @@ impl LightClientProtocol {
         let tip_hash = snapshot.tip_hash();
         let tip_block = snapshot
             .get_block(&tip_hash)
+            .map_err(|err| err.to_string())?
             .expect("checked: tip block should be existed");
         let parent_chain_root = if tip_block.is_genesis() {
             Default::default()
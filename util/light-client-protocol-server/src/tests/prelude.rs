@@ -48,7 +48,7 @@ impl SnapshotExt for Snapshot {
 
     fn get_block_by_number(&self, num: BlockNumber) -> Option<BlockView> {
         self.get_block_hash(num)
-            .and_then(|hash| self.get_block(&hash))
+            .and_then(|hash| self.get_block(&hash).unwrap())
     }
 
     fn get_block_ext_by_number(&self, num: BlockNumber) -> Option<BlockExt> {
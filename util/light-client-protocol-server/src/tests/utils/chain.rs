@@ -76,6 +76,7 @@ impl MockChain {
                 notify: vec![],
                 notify_scripts: vec![],
                 notify_timeout_millis: 800,
+                policy: Default::default(),
             };
             SharedBuilder::with_temp_db()
                 .consensus(consensus)
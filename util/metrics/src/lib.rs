@@ -7,8 +7,9 @@
 //! [`ckb-metrics-service`]: ../ckb_metrics_service/index.html
 
 use prometheus::{
-    register_histogram, register_histogram_vec, register_int_counter, register_int_gauge,
-    register_int_gauge_vec, Histogram, HistogramVec, IntCounter, IntGauge, IntGaugeVec,
+    register_histogram, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    register_int_gauge, register_int_gauge_vec, Histogram, HistogramVec, IntCounter, IntCounterVec,
+    IntGauge, IntGaugeVec,
 };
 use prometheus_static_metric::make_static_metric;
 use std::cell::Cell;
@@ -46,6 +47,15 @@ make_static_metric! {
             proposed,
         },
     }
+
+    // Struct for CKB tx-pool cell dep churn statistics type label
+    struct CkbTxPoolCellDepChurnStatistics: IntCounter{
+        "type" => {
+            evicted,
+            exceeded_references,
+            low_fee,
+        },
+    }
 }
 
 pub struct Metrics {
@@ -55,12 +65,18 @@ pub struct Metrics {
     pub ckb_freezer_size: IntGauge,
     /// Counter for measuring the effective amount of data read
     pub ckb_freezer_read: IntCounter,
+    /// Counter for frozen items failed the checksum verification
+    pub ckb_freezer_checksum_failed: IntCounter,
     /// Counter for relay transaction short id collide
     pub ckb_relay_transaction_short_id_collide: IntCounter,
     /// Histogram for relay compact block verify duration
     pub ckb_relay_cb_verify_duration: Histogram,
     /// Histogram for block process duration
     pub ckb_block_process_duration: Histogram,
+    /// HistogramVec for the duration of each block process stage
+    pub ckb_block_process_stage_duration: HistogramVec,
+    /// Counter for rejected blocks by the error kind
+    pub ckb_block_rejected: IntCounterVec,
     /// Counter for relay compact block transaction count
     pub ckb_relay_cb_transaction_count: IntCounter,
     /// Counter for relay compact block reconstruct ok
@@ -77,6 +93,10 @@ pub struct Metrics {
     pub ckb_sys_mem_jemalloc: CkbSysMemJemallocStatistics,
     // GaugeVec for CKB tx-pool tx entry status statistics
     pub ckb_tx_pool_entry: CkbTxPoolEntryStatistics,
+    // CounterVec for CKB tx-pool txs evicted or rejected due to the cell deps
+    pub ckb_tx_pool_cell_dep_churn: CkbTxPoolCellDepChurnStatistics,
+    /// Counter for the tx history events dropped when the writer is busy
+    pub ckb_tx_pool_history_dropped: IntCounter,
     /// Histogram for CKB network connections
     pub ckb_message_bytes: HistogramVec,
     /// Gauge for CKB rocksdb statistics
@@ -89,6 +109,11 @@ static METRICS: once_cell::sync::Lazy<Metrics> = once_cell::sync::Lazy::new(|| M
     ckb_chain_tip: register_int_gauge!("ckb_chain_tip", "The CKB chain tip header number").unwrap(),
     ckb_freezer_size: register_int_gauge!("ckb_freezer_size", "The CKB freezer size").unwrap(),
     ckb_freezer_read: register_int_counter!("ckb_freezer_read", "The CKB freezer read").unwrap(),
+    ckb_freezer_checksum_failed: register_int_counter!(
+        "ckb_freezer_checksum_failed",
+        "The CKB freezer items failed the checksum verification"
+    )
+    .unwrap(),
     ckb_relay_transaction_short_id_collide: register_int_counter!(
         "ckb_relay_transaction_short_id_collide",
         "The CKB relay transaction short id collide"
@@ -104,6 +129,18 @@ static METRICS: once_cell::sync::Lazy<Metrics> = once_cell::sync::Lazy::new(|| M
         "The CKB block process duration"
     )
     .unwrap(),
+    ckb_block_process_stage_duration: register_histogram_vec!(
+        "ckb_block_process_stage_duration",
+        "The CKB block process duration of each stage",
+        &["stage"]
+    )
+    .unwrap(),
+    ckb_block_rejected: register_int_counter_vec!(
+        "ckb_block_rejected",
+        "The CKB rejected block count by the error kind",
+        &["kind"]
+    )
+    .unwrap(),
     ckb_relay_cb_transaction_count: register_int_counter!(
         "ckb_relay_cb_transaction_count",
         "The CKB relay compact block transaction count"
@@ -153,6 +190,14 @@ static METRICS: once_cell::sync::Lazy<Metrics> = once_cell::sync::Lazy::new(|| M
         )
         .unwrap(),
     ),
+    ckb_tx_pool_cell_dep_churn: CkbTxPoolCellDepChurnStatistics::from(
+        &register_int_counter_vec!(
+            "ckb_tx_pool_cell_dep_churn",
+            "CKB tx-pool txs evicted or rejected due to the cell deps",
+            &["type"]
+        )
+        .unwrap(),
+    ),
     ckb_message_bytes: register_histogram_vec!(
         "ckb_message_bytes",
         "The CKB message bytes",
@@ -169,6 +214,11 @@ static METRICS: once_cell::sync::Lazy<Metrics> = once_cell::sync::Lazy::new(|| M
         &["type", "cf"]
     )
     .unwrap(),
+    ckb_tx_pool_history_dropped: register_int_counter!(
+        "ckb_tx_pool_history_dropped",
+        "The tx history events dropped when the writer is busy"
+    )
+    .unwrap(),
     ckb_network_ban_peer: register_int_counter!(
         "ckb_network_ban_peer",
         "CKB network baned peer count"
@@ -18,7 +18,7 @@ impl Migration for AddBlockFilterHash {
         db: RocksDB,
         pb: Arc<dyn Fn(u64) -> ProgressBar + Send + Sync>,
     ) -> Result<RocksDB, Error> {
-        let chain_db = ChainDB::new(db, StoreConfig::default());
+        let chain_db = ChainDB::new(db.clone(), StoreConfig::default());
         if let Some(block_hash) = chain_db.get_latest_built_filter_data_block_hash() {
             let latest_built_filter_data_block_number = if chain_db.is_main_chain(&block_hash) {
                 chain_db
@@ -52,7 +52,7 @@ impl Migration for AddBlockFilterHash {
             let mut block_number = 0;
             let mut parent_block_filter_hash = [0u8; 32];
             loop {
-                let db_txn = chain_db.db().transaction();
+                let db_txn = db.transaction();
                 for _ in 0..10000 {
                     if block_number > latest_built_filter_data_block_number {
                         break;
@@ -85,7 +85,7 @@ impl Migration for AddBlockFilterHash {
                 }
             }
         }
-        Ok(chain_db.into_inner())
+        Ok(db)
     }
 
     fn version(&self) -> &str {
@@ -16,7 +16,7 @@ impl Migration for AddChainRootMMR {
         db: RocksDB,
         pb: Arc<dyn Fn(u64) -> ProgressBar + Send + Sync>,
     ) -> Result<RocksDB> {
-        let chain_db = ChainDB::new(db, StoreConfig::default());
+        let chain_db = ChainDB::new(db.clone(), StoreConfig::default());
         let tip = chain_db
             .get_tip_header()
             .ok_or_else(|| InternalErrorKind::MMR.other("tip block is not found"))?;
@@ -76,7 +76,7 @@ impl Migration for AddChainRootMMR {
 
         pbi.finish_with_message("done!");
 
-        Ok(chain_db.into_inner())
+        Ok(db)
     }
 
     fn version(&self) -> &str {
@@ -0,0 +1,25 @@
+use ckb_db::{Result, RocksDB};
+use ckb_db_migration::{Migration, ProgressBar};
+use std::sync::Arc;
+
+pub struct AddInvalidBlockColumnFamily;
+
+const VERSION: &str = "20241018000000";
+
+impl Migration for AddInvalidBlockColumnFamily {
+    fn migrate(
+        &self,
+        db: RocksDB,
+        _pb: Arc<dyn Fn(u64) -> ProgressBar + Send + Sync>,
+    ) -> Result<RocksDB> {
+        Ok(db)
+    }
+
+    fn version(&self) -> &str {
+        VERSION
+    }
+
+    fn expensive(&self) -> bool {
+        false
+    }
+}
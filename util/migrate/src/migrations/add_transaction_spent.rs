@@ -0,0 +1,25 @@
+use ckb_db::{Result, RocksDB};
+use ckb_db_migration::{Migration, ProgressBar};
+use std::sync::Arc;
+
+pub struct AddTransactionSpentColumnFamily;
+
+const VERSION: &str = "20240715000000";
+
+impl Migration for AddTransactionSpentColumnFamily {
+    fn migrate(
+        &self,
+        db: RocksDB,
+        _pb: Arc<dyn Fn(u64) -> ProgressBar + Send + Sync>,
+    ) -> Result<RocksDB> {
+        Ok(db)
+    }
+
+    fn version(&self) -> &str {
+        VERSION
+    }
+
+    fn expensive(&self) -> bool {
+        false
+    }
+}
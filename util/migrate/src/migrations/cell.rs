@@ -31,7 +31,7 @@ impl Migration for CellMigration {
                 for number in i * chunk_size..end {
                     let block = chain_db
                         .get_block_hash(number)
-                        .and_then(|hash| chain_db.get_block(&hash)).expect("DB data integrity");
+                        .and_then(|hash| chain_db.get_block(&hash).expect("no block is pruned without the freezer")).expect("DB data integrity");
 
                     if block.transactions().len() > 1 {
                         hashes.push(block.hash());
@@ -3,7 +3,9 @@ mod add_block_filter;
 mod add_block_filter_hash;
 mod add_chain_root_mmr;
 mod add_extra_data_hash;
+mod add_invalid_block;
 mod add_number_hash_mapping;
+mod add_transaction_spent;
 mod cell;
 mod set_2019_block_cycle_zero;
 mod table_to_struct;
@@ -13,7 +15,9 @@ pub use add_block_filter::AddBlockFilterColumnFamily;
 pub use add_block_filter_hash::AddBlockFilterHash;
 pub use add_chain_root_mmr::AddChainRootMMR;
 pub use add_extra_data_hash::AddExtraDataHash;
+pub use add_invalid_block::AddInvalidBlockColumnFamily;
 pub use add_number_hash_mapping::AddNumberHashMapping;
+pub use add_transaction_spent::AddTransactionSpentColumnFamily;
 pub use cell::CellMigration;
 pub use set_2019_block_cycle_zero::BlockExt2019ToZero;
 pub use table_to_struct::ChangeMoleculeTableToStruct;
@@ -30,7 +30,7 @@ impl Migration for BlockExt2019ToZero {
         db: ckb_db::RocksDB,
         pb: std::sync::Arc<dyn Fn(u64) -> ProgressBar + Send + Sync>,
     ) -> Result<ckb_db::RocksDB, ckb_error::Error> {
-        let chain_db = ChainDB::new(db, StoreConfig::default());
+        let chain_db = ChainDB::new(db.clone(), StoreConfig::default());
         let limit_epoch = self.hardforks.ckb2021.rfc_0032();
 
         eprintln!(
@@ -39,7 +39,7 @@ impl Migration for BlockExt2019ToZero {
         );
 
         if limit_epoch == 0 {
-            return Ok(chain_db.into_inner());
+            return Ok(db);
         }
 
         let hard_fork_epoch_number: packed::Uint64 = limit_epoch.pack();
@@ -106,7 +106,7 @@ impl Migration for BlockExt2019ToZero {
             }
         }
 
-        Ok(chain_db.into_inner())
+        Ok(db)
     }
     fn version(&self) -> &str {
         VERSION
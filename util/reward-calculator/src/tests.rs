@@ -49,7 +49,7 @@ fn get_proposal_ids_by_hash() {
     let txn = store.begin_transaction();
     txn.insert_block(&block).unwrap();
     txn.commit().unwrap();
-    assert_eq!(block, store.get_block(&hash).unwrap());
+    assert_eq!(block, store.get_block(&hash).unwrap().unwrap());
 
     let consensus = Consensus::default();
     let reward_calculator = RewardCalculator::new(&consensus, &store);
@@ -5,17 +5,17 @@ use ckb_chain_spec::{
     consensus::{Consensus, ConsensusProvider},
     versionbits::{DeploymentPos, ThresholdState, VersionbitsIndexer},
 };
-use ckb_db::{
-    iter::{DBIter, IteratorMode},
-    DBPinnableSlice,
-};
+use ckb_db::iter::IteratorMode;
 use ckb_db_schema::Col;
 use ckb_freezer::Freezer;
 use ckb_merkle_mountain_range::{
     leaf_index_to_mmr_size, Error as MMRError, MMRStore, Result as MMRResult,
 };
 use ckb_proposal_table::ProposalView;
-use ckb_store::{ChainStore, StoreCache, StoreSnapshot};
+use ckb_store::{
+    backend::{StoreIter, StoreSlice},
+    ChainStore, StoreCache, StoreSnapshot,
+};
 use ckb_traits::{HeaderFields, HeaderFieldsProvider, HeaderProvider};
 use ckb_types::core::error::OutPointError;
 use ckb_types::{
@@ -189,7 +189,7 @@ impl ChainStore for Snapshot {
         self.store.cache()
     }
 
-    fn get(&self, col: Col, key: &[u8]) -> Option<DBPinnableSlice> {
+    fn get(&self, col: Col, key: &[u8]) -> Option<StoreSlice> {
         self.store.get(col, key)
     }
 
@@ -197,7 +197,7 @@ impl ChainStore for Snapshot {
         self.store.freezer()
     }
 
-    fn get_iter(&self, col: Col, mode: IteratorMode) -> DBIter {
+    fn get_iter(&self, col: Col, mode: IteratorMode) -> StoreIter {
         self.store.get_iter(col, mode)
     }
 
@@ -41,7 +41,7 @@ impl MockStore {
     /// Create a new `MockStore` with insert parent block into the temporary database for reference.
     #[doc(hidden)]
     pub fn new(parent: &HeaderView, chain_store: &ChainDB) -> Self {
-        let block = chain_store.get_block(&parent.hash()).unwrap();
+        let block = chain_store.get_block(&parent.hash()).unwrap().unwrap();
         let epoch_ext = chain_store
             .get_block_epoch_index(&parent.hash())
             .and_then(|index| chain_store.get_epoch_ext(&index))
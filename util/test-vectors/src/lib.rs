@@ -0,0 +1,29 @@
+//! Declarative test vectors of the CKB consensus rules.
+//!
+//! A test vector is a chain spec and a sequence of blocks, each with the expected result of
+//! processing it on top of the blocks before. The [`run`] function executes a vector against
+//! `ChainService` in-process on an in-memory store, so the vectors can be shared with alternative
+//! implementations as conformance tests.
+//!
+//! The vectors are JSON files:
+//!
+//! ```json
+//! {
+//!   "name": "number must follow the parent",
+//!   "spec": { "bundled": "specs/dev.toml" },
+//!   "blocks": [
+//!     { "block": { "header": { ... }, ... }, "expect": { "reject": "Header(Number(NumberError(expected: 1, actual: 2)))" } }
+//!   ]
+//! }
+//! ```
+//!
+//! The blocks use the same JSON format as the RPC, and the rejections are identified by the
+//! displayed errors, which spell out the whole chain from the [`ErrorKind`](ckb_error::ErrorKind)
+//! down to the failed rule.
+mod runner;
+#[cfg(test)]
+mod tests;
+mod vector;
+
+pub use crate::runner::{run, Failure};
+pub use crate::vector::{Expect, TestVector, VectorBlock};
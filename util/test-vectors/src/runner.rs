@@ -0,0 +1,123 @@
+use crate::vector::{Expect, TestVector};
+use ckb_app_config::{DBBackend, DBConfig};
+use ckb_async_runtime::Handle;
+use ckb_chain::chain::ChainService;
+use ckb_chain_spec::ChainSpec;
+use ckb_error::Error;
+use ckb_shared::{Shared, SharedBuilder};
+use ckb_types::{core::BlockView, packed, prelude::*, H256};
+use ckb_verification::HeaderVerifier;
+use ckb_verification_traits::{Switch, Verifier};
+use std::fmt;
+use std::sync::Arc;
+
+/// The reason why a vector fails.
+#[derive(Debug)]
+pub enum Failure {
+    /// Failed to load the chain spec or to set up the chain.
+    Setup(String),
+    /// The result of processing a block differs from the expectation.
+    Mismatch {
+        /// The index of the block in the vector.
+        index: usize,
+        /// The expected result.
+        expected: Expect,
+        /// The actual result, `Ok` if the block is accepted.
+        actual: Result<(), Error>,
+    },
+    /// The tip after processing all the blocks differs from the expectation.
+    Tip {
+        /// The expected tip hash.
+        expected: H256,
+        /// The actual tip hash.
+        actual: H256,
+    },
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Failure::Setup(reason) => write!(f, "failed to set up the chain: {reason}"),
+            Failure::Mismatch {
+                index,
+                expected,
+                actual: Ok(()),
+            } => write!(f, "block {index}: expect {expected}, but it's accepted"),
+            Failure::Mismatch {
+                index,
+                expected,
+                actual: Err(err),
+            } => write!(
+                f,
+                "block {index}: expect {expected}, but it's rejected: {err}"
+            ),
+            Failure::Tip { expected, actual } => {
+                write!(f, "expect tip {expected:#x}, actual {actual:#x}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Processes the blocks of the vector in order on a fresh in-memory chain, and checks the
+/// results against the expectations.
+///
+/// The headers are verified before the blocks are processed, as the sync protocol does. A
+/// rejection matches only when the whole error chain is the expected one.
+pub fn run(vector: &TestVector, async_handle: &Handle) -> Result<(), Failure> {
+    let consensus = ChainSpec::load_from(&vector.spec)
+        .and_then(|spec| spec.build_consensus())
+        .map_err(|err| Failure::Setup(err.to_string()))?;
+    let db_config = DBConfig {
+        backend: DBBackend::Memory,
+        ..Default::default()
+    };
+    let tmp_dir = tempfile::tempdir().map_err(|err| Failure::Setup(err.to_string()))?;
+    let (shared, mut pack) = SharedBuilder::new(
+        "ckb",
+        tmp_dir.path(),
+        &db_config,
+        None,
+        async_handle.clone(),
+        consensus,
+    )
+    .and_then(SharedBuilder::build)
+    .map_err(|code| Failure::Setup(format!("{code:?}")))?;
+    let mut chain = ChainService::new(shared.clone(), pack.take_proposal_table());
+
+    for (index, vector_block) in vector.blocks.iter().enumerate() {
+        let block = packed::Block::from(vector_block.block.clone()).into_view();
+        let actual = process_block(&shared, &mut chain, block);
+        let matched = match (&vector_block.expect, &actual) {
+            (Expect::Accept, Ok(())) => true,
+            (Expect::Reject(error), Err(err)) => err.to_string() == *error,
+            _ => false,
+        };
+        if !matched {
+            return Err(Failure::Mismatch {
+                index,
+                expected: vector_block.expect.clone(),
+                actual,
+            });
+        }
+    }
+
+    if let Some(ref expected) = vector.tip {
+        let actual: H256 = shared.snapshot().tip_hash().unpack();
+        if actual != *expected {
+            return Err(Failure::Tip {
+                expected: expected.clone(),
+                actual,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn process_block(shared: &Shared, chain: &mut ChainService, block: BlockView) -> Result<(), Error> {
+    HeaderVerifier::new(shared.snapshot().as_ref(), shared.consensus()).verify(&block.header())?;
+    chain
+        .process_block(Arc::new(block), Switch::NONE)
+        .map(|_| ())
+}
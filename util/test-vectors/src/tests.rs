@@ -0,0 +1,65 @@
+use crate::{run, Expect, Failure, TestVector};
+use ckb_async_runtime::new_background_runtime;
+use std::fs;
+use std::path::PathBuf;
+
+fn vectors_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("vectors")
+}
+
+#[test]
+fn bundled_vectors() {
+    let handle = new_background_runtime();
+    let mut paths: Vec<_> = fs::read_dir(vectors_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty());
+
+    for path in paths {
+        let vector = TestVector::load(&path).unwrap();
+        if let Err(failure) = run(&vector, &handle) {
+            panic!("{}({}): {failure}", vector.name, path.display());
+        }
+    }
+}
+
+#[test]
+fn report_mismatch() {
+    let handle = new_background_runtime();
+    let mut vector = TestVector::load(vectors_dir().join("unknown_parent.json")).unwrap();
+    vector.blocks[0].expect = Expect::Accept;
+
+    match run(&vector, &handle) {
+        Err(Failure::Mismatch {
+            index: 0,
+            expected: Expect::Accept,
+            actual: Err(err),
+        }) => assert_eq!(err.kind().to_string(), "Block"),
+        ret => panic!("unexpected result {ret:?}"),
+    }
+}
+
+#[test]
+fn report_wrong_rejection() {
+    let handle = new_background_runtime();
+    let mut vector = TestVector::load(vectors_dir().join("reject_dao.json")).unwrap();
+    let index = vector.blocks.len() - 1;
+    let expected = Expect::Reject("Block(Cellbase(InvalidRewardAmount))".to_owned());
+    vector.blocks[index].expect = expected.clone();
+
+    match run(&vector, &handle) {
+        Err(Failure::Mismatch {
+            index: actual_index,
+            expected: actual_expected,
+            actual: Err(err),
+        }) => {
+            assert_eq!(actual_index, index);
+            assert_eq!(actual_expected, expected);
+            assert_eq!(err.to_string(), "Block(InvalidDAO(No reason provided))");
+        }
+        ret => panic!("unexpected result {ret:?}"),
+    }
+}
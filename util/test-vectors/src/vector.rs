@@ -0,0 +1,70 @@
+use ckb_jsonrpc_types::Block;
+use ckb_resource::Resource;
+use ckb_types::H256;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A chain spec and the blocks processed on top of its genesis in order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestVector {
+    /// The name of the vector.
+    pub name: String,
+    /// What the vector checks.
+    #[serde(default)]
+    pub description: String,
+    /// The chain spec, the relative file paths are based on the directory of the vector file.
+    pub spec: Resource,
+    /// The blocks to process.
+    pub blocks: Vec<VectorBlock>,
+    /// The expected tip hash after processing all the blocks.
+    #[serde(default)]
+    pub tip: Option<H256>,
+}
+
+/// A block and the expected result of processing it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VectorBlock {
+    /// The block in the RPC format.
+    pub block: Block,
+    /// The expected result.
+    pub expect: Expect,
+}
+
+/// The expected result of processing a block.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expect {
+    /// The block is valid and stored, it's not necessarily on the main chain.
+    Accept,
+    /// The block is rejected with the error, written as its full chain, e.g.
+    /// `Block(Cellbase(InvalidRewardAmount))`.
+    Reject(String),
+}
+
+impl fmt::Display for Expect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expect::Accept => write!(f, "accept"),
+            Expect::Reject(error) => write!(f, "reject({error})"),
+        }
+    }
+}
+
+impl TestVector {
+    /// Loads the vector from a JSON file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<TestVector> {
+        let path = path.as_ref();
+        let content = fs::read(path)?;
+        let mut vector: TestVector = serde_json::from_slice(&content)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        if let Some(dir) = path.parent() {
+            vector.spec.absolutize(dir);
+        }
+        Ok(vector)
+    }
+}
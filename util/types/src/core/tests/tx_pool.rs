@@ -22,6 +22,18 @@ fn test_if_is_malformed_tx() {
     let reject = Reject::Duplicated(Default::default());
     assert!(!reject.is_malformed_tx());
 
+    let reject = Reject::ExceededLowFeeParentsLimit(0);
+    assert!(!reject.is_malformed_tx());
+
+    let reject = Reject::LowPackageFeeRate(Default::default(), 0, 0);
+    assert!(!reject.is_malformed_tx());
+
+    let reject = Reject::ExceededCellDepReferencesLimit(0);
+    assert!(!reject.is_malformed_tx());
+
+    let reject = Reject::LowCellDepChurnFee(0, 0, 0);
+    assert!(!reject.is_malformed_tx());
+
     let reject = Reject::Malformed(Default::default(), Default::default());
     assert!(reject.is_malformed_tx());
 
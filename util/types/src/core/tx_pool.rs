@@ -5,7 +5,7 @@ use crate::{
         error::{OutPointError, TransactionError},
         BlockNumber, Capacity, Cycle, FeeRate,
     },
-    packed::Byte32,
+    packed::{Byte32, OutPoint},
     H256,
 };
 use ckb_error::{
@@ -63,6 +63,27 @@ pub enum Reject {
     /// Invalidated by cell consuming Tx
     #[error("Invalidated: {0}")]
     Invalidated(String),
+
+    /// Transaction fee lower than config, and too many such transactions are waiting for a child
+    /// to pay their fee
+    #[error(
+        "Exceeded the limit {0} of transactions waiting for a child to pay the fee; try later"
+    )]
+    ExceededLowFeeParentsLimit(usize),
+
+    /// The fee of the transaction and its descendants is lower than config when the waiting
+    /// window ends
+    #[error("The min fee rate is {0}, requiring a package fee of at least {1} shannons for the transaction and its descendants, but the package fee is only {2}")]
+    LowPackageFeeRate(FeeRate, u64, u64),
+
+    /// Too many transactions in the pool are referencing the same cell dep
+    #[error("Exceeded the limit {0} of transactions referencing the same cell dep; try later")]
+    ExceededCellDepReferencesLimit(usize),
+
+    /// The fee of the transaction consuming the cells referenced as cell deps by many transactions
+    /// in the pool is lower than the total fee of them
+    #[error("Consuming the cell deps of {0} transactions in the pool, requiring a transaction fee higher than {1} shannons, but the fee provided is only {2}")]
+    LowCellDepChurnFee(usize, u64, u64),
 }
 
 fn is_malformed_from_verification(error: &Error) -> bool {
@@ -108,7 +129,13 @@ impl Reject {
     ///     and expired clearing
     pub fn is_allowed_relay(&self) -> bool {
         matches!(self, Reject::DeclaredWrongCycles(..))
-            || (!matches!(self, Reject::LowFeeRate(..)) && !self.is_malformed_tx())
+            || (!matches!(
+                self,
+                Reject::LowFeeRate(..)
+                    | Reject::ExceededLowFeeParentsLimit(..)
+                    | Reject::LowPackageFeeRate(..)
+                    | Reject::LowCellDepChurnFee(..)
+            ) && !self.is_malformed_tx())
     }
 }
 
@@ -131,6 +158,48 @@ pub enum TxStatus {
     Rejected(String),
 }
 
+/// An event in the lifecycle of a transaction in the tx-pool
+#[derive(Clone, Debug)]
+pub enum TxEvent {
+    /// Received from the peer with the session index, or submitted locally when it's `None`
+    Received(Option<usize>),
+    /// Passed the verification with the cycles and the fee
+    Verified(Cycle, Capacity),
+    /// Entered the pending pool
+    Pending,
+    /// Entered the gap pool
+    Gap,
+    /// Entered the proposed pool
+    Proposed,
+    /// Committed in the block with the number and the hash
+    Committed(BlockNumber, Byte32),
+    /// Rejected by the pool, or removed from the pool for the reason
+    Rejected(Reject),
+    /// Removed from the pool manually
+    Removed,
+}
+
+/// The kind of a conflict which removes a transaction from the tx-pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// Replaced by a transaction paying a higher fee via RBF
+    Replaced,
+    /// Double spent by a transaction committed in the chain
+    DoubleSpent,
+}
+
+/// A conflict which removes a transaction from the tx-pool
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxConflict {
+    /// The kind of the conflict
+    pub kind: ConflictKind,
+    /// The hash of the transaction which wins the conflict
+    pub conflicting_tx_hash: Byte32,
+    /// The out points contested by both transactions. For a transaction removed because its
+    /// ancestor conflicts, these are the out points contested by the ancestor.
+    pub out_points: Vec<OutPoint>,
+}
+
 /// Tx-pool entry info
 #[derive(Debug, PartialEq, Eq)]
 pub struct TxEntryInfo {
@@ -401,6 +470,23 @@ impl PoolTxDetailInfo {
     }
 }
 
+/// A local tx in tx-pool, which is rebroadcast until committed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LocalTxInfo {
+    /// The transaction hash
+    pub tx_hash: Byte32,
+    /// The detailed status in tx-pool, `Pending`, `Gap`, `Proposed`
+    pub entry_status: String,
+    /// The time added into tx-pool
+    pub timestamp: u64,
+    /// The transaction fee
+    pub fee: Capacity,
+    /// The times the tx has been rebroadcast
+    pub rebroadcast_count: u32,
+    /// The time of the next rebroadcast, `None` if the rebroadcast is disabled
+    pub next_rebroadcast_at: Option<u64>,
+}
+
 /// A Tx CacheEntry
 #[derive(Clone, PartialEq, Eq, Debug, Default)]
 pub struct EntryCompleted {
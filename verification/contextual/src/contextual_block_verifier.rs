@@ -26,14 +26,15 @@ use ckb_verification::cache::{
     TxVerificationCache, {CacheEntry, Completed},
 };
 use ckb_verification::{
-    BlockErrorKind, CellbaseError, CommitError, ContextualTransactionVerifier,
-    DaoScriptSizeVerifier, TimeRelativeTransactionVerifier, UnknownParentError,
+    BlockErrorKind, CellbaseError, CommitError, ContextualWithoutScriptTransactionVerifier,
+    DaoScriptSizeVerifier, ScriptVerifier, TimeRelativeTransactionVerifier, UnknownParentError,
 };
-use ckb_verification::{BlockTransactionsError, EpochError, TxVerifyEnv};
+use ckb_verification::{BlockTransactionsError, CyclesBudget, EpochError, TxVerifyEnv};
 use ckb_verification_traits::Switch;
 use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::{oneshot, RwLock};
 
 /// Context for context-dependent block verification
@@ -320,7 +321,16 @@ impl<'a, 'b, 'c, CS: ChainStore + VersionbitsIndexer> DaoHeaderVerifier<'a, 'b,
     }
 }
 
-struct BlockTxsVerifier<'a, 'b, CS> {
+/// The transactions of a block which pass the verification except the scripts, see
+/// [`BlockTxsVerifier::check`].
+pub(crate) struct CheckedTransactions {
+    fetched_cache: HashMap<Byte32, CacheEntry>,
+    // the fees of the transactions before the first failing one
+    fees: Vec<Capacity>,
+    failure: Option<BlockTransactionsError>,
+}
+
+pub(crate) struct BlockTxsVerifier<'a, 'b, CS> {
     context: VerifyContext<CS>,
     header: HeaderView,
     handle: &'a Handle,
@@ -384,11 +394,9 @@ impl<'a, 'b, CS: ChainStore + VersionbitsIndexer + 'static> BlockTxsVerifier<'a,
         });
     }
 
-    pub fn verify(
-        &self,
-        resolved: &'a [Arc<ResolvedTransaction>],
-        skip_script_verify: bool,
-    ) -> Result<(Cycle, Vec<Completed>), Error> {
+    /// Verifies the transactions except the scripts, in parallel. The scripts are run by
+    /// [`verify`](Self::verify) with the returned checks.
+    pub fn check(&self, resolved: &'a [Arc<ResolvedTransaction>]) -> CheckedTransactions {
         // We should skip updating tx_verify_cache about the cellbase tx,
         // putting it in cache that will never be used until lru cache expires.
         let fetched_cache = if resolved.len() > 1 {
@@ -398,85 +406,142 @@ impl<'a, 'b, CS: ChainStore + VersionbitsIndexer + 'static> BlockTxsVerifier<'a,
         };
 
         let tx_env = Arc::new(TxVerifyEnv::new_commit(&self.header));
+        let results: Vec<Result<Capacity, Error>> = resolved
+            .par_iter()
+            .skip(1) // skip cellbase tx
+            .map(|tx| match fetched_cache.get(&tx.transaction.witness_hash()) {
+                Some(CacheEntry::Completed(completed)) => TimeRelativeTransactionVerifier::new(
+                    Arc::clone(tx),
+                    Arc::clone(&self.context.consensus),
+                    self.context.store.as_data_loader(),
+                    Arc::clone(&tx_env),
+                )
+                .verify()
+                .map(|_| completed.fee),
+                _ => ContextualWithoutScriptTransactionVerifier::new(
+                    Arc::clone(tx),
+                    Arc::clone(&self.context.consensus),
+                    self.context.store.as_data_loader(),
+                    Arc::clone(&tx_env),
+                )
+                .verify(),
+            })
+            .collect();
 
-        // make verifiers orthogonal
-        let ret = resolved
+        let mut fees = Vec::with_capacity(results.len());
+        let mut failure = None;
+        for (index, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(fee) => fees.push(fee),
+                Err(error) => {
+                    failure = Some(BlockTransactionsError {
+                        index: index as u32 + 1,
+                        error,
+                    });
+                    break;
+                }
+            }
+        }
+        CheckedTransactions {
+            fetched_cache,
+            fees,
+            failure,
+        }
+    }
+
+    /// Runs the scripts of the transactions passing the [`check`](Self::check).
+    pub fn verify(
+        &self,
+        resolved: &'a [Arc<ResolvedTransaction>],
+        skip_script_verify: bool,
+        checked: CheckedTransactions,
+    ) -> Result<(Cycle, Vec<Completed>), Error> {
+        let CheckedTransactions {
+            fetched_cache,
+            fees,
+            failure,
+        } = checked;
+        let tx_env = Arc::new(TxVerifyEnv::new_commit(&self.header));
+        let max_block_cycles = self.context.consensus.max_block_cycles();
+        // the transactions running scripts share the cycles limit of the block
+        let budget = CyclesBudget::new(max_block_cycles);
+        let script_verifier = |tx: &Arc<ResolvedTransaction>| {
+            ScriptVerifier::new(
+                Arc::clone(tx),
+                self.context.store.as_data_loader(),
+                Arc::clone(&self.context.consensus),
+                Arc::clone(&tx_env),
+            )
+        };
+
+        // make verifiers orthogonal, `None` if the transaction isn't verified to the end since
+        // the budget runs out or another transaction fails. Only the transactions before the
+        // first failing check are verified.
+        let results: Vec<Option<Result<Completed, Error>>> = resolved
             .par_iter()
-            .enumerate()
-            .map(|(index, tx)| {
+            .skip(1) // skip cellbase tx
+            .zip(fees.par_iter())
+            .map(|(tx, fee)| {
                 let wtx_hash = tx.transaction.witness_hash();
+                let cycles = match fetched_cache.get(&wtx_hash) {
+                    Some(CacheEntry::Completed(completed)) => Ok(Some(completed.cycles)),
+                    _ if skip_script_verify => Ok(Some(0)),
+                    Some(CacheEntry::Suspended(suspended)) => script_verifier(tx)
+                        .complete(&suspended.snap, max_block_cycles)
+                        .map(Some),
+                    None => script_verifier(tx).parallel_verify_with_budget(max_block_cycles, &budget),
+                };
+                let result = cycles.and_then(|cycles| {
+                    self.verify_dao_script_size(tx).map(|_| {
+                        cycles.map(|cycles| Completed { cycles, fee: *fee })
+                    })
+                });
+                if result.is_err() {
+                    budget.cancel();
+                }
+                result.transpose()
+            })
+            .collect();
 
-                if let Some(cache_entry) = fetched_cache.get(&wtx_hash) {
-                    match cache_entry {
-                        CacheEntry::Completed(completed) => TimeRelativeTransactionVerifier::new(
-                            Arc::clone(tx),
-                            Arc::clone(&self.context.consensus),
-                            self.context.store.as_data_loader(),
-                            Arc::clone(&tx_env),
-                        )
-                        .verify()
-                        .map_err(|error| {
-                            BlockTransactionsError {
-                                index: index as u32,
-                                error,
-                            }
-                            .into()
+        // The results are taken in order, and the transactions not verified to the end or
+        // failed are verified again, so the error doesn't depend on the scheduling: it's the one
+        // of the first failing transaction, unless the transactions before it exceed the cycles
+        // limit of the block. The transactions verified again consume at most the limit plus
+        // the cycles of the last one.
+        let mut sum: Cycle = 0;
+        let mut ret = Vec::with_capacity(results.len());
+        for (index, ((tx, fee), result)) in
+            resolved.iter().skip(1).zip(&fees).zip(results).enumerate()
+        {
+            let completed = match result {
+                Some(Ok(completed)) => completed,
+                _ => {
+                    let cycles = if skip_script_verify {
+                        Ok(0)
+                    } else {
+                        script_verifier(tx).verify(max_block_cycles)
+                    };
+                    cycles
+                        .and_then(|cycles| {
+                            self.verify_dao_script_size(tx)
+                                .map(|_| Completed { cycles, fee: *fee })
                         })
-                        .map(|_| (wtx_hash, *completed)),
-                        CacheEntry::Suspended(suspended) => ContextualTransactionVerifier::new(
-                            Arc::clone(tx),
-                            Arc::clone(&self.context.consensus),
-                            self.context.store.as_data_loader(),
-                            Arc::clone(&tx_env),
-                        )
-                        .complete(
-                            self.context.consensus.max_block_cycles(),
-                            skip_script_verify,
-                            &suspended.snap,
-                        )
-                        .map_err(|error| {
-                            BlockTransactionsError {
-                                index: index as u32,
-                                error,
-                            }
-                            .into()
-                        })
-                        .map(|completed| (wtx_hash, completed)),
-                    }
-                } else {
-                    ContextualTransactionVerifier::new(
-                        Arc::clone(tx),
-                        Arc::clone(&self.context.consensus),
-                        self.context.store.as_data_loader(),
-                        Arc::clone(&tx_env),
-                    )
-                    .verify(
-                        self.context.consensus.max_block_cycles(),
-                        skip_script_verify,
-                    )
-                    .map_err(|error| {
-                        BlockTransactionsError {
-                            index: index as u32,
+                        .map_err(|error| BlockTransactionsError {
+                            index: index as u32 + 1,
                             error,
-                        }
-                        .into()
-                    })
-                    .map(|completed| (wtx_hash, completed))
-                }.and_then(|result| {
-                    if self.context.consensus.rfc0044_active(self.parent.epoch().number()) {
-                        DaoScriptSizeVerifier::new(
-                            Arc::clone(tx),
-                            Arc::clone(&self.context.consensus),
-                            self.context.store.as_data_loader(),
-                        ).verify()?;
-                    }
-                    Ok(result)
-                })
-            })
-            .skip(1) // skip cellbase tx
-            .collect::<Result<Vec<(Byte32, Completed)>, Error>>()?;
+                        })?
+                }
+            };
+            sum = sum.saturating_add(completed.cycles);
+            if sum > max_block_cycles {
+                return Err(BlockErrorKind::ExceededMaximumCycles.into());
+            }
+            ret.push((tx.transaction.witness_hash(), completed));
+        }
+        if let Some(failure) = failure {
+            return Err(failure.into());
+        }
 
-        let sum: Cycle = ret.iter().map(|(_, cache_entry)| cache_entry.cycles).sum();
         let cache_entires = ret
             .iter()
             .map(|(_, completed)| completed)
@@ -485,14 +550,26 @@ impl<'a, 'b, CS: ChainStore + VersionbitsIndexer + 'static> BlockTxsVerifier<'a,
         if !ret.is_empty() {
             self.update_cache(ret);
         }
+        Ok((sum, cache_entires))
+    }
 
-        if sum > self.context.consensus.max_block_cycles() {
-            Err(BlockErrorKind::ExceededMaximumCycles.into())
-        } else {
-            Ok((sum, cache_entires))
+    fn verify_dao_script_size(&self, tx: &Arc<ResolvedTransaction>) -> Result<(), Error> {
+        if self
+            .context
+            .consensus
+            .rfc0044_active(self.parent.epoch().number())
+        {
+            DaoScriptSizeVerifier::new(
+                Arc::clone(tx),
+                Arc::clone(&self.context.consensus),
+                self.context.store.as_data_loader(),
+            )
+            .verify()?;
         }
+        Ok(())
     }
 }
+
 /// EpochVerifier
 ///
 /// Check for block epoch
@@ -625,8 +702,50 @@ pub struct ContextualBlockVerifier<'a, CS, MS> {
     handle: &'a Handle,
     txs_verify_cache: Arc<RwLock<TxVerificationCache>>,
     chain_root_mmr: &'a ChainRootMMR<MS>,
+    stage_observer: Option<StageObserver>,
+}
+
+/// The stages of the context-dependent block verification
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VerifyStage {
+    /// Verifies the epoch of the block
+    Epoch,
+    /// Verifies the uncles
+    Uncles,
+    /// Verifies the proposals of the committed transactions
+    TwoPhaseCommit,
+    /// Verifies the DAO field of the header
+    DaoHeader,
+    /// Verifies the cellbase reward
+    Reward,
+    /// Verifies the block extension
+    Extension,
+    /// Verifies the transactions except the scripts
+    Transactions,
+    /// Runs the scripts of the transactions
+    Scripts,
+}
+
+impl VerifyStage {
+    /// The stage name in snake case
+    pub fn name(&self) -> &'static str {
+        match self {
+            VerifyStage::Epoch => "epoch",
+            VerifyStage::Uncles => "uncles",
+            VerifyStage::TwoPhaseCommit => "two_phase_commit",
+            VerifyStage::DaoHeader => "dao_header",
+            VerifyStage::Reward => "reward",
+            VerifyStage::Extension => "extension",
+            VerifyStage::Transactions => "transactions",
+            VerifyStage::Scripts => "scripts",
+        }
+    }
 }
 
+/// The callback invoked with the elapsed time once a verification stage finishes, whether it
+/// passes or not.
+pub type StageObserver = Arc<dyn Fn(VerifyStage, Duration) + Send + Sync>;
+
 impl<'a, CS: ChainStore + VersionbitsIndexer + 'static, MS: MMRStore<HeaderDigest>>
     ContextualBlockVerifier<'a, CS, MS>
 {
@@ -644,7 +763,38 @@ impl<'a, CS: ChainStore + VersionbitsIndexer + 'static, MS: MMRStore<HeaderDiges
             switch,
             txs_verify_cache,
             chain_root_mmr,
+            stage_observer: None,
+        }
+    }
+
+    /// Reports the elapsed time of each verification stage to the observer
+    pub fn with_stage_observer(mut self, observer: StageObserver) -> Self {
+        self.stage_observer = Some(observer);
+        self
+    }
+
+    fn observe<T>(
+        &self,
+        stage: VerifyStage,
+        verify: impl FnOnce() -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let metrics = ckb_metrics::handle();
+        if metrics.is_none() && self.stage_observer.is_none() {
+            return verify();
+        }
+        let start = Instant::now();
+        let ret = verify();
+        let elapsed = start.elapsed();
+        if let Some(metrics) = metrics {
+            metrics
+                .ckb_block_process_stage_duration
+                .with_label_values(&[stage.name()])
+                .observe(elapsed.as_secs_f64());
         }
+        if let Some(ref observer) = self.stage_observer {
+            observer(stage, elapsed);
+        }
+        ret
     }
 
     /// Perform context-dependent verification checks for block
@@ -676,39 +826,55 @@ impl<'a, CS: ChainStore + VersionbitsIndexer + 'static, MS: MMRStore<HeaderDiges
         };
 
         if !self.switch.disable_epoch() {
-            EpochVerifier::new(&epoch_ext, block).verify()?;
+            self.observe(VerifyStage::Epoch, || {
+                EpochVerifier::new(&epoch_ext, block).verify()
+            })?;
         }
 
         if !self.switch.disable_uncles() {
-            let uncle_verifier_context = UncleVerifierContext::new(&self.context, &epoch_ext);
-            UnclesVerifier::new(uncle_verifier_context, block).verify()?;
+            self.observe(VerifyStage::Uncles, || {
+                let uncle_verifier_context = UncleVerifierContext::new(&self.context, &epoch_ext);
+                UnclesVerifier::new(uncle_verifier_context, block).verify()
+            })?;
         }
 
         if !self.switch.disable_two_phase_commit() {
-            TwoPhaseCommitVerifier::new(&self.context, block).verify()?;
+            self.observe(VerifyStage::TwoPhaseCommit, || {
+                TwoPhaseCommitVerifier::new(&self.context, block).verify()
+            })?;
         }
 
         if !self.switch.disable_daoheader() {
-            DaoHeaderVerifier::new(&self.context, resolved, &parent, &block.header()).verify()?;
+            self.observe(VerifyStage::DaoHeader, || {
+                DaoHeaderVerifier::new(&self.context, resolved, &parent, &block.header()).verify()
+            })?;
         }
 
         if !self.switch.disable_reward() {
-            RewardVerifier::new(&self.context, resolved, &parent).verify()?;
+            self.observe(VerifyStage::Reward, || {
+                RewardVerifier::new(&self.context, resolved, &parent).verify()
+            })?;
         }
 
         if !self.switch.disable_extension() {
-            BlockExtensionVerifier::new(&self.context, self.chain_root_mmr, &parent)
-                .verify(block)?;
+            self.observe(VerifyStage::Extension, || {
+                BlockExtensionVerifier::new(&self.context, self.chain_root_mmr, &parent)
+                    .verify(block)
+            })?;
         }
 
-        let ret = BlockTxsVerifier::new(
+        let txs_verifier = BlockTxsVerifier::new(
             self.context.clone(),
             header,
             self.handle,
             &self.txs_verify_cache,
             &parent,
-        )
-        .verify(resolved, self.switch.disable_script())?;
-        Ok(ret)
+        );
+        let checked = self.observe(VerifyStage::Transactions, || {
+            Ok(txs_verifier.check(resolved))
+        })?;
+        self.observe(VerifyStage::Scripts, || {
+            txs_verifier.verify(resolved, self.switch.disable_script(), checked)
+        })
     }
 }
@@ -1,23 +1,30 @@
-use super::super::contextual_block_verifier::{EpochVerifier, TwoPhaseCommitVerifier};
+use super::super::contextual_block_verifier::{
+    BlockTxsVerifier, EpochVerifier, TwoPhaseCommitVerifier,
+};
 use crate::contextual_block_verifier::{RewardVerifier, VerifyContext};
 use ckb_chain::chain::{ChainController, ChainService};
 use ckb_chain_spec::consensus::{Consensus, ConsensusBuilder};
 use ckb_error::assert_error_eq;
+use ckb_error::Error;
 use ckb_shared::{Shared, SharedBuilder};
 use ckb_store::{ChainDB, ChainStore};
 use ckb_test_chain_utils::always_success_cell;
 use ckb_types::{
     bytes::Bytes,
     core::{
-        capacity_bytes, cell::ResolvedTransaction, BlockBuilder, BlockNumber, BlockView, Capacity,
-        EpochExt, EpochNumberWithFraction, HeaderBuilder, HeaderView, TransactionBuilder,
-        TransactionView, UncleBlockView,
+        capacity_bytes,
+        cell::{CellMetaBuilder, ResolvedTransaction},
+        BlockBuilder, BlockNumber, BlockView, Capacity, Cycle, EpochExt, EpochNumberWithFraction,
+        HeaderBuilder, HeaderView, ScriptHashType, TransactionBuilder, TransactionView,
+        UncleBlockView,
     },
     packed::{Byte32, CellDep, CellInput, CellOutputBuilder, OutPoint, ProposalShortId, Script},
     prelude::*,
     utilities::DIFF_TWO,
 };
-use ckb_verification::{CellbaseError, CommitError, EpochError};
+use ckb_verification::{
+    BlockError, BlockErrorKind, BlockTransactionsError, CellbaseError, CommitError, EpochError,
+};
 use ckb_verification_traits::Switch;
 use std::sync::Arc;
 
@@ -350,3 +357,146 @@ fn test_uncle_proposal() {
     let verifier = TwoPhaseCommitVerifier::new(&context, &block);
     assert!(verifier.verify().is_ok());
 }
+
+fn verify_block_txs(
+    shared: &Shared,
+    resolved: &[Arc<ResolvedTransaction>],
+) -> Result<Cycle, Error> {
+    let parent = shared.consensus().genesis_block().header();
+    let transactions = resolved
+        .iter()
+        .skip(1)
+        .map(|rtx| rtx.transaction.clone())
+        .collect();
+    let block = gen_block(&parent, transactions, vec![], vec![]);
+    let txs_verify_cache = shared.txs_verify_cache();
+    let verifier = BlockTxsVerifier::new(
+        dummy_context(shared),
+        block.header(),
+        shared.async_handle(),
+        &txs_verify_cache,
+        &parent,
+    );
+    let checked = verifier.check(resolved);
+    verifier
+        .verify(resolved, false, checked)
+        .map(|(cycles, _)| cycles)
+}
+
+#[test]
+fn test_block_txs_verifier_error() {
+    let (always_success_cell, always_success_cell_data, always_success_script) =
+        always_success_cell();
+    let always_success_cell = CellMetaBuilder::from_cell_output(
+        always_success_cell.clone(),
+        always_success_cell_data.clone(),
+    )
+    .build();
+    let not_found_script = Script::new_builder()
+        .code_hash(Byte32::new([1u8; 32]))
+        .hash_type(ScriptHashType::Data.into())
+        .build();
+    let resolve = |index: u32, lock: &Script| {
+        let output = CellOutputBuilder::default()
+            .capacity(capacity_bytes!(100).pack())
+            .lock(lock.clone())
+            .build();
+        let input = CellMetaBuilder::from_cell_output(output, Bytes::new()).build();
+        // the verified transactions are cached by hash, the ones of different locks must differ
+        let transaction = TransactionBuilder::default()
+            .input(CellInput::new(
+                OutPoint::new(lock.calc_script_hash(), index),
+                0,
+            ))
+            .build();
+        Arc::new(ResolvedTransaction {
+            transaction,
+            resolved_cell_deps: vec![always_success_cell.clone()],
+            resolved_inputs: vec![input],
+            resolved_dep_groups: vec![],
+        })
+    };
+    let cellbase = Arc::new(ResolvedTransaction::dummy_resolve(create_cellbase(1)));
+    let txs = |locks: &[&Script]| {
+        let mut resolved = vec![Arc::clone(&cellbase)];
+        resolved.extend(
+            locks
+                .iter()
+                .enumerate()
+                .map(|(index, lock)| resolve(index as u32, lock)),
+        );
+        resolved
+    };
+    let success = &always_success_script;
+    let not_found = &not_found_script;
+
+    let (_chain_controller, shared) = start_chain(None);
+    let cycles = verify_block_txs(&shared, &txs(&[success])).unwrap();
+
+    // the block limit takes the cycles of two transactions
+    let consensus = ConsensusBuilder::default()
+        .max_block_cycles(cycles * 2)
+        .build();
+    let (_chain_controller, shared) = start_chain(Some(consensus));
+    assert_eq!(
+        verify_block_txs(&shared, &txs(&[success, success])).unwrap(),
+        cycles * 2
+    );
+    for _ in 0..8 {
+        // the first failing transaction reports the error
+        let error =
+            verify_block_txs(&shared, &txs(&[success, not_found, success, not_found])).unwrap_err();
+        let error = error
+            .downcast_ref::<BlockError>()
+            .and_then(|error| error.downcast_ref::<BlockTransactionsError>())
+            .unwrap();
+        assert_eq!(error.index, 2);
+
+        // the failing transaction after the block limit is exceeded is not reported
+        let error =
+            verify_block_txs(&shared, &txs(&[success, success, success, not_found])).unwrap_err();
+        assert_error_eq!(error, BlockErrorKind::ExceededMaximumCycles);
+
+        // the failing transaction before the block limit is exceeded is reported
+        let error =
+            verify_block_txs(&shared, &txs(&[success, not_found, success, success])).unwrap_err();
+        let error = error
+            .downcast_ref::<BlockError>()
+            .and_then(|error| error.downcast_ref::<BlockTransactionsError>())
+            .unwrap();
+        assert_eq!(error.index, 2);
+    }
+
+    // the transactions failing the checks before running the scripts are reported in order too
+    let overspent = {
+        let rtx = resolve(4, success);
+        let transaction = rtx
+            .transaction
+            .as_advanced_builder()
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(200).pack())
+                    .build(),
+            )
+            .output_data(Bytes::new().pack())
+            .build();
+        Arc::new(ResolvedTransaction {
+            transaction,
+            ..(*rtx).clone()
+        })
+    };
+    let error_index = |resolved: &[Arc<ResolvedTransaction>]| {
+        let error = verify_block_txs(&shared, resolved).unwrap_err();
+        error
+            .downcast_ref::<BlockError>()
+            .and_then(|error| error.downcast_ref::<BlockTransactionsError>())
+            .unwrap()
+            .index
+    };
+    let mut resolved = txs(&[not_found]);
+    resolved.insert(1, Arc::clone(&overspent));
+    assert_eq!(error_index(&resolved), 1);
+    let mut resolved = txs(&[not_found]);
+    resolved.push(overspent);
+    assert_eq!(error_index(&resolved), 1);
+}
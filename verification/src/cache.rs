@@ -1,22 +1,184 @@
 //! TX verification cache
 
-use ckb_script::TransactionSnapshot;
+use ckb_chain_spec::consensus::Consensus;
+use ckb_error::{AnyError, OtherError};
+use ckb_script::{ScriptVersion, TransactionSnapshot, TxVerifyEnv};
 use ckb_types::{
-    core::{Capacity, Cycle, EntryCompleted},
+    core::{Capacity, Cycle, EntryCompleted, EpochNumber, HeaderView},
     packed::Byte32,
+    prelude::*,
+};
+use std::{
+    fs::{self, OpenOptions},
+    io::{Read as _, Write as _},
+    path::Path,
+    sync::Arc,
 };
-use std::sync::Arc;
 
 /// TX verification lru cache
 pub type TxVerificationCache = lru::LruCache<Byte32, CacheEntry>;
 
 const CACHE_SIZE: usize = 1000 * 30;
 
+/// The version of the persisted cache data.
+const PERSISTED_VERSION: u32 = 1;
+
+// tx hash, cycles and fee
+const PERSISTED_ENTRY_SIZE: usize = 32 + 8 + 8;
+
 /// Initialize cache
 pub fn init_cache() -> TxVerificationCache {
     lru::LruCache::new(CACHE_SIZE)
 }
 
+/// Returns the latest script version enabled in the epoch.
+///
+/// The cycles of a transaction depend on the script versions it runs in, so the persisted
+/// entries are reusable only when the version is unchanged.
+pub fn script_version_in_epoch(consensus: &Consensus, epoch: EpochNumber) -> ScriptVersion {
+    let hardfork_switch = consensus.hardfork_switch();
+    if hardfork_switch
+        .ckb2023
+        .is_vm_version_2_and_syscalls_3_enabled(epoch)
+    {
+        ScriptVersion::V2
+    } else if hardfork_switch
+        .ckb2021
+        .is_vm_version_1_and_syscalls_2_enabled(epoch)
+    {
+        ScriptVersion::V1
+    } else {
+        ScriptVersion::V0
+    }
+}
+
+/// Returns the script version which the tx-pool verifies the transactions in on top of the tip.
+///
+/// The pool verifies the transactions against the block after the tip, which may start a new
+/// epoch.
+pub fn pool_script_version(consensus: &Consensus, tip_header: &HeaderView) -> ScriptVersion {
+    let epoch = TxVerifyEnv::new_submit(tip_header).epoch_number_without_proposal_window();
+    script_version_in_epoch(consensus, epoch)
+}
+
+/// Saves at most `limit` completed entries into the file, the most recently used ones are
+/// kept. The suspended entries are dropped.
+///
+/// The entries are written into a temporary file which then replaces the file, so a crash in the
+/// middle never leaves a broken file.
+///
+/// Returns the count of the saved entries.
+pub fn save_cache(
+    cache: &TxVerificationCache,
+    path: &Path,
+    script_version: ScriptVersion,
+    limit: usize,
+) -> Result<usize, AnyError> {
+    let path = path.with_extension(format!("v{PERSISTED_VERSION}"));
+    let mut buffer = vec![script_version as u8];
+    let mut count = 0;
+    for (tx_hash, completed) in cache
+        .iter()
+        .filter_map(|(tx_hash, entry)| match entry {
+            CacheEntry::Completed(completed) => Some((tx_hash, completed)),
+            CacheEntry::Suspended(_) => None,
+        })
+        .take(limit)
+    {
+        buffer.extend_from_slice(tx_hash.as_slice());
+        buffer.extend_from_slice(&completed.cycles.to_le_bytes());
+        buffer.extend_from_slice(&completed.fee.as_u64().to_le_bytes());
+        count += 1;
+    }
+
+    let tmp_path = path.with_extension(format!("v{PERSISTED_VERSION}.tmp"));
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(&tmp_path)
+        .map_err(|err| {
+            let errmsg = format!(
+                "Failed to open the verification cache persisted data file [{tmp_path:?}], cause: {err}"
+            );
+            OtherError::new(errmsg)
+        })?;
+    file.write_all(&buffer).map_err(|err| {
+        let errmsg = format!(
+            "Failed to write the verification cache persisted data into file [{tmp_path:?}], cause: {err}"
+        );
+        OtherError::new(errmsg)
+    })?;
+    file.sync_all().map_err(|err| {
+        let errmsg = format!(
+            "Failed to sync the verification cache persisted data file [{tmp_path:?}], cause: {err}"
+        );
+        OtherError::new(errmsg)
+    })?;
+    fs::rename(&tmp_path, &path).map_err(|err| {
+        let errmsg = format!(
+            "Failed to rename the verification cache persisted data file [{tmp_path:?}] to [{path:?}], cause: {err}"
+        );
+        OtherError::new(errmsg)
+    })?;
+    Ok(count)
+}
+
+/// Loads the entries saved by [`save_cache`] into the cache, nothing is loaded when the entries
+/// are saved in another script version.
+///
+/// Returns the count of the loaded entries.
+pub fn load_cache(
+    cache: &mut TxVerificationCache,
+    path: &Path,
+    script_version: ScriptVersion,
+) -> Result<usize, AnyError> {
+    let path = path.with_extension(format!("v{PERSISTED_VERSION}"));
+    if !path.exists() {
+        return Ok(0);
+    }
+    let mut buffer = Vec::new();
+    OpenOptions::new()
+        .read(true)
+        .open(&path)
+        .and_then(|mut file| file.read_to_end(&mut buffer))
+        .map_err(|err| {
+            let errmsg = format!(
+                "Failed to read the verification cache persisted data file [{path:?}], cause: {err}"
+            );
+            OtherError::new(errmsg)
+        })?;
+
+    let entries = match buffer.split_first() {
+        Some((version, entries)) if entries.len() % PERSISTED_ENTRY_SIZE == 0 => {
+            if *version != script_version as u8 {
+                return Ok(0);
+            }
+            entries
+        }
+        _ => {
+            let errmsg = format!("The verification cache persisted data file [{path:?}] is broken");
+            return Err(OtherError::new(errmsg).into());
+        }
+    };
+    // the entries are saved from the most recently used one, loads them in reverse to keep
+    // the order
+    let mut count = 0;
+    for entry in entries.rchunks_exact(PERSISTED_ENTRY_SIZE) {
+        let (tx_hash, rest) = entry.split_at(32);
+        let (cycles, fee) = rest.split_at(8);
+        let tx_hash = Byte32::from_slice(tx_hash).expect("checked length");
+        let cycles = Cycle::from_le_bytes(cycles.try_into().expect("checked length"));
+        let fee = u64::from_le_bytes(fee.try_into().expect("checked length"));
+        cache.put(
+            tx_hash,
+            CacheEntry::completed(cycles, Capacity::shannons(fee)),
+        );
+        count += 1;
+    }
+    Ok(count)
+}
+
 #[derive(Clone, Debug)]
 /// TX verification lru entry
 pub enum CacheEntry {
@@ -0,0 +1,104 @@
+use crate::cache::{
+    init_cache, load_cache, pool_script_version, save_cache, script_version_in_epoch, CacheEntry,
+    Completed,
+};
+use ckb_chain_spec::consensus::ConsensusBuilder;
+use ckb_script::ScriptVersion;
+use ckb_types::{
+    core::{
+        hardfork::{HardForks, CKB2021, CKB2023},
+        Capacity, EpochNumberWithFraction, HeaderBuilder,
+    },
+    packed::Byte32,
+    prelude::*,
+};
+use tempfile::TempDir;
+
+fn tx_hash(n: u8) -> Byte32 {
+    let mut hash = [0u8; 32];
+    hash[0] = n;
+    hash.pack()
+}
+
+#[test]
+fn persist_cache() {
+    let tmp_dir = TempDir::new().unwrap();
+    let path = tmp_dir.path().join("verify_cache");
+
+    let mut cache = init_cache();
+    for n in 0..4 {
+        cache.put(
+            tx_hash(n),
+            CacheEntry::completed(n.into(), Capacity::shannons(n.into())),
+        );
+    }
+    // the most recently used 3 entries are saved
+    assert_eq!(save_cache(&cache, &path, ScriptVersion::V2, 3).unwrap(), 3);
+    // the temporary file is renamed to the persisted one
+    assert!(path.with_extension("v1").exists());
+    assert!(!path.with_extension("v1.tmp").exists());
+
+    let mut loaded = init_cache();
+    assert_eq!(
+        load_cache(&mut loaded, &path, ScriptVersion::V2).unwrap(),
+        3
+    );
+    let hashes: Vec<_> = loaded.iter().map(|(hash, _)| hash.clone()).collect();
+    assert_eq!(hashes, vec![tx_hash(3), tx_hash(2), tx_hash(1)]);
+    match loaded.peek(&tx_hash(2)) {
+        Some(CacheEntry::Completed(completed)) => assert_eq!(
+            completed,
+            &Completed {
+                cycles: 2,
+                fee: Capacity::shannons(2),
+            }
+        ),
+        _ => panic!("the entry should be loaded"),
+    }
+
+    // the cycles are invalid in another script version
+    let mut loaded = init_cache();
+    assert_eq!(
+        load_cache(&mut loaded, &path, ScriptVersion::V1).unwrap(),
+        0
+    );
+    assert!(loaded.is_empty());
+
+    let mut loaded = init_cache();
+    let missing = tmp_dir.path().join("missing");
+    assert_eq!(
+        load_cache(&mut loaded, &missing, ScriptVersion::V2).unwrap(),
+        0
+    );
+}
+
+#[test]
+fn pool_script_version_at_epoch_end() {
+    let hardfork_switch = HardForks {
+        ckb2021: CKB2021::new_mirana()
+            .as_builder()
+            .rfc_0032(0)
+            .build()
+            .unwrap(),
+        ckb2023: CKB2023::new_mirana()
+            .as_builder()
+            .rfc_0049(1)
+            .build()
+            .unwrap(),
+    };
+    let consensus = ConsensusBuilder::default()
+        .hardfork_switch(hardfork_switch)
+        .build();
+
+    let header = HeaderBuilder::default()
+        .epoch(EpochNumberWithFraction::new(0, 0, 1000).pack())
+        .build();
+    assert_eq!(pool_script_version(&consensus, &header), ScriptVersion::V1);
+
+    // the next block of the tip starts the epoch 1
+    let header = HeaderBuilder::default()
+        .epoch(EpochNumberWithFraction::new(0, 999, 1000).pack())
+        .build();
+    assert_eq!(script_version_in_epoch(&consensus, 0), ScriptVersion::V1);
+    assert_eq!(pool_script_version(&consensus, &header), ScriptVersion::V2);
+}
@@ -1,6 +1,7 @@
 mod error;
 
 mod block_verifier;
+mod cache;
 mod genesis_verifier;
 mod header_verifier;
 mod transaction_verifier;
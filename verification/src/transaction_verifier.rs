@@ -5,7 +5,7 @@ use ckb_chain_spec::consensus::Consensus;
 use ckb_dao::DaoCalculator;
 use ckb_dao_utils::DaoError;
 use ckb_error::Error;
-use ckb_script::{TransactionScriptsVerifier, TransactionSnapshot, VerifyResult};
+use ckb_script::{CyclesBudget, TransactionScriptsVerifier, TransactionSnapshot, VerifyResult};
 use ckb_traits::{
     CellDataProvider, EpochProvider, ExtensionProvider, HeaderFieldsProvider, HeaderProvider,
 };
@@ -180,6 +180,37 @@ where
         Ok(Completed { cycles, fee })
     }
 
+    /// Perform context-dependent verification like `verify`, but runs the script groups in
+    /// parallel with the cycles budget shared by other transactions, see
+    /// [`TransactionScriptsVerifier::parallel_verify_with_budget`].
+    ///
+    /// Returns `Ok(None)` if the scripts are not verified to the end within the budget.
+    ///
+    /// skip script verify will result in the return value cycle always is zero
+    pub fn parallel_verify(
+        &self,
+        max_cycles: Cycle,
+        skip_script_verify: bool,
+        budget: &CyclesBudget,
+    ) -> Result<Option<Completed>, Error> {
+        self.compatible.verify()?;
+        self.time_relative.verify()?;
+        self.capacity.verify()?;
+        let cycles = if skip_script_verify {
+            0
+        } else {
+            match self
+                .script
+                .parallel_verify_with_budget(max_cycles, budget)?
+            {
+                Some(cycles) => cycles,
+                None => return Ok(None),
+            }
+        };
+        let fee = self.fee_calculator.transaction_fee()?;
+        Ok(Some(Completed { cycles, fee }))
+    }
+
     /// Perform complete a suspend context-dependent verification, return a `Result` to `CacheEntry`
     ///
     /// skip script verify will result in the return value cycle always is zero